@@ -7,12 +7,12 @@ use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
 use bonsaidb_core::networking::CURRENT_PROTOCOL_VERSION;
 #[cfg(not(target_arch = "wasm32"))]
-use fabruic::Certificate;
+use fabruic::{Certificate, KeyPair};
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::runtime::Handle;
 use url::Url;
 
-use crate::client::{AnyApiCallback, ApiCallback};
+use crate::client::{AnyApiCallback, ApiCallback, ReconnectPolicy};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::BlockingClient;
 use crate::{AsyncClient, Error};
@@ -34,9 +34,12 @@ pub struct Builder<AsyncMode> {
     custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
     connect_timeout: Option<Duration>,
     request_timeout: Option<Duration>,
+    reconnect_policy: ReconnectPolicy,
     #[cfg(not(target_arch = "wasm32"))]
     certificate: Option<fabruic::Certificate>,
     #[cfg(not(target_arch = "wasm32"))]
+    client_key_pair: Option<fabruic::KeyPair>,
+    #[cfg(not(target_arch = "wasm32"))]
     tokio: Option<Handle>,
     mode: PhantomData<AsyncMode>,
 }
@@ -50,9 +53,12 @@ impl<AsyncMode> Builder<AsyncMode> {
             custom_apis: HashMap::new(),
             request_timeout: None,
             connect_timeout: None,
+            reconnect_policy: ReconnectPolicy::default(),
             #[cfg(not(target_arch = "wasm32"))]
             certificate: None,
             #[cfg(not(target_arch = "wasm32"))]
+            client_key_pair: None,
+            #[cfg(not(target_arch = "wasm32"))]
             tokio: None,
             mode: PhantomData,
         }
@@ -84,7 +90,8 @@ impl<AsyncMode> Builder<AsyncMode> {
         self
     }
 
-    /// Connects to a server using a pinned `certificate`. Only supported with BonsaiDb protocol-based connections.
+    /// Connects to a server using a pinned `certificate`. When connecting over
+    /// secure WebSockets, only the pinned certificate is trusted.
     #[cfg(not(target_arch = "wasm32"))]
     #[allow(clippy::missing_const_for_fn)]
     pub fn with_certificate(mut self, certificate: Certificate) -> Self {
@@ -92,6 +99,16 @@ impl<AsyncMode> Builder<AsyncMode> {
         self
     }
 
+    /// Presents the certificate in `key_pair` to the server when connecting,
+    /// allowing the server to authenticate this client using the certificate.
+    /// Supported by BonsaiDb protocol-based and secure WebSocket connections.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn with_client_certificate(mut self, key_pair: KeyPair) -> Self {
+        self.client_key_pair = Some(key_pair);
+        self
+    }
+
     /// Overrides the protocol version. Only for testing purposes.
     #[cfg(feature = "test-util")]
     #[allow(clippy::missing_const_for_fn)]
@@ -117,6 +134,16 @@ impl<AsyncMode> Builder<AsyncMode> {
         self
     }
 
+    /// Sets the policy used to reconnect after the connection to the server is
+    /// lost.
+    ///
+    /// If not specified, [`ReconnectPolicy::default()`] is used.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
     fn finish_internal(self) -> Result<AsyncClient, Error> {
         AsyncClient::new_from_parts(
             self.url,
@@ -124,9 +151,12 @@ impl<AsyncMode> Builder<AsyncMode> {
             self.custom_apis,
             self.connect_timeout,
             self.request_timeout,
+            self.reconnect_policy,
             #[cfg(not(target_arch = "wasm32"))]
             self.certificate,
             #[cfg(not(target_arch = "wasm32"))]
+            self.client_key_pair,
+            #[cfg(not(target_arch = "wasm32"))]
             self.tokio.or_else(|| Handle::try_current().ok()),
         )
     }
@@ -2,7 +2,7 @@ use std::any::TypeId;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::ops::Deref;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -12,14 +12,19 @@ use bonsaidb_core::api::{self, Api, ApiName};
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::arc_bytes::OwnedBytes;
 use bonsaidb_core::connection::{
-    AsyncStorageConnection, Database, HasSession, IdentityReference, Session,
+    AsyncStorageConnection, Database, HasSession, IdentityReference, Session, SessionId,
 };
 use bonsaidb_core::networking::{
-    AlterUserPermissionGroupMembership, AlterUserRoleMembership, AssumeIdentity, CreateDatabase,
-    CreateUser, DeleteDatabase, DeleteUser, ListAvailableSchemas, ListDatabases, LogOutSession,
-    MessageReceived, Payload, UnregisterSubscriber, CURRENT_PROTOCOL_VERSION,
+    ActiveSession, AlterUserPermissionGroupMembership, AlterUserRoleMembership, AssumeIdentity,
+    CreateDatabase, CreatePermissionGroup, CreateRole, CreateUser, DeleteDatabase,
+    DeletePermissionGroup, DeleteRole, DeleteUser, DisconnectClient, ExplainPermission,
+    ListAuthenticationTokens, ListAvailableSchemas, ListDatabases, ListSessions, LogOutSession,
+    MessageReceived, Payload, RevokeAuthenticationToken, TerminateSession, UnregisterSubscriber,
+    UpdatePermissionGroup, UpdateRole, CURRENT_PROTOCOL_VERSION,
+};
+use bonsaidb_core::permissions::{
+    ActionName, ClientContext, PermissionExplanation, Permissions, ResourceName,
 };
-use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::{Nameable, Schema, SchemaName, SchemaSummary, Schematic};
 use bonsaidb_utils::fast_async_lock;
 use flume::Sender;
@@ -30,6 +35,8 @@ use parking_lot::Mutex;
 use tokio::runtime::Handle;
 use url::Url;
 
+pub use self::reconnect::{ConnectionState, ReconnectPolicy};
+use self::reconnect::{Reconnector, SessionCredentials};
 pub use self::remote_database::{AsyncRemoteDatabase, AsyncRemoteSubscriber};
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::sync::{BlockingClient, BlockingRemoteDatabase, BlockingRemoteSubscriber};
@@ -39,6 +46,7 @@ use crate::{ApiError, Builder};
 
 #[cfg(not(target_arch = "wasm32"))]
 mod quic_worker;
+mod reconnect;
 mod remote_database;
 #[cfg(not(target_arch = "wasm32"))]
 mod sync;
@@ -85,17 +93,32 @@ pub type WebSocketError = wasm_websocket_worker::WebSocketError;
 /// returned. This allows the application to detect when a networking issue has
 /// arisen.
 ///
-/// If the disconnect happens while the client is completely idle, the next
-/// request will report the disconnection error. The subsequent request will
-/// cause the client to begin reconnecting again.
+/// After the connection is lost, the client reconnects in the background using
+/// its [`ReconnectPolicy`], waiting longer between each failed attempt.
+/// Requests made while reconnecting are queued and sent once the connection is
+/// restored. Before any queued requests are sent, the client:
+///
+/// - re-authenticates each session that was created using
+///   [`authenticate()`](AsyncStorageConnection::authenticate) with a password,
+///   [`authenticate_with_token()`](AsyncStorageConnection::authenticate_with_token),
+///   or [`assume_identity()`](AsyncStorageConnection::assume_identity) and is
+///   still referenced by a client handle;
+/// - re-registers each [`AsyncRemoteSubscriber`] that is still alive, along
+///   with its topic subscriptions.
+///
+/// Existing client handles continue to work using their restored sessions. If
+/// a session cannot be restored, the handles using it lose their
+/// authentication, which can be checked using [`HasSession::session()`].
 ///
-/// When unauthenticated, this reconnection behavior is mostly transparent --
-/// disconnection errors can be shown to the user, and service will be restored
-/// automatically. However, when dealing with authentication, the client does
-/// not store credentials to be able to send them again when reconnecting. This
-/// means that the existing client handles will lose their authentication when
-/// the network connection is broken. The current authentication status can be
-/// checked using [`HasSession::session()`].
+/// If every reconnection attempt allowed by the policy fails, or if
+/// reconnection is [disabled](ReconnectPolicy::disabled), no sessions or
+/// subscribers are restored. The next request will report the disconnection
+/// error, and the subsequent request will cause the client to begin connecting
+/// again.
+///
+/// The state of the connection can be observed using
+/// [`connection_state()`](Self::connection_state) or
+/// [`watch_connection_state()`](Self::watch_connection_state).
 ///
 /// ## Connecting via QUIC
 ///
@@ -233,10 +256,13 @@ pub struct AsyncClient {
 
 impl Drop for AsyncClient {
     fn drop(&mut self) {
-        if self.session_is_current() && Arc::strong_count(&self.session.session) == 1 {
+        if Arc::strong_count(&self.session.session) == 1 {
             if let Some(session_id) = self.session.session.id {
-                // Final reference to an authenticated session
-                drop(self.invoke_blocking_api_request(&LogOutSession(session_id)));
+                // Final reference to an authenticated session. If the session
+                // is valid on the current connection, log it out.
+                if let Some(current_id) = self.data.reconnector.session_ended(session_id) {
+                    drop(self.invoke_blocking_api_request(&LogOutSession(current_id)));
+                }
             }
         }
     }
@@ -253,9 +279,7 @@ pub struct Data {
     request_sender: Sender<PendingRequest>,
     effective_permissions: Mutex<Option<Permissions>>,
     schemas: Mutex<HashMap<TypeId, Arc<Schematic>>>,
-    connection_counter: Arc<AtomicU32>,
-    request_id: AtomicU32,
-    subscribers: SubscriberMap,
+    reconnector: Arc<Reconnector>,
 }
 
 impl AsyncClient {
@@ -272,7 +296,8 @@ impl AsyncClient {
     /// present that error. If the client disconnects while processing requests,
     /// all requests being processed will exit and return
     /// [`Error::Disconnected`](bonsaidb_core::networking::Error::Disconnected).
-    /// The client will automatically try reconnecting.
+    /// The client will automatically reconnect using the default
+    /// [`ReconnectPolicy`].
     ///
     /// The goal of this design of this reconnection strategy is to make it
     /// easier to build resilliant apps. By allowing existing Client instances
@@ -286,6 +311,9 @@ impl AsyncClient {
             HashMap::default(),
             None,
             None,
+            ReconnectPolicy::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            None,
             #[cfg(not(target_arch = "wasm32"))]
             None,
             #[cfg(not(target_arch = "wasm32"))]
@@ -308,13 +336,16 @@ impl AsyncClient {
     /// to recover and reconnect, each component of the apps built can adopt a
     /// "retry-to-recover" design, or "abort-and-fail" depending on how critical
     /// the database is to operation.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new_from_parts(
         url: Url,
         protocol_version: &'static str,
         mut custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
         connect_timeout: Option<Duration>,
         request_timeout: Option<Duration>,
+        reconnect_policy: ReconnectPolicy,
         #[cfg(not(target_arch = "wasm32"))] certificate: Option<fabruic::Certificate>,
+        #[cfg(not(target_arch = "wasm32"))] client_key_pair: Option<fabruic::KeyPair>,
         #[cfg(not(target_arch = "wasm32"))] tokio: Option<Handle>,
     ) -> Result<Self, Error> {
         let subscribers = SubscriberMap::default();
@@ -344,7 +375,7 @@ impl AsyncClient {
         // Default timeouts to 1 minute.
         let connection = ConnectionInfo {
             url,
-            subscribers,
+            reconnector: Arc::new(Reconnector::new(reconnect_policy, subscribers)),
             connect_timeout: connect_timeout.unwrap_or(Duration::from_secs(60)),
             request_timeout: request_timeout.unwrap_or(Duration::from_secs(60)),
         };
@@ -354,6 +385,7 @@ impl AsyncClient {
                 connection,
                 protocol_version,
                 certificate,
+                client_key_pair,
                 custom_apis,
                 tokio,
             )),
@@ -361,6 +393,10 @@ impl AsyncClient {
             "wss" | "ws" => Ok(Self::new_websocket_client(
                 connection,
                 protocol_version,
+                #[cfg(not(target_arch = "wasm32"))]
+                certificate,
+                #[cfg(not(target_arch = "wasm32"))]
+                client_key_pair,
                 custom_apis,
                 #[cfg(not(target_arch = "wasm32"))]
                 tokio,
@@ -374,22 +410,22 @@ impl AsyncClient {
         server: ConnectionInfo,
         protocol_version: &'static str,
         certificate: Option<fabruic::Certificate>,
+        client_key_pair: Option<fabruic::KeyPair>,
         custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
         tokio: Option<Handle>,
     ) -> Self {
         let (request_sender, request_receiver) = flume::unbounded();
-        let connection_counter = Arc::new(AtomicU32::default());
         let request_timeout = server.request_timeout;
-        let subscribers = server.subscribers.clone();
+        let reconnector = server.reconnector.clone();
 
         sync::spawn_client(
             quic_worker::reconnecting_client_loop(
                 server,
                 protocol_version,
                 certificate,
+                client_key_pair,
                 request_receiver,
                 Arc::new(custom_apis),
-                connection_counter.clone(),
             ),
             tokio,
         );
@@ -398,10 +434,8 @@ impl AsyncClient {
             data: Arc::new(Data {
                 request_sender,
                 schemas: Mutex::default(),
-                connection_counter,
-                request_id: AtomicU32::default(),
                 effective_permissions: Mutex::default(),
-                subscribers,
+                reconnector,
             }),
             session: ClientSession::default(),
             request_timeout,
@@ -412,21 +446,23 @@ impl AsyncClient {
     fn new_websocket_client(
         server: ConnectionInfo,
         protocol_version: &'static str,
+        certificate: Option<fabruic::Certificate>,
+        client_key_pair: Option<fabruic::KeyPair>,
         custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
         tokio: Option<Handle>,
     ) -> Self {
         let (request_sender, request_receiver) = flume::unbounded();
-        let connection_counter = Arc::new(AtomicU32::default());
         let request_timeout = server.request_timeout;
-        let subscribers = server.subscribers.clone();
+        let reconnector = server.reconnector.clone();
 
         sync::spawn_client(
             tungstenite_worker::reconnecting_client_loop(
                 server,
                 protocol_version,
+                certificate,
+                client_key_pair,
                 request_receiver,
                 Arc::new(custom_apis),
-                connection_counter.clone(),
             ),
             tokio,
         );
@@ -435,10 +471,8 @@ impl AsyncClient {
             data: Arc::new(Data {
                 request_sender,
                 schemas: Mutex::default(),
-                request_id: AtomicU32::default(),
-                connection_counter,
                 effective_permissions: Mutex::default(),
-                subscribers,
+                reconnector,
             }),
             session: ClientSession::default(),
             request_timeout,
@@ -452,32 +486,24 @@ impl AsyncClient {
         custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
     ) -> Self {
         let (request_sender, request_receiver) = flume::unbounded();
-        let connection_counter = Arc::new(AtomicU32::default());
 
         wasm_websocket_worker::spawn_client(
             Arc::new(server.url),
             protocol_version,
             request_receiver,
             Arc::new(custom_apis),
-            server.subscribers.clone(),
-            connection_counter.clone(),
+            server.reconnector.clone(),
+            None,
             None,
             server.connect_timeout,
         );
 
-        #[cfg(feature = "test-util")]
-        let background_task_running = Arc::new(AtomicBool::new(true));
-
         Self {
             data: Arc::new(Data {
                 request_sender,
                 schemas: Mutex::default(),
-                request_id: AtomicU32::default(),
-                connection_counter,
                 effective_permissions: Mutex::default(),
-                subscribers: server.subscribers,
-                #[cfg(feature = "test-util")]
-                background_task_running,
+                reconnector: server.reconnector,
             }),
             session: ClientSession::default(),
             request_timeout: server.request_timeout,
@@ -490,7 +516,7 @@ impl AsyncClient {
         bytes: Bytes,
     ) -> Result<flume::Receiver<Result<Bytes, Error>>, Error> {
         let (result_sender, result_receiver) = flume::bounded(1);
-        let id = self.data.request_id.fetch_add(1, Ordering::SeqCst);
+        let id = self.data.reconnector.next_request_id();
         self.data.request_sender.send(PendingRequest {
             request: Payload {
                 session_id: self.session.session.id,
@@ -587,31 +613,95 @@ impl AsyncClient {
         effective_permissions.clone()
     }
 
-    pub(crate) fn register_subscriber(&self, id: u64, sender: flume::Sender<Message>) {
-        let mut subscribers = self.data.subscribers.lock();
-        subscribers.insert(id, sender);
+    /// Returns the current state of this client's connection to the server.
+    #[must_use]
+    pub fn connection_state(&self) -> ConnectionState {
+        self.data.reconnector.state()
     }
 
-    pub(crate) async fn unregister_subscriber_async(&self, database: String, id: u64) {
+    /// Returns a [`Watcher`](watchable::Watcher) that is notified each time
+    /// this client's connection state changes.
+    #[must_use]
+    pub fn watch_connection_state(&self) -> watchable::Watcher<ConnectionState> {
+        self.data.reconnector.watch()
+    }
+
+    /// Returns the sessions of every client connected to the server.
+    ///
+    /// Requires permission to [`ServerAction::ListSessions`](bonsaidb_core::permissions::bonsai::ServerAction::ListSessions).
+    pub async fn list_sessions(&self) -> Result<Vec<ActiveSession>, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ListSessions).await?)
+    }
+
+    /// Ends the authenticated session `session_id` on the server.
+    ///
+    /// Requires permission to [`ServerAction::TerminateSession`](bonsaidb_core::permissions::bonsai::ServerAction::TerminateSession).
+    pub async fn terminate_session(
+        &self,
+        session_id: SessionId,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&TerminateSession(session_id)).await?)
+    }
+
+    /// Closes the server's connection to the client with id `client_id`.
+    ///
+    /// Requires permission to [`ServerAction::DisconnectClient`](bonsaidb_core::permissions::bonsai::ServerAction::DisconnectClient).
+    pub async fn disconnect_client(&self, client_id: u32) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&DisconnectClient(client_id)).await?)
+    }
+
+    /// Returns a new client handle using `session`, which was created from
+    /// this handle's session.
+    fn authenticated(&self, session: Session, credentials: Option<SessionCredentials>) -> Self {
+        self.data
+            .reconnector
+            .session_created(&session, self.session.session.id, credentials);
+        Self {
+            data: self.data.clone(),
+            session: ClientSession {
+                session: Arc::new(session),
+            },
+            request_timeout: self.request_timeout,
+        }
+    }
+
+    pub(crate) fn register_subscriber(
+        &self,
+        id: u64,
+        database: &str,
+        sender: flume::Sender<Message>,
+    ) -> Arc<AtomicU64> {
+        self.data
+            .reconnector
+            .subscriber_created(id, self.session.session.id, database, sender)
+    }
+
+    pub(crate) fn subscribed(&self, id: &Arc<AtomicU64>, topic: Bytes) {
+        self.data.reconnector.subscribed(id, topic);
+    }
+
+    pub(crate) fn unsubscribed(&self, id: &Arc<AtomicU64>, topic: &[u8]) {
+        self.data.reconnector.unsubscribed(id, topic);
+    }
+
+    pub(crate) async fn unregister_subscriber_async(&self, database: String, id: Arc<AtomicU64>) {
         drop(
             self.send_api_request(&UnregisterSubscriber {
                 database,
-                subscriber_id: id,
+                subscriber_id: id.load(Ordering::SeqCst),
             })
             .await,
         );
-        let mut subscribers = self.data.subscribers.lock();
-        subscribers.remove(&id);
+        self.data.reconnector.subscriber_removed(&id);
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub(crate) fn unregister_subscriber(&self, database: String, id: u64) {
+    pub(crate) fn unregister_subscriber(&self, database: String, id: &Arc<AtomicU64>) {
         drop(self.send_blocking_api_request(&UnregisterSubscriber {
             database,
-            subscriber_id: id,
+            subscriber_id: id.load(Ordering::SeqCst),
         }));
-        let mut subscribers = self.data.subscribers.lock();
-        subscribers.remove(&id);
+        self.data.reconnector.subscriber_removed(id);
     }
 
     fn remote_database<DB: bonsaidb_core::schema::Schema>(
@@ -635,8 +725,9 @@ impl AsyncClient {
     }
 
     fn session_is_current(&self) -> bool {
-        self.session.session.id.is_none()
-            || self.data.connection_counter.load(Ordering::SeqCst) == self.session.connection_id
+        self.session.session.id.map_or(true, |id| {
+            self.data.reconnector.current_session_id(id).is_some()
+        })
     }
 
     /// Sets this instance's request timeout.
@@ -741,34 +832,48 @@ impl AsyncStorageConnection for AsyncClient {
         &self,
         authentication: bonsaidb_core::connection::Authentication,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
+        let credentials = SessionCredentials::for_authentication(&authentication);
         let session = self
             .send_api_request(&bonsaidb_core::networking::Authenticate { authentication })
             .await?;
-        Ok(Self {
-            data: self.data.clone(),
-            session: ClientSession {
-                session: Arc::new(session),
-                connection_id: self.data.connection_counter.load(Ordering::SeqCst),
-            },
-            request_timeout: self.request_timeout,
-        })
+        Ok(self.authenticated(session, credentials))
+    }
+
+    #[cfg(feature = "token-authentication")]
+    async fn authenticate_with_token(
+        &self,
+        id: u64,
+        token: &bonsaidb_core::connection::SensitiveString,
+    ) -> Result<Self, bonsaidb_core::Error> {
+        let challenge = self
+            .authenticate(bonsaidb_core::connection::Authentication::token(id, token)?)
+            .await?;
+        let session = challenge
+            .send_api_request(&bonsaidb_core::networking::Authenticate {
+                authentication: reconnect::token_challenge_response(
+                    &challenge.session.session,
+                    token,
+                )?,
+            })
+            .await?;
+        Ok(self.authenticated(
+            session,
+            Some(SessionCredentials::Token {
+                id,
+                token: token.clone(),
+            }),
+        ))
     }
 
     async fn assume_identity(
         &self,
         identity: IdentityReference<'_>,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
+        let identity = identity.into_owned();
         let session = self
-            .send_api_request(&AssumeIdentity(identity.into_owned()))
+            .send_api_request(&AssumeIdentity(identity.clone()))
             .await?;
-        Ok(Self {
-            data: self.data.clone(),
-            session: ClientSession {
-                session: Arc::new(session),
-                connection_id: self.data.connection_counter.load(Ordering::SeqCst),
-            },
-            request_timeout: self.request_timeout,
-        })
+        Ok(self.authenticated(session, Some(SessionCredentials::AssumeIdentity(identity))))
     }
 
     async fn add_permission_group_to_user<
@@ -846,6 +951,118 @@ impl AsyncStorageConnection for AsyncClient {
         .await?;
         Ok(())
     }
+
+    async fn create_permission_group(
+        &self,
+        group: bonsaidb_core::admin::PermissionGroup,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&CreatePermissionGroup { group })
+            .await?)
+    }
+
+    async fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: bonsaidb_core::admin::PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&UpdatePermissionGroup {
+                group: permission_group.name()?.into_owned(),
+                updated,
+            })
+            .await?)
+    }
+
+    async fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&DeletePermissionGroup {
+                group: permission_group.name()?.into_owned(),
+            })
+            .await?)
+    }
+
+    async fn create_role(
+        &self,
+        role: bonsaidb_core::admin::Role,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&CreateRole { role }).await?)
+    }
+
+    async fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: bonsaidb_core::admin::Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&UpdateRole {
+                role: role.name()?.into_owned(),
+                updated,
+            })
+            .await?)
+    }
+
+    async fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&DeleteRole {
+                role: role.name()?.into_owned(),
+            })
+            .await?)
+    }
+
+    async fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<bonsaidb_core::admin::AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&ListAuthenticationTokens {
+                user: user.name()?.into_owned(),
+            })
+            .await?)
+    }
+
+    #[cfg(feature = "token-authentication")]
+    async fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: bonsaidb_core::admin::AuthenticationTokenOptions,
+    ) -> Result<bonsaidb_core::admin::CreatedAuthenticationToken, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&bonsaidb_core::networking::CreateAuthenticationToken {
+                identity: identity.into_owned(),
+                options,
+            })
+            .await?)
+    }
+
+    async fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&RevokeAuthenticationToken { token_id })
+            .await?)
+    }
+
+    async fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&ExplainPermission {
+                identity: identity.into_owned(),
+                resource_name,
+                action,
+                client,
+            })
+            .await?)
+    }
 }
 
 type OutstandingRequestMap = HashMap<u32, PendingRequest>;
@@ -970,7 +1187,6 @@ impl<Api: api::Api> AnyApiCallback for ApiCallback<Api> {
 #[derive(Debug, Clone, Default)]
 pub struct ClientSession {
     session: Arc<Session>,
-    connection_id: u32,
 }
 
 async fn disconnect_pending_requests(
@@ -989,7 +1205,7 @@ async fn disconnect_pending_requests(
 
 struct ConnectionInfo {
     pub url: Url,
-    pub subscribers: SubscriberMap,
+    pub reconnector: Arc<Reconnector>,
     #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
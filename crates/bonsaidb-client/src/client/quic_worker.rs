@@ -1,97 +1,119 @@
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 
 use bonsaidb_core::api::ApiName;
 use bonsaidb_core::networking::Payload;
 use bonsaidb_utils::fast_async_lock;
-use fabruic::{self, Certificate, Endpoint};
+use fabruic::{self, Certificate, Endpoint, KeyPair};
 use flume::Receiver;
 use futures::StreamExt;
 use url::Url;
 
+use super::reconnect::{reconnecting_loop, ConnectionState, Disconnection, Reconnector};
 use super::PendingRequest;
 use crate::client::{
     disconnect_pending_requests, AnyApiCallback, ConnectionInfo, OutstandingRequestMapHandle,
 };
 use crate::Error;
 
-/// This function will establish a connection and try to keep it active. If an
-/// error occurs, any queries that come in while reconnecting will have the
-/// error replayed to them.
+/// This function will establish a connection and try to keep it active. If the
+/// connection is lost, it will reconnect and restore the client's sessions and
+/// subscribers. If reconnecting fails, any queries that come in afterwards
+/// will have the error replayed to them.
 pub(super) async fn reconnecting_client_loop(
     mut server: ConnectionInfo,
     protocol_version: &'static str,
     certificate: Option<Certificate>,
+    client_key_pair: Option<KeyPair>,
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
-    connection_counter: Arc<AtomicU32>,
 ) -> Result<(), Error> {
     if server.url.port().is_none() && server.url.scheme() == "bonsaidb" {
         let _: Result<_, _> = server.url.set_port(Some(5645));
     }
 
-    server.subscribers.clear();
-    let mut pending_error = None;
-    while let Ok(request) = request_receiver.recv_async().await {
-        if let Some(pending_error) = pending_error.take() {
-            drop(request.responder.send(Err(pending_error)));
-            continue;
-        }
-        connection_counter.fetch_add(1, Ordering::SeqCst);
-        if let Err((failed_request, Some(err))) = connect_and_process(
-            &server.url,
+    reconnecting_loop(&server.reconnector, &request_receiver, |initial_request| {
+        connect_and_process(
+            &server,
             protocol_version,
             certificate.as_ref(),
-            request,
+            client_key_pair.as_ref(),
+            initial_request,
             &request_receiver,
             custom_apis.clone(),
-            server.connect_timeout,
         )
-        .await
-        {
-            if let Some(failed_request) = failed_request {
-                drop(failed_request.responder.send(Err(err)));
-            } else {
-                pending_error = Some(err);
-            }
-        }
-    }
+    })
+    .await;
 
     Ok(())
 }
 
 async fn connect_and_process(
-    url: &Url,
+    server: &ConnectionInfo,
     protocol_version: &str,
     certificate: Option<&Certificate>,
-    initial_request: PendingRequest,
+    client_key_pair: Option<&KeyPair>,
+    initial_request: Option<PendingRequest>,
     request_receiver: &Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
-    connect_timeout: Duration,
-) -> Result<(), (Option<PendingRequest>, Option<Error>)> {
-    let (_connection, payload_sender, payload_receiver) =
-        match tokio::time::timeout(connect_timeout, connect(url, certificate, protocol_version))
-            .await
-        {
-            Ok(Ok(result)) => result,
-            Ok(Err(err)) => return Err((Some(initial_request), Some(err))),
-            Err(_) => return Err((Some(initial_request), Some(Error::connect_timeout()))),
-        };
+) -> Disconnection {
+    let (_connection, payload_sender, payload_receiver) = match tokio::time::timeout(
+        server.connect_timeout,
+        connect(&server.url, certificate, client_key_pair, protocol_version),
+    )
+    .await
+    {
+        Ok(Ok(result)) => result,
+        Ok(Err(error)) => {
+            return Disconnection::Failed {
+                request: initial_request,
+                error,
+            }
+        }
+        Err(_) => {
+            return Disconnection::Failed {
+                request: initial_request,
+                error: Error::connect_timeout(),
+            }
+        }
+    };
 
     let outstanding_requests = OutstandingRequestMapHandle::default();
-    let request_processor = tokio::spawn(process(
+    let mut request_processor = tokio::spawn(process(
         outstanding_requests.clone(),
         payload_receiver,
         custom_apis,
     ));
 
-    if let Err(err) = payload_sender.send(&initial_request.request) {
-        return Err((Some(initial_request), Some(Error::from(err))));
+    // Restore the previous connection's state before sending any requests.
+    {
+        let restore_sink = futures::sink::unfold((), |(), payload: Payload| {
+            futures::future::ready(payload_sender.send(&payload).map_err(Error::from))
+        });
+        futures::pin_mut!(restore_sink);
+        tokio::select! {
+            () = server.reconnector.restore(&outstanding_requests, &mut restore_sink) => {}
+            result = &mut request_processor => {
+                let mut pending_error = match result {
+                    Ok(result) => result.err(),
+                    Err(_) => Some(Error::disconnected()),
+                };
+                disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
+                return Disconnection::Lost(pending_error);
+            }
+        }
     }
+    server.reconnector.set_state(ConnectionState::Connected);
+
+    if let Some(mut initial_request) = initial_request {
+        server.reconnector.prepare(&mut initial_request.request);
+        if let Err(err) = payload_sender.send(&initial_request.request) {
+            return Disconnection::Failed {
+                request: Some(initial_request),
+                error: Error::from(err),
+            };
+        }
 
-    {
         let mut outstanding_requests = fast_async_lock!(outstanding_requests);
         outstanding_requests.insert(
             initial_request
@@ -104,6 +126,7 @@ async fn connect_and_process(
 
     if let Err(err) = futures::try_join!(
         process_requests(
+            &server.reconnector,
             outstanding_requests.clone(),
             request_receiver,
             payload_sender
@@ -113,18 +136,20 @@ async fn connect_and_process(
         let mut pending_error = Some(err);
         // Our socket was disconnected, clear the outstanding requests before returning.
         disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
-        return Err((None, pending_error));
+        return Disconnection::Lost(pending_error);
     }
 
-    Ok(())
+    Disconnection::Lost(None)
 }
 
 async fn process_requests(
+    reconnector: &Reconnector,
     outstanding_requests: OutstandingRequestMapHandle,
     request_receiver: &Receiver<PendingRequest>,
     payload_sender: fabruic::Sender<Payload>,
 ) -> Result<(), Error> {
-    while let Ok(client_request) = request_receiver.recv_async().await {
+    while let Ok(mut client_request) = request_receiver.recv_async().await {
+        reconnector.prepare(&mut client_request.request);
         let mut outstanding_requests = fast_async_lock!(outstanding_requests);
         payload_sender.send(&client_request.request)?;
         outstanding_requests.insert(
@@ -155,6 +180,7 @@ pub async fn process(
 async fn connect(
     url: &Url,
     certificate: Option<&Certificate>,
+    client_key_pair: Option<&KeyPair>,
     protocol_version: &str,
 ) -> Result<
     (
@@ -169,6 +195,7 @@ async fn connect(
         .set_max_idle_timeout(None)
         .map_err(|err| Error::Core(bonsaidb_core::Error::other("quic", err)))?;
     endpoint.set_protocols([protocol_version.as_bytes().to_vec()]);
+    endpoint.set_client_key_pair(client_key_pair.cloned());
     let endpoint = endpoint
         .build()
         .map_err(|err| Error::Core(bonsaidb_core::Error::other("quic", err)))?;
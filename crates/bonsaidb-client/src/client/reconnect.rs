@@ -0,0 +1,703 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bonsaidb_core::api::Api;
+use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::circulate::Message;
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+use bonsaidb_core::connection::Authentication;
+#[cfg(feature = "token-authentication")]
+use bonsaidb_core::connection::SensitiveString;
+use bonsaidb_core::connection::{IdentityReference, Session, SessionId};
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+use bonsaidb_core::networking::Authenticate;
+use bonsaidb_core::networking::{AssumeIdentity, CreateSubscriber, Payload, SubscribeTo};
+use bonsaidb_utils::fast_async_lock;
+#[cfg(not(target_arch = "wasm32"))]
+use flume::Receiver;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::Future;
+use futures::{Sink, SinkExt};
+use parking_lot::Mutex;
+use watchable::{Watchable, Watcher};
+
+use super::{OutstandingRequestMapHandle, PendingRequest, SubscriberMap};
+use crate::Error;
+
+/// Controls how a client reconnects after its connection to the server is
+/// lost.
+///
+/// After an established connection is lost, the client waits `initial_delay`
+/// before reconnecting. Each failed attempt doubles the delay, up to
+/// `max_delay`. Once `max_attempts` consecutive attempts have failed, the
+/// client stops reconnecting in the background and the next request made will
+/// start a new connection attempt.
+///
+/// After reconnecting, the client re-authenticates each of its sessions that
+/// is still in use and re-registers its PubSub subscribers before sending any
+/// queued requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct ReconnectPolicy {
+    /// The delay before the first reconnection attempt.
+    pub initial_delay: Duration,
+    /// The maximum delay between reconnection attempts.
+    pub max_delay: Duration,
+    /// The maximum number of consecutive reconnection attempts. `None` will
+    /// retry indefinitely.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    /// Returns a policy that retries indefinitely, starting with a 100
+    /// millisecond delay and backing off to a maximum of 30 seconds between
+    /// attempts.
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns a policy that never reconnects in the background. Sessions
+    /// and subscribers are not restored, and the client only reconnects when
+    /// the next request is made.
+    pub const fn disabled() -> Self {
+        Self {
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            max_attempts: Some(0),
+        }
+    }
+
+    /// Sets the delay before the first reconnection attempt and returns self.
+    pub const fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Sets the maximum delay between reconnection attempts and returns self.
+    pub const fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Sets the maximum number of consecutive reconnection attempts and
+    /// returns self.
+    pub const fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Returns true if this policy reconnects after a connection is lost.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        !matches!(self.max_attempts, Some(0))
+    }
+
+    /// Returns the delay before reconnection attempt number `attempt`,
+    /// starting at 1.
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1_u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+
+    pub(crate) const fn allows_attempt(&self, attempt: u32) -> bool {
+        match self.max_attempts {
+            Some(max_attempts) => attempt <= max_attempts,
+            None => true,
+        }
+    }
+}
+
+/// The state of a client's connection to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The client is not connected. A connection will be established when the
+    /// next request is made.
+    Disconnected,
+    /// The client is establishing a connection.
+    Connecting,
+    /// The client is connected.
+    Connected,
+    /// The connection was lost, and the client is reconnecting.
+    Reconnecting {
+        /// The current reconnection attempt, starting at 1.
+        attempt: u32,
+    },
+}
+
+/// The information needed to re-establish a session on a new connection.
+#[derive(Debug, Clone)]
+pub(crate) enum SessionCredentials {
+    #[cfg(feature = "password-hashing")]
+    Password(Authentication),
+    #[cfg(feature = "token-authentication")]
+    Token {
+        id: u64,
+        token: SensitiveString,
+    },
+    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+    External(Authentication),
+    AssumeIdentity(IdentityReference<'static>),
+}
+
+impl SessionCredentials {
+    /// Returns the credentials needed to repeat `authentication`, if it can be
+    /// repeated.
+    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+    pub fn for_authentication(authentication: &Authentication) -> Option<Self> {
+        match authentication {
+            #[cfg(feature = "password-hashing")]
+            Authentication::Password { .. } => Some(Self::Password(authentication.clone())),
+            Authentication::External(_) => Some(Self::External(authentication.clone())),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RestorableSession {
+    /// The id the server assigned when this session was created. Requests are
+    /// always made using this id, and are mapped to `current` when sent.
+    original: SessionId,
+    parent: Option<SessionId>,
+    credentials: Option<SessionCredentials>,
+    /// The id of this session on the current connection, if it is valid on
+    /// the current connection.
+    current: Option<SessionId>,
+}
+
+#[derive(Debug)]
+struct RestorableSubscriber {
+    id: Arc<AtomicU64>,
+    session: Option<SessionId>,
+    database: String,
+    topics: Vec<Bytes>,
+    sender: flume::Sender<Message>,
+}
+
+/// Tracks the state needed to transparently restore a client's sessions and
+/// subscribers after reconnecting.
+#[derive(Debug)]
+pub(crate) struct Reconnector {
+    policy: ReconnectPolicy,
+    state: Watchable<ConnectionState>,
+    request_id: AtomicU32,
+    subscriber_map: SubscriberMap,
+    sessions: Mutex<Vec<RestorableSession>>,
+    subscribers: Mutex<Vec<RestorableSubscriber>>,
+}
+
+impl Reconnector {
+    pub fn new(policy: ReconnectPolicy, subscriber_map: SubscriberMap) -> Self {
+        Self {
+            policy,
+            state: Watchable::new(ConnectionState::Disconnected),
+            request_id: AtomicU32::default(),
+            subscriber_map,
+            sessions: Mutex::default(),
+            subscribers: Mutex::default(),
+        }
+    }
+
+    pub const fn policy(&self) -> &ReconnectPolicy {
+        &self.policy
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.state.read()
+    }
+
+    pub fn watch(&self) -> Watcher<ConnectionState> {
+        self.state.watch()
+    }
+
+    pub fn set_state(&self, state: ConnectionState) {
+        let _: Result<_, _> = self.state.update(state);
+    }
+
+    pub fn next_request_id(&self) -> u32 {
+        self.request_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Records a newly created `session`, which was created from the `parent`
+    /// session. If `credentials` are provided, the session will be restored
+    /// after reconnecting.
+    pub fn session_created(
+        &self,
+        session: &Session,
+        parent: Option<SessionId>,
+        credentials: Option<SessionCredentials>,
+    ) {
+        if let Some(id) = session.id {
+            let mut sessions = self.sessions.lock();
+            sessions.push(RestorableSession {
+                original: id,
+                parent,
+                credentials: credentials.filter(|_| self.policy.is_enabled()),
+                current: Some(id),
+            });
+        }
+    }
+
+    /// Forgets the session `original`, returning the session's id on the
+    /// current connection if it is still valid.
+    pub fn session_ended(&self, original: SessionId) -> Option<SessionId> {
+        let mut sessions = self.sessions.lock();
+        let index = sessions
+            .iter()
+            .position(|session| session.original == original)?;
+        sessions.remove(index).current
+    }
+
+    /// Returns the id of the session `original` on the current connection, if
+    /// it is valid on the current connection.
+    pub fn current_session_id(&self, original: SessionId) -> Option<SessionId> {
+        let sessions = self.sessions.lock();
+        sessions
+            .iter()
+            .find(|session| session.original == original)
+            .and_then(|session| session.current)
+    }
+
+    /// Updates `payload` to use the current connection's id for its session.
+    pub fn prepare(&self, payload: &mut Payload) {
+        if let Some(original) = payload.session_id {
+            if let Some(current) = self.current_session_id(original) {
+                payload.session_id = Some(current);
+            }
+        }
+    }
+
+    /// Records a newly created subscriber, returning the shared id that is
+    /// updated when the subscriber is re-registered after reconnecting.
+    pub fn subscriber_created(
+        &self,
+        id: u64,
+        session: Option<SessionId>,
+        database: &str,
+        sender: flume::Sender<Message>,
+    ) -> Arc<AtomicU64> {
+        let shared_id = Arc::new(AtomicU64::new(id));
+        self.subscriber_map.lock().insert(id, sender.clone());
+        let mut subscribers = self.subscribers.lock();
+        subscribers.push(RestorableSubscriber {
+            id: shared_id.clone(),
+            session,
+            database: database.to_string(),
+            topics: Vec::new(),
+            sender,
+        });
+        shared_id
+    }
+
+    pub fn subscribed(&self, id: &Arc<AtomicU64>, topic: Bytes) {
+        let mut subscribers = self.subscribers.lock();
+        if let Some(subscriber) = subscribers
+            .iter_mut()
+            .find(|subscriber| Arc::ptr_eq(&subscriber.id, id))
+        {
+            if !subscriber.topics.contains(&topic) {
+                subscriber.topics.push(topic);
+            }
+        }
+    }
+
+    pub fn unsubscribed(&self, id: &Arc<AtomicU64>, topic: &[u8]) {
+        let mut subscribers = self.subscribers.lock();
+        if let Some(subscriber) = subscribers
+            .iter_mut()
+            .find(|subscriber| Arc::ptr_eq(&subscriber.id, id))
+        {
+            subscriber.topics.retain(|existing| &existing[..] != topic);
+        }
+    }
+
+    pub fn subscriber_removed(&self, id: &Arc<AtomicU64>) {
+        self.subscriber_map
+            .lock()
+            .remove(&id.load(Ordering::SeqCst));
+        let mut subscribers = self.subscribers.lock();
+        subscribers.retain(|subscriber| !Arc::ptr_eq(&subscriber.id, id));
+    }
+
+    /// Invalidates all sessions and subscribers after a connection is lost.
+    /// Returns true if the client should reconnect.
+    pub fn connection_lost(&self) -> bool {
+        self.subscriber_map.clear();
+        if self.policy.is_enabled() {
+            let mut sessions = self.sessions.lock();
+            for session in sessions.iter_mut() {
+                session.current = None;
+            }
+            true
+        } else {
+            self.forget_all();
+            self.set_state(ConnectionState::Disconnected);
+            false
+        }
+    }
+
+    /// Gives up on restoring any sessions or subscribers after all
+    /// reconnection attempts have failed.
+    pub fn reconnect_failed(&self) {
+        self.forget_all();
+        self.set_state(ConnectionState::Disconnected);
+    }
+
+    fn forget_all(&self) {
+        self.subscriber_map.clear();
+        self.sessions.lock().clear();
+        self.subscribers.lock().clear();
+    }
+
+    /// Re-authenticates all restorable sessions and re-registers all
+    /// subscribers using a newly established connection. Requests are sent
+    /// using `sink`, and their responses must be delivered through
+    /// `outstanding_requests`.
+    ///
+    /// Failures are logged rather than returned: a session that cannot be
+    /// restored behaves the same as a session that was lost before automatic
+    /// reconnection was supported.
+    pub async fn restore<S>(&self, outstanding_requests: &OutstandingRequestMapHandle, sink: &mut S)
+    where
+        S: Sink<Payload, Error = Error> + Unpin,
+    {
+        let sessions = {
+            let sessions = self.sessions.lock();
+            sessions
+                .iter()
+                .filter_map(|session| {
+                    session
+                        .credentials
+                        .clone()
+                        .map(|credentials| (session.original, session.parent, credentials))
+                })
+                .collect::<Vec<_>>()
+        };
+        for (original, parent, credentials) in sessions {
+            let parent = match parent {
+                Some(parent) => match self.current_session_id(parent) {
+                    Some(current) => Some(current),
+                    None => {
+                        log::warn!("unable to restore session: parent session was not restored");
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            match self
+                .restore_session(parent, credentials, outstanding_requests, sink)
+                .await
+            {
+                Ok(session) => {
+                    let mut sessions = self.sessions.lock();
+                    if let Some(restored) = sessions
+                        .iter_mut()
+                        .find(|session| session.original == original)
+                    {
+                        restored.current = session.id;
+                    }
+                }
+                Err(err) => log::warn!("unable to restore session: {err}"),
+            }
+        }
+
+        let subscribers = {
+            let subscribers = self.subscribers.lock();
+            subscribers
+                .iter()
+                .map(|subscriber| {
+                    (
+                        subscriber.id.clone(),
+                        subscriber.session,
+                        subscriber.database.clone(),
+                        subscriber.topics.clone(),
+                        subscriber.sender.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        for (id, session, database, topics, sender) in subscribers {
+            let session = match session {
+                Some(session) => match self.current_session_id(session) {
+                    Some(current) => Some(current),
+                    None => continue,
+                },
+                None => None,
+            };
+            let subscriber_id = match self
+                .request(
+                    session,
+                    &CreateSubscriber {
+                        database: database.clone(),
+                    },
+                    outstanding_requests,
+                    sink,
+                )
+                .await
+            {
+                Ok(subscriber_id) => subscriber_id,
+                Err(err) => {
+                    log::warn!("unable to restore subscriber: {err}");
+                    continue;
+                }
+            };
+            id.store(subscriber_id, Ordering::SeqCst);
+            self.subscriber_map.lock().insert(subscriber_id, sender);
+
+            for topic in topics {
+                if let Err(err) = self
+                    .request(
+                        session,
+                        &SubscribeTo {
+                            database: database.clone(),
+                            subscriber_id,
+                            topic,
+                        },
+                        outstanding_requests,
+                        sink,
+                    )
+                    .await
+                {
+                    log::warn!("unable to restore subscription: {err}");
+                }
+            }
+        }
+    }
+
+    async fn restore_session<S>(
+        &self,
+        parent: Option<SessionId>,
+        credentials: SessionCredentials,
+        outstanding_requests: &OutstandingRequestMapHandle,
+        sink: &mut S,
+    ) -> Result<Session, Error>
+    where
+        S: Sink<Payload, Error = Error> + Unpin,
+    {
+        match credentials {
+            #[cfg(feature = "password-hashing")]
+            SessionCredentials::Password(authentication) => {
+                self.request(
+                    parent,
+                    &Authenticate { authentication },
+                    outstanding_requests,
+                    sink,
+                )
+                .await
+            }
+            #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+            SessionCredentials::External(authentication) => {
+                self.request(
+                    parent,
+                    &Authenticate { authentication },
+                    outstanding_requests,
+                    sink,
+                )
+                .await
+            }
+            #[cfg(feature = "token-authentication")]
+            SessionCredentials::Token { id, token } => {
+                let challenge = self
+                    .request(
+                        parent,
+                        &Authenticate {
+                            authentication: Authentication::token(id, &token)?,
+                        },
+                        outstanding_requests,
+                        sink,
+                    )
+                    .await?;
+                let session = self
+                    .request(
+                        challenge.id,
+                        &Authenticate {
+                            authentication: token_challenge_response(&challenge, &token)?,
+                        },
+                        outstanding_requests,
+                        sink,
+                    )
+                    .await;
+                if let Some(challenge_id) = challenge.id {
+                    drop(
+                        self.request(
+                            challenge.id,
+                            &bonsaidb_core::networking::LogOutSession(challenge_id),
+                            outstanding_requests,
+                            sink,
+                        )
+                        .await,
+                    );
+                }
+                session
+            }
+            SessionCredentials::AssumeIdentity(identity) => {
+                self.request(
+                    parent,
+                    &AssumeIdentity(identity),
+                    outstanding_requests,
+                    sink,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn request<A, S>(
+        &self,
+        session_id: Option<SessionId>,
+        request: &A,
+        outstanding_requests: &OutstandingRequestMapHandle,
+        sink: &mut S,
+    ) -> Result<A::Response, Error>
+    where
+        A: Api<Error = bonsaidb_core::Error>,
+        S: Sink<Payload, Error = Error> + Unpin,
+    {
+        let id = self.next_request_id();
+        let payload = Payload {
+            session_id,
+            id: Some(id),
+            name: A::name(),
+            value: Ok(Bytes::from(pot::to_vec(request)?)),
+        };
+        let (responder, response) = flume::bounded(1);
+        fast_async_lock!(outstanding_requests).insert(
+            id,
+            PendingRequest {
+                request: payload.clone(),
+                responder,
+            },
+        );
+        sink.send(payload).await?;
+
+        let response = response.recv_async().await??;
+        pot::from_slice::<Result<A::Response, A::Error>>(&response)?.map_err(Error::from)
+    }
+}
+
+/// Computes the response to the token authentication challenge in `challenge`.
+#[cfg(feature = "token-authentication")]
+pub(crate) fn token_challenge_response(
+    challenge: &Session,
+    token: &SensitiveString,
+) -> Result<Authentication, bonsaidb_core::Error> {
+    use bonsaidb_core::connection::{SessionAuthentication, TokenChallengeAlgorithm};
+
+    match &challenge.authentication {
+        SessionAuthentication::TokenChallenge {
+            algorithm: TokenChallengeAlgorithm::Blake3,
+            nonce,
+            server_timestamp,
+            ..
+        } => {
+            let response =
+                bonsaidb_core::admin::AuthenticationToken::compute_challenge_response_blake3(
+                    token,
+                    nonce,
+                    *server_timestamp,
+                );
+            Ok(Authentication::TokenChallengeResponse(Bytes::from(
+                response.as_bytes().to_vec(),
+            )))
+        }
+        _ => Err(bonsaidb_core::Error::InvalidCredentials),
+    }
+}
+
+/// The reason a call to a worker's `connect_and_process` function returned.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) enum Disconnection {
+    /// A connection could not be established.
+    Failed {
+        /// The request that initiated the connection, if any.
+        request: Option<PendingRequest>,
+        error: Error,
+    },
+    /// An established connection was lost. The error has not been reported
+    /// to any requests.
+    Lost(Option<Error>),
+}
+
+/// Drives `connect_and_process` until `request_receiver` is disconnected.
+///
+/// When idle, a connection is made once a request is received. If an
+/// established connection is lost, the client reconnects using the
+/// [`ReconnectPolicy`]. If reconnecting is disabled or every attempt fails, the
+/// error is reported to the next request received.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn reconnecting_loop<C, F>(
+    reconnector: &Reconnector,
+    request_receiver: &Receiver<PendingRequest>,
+    mut connect_and_process: C,
+) where
+    C: FnMut(Option<PendingRequest>) -> F,
+    F: Future<Output = Disconnection>,
+{
+    let mut pending_error = None;
+    let mut reconnect_attempt = None;
+    loop {
+        let initial_request = if let Some(attempt) = reconnect_attempt {
+            if request_receiver.is_disconnected() {
+                break;
+            }
+            reconnector.set_state(ConnectionState::Reconnecting { attempt });
+            tokio::time::sleep(reconnector.policy().delay(attempt)).await;
+            None
+        } else {
+            reconnector.set_state(ConnectionState::Disconnected);
+            let Ok(request) = request_receiver.recv_async().await else {
+                break;
+            };
+            if let Some(pending_error) = pending_error.take() {
+                drop(request.responder.send(Err(pending_error)));
+                continue;
+            }
+            reconnector.set_state(ConnectionState::Connecting);
+            Some(request)
+        };
+
+        match connect_and_process(initial_request).await {
+            Disconnection::Failed {
+                request: Some(request),
+                error,
+            } => {
+                drop(request.responder.send(Err(error)));
+            }
+            Disconnection::Failed {
+                request: None,
+                error,
+            } => {
+                let attempt = reconnect_attempt.unwrap_or_default() + 1;
+                if reconnector.policy().allows_attempt(attempt) {
+                    reconnect_attempt = Some(attempt);
+                } else {
+                    log::error!("unable to reconnect: {error}");
+                    reconnector.reconnect_failed();
+                    reconnect_attempt = None;
+                    pending_error = Some(error);
+                }
+            }
+            Disconnection::Lost(error) => {
+                if reconnector.connection_lost() {
+                    reconnect_attempt = Some(1);
+                } else {
+                    reconnect_attempt = None;
+                    pending_error = error;
+                }
+            }
+        }
+    }
+}
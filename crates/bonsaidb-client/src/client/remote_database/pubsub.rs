@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -22,11 +23,13 @@ impl AsyncPubSub for super::AsyncRemoteDatabase {
             .await?;
 
         let (sender, receiver) = flume::unbounded();
-        self.client.register_subscriber(subscriber_id, sender);
+        let id = self
+            .client
+            .register_subscriber(subscriber_id, &self.name, sender);
         Ok(AsyncRemoteSubscriber {
             client: self.client.clone(),
             database: self.name.clone(),
-            id: subscriber_id,
+            id,
             receiver: Receiver::new(receiver),
             #[cfg(not(target_arch = "wasm32"))]
             tokio: tokio::runtime::Handle::try_current().ok().map(Arc::new),
@@ -70,22 +73,32 @@ impl AsyncPubSub for super::AsyncRemoteDatabase {
 pub struct AsyncRemoteSubscriber {
     pub(crate) client: AsyncClient,
     pub(crate) database: Arc<String>,
-    pub(crate) id: u64,
+    /// The subscriber's id on the current connection. It changes when the
+    /// subscriber is re-registered after reconnecting.
+    pub(crate) id: Arc<AtomicU64>,
     pub(crate) receiver: Receiver,
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) tokio: Option<Arc<tokio::runtime::Handle>>,
 }
 
+impl AsyncRemoteSubscriber {
+    pub(crate) fn id(&self) -> u64 {
+        self.id.load(Ordering::SeqCst)
+    }
+}
+
 #[async_trait]
 impl AsyncSubscriber for AsyncRemoteSubscriber {
     async fn subscribe_to_bytes(&self, topic: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        let topic = Bytes::from(topic);
         self.client
             .send_api_request(&SubscribeTo {
                 database: self.database.to_string(),
-                subscriber_id: self.id,
-                topic: Bytes::from(topic),
+                subscriber_id: self.id(),
+                topic: topic.clone(),
             })
             .await?;
+        self.client.subscribed(&self.id, topic);
         Ok(())
     }
 
@@ -93,10 +106,11 @@ impl AsyncSubscriber for AsyncRemoteSubscriber {
         self.client
             .send_api_request(&UnsubscribeFrom {
                 database: self.database.to_string(),
-                subscriber_id: self.id,
+                subscriber_id: self.id(),
                 topic: Bytes::from(topic),
             })
             .await?;
+        self.client.unsubscribed(&self.id, topic);
         Ok(())
     }
 
@@ -110,7 +124,7 @@ impl Drop for AsyncRemoteSubscriber {
     fn drop(&mut self) {
         let client = self.client.clone();
         let database = self.database.to_string();
-        let subscriber_id = self.id;
+        let subscriber_id = self.id.clone();
         let drop_future = async move {
             client
                 .unregister_subscriber_async(database, subscriber_id)
@@ -126,7 +140,7 @@ impl Drop for AsyncRemoteSubscriber {
         if let Some(tokio) = &self.tokio {
             let client = self.client.clone();
             let database = self.database.to_string();
-            let subscriber_id = self.id;
+            let subscriber_id = self.id.clone();
             tokio.spawn(async move {
                 client
                     .unregister_subscriber_async(database, subscriber_id)
@@ -134,7 +148,7 @@ impl Drop for AsyncRemoteSubscriber {
             });
         } else {
             self.client
-                .unregister_subscriber(self.database.to_string(), self.id);
+                .unregister_subscriber(self.database.to_string(), &self.id);
         }
     }
 }
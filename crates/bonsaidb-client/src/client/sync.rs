@@ -1,6 +1,4 @@
 use std::collections::HashMap;
-use std::sync::atomic::Ordering;
-use std::sync::Arc;
 use std::time::Duration;
 
 use bonsaidb_core::admin::{Admin, ADMIN_DATABASE_NAME};
@@ -8,18 +6,22 @@ use bonsaidb_core::api;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{
     AccessPolicy, Connection, Database, HasSchema, HasSession, IdentityReference,
-    LowLevelConnection, Range, SerializedQueryKey, Sort, StorageConnection,
+    LowLevelConnection, Range, SerializedQueryKey, SessionId, Sort, StorageConnection,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::KeyValue;
 use bonsaidb_core::networking::{
-    AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction, AssumeIdentity,
-    Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreateSubscriber,
-    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get, GetMultiple,
-    LastTransactionId, List, ListAvailableSchemas, ListDatabases, ListExecutedTransactions,
-    ListHeaders, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped, SubscribeTo,
-    UnsubscribeFrom, CURRENT_PROTOCOL_VERSION,
+    ActiveSession, AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction,
+    AssumeIdentity, Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase,
+    CreatePermissionGroup, CreateRole, CreateSubscriber, CreateUser, DeleteDatabase, DeleteDocs,
+    DeletePermissionGroup, DeleteRole, DeleteUser, DisconnectClient, ExecuteKeyOperation,
+    ExplainPermission, Get, GetMultiple, LastTransactionId, List, ListAuthenticationTokens,
+    ListAvailableSchemas, ListDatabases, ListExecutedTransactions, ListHeaders, ListSessions,
+    Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped, RevokeAuthenticationToken,
+    SubscribeTo, TerminateSession, UnsubscribeFrom, UpdatePermissionGroup, UpdateRole,
+    CURRENT_PROTOCOL_VERSION,
 };
+use bonsaidb_core::permissions::{ActionName, ClientContext, PermissionExplanation, ResourceName};
 use bonsaidb_core::pubsub::{AsyncSubscriber, PubSub, Receiver, Subscriber};
 use bonsaidb_core::schema::view::map;
 use bonsaidb_core::schema::{CollectionName, ViewName};
@@ -30,7 +32,8 @@ use tokio::task::JoinHandle;
 use url::Url;
 
 use crate::builder::Blocking;
-use crate::client::ClientSession;
+use crate::client::reconnect::SessionCredentials;
+use crate::client::ReconnectPolicy;
 use crate::{ApiError, AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber, Builder, Error};
 
 /// A BonsaiDb client that blocks the current thread when performing requests.
@@ -51,7 +54,8 @@ impl BlockingClient {
     /// present that error. If the client disconnects while processing requests,
     /// all requests being processed will exit and return
     /// [`Error::Disconnected`](bonsaidb_core::networking::Error::Disconnected).
-    /// The client will automatically try reconnecting.
+    /// The client will automatically reconnect using the default
+    /// [`ReconnectPolicy`].
     ///
     /// The goal of this design of this reconnection strategy is to make it
     /// easier to build resilliant apps. By allowing existing Client instances
@@ -65,6 +69,9 @@ impl BlockingClient {
             HashMap::default(),
             None,
             None,
+            ReconnectPolicy::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            None,
             #[cfg(not(target_arch = "wasm32"))]
             None,
             #[cfg(not(target_arch = "wasm32"))]
@@ -96,6 +103,27 @@ impl BlockingClient {
         &self.0
     }
 
+    /// Returns the sessions of every client connected to the server.
+    ///
+    /// Requires permission to [`ServerAction::ListSessions`](bonsaidb_core::permissions::bonsai::ServerAction::ListSessions).
+    pub fn list_sessions(&self) -> Result<Vec<ActiveSession>, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ListSessions)?)
+    }
+
+    /// Ends the authenticated session `session_id` on the server.
+    ///
+    /// Requires permission to [`ServerAction::TerminateSession`](bonsaidb_core::permissions::bonsai::ServerAction::TerminateSession).
+    pub fn terminate_session(&self, session_id: SessionId) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&TerminateSession(session_id))?)
+    }
+
+    /// Closes the server's connection to the client with id `client_id`.
+    ///
+    /// Requires permission to [`ServerAction::DisconnectClient`](bonsaidb_core::permissions::bonsai::ServerAction::DisconnectClient).
+    pub fn disconnect_client(&self, client_id: u32) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&DisconnectClient(client_id))?)
+    }
+
     /// Sets this instance's request timeout.
     ///
     /// Each client has its own timeout. When cloning a client, this timeout
@@ -207,31 +235,45 @@ impl StorageConnection for BlockingClient {
         &self,
         authentication: bonsaidb_core::connection::Authentication,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
+        let credentials = SessionCredentials::for_authentication(&authentication);
         let session =
             self.send_api_request(&bonsaidb_core::networking::Authenticate { authentication })?;
-        Ok(Self(AsyncClient {
-            data: self.0.data.clone(),
-            session: ClientSession {
-                session: Arc::new(session),
-                connection_id: self.0.data.connection_counter.load(Ordering::SeqCst),
-            },
-            request_timeout: self.0.request_timeout,
-        }))
+        Ok(Self(self.0.authenticated(session, credentials)))
+    }
+
+    #[cfg(feature = "token-authentication")]
+    fn authenticate_with_token(
+        &self,
+        id: u64,
+        token: &bonsaidb_core::connection::SensitiveString,
+    ) -> Result<Self, bonsaidb_core::Error> {
+        let challenge =
+            self.authenticate(bonsaidb_core::connection::Authentication::token(id, token)?)?;
+        let session = challenge.send_api_request(&bonsaidb_core::networking::Authenticate {
+            authentication: crate::client::reconnect::token_challenge_response(
+                &challenge.0.session.session,
+                token,
+            )?,
+        })?;
+        Ok(Self(self.0.authenticated(
+            session,
+            Some(SessionCredentials::Token {
+                id,
+                token: token.clone(),
+            }),
+        )))
     }
 
     fn assume_identity(
         &self,
         identity: IdentityReference<'_>,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
-        let session = self.send_api_request(&AssumeIdentity(identity.into_owned()))?;
-        Ok(Self(AsyncClient {
-            data: self.0.data.clone(),
-            session: ClientSession {
-                session: Arc::new(session),
-                connection_id: self.0.data.connection_counter.load(Ordering::SeqCst),
-            },
-            request_timeout: self.0.request_timeout,
-        }))
+        let identity = identity.into_owned();
+        let session = self.send_api_request(&AssumeIdentity(identity.clone()))?;
+        Ok(Self(self.0.authenticated(
+            session,
+            Some(SessionCredentials::AssumeIdentity(identity)),
+        )))
     }
 
     fn add_permission_group_to_user<
@@ -305,6 +347,105 @@ impl StorageConnection for BlockingClient {
         })?;
         Ok(())
     }
+
+    fn create_permission_group(
+        &self,
+        group: bonsaidb_core::admin::PermissionGroup,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&CreatePermissionGroup { group })?)
+    }
+
+    fn update_permission_group<
+        'group,
+        G: bonsaidb_core::schema::Nameable<'group, u64> + Send + Sync,
+    >(
+        &self,
+        permission_group: G,
+        updated: bonsaidb_core::admin::PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&UpdatePermissionGroup {
+            group: permission_group.name()?.into_owned(),
+            updated,
+        })?)
+    }
+
+    fn delete_permission_group<
+        'group,
+        G: bonsaidb_core::schema::Nameable<'group, u64> + Send + Sync,
+    >(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&DeletePermissionGroup {
+            group: permission_group.name()?.into_owned(),
+        })?)
+    }
+
+    fn create_role(&self, role: bonsaidb_core::admin::Role) -> Result<u64, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&CreateRole { role })?)
+    }
+
+    fn update_role<'role, R: bonsaidb_core::schema::Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: bonsaidb_core::admin::Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&UpdateRole {
+            role: role.name()?.into_owned(),
+            updated,
+        })?)
+    }
+
+    fn delete_role<'role, R: bonsaidb_core::schema::Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&DeleteRole {
+            role: role.name()?.into_owned(),
+        })?)
+    }
+
+    fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<bonsaidb_core::admin::AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ListAuthenticationTokens {
+            user: user.name()?.into_owned(),
+        })?)
+    }
+
+    #[cfg(feature = "token-authentication")]
+    fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: bonsaidb_core::admin::AuthenticationTokenOptions,
+    ) -> Result<bonsaidb_core::admin::CreatedAuthenticationToken, bonsaidb_core::Error> {
+        use bonsaidb_core::networking::CreateAuthenticationToken;
+
+        Ok(self.send_api_request(&CreateAuthenticationToken {
+            identity: identity.into_owned(),
+            options,
+        })?)
+    }
+
+    fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        Ok(self.send_api_request(&RevokeAuthenticationToken { token_id })?)
+    }
+
+    fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ExplainPermission {
+            identity: identity.into_owned(),
+            resource_name,
+            action,
+            client,
+        })?)
+    }
 }
 
 impl HasSession for BlockingClient {
@@ -566,11 +707,14 @@ impl PubSub for BlockingRemoteDatabase {
         })?;
 
         let (sender, receiver) = flume::unbounded();
-        self.0.client.register_subscriber(subscriber_id, sender);
+        let id = self
+            .0
+            .client
+            .register_subscriber(subscriber_id, &self.0.name, sender);
         Ok(BlockingRemoteSubscriber(AsyncRemoteSubscriber {
             client: self.0.client.clone(),
             database: self.0.name.clone(),
-            id: subscriber_id,
+            id,
             receiver: Receiver::new(receiver),
             tokio: None,
         }))
@@ -607,20 +751,23 @@ pub struct BlockingRemoteSubscriber(AsyncRemoteSubscriber);
 
 impl Subscriber for BlockingRemoteSubscriber {
     fn subscribe_to_bytes(&self, topic: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        let topic = Bytes::from(topic);
         self.0.client.send_blocking_api_request(&SubscribeTo {
             database: self.0.database.to_string(),
-            subscriber_id: self.0.id,
-            topic: Bytes::from(topic),
+            subscriber_id: self.0.id(),
+            topic: topic.clone(),
         })?;
+        self.0.client.subscribed(&self.0.id, topic);
         Ok(())
     }
 
     fn unsubscribe_from_bytes(&self, topic: &[u8]) -> Result<(), bonsaidb_core::Error> {
         self.0.client.send_blocking_api_request(&UnsubscribeFrom {
             database: self.0.database.to_string(),
-            subscriber_id: self.0.id,
+            subscriber_id: self.0.id(),
             topic: Bytes::from(topic),
         })?;
+        self.0.client.unsubscribed(&self.0.id, topic);
         Ok(())
     }
 
@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 use bonsaidb_core::api::ApiName;
@@ -11,8 +10,9 @@ use futures::{SinkExt, StreamExt};
 use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::handshake::client::generate_key;
 use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
 
+use super::reconnect::{reconnecting_loop, ConnectionState, Disconnection, Reconnector};
 use super::PendingRequest;
 use crate::client::{
     disconnect_pending_requests, AnyApiCallback, ConnectionInfo, OutstandingRequestMapHandle,
@@ -22,27 +22,49 @@ use crate::Error;
 pub(super) async fn reconnecting_client_loop(
     server: ConnectionInfo,
     protocol_version: &str,
+    certificate: Option<fabruic::Certificate>,
+    client_key_pair: Option<fabruic::KeyPair>,
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
-    connection_counter: Arc<AtomicU32>,
 ) -> Result<(), Error> {
-    let mut pending_error = None;
-    while let Ok(request) = {
-        server.subscribers.clear();
-        request_receiver.recv_async().await
-    } {
-        if let Some(pending_error) = pending_error.take() {
-            drop(request.responder.send(Err(pending_error)));
-            continue;
-        }
+    reconnecting_loop(&server.reconnector, &request_receiver, |initial_request| {
+        connect_and_process(
+            &server,
+            protocol_version,
+            certificate.as_ref(),
+            client_key_pair.as_ref(),
+            initial_request,
+            &request_receiver,
+            &custom_apis,
+        )
+    })
+    .await;
 
-        connection_counter.fetch_add(1, Ordering::SeqCst);
-        let (stream, _) = match tokio::time::timeout(
-            server.connect_timeout,
-            tokio_tungstenite::connect_async(
-                tokio_tungstenite::tungstenite::handshake::client::Request::get(
-                    server.url.as_str(),
-                )
+    Ok(())
+}
+
+async fn connect_and_process(
+    server: &ConnectionInfo,
+    protocol_version: &str,
+    certificate: Option<&fabruic::Certificate>,
+    client_key_pair: Option<&fabruic::KeyPair>,
+    initial_request: Option<PendingRequest>,
+    request_receiver: &Receiver<PendingRequest>,
+    custom_apis: &HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
+) -> Disconnection {
+    let connector = match tls_connector(certificate, client_key_pair) {
+        Ok(connector) => connector,
+        Err(error) => {
+            return Disconnection::Failed {
+                request: initial_request,
+                error,
+            }
+        }
+    };
+    let (stream, _) = match tokio::time::timeout(
+        server.connect_timeout,
+        tokio_tungstenite::connect_async_tls_with_config(
+            tokio_tungstenite::tungstenite::handshake::client::Request::get(server.url.as_str())
                 .header("Sec-WebSocket-Protocol", protocol_version)
                 .header("Sec-WebSocket-Version", "13")
                 .header("Sec-WebSocket-Key", generate_key())
@@ -51,59 +73,155 @@ pub(super) async fn reconnecting_client_loop(
                 .header("Upgrade", "websocket")
                 .body(())
                 .unwrap(),
-            ),
-        )
-        .await
-        {
-            Ok(Ok(result)) => result,
-            Ok(Err(err)) => {
-                drop(request.responder.send(Err(Error::from(err))));
-                continue;
+            None,
+            false,
+            connector,
+        ),
+    )
+    .await
+    {
+        Ok(Ok(result)) => result,
+        Ok(Err(err)) => {
+            return Disconnection::Failed {
+                request: initial_request,
+                error: Error::from(err),
             }
-            Err(_) => {
-                drop(request.responder.send(Err(Error::connect_timeout())));
-                continue;
+        }
+        Err(_) => {
+            return Disconnection::Failed {
+                request: initial_request,
+                error: Error::connect_timeout(),
             }
-        };
-
-        let (mut sender, receiver) = stream.split();
-
-        let outstanding_requests = OutstandingRequestMapHandle::default();
-        {
-            let mut outstanding_requests = fast_async_lock!(outstanding_requests);
-            if let Err(err) = sender
-                .send(Message::Binary(bincode::serialize(&request.request)?))
-                .await
-            {
-                drop(request.responder.send(Err(Error::from(err))));
-                continue;
+        }
+    };
+
+    let (mut sender, receiver) = stream.split();
+
+    let outstanding_requests = OutstandingRequestMapHandle::default();
+    let responses = response_processor(receiver, outstanding_requests.clone(), custom_apis);
+    futures::pin_mut!(responses);
+
+    // Restore the previous connection's state before sending any requests.
+    {
+        let mut restore_sink = (&mut sender)
+            .sink_map_err(Error::from)
+            .with(|payload: Payload| {
+                futures::future::ready(
+                    bincode::serialize(&payload)
+                        .map(Message::Binary)
+                        .map_err(Error::from),
+                )
+            });
+        tokio::select! {
+            () = server.reconnector.restore(&outstanding_requests, &mut restore_sink) => {}
+            result = &mut responses => {
+                let mut pending_error = Some(result.err().unwrap_or_else(Error::disconnected));
+                disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
+                return Disconnection::Lost(pending_error);
             }
-            outstanding_requests.insert(
-                request.request.id.expect("all requests must have ids"),
-                request,
-            );
         }
+    }
+    server.reconnector.set_state(ConnectionState::Connected);
 
-        if let Err(err) = tokio::try_join!(
-            request_sender(&request_receiver, sender, outstanding_requests.clone()),
-            response_processor(receiver, outstanding_requests.clone(), &custom_apis,)
-        ) {
-            // Our socket was disconnected, clear the outstanding requests before returning.
-            log::error!("Error on socket {:?}", err);
-            pending_error = Some(err);
-            disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
+    if let Some(mut initial_request) = initial_request {
+        server.reconnector.prepare(&mut initial_request.request);
+        let mut outstanding_requests = fast_async_lock!(outstanding_requests);
+        match bincode::serialize(&initial_request.request) {
+            Ok(message) => {
+                if let Err(err) = sender.send(Message::Binary(message)).await {
+                    return Disconnection::Failed {
+                        request: Some(initial_request),
+                        error: Error::from(err),
+                    };
+                }
+                outstanding_requests.insert(
+                    initial_request
+                        .request
+                        .id
+                        .expect("all requests must have ids"),
+                    initial_request,
+                );
+            }
+            Err(err) => {
+                drop(initial_request.responder.send(Err(Error::from(err))));
+            }
         }
     }
 
-    Ok(())
+    if let Err(err) = tokio::try_join!(
+        request_sender(
+            &server.reconnector,
+            request_receiver,
+            sender,
+            outstanding_requests.clone()
+        ),
+        &mut responses
+    ) {
+        // Our socket was disconnected, clear the outstanding requests before returning.
+        log::error!("Error on socket {:?}", err);
+        let mut pending_error = Some(err);
+        disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
+        return Disconnection::Lost(pending_error);
+    }
+
+    Disconnection::Lost(None)
+}
+
+/// Returns a TLS connector that only trusts the pinned `certificate` and
+/// presents `client_key_pair` to the server. If neither are provided, `None`
+/// is returned and the default connector is used.
+fn tls_connector(
+    certificate: Option<&fabruic::Certificate>,
+    client_key_pair: Option<&fabruic::KeyPair>,
+) -> Result<Option<Connector>, Error> {
+    if certificate.is_none() && client_key_pair.is_none() {
+        return Ok(None);
+    }
+
+    let mut roots = rustls::RootCertStore::empty();
+    if let Some(certificate) = certificate {
+        roots
+            .add(&rustls::Certificate(certificate.as_ref().to_vec()))
+            .map_err(tls_error)?;
+    } else {
+        let native_certificates = rustls_native_certs::load_native_certs().map_err(tls_error)?;
+        roots.add_parsable_certificates(&native_certificates);
+    }
+
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+    let config = if let Some(key_pair) = client_key_pair {
+        let chain = key_pair
+            .certificate_chain()
+            .iter()
+            .map(|certificate| rustls::Certificate(certificate.as_ref().to_vec()))
+            .collect();
+        let private_key = rustls::PrivateKey(
+            fabruic::dangerous::PrivateKey::as_ref(key_pair.private_key()).to_vec(),
+        );
+        config
+            .with_client_auth_cert(chain, private_key)
+            .map_err(tls_error)?
+    } else {
+        config.with_no_client_auth()
+    };
+
+    Ok(Some(Connector::Rustls(Arc::new(config))))
+}
+
+fn tls_error(error: impl std::fmt::Display) -> Error {
+    Error::Core(bonsaidb_core::Error::other("tls", error))
 }
 
 async fn request_sender(
+    reconnector: &Reconnector,
     request_receiver: &Receiver<PendingRequest>,
     mut sender: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
     outstanding_requests: OutstandingRequestMapHandle,
 ) -> Result<(), Error> {
-    while let Ok(pending) = request_receiver.recv_async().await {
+    while let Ok(mut pending) = request_receiver.recv_async().await {
+        reconnector.prepare(&mut pending.request);
         let mut outstanding_requests = fast_async_lock!(outstanding_requests);
         sender
             .send(Message::Binary(bincode::serialize(&pending.request)?))
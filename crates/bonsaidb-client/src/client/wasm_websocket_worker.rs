@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -12,9 +12,9 @@ use wasm_bindgen::closure::Closure;
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{CloseEvent, ErrorEvent, MessageEvent, WebSocket};
 
+use super::reconnect::{ConnectionState, Reconnector};
 use crate::client::{
     disconnect_pending_requests, AnyApiCallback, OutstandingRequestMapHandle, PendingRequest,
-    SubscriberMap,
 };
 use crate::Error;
 
@@ -24,9 +24,9 @@ pub fn spawn_client(
     protocol_version: &'static str,
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
-    subscribers: SubscriberMap,
-    connection_counter: Arc<AtomicU32>,
+    reconnector: Arc<Reconnector>,
     pending_error: Option<Error>,
+    reconnect_attempt: Option<u32>,
     connect_timeout: Duration,
 ) {
     wasm_bindgen_futures::spawn_local(create_websocket(
@@ -34,9 +34,9 @@ pub fn spawn_client(
         protocol_version,
         request_receiver,
         custom_apis,
-        subscribers,
-        connection_counter,
+        reconnector,
         pending_error,
+        reconnect_attempt,
         connect_timeout,
     ));
 }
@@ -47,45 +47,58 @@ async fn create_websocket(
     protocol_version: &'static str,
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
-    subscribers: SubscriberMap,
-    connection_counter: Arc<AtomicU32>,
+    reconnector: Arc<Reconnector>,
     pending_error: Option<Error>,
+    reconnect_attempt: Option<u32>,
     connect_timeout: Duration,
 ) {
-    subscribers.clear();
-
-    // Receive the next/initial request when we are reconnecting.
-    let Ok(mut initial_request) = request_receiver.recv_async().await else {
-        return;
-    };
-    if let Some(error) = pending_error {
-        drop(initial_request.responder.send(Err(error)));
-        let Ok(next_request) = request_receiver.recv_async().await else {
+    let initial_request = if let Some(attempt) = reconnect_attempt {
+        if request_receiver.is_disconnected() {
+            return;
+        }
+        reconnector.set_state(ConnectionState::Reconnecting { attempt });
+        sleep(reconnector.policy().delay(attempt)).await;
+        None
+    } else {
+        reconnector.set_state(ConnectionState::Disconnected);
+        // Receive the next/initial request when we are reconnecting.
+        let Ok(mut initial_request) = request_receiver.recv_async().await else {
             return;
         };
-        initial_request = next_request;
-    }
+        if let Some(error) = pending_error {
+            drop(initial_request.responder.send(Err(error)));
+            let Ok(next_request) = request_receiver.recv_async().await else {
+                return;
+            };
+            initial_request = next_request;
+        }
+        reconnector.set_state(ConnectionState::Connecting);
+        Some(initial_request)
+    };
 
-    connection_counter.fetch_add(1, Ordering::SeqCst);
     // In wasm we're not going to have a real loop. We're going create a
     // websocket and store it in JS. This will allow us to get around Send/Sync
     // issues since each access of the websocket can pull it from js.
     let ws = match WebSocket::new_with_str(&url.to_string(), protocol_version) {
         Ok(ws) => ws,
         Err(err) => {
-            drop(
-                initial_request
-                    .responder
-                    .send(Err(Error::from(WebSocketError::from(err)))),
-            );
+            let error = Error::from(WebSocketError::from(err));
+            let (pending_error, reconnect_attempt) = match (initial_request, reconnect_attempt) {
+                (Some(initial_request), _) => {
+                    drop(initial_request.responder.send(Err(error)));
+                    (None, None)
+                }
+                (None, Some(attempt)) => attempt_failed(&reconnector, attempt, error),
+                (None, None) => (Some(error), None),
+            };
             spawn_client(
                 url,
                 protocol_version,
                 request_receiver,
                 custom_apis.clone(),
-                subscribers,
-                connection_counter,
-                None,
+                reconnector,
+                pending_error,
+                reconnect_attempt,
                 connect_timeout,
             );
             return;
@@ -100,14 +113,17 @@ async fn create_websocket(
         connection_request_sender,
     );
 
-    let initial_request = Arc::new(Mutex::new(Some(initial_request)));
+    let initial_request = Arc::new(Mutex::new(initial_request));
+    let opened = Arc::new(AtomicBool::new(false));
     ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
     let outstanding_requests = OutstandingRequestMapHandle::default();
 
     let onopen_callback = on_open_callback(
         connection_request_receiver,
         initial_request.clone(),
+        opened.clone(),
         outstanding_requests.clone(),
+        reconnector.clone(),
         ws.clone(),
     );
     ws.set_onopen(Some(onopen_callback.as_ref().unchecked_ref()));
@@ -115,15 +131,24 @@ async fn create_websocket(
     let onmessage_callback = on_message_callback(outstanding_requests.clone(), custom_apis.clone());
     ws.set_onmessage(Some(onmessage_callback.as_ref().unchecked_ref()));
 
-    let onerror_callback =
-        on_error_callback(ws.clone(), initial_request.clone(), shutdown_sender.clone());
+    let onerror_callback = on_error_callback(
+        ws.clone(),
+        initial_request.clone(),
+        opened.clone(),
+        shutdown_sender.clone(),
+    );
     ws.set_onerror(Some(onerror_callback.as_ref().unchecked_ref()));
 
     if let Some(window) = web_sys::window() {
         let _: Result<_, _> = window.set_timeout_with_callback_and_timeout_and_arguments_0(
-            connect_timeout_callback(ws.clone(), initial_request.clone(), shutdown_sender.clone())
-                .as_ref()
-                .unchecked_ref(),
+            connect_timeout_callback(
+                ws.clone(),
+                initial_request.clone(),
+                opened.clone(),
+                shutdown_sender.clone(),
+            )
+            .as_ref()
+            .unchecked_ref(),
             connect_timeout.as_millis().try_into().unwrap_or(i32::MAX),
         );
     }
@@ -135,15 +160,47 @@ async fn create_websocket(
         shutdown_sender,
         ws.clone(),
         initial_request,
+        opened,
+        reconnect_attempt,
         outstanding_requests,
         custom_apis.clone(),
-        subscribers.clone(),
-        connection_counter.clone(),
+        reconnector,
         connect_timeout,
     );
     ws.set_onclose(Some(onclose_callback.as_ref().unchecked_ref()));
 }
 
+/// Returns the pending error and next reconnection attempt after reconnection
+/// attempt `attempt` failed with `error`.
+fn attempt_failed(
+    reconnector: &Reconnector,
+    attempt: u32,
+    error: Error,
+) -> (Option<Error>, Option<u32>) {
+    if reconnector.policy().allows_attempt(attempt + 1) {
+        (None, Some(attempt + 1))
+    } else {
+        log::error!("unable to reconnect: {error}");
+        reconnector.reconnect_failed();
+        (Some(error), None)
+    }
+}
+
+#[allow(clippy::future_not_send)]
+async fn sleep(duration: Duration) {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    if let Some(window) = web_sys::window() {
+        let callback = Closure::once_into_js(move || {
+            let _: Result<_, _> = sender.send(());
+        });
+        let _: Result<_, _> = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            callback.as_ref().unchecked_ref(),
+            duration.as_millis().try_into().unwrap_or(i32::MAX),
+        );
+    }
+    let _: Result<_, _> = receiver.await;
+}
+
 #[allow(clippy::mut_mut)] // futures::select!
 fn forward_request_with_shutdown(
     request_receiver: flume::Receiver<PendingRequest>,
@@ -172,17 +229,43 @@ fn forward_request_with_shutdown(
 fn on_open_callback(
     request_receiver: Receiver<PendingRequest>,
     initial_request: Arc<Mutex<Option<PendingRequest>>>,
+    opened: Arc<AtomicBool>,
     requests: OutstandingRequestMapHandle,
+    reconnector: Arc<Reconnector>,
     ws: WebSocket,
 ) -> JsValue {
     Closure::once_into_js(move || {
+        opened.store(true, Ordering::SeqCst);
+        let initial_request = take_initial_request(&initial_request);
         wasm_bindgen_futures::spawn_local(async move {
-            if let Some(initial_request) = take_initial_request(&initial_request) {
-                if send_request(&ws, initial_request, &requests).await {
-                    while let Ok(pending) = request_receiver.recv_async().await {
-                        if !send_request(&ws, pending, &requests).await {
-                            break;
-                        }
+            // Restore the previous connection's state before sending any
+            // requests.
+            {
+                let restore_sink = futures::sink::unfold((), |(), payload: Payload| {
+                    futures::future::ready(
+                        bincode::serialize(&payload)
+                            .map_err(Error::from)
+                            .and_then(|bytes| {
+                                ws.send_with_u8_array(&bytes)
+                                    .map_err(|err| Error::from(WebSocketError::from(err)))
+                            }),
+                    )
+                });
+                futures::pin_mut!(restore_sink);
+                reconnector.restore(&requests, &mut restore_sink).await;
+            }
+            reconnector.set_state(ConnectionState::Connected);
+
+            let connected = match initial_request {
+                Some(initial_request) => {
+                    send_request(&ws, &reconnector, initial_request, &requests).await
+                }
+                None => true,
+            };
+            if connected {
+                while let Ok(pending) = request_receiver.recv_async().await {
+                    if !send_request(&ws, &reconnector, pending, &requests).await {
+                        break;
                     }
                 }
             }
@@ -196,9 +279,11 @@ fn on_open_callback(
 #[allow(clippy::future_not_send)]
 async fn send_request(
     ws: &WebSocket,
-    pending: PendingRequest,
+    reconnector: &Reconnector,
+    mut pending: PendingRequest,
     requests: &OutstandingRequestMapHandle,
 ) -> bool {
+    reconnector.prepare(&mut pending.request);
     let mut outstanding_requests = fast_async_lock!(requests);
     let bytes = match bincode::serialize(&pending.request) {
         Ok(bytes) => bytes,
@@ -259,19 +344,22 @@ fn on_message_callback(
 fn connect_timeout_callback(
     ws: WebSocket,
     initial_request: Arc<Mutex<Option<PendingRequest>>>,
+    opened: Arc<AtomicBool>,
     shutdown: flume::Sender<()>,
 ) -> JsValue {
     Closure::once_into_js(move || {
-        // We only want to treat this as a timeout if the initial request hasn't
-        // been processed yet.
-        if let Some(initial_request) = take_initial_request(&initial_request) {
+        // We only want to treat this as a timeout if the connection hasn't
+        // been opened yet.
+        if !opened.load(Ordering::SeqCst) {
             ws.set_onerror(None);
             let _: Result<_, _> = shutdown.send(());
-            drop(
-                initial_request
-                    .responder
-                    .send(Err(Error::connect_timeout())),
-            );
+            if let Some(initial_request) = take_initial_request(&initial_request) {
+                drop(
+                    initial_request
+                        .responder
+                        .send(Err(Error::connect_timeout())),
+                );
+            }
             ws.close().unwrap();
         }
     })
@@ -280,12 +368,15 @@ fn connect_timeout_callback(
 fn on_error_callback(
     ws: WebSocket,
     initial_request: Arc<Mutex<Option<PendingRequest>>>,
+    opened: Arc<AtomicBool>,
     shutdown: flume::Sender<()>,
 ) -> JsValue {
     Closure::once_into_js(move |e: ErrorEvent| {
         ws.set_onerror(None);
         let _: Result<_, _> = shutdown.send(());
-        if let Some(initial_request) = take_initial_request(&initial_request) {
+        if let Some(initial_request) =
+            take_initial_request(&initial_request).filter(|_| !opened.load(Ordering::SeqCst))
+        {
             drop(
                 initial_request
                     .responder
@@ -317,10 +408,11 @@ fn on_close_callback(
     shutdown: flume::Sender<()>,
     ws: WebSocket,
     initial_request: Arc<Mutex<Option<PendingRequest>>>,
+    opened: Arc<AtomicBool>,
+    reconnect_attempt: Option<u32>,
     outstanding_requests: OutstandingRequestMapHandle,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
-    subscribers: SubscriberMap,
-    connection_counter: Arc<AtomicU32>,
+    reconnector: Arc<Reconnector>,
     connect_timeout: Duration,
 ) -> JsValue {
     Closure::once_into_js(move |c: CloseEvent| {
@@ -341,17 +433,34 @@ fn on_close_callback(
             );
         }
 
+        let opened = opened.load(Ordering::SeqCst);
         wasm_bindgen_futures::spawn_local(async move {
             disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
 
+            let (pending_error, reconnect_attempt) = if opened {
+                if reconnector.connection_lost() {
+                    (None, Some(1))
+                } else {
+                    (pending_error, None)
+                }
+            } else if let Some(attempt) = reconnect_attempt {
+                attempt_failed(
+                    &reconnector,
+                    attempt,
+                    pending_error.unwrap_or_else(Error::disconnected),
+                )
+            } else {
+                (pending_error, None)
+            };
+
             spawn_client(
                 url,
                 protocol_version,
                 request_receiver,
                 custom_apis.clone(),
-                subscribers,
-                connection_counter,
+                reconnector,
                 pending_error,
+                reconnect_attempt,
                 connect_timeout,
             );
         });
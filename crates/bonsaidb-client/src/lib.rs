@@ -16,6 +16,7 @@
 )]
 
 pub use url;
+pub use watchable;
 
 mod builder;
 mod client;
@@ -27,7 +28,10 @@ pub use fabruic;
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::builder::Blocking;
 pub use self::builder::{Async, Builder};
-pub use self::client::{ApiCallback, AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber};
+pub use self::client::{
+    ApiCallback, AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber, ConnectionState,
+    ReconnectPolicy,
+};
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::client::{BlockingClient, BlockingRemoteDatabase, BlockingRemoteSubscriber};
 pub use self::error::{ApiError, Error};
@@ -0,0 +1,44 @@
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::connection::{IdentityId, SessionId};
+use crate::define_basic_mapped_view;
+use crate::document::{CollectionDocument, Emit};
+use crate::key::time::TimestampAsNanoseconds;
+use crate::schema::Collection;
+
+/// A record of an action a session attempted to perform. Entries are only
+/// recorded when the audit log is enabled in the storage configuration.
+#[derive(Clone, Debug, Serialize, Deserialize, Collection)]
+#[collection(name = "audit-log", authority = "khonsulabs", views = [ByTimestamp], core = crate)]
+pub struct AuditEntry {
+    /// When the action was attempted.
+    pub timestamp: TimestampAsNanoseconds,
+    /// The id of the session that attempted the action, if it was
+    /// authenticated.
+    pub session: Option<SessionId>,
+    /// The identity the session was authenticated as, if any.
+    pub identity: Option<IdentityId>,
+    /// The name of the attempted action, for example
+    /// `Database.Document.Get`.
+    pub action: String,
+    /// The name of the resource the action was attempted on.
+    pub resource: String,
+    /// True if the action was allowed, false if permission was denied.
+    pub allowed: bool,
+    /// The address of the client that attempted the action, if it was
+    /// attempted over the network.
+    pub address: Option<SocketAddr>,
+}
+
+define_basic_mapped_view!(
+    ByTimestamp,
+    AuditEntry,
+    1,
+    "by-timestamp",
+    TimestampAsNanoseconds,
+    |document: CollectionDocument<AuditEntry>| {
+        document.header.emit_key(document.contents.timestamp)
+    }
+);
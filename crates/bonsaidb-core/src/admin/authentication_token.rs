@@ -1,15 +1,152 @@
 use serde::{Deserialize, Serialize};
 
 use crate::connection::{IdentityId, SensitiveString};
+use crate::define_basic_mapped_view;
+use crate::document::{CollectionDocument, Emit};
 use crate::key::time::TimestampAsNanoseconds;
+use crate::permissions::{Permissions, Statement};
 use crate::schema::Collection;
 
 #[derive(Collection, Clone, Serialize, Deserialize, Debug)]
-#[collection(name = "authentication-tokens", authority = "bonsaidb", core = crate)]
+#[collection(name = "authentication-tokens", authority = "bonsaidb", views = [ByIdentity], core = crate)]
 pub struct AuthenticationToken {
     pub identity: IdentityId,
     pub token: SensitiveString,
     pub created_at: TimestampAsNanoseconds,
+    /// A human-readable description of the token's purpose.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// If set, the token can no longer be used to authenticate at or after
+    /// this time.
+    #[serde(default)]
+    pub expires_at: Option<TimestampAsNanoseconds>,
+    /// The last time the token was used to authenticate successfully.
+    #[serde(default)]
+    pub last_used_at: Option<TimestampAsNanoseconds>,
+    /// If set, sessions authenticated with this token are only allowed to
+    /// perform actions that are permitted by both these statements and the
+    /// identity's own permissions.
+    #[serde(default)]
+    pub scope: Option<Vec<Statement>>,
+}
+
+impl AuthenticationToken {
+    /// Returns true if this token has an expiration that has passed.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.map_or(false, |expires_at| {
+            expires_at <= TimestampAsNanoseconds::now()
+        })
+    }
+
+    /// Returns the permissions that sessions authenticated with this token are
+    /// restricted to, if the token is scoped.
+    #[must_use]
+    pub fn scope_permissions(&self) -> Option<Permissions> {
+        self.scope.clone().map(Permissions::from)
+    }
+}
+
+define_basic_mapped_view!(
+    ByIdentity,
+    AuthenticationToken,
+    1,
+    "by-identity",
+    (u8, u64),
+    |document: CollectionDocument<AuthenticationToken>| {
+        document
+            .header
+            .emit_key(ByIdentity::key(document.contents.identity))
+    }
+);
+
+impl ByIdentity {
+    /// Returns the key this view emits for tokens that authenticate as
+    /// `identity`.
+    #[must_use]
+    pub const fn key(identity: IdentityId) -> (u8, u64) {
+        match identity {
+            IdentityId::User(id) => (0, id),
+            IdentityId::Role(id) => (1, id),
+        }
+    }
+}
+
+/// Options for creating an [`AuthenticationToken`].
+#[derive(Default, Clone, Serialize, Deserialize, Debug)]
+#[must_use]
+pub struct AuthenticationTokenOptions {
+    /// A human-readable description of the token's purpose.
+    pub label: Option<String>,
+    /// If set, the token can no longer be used to authenticate at or after
+    /// this time.
+    pub expires_at: Option<TimestampAsNanoseconds>,
+    /// If set, restricts the token to the actions permitted by both these
+    /// statements and the identity's own permissions.
+    pub scope: Option<Vec<Statement>>,
+}
+
+impl AuthenticationTokenOptions {
+    /// Sets the label of the token and returns self.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the time the token expires and returns self.
+    pub fn expiring_at(mut self, expires_at: TimestampAsNanoseconds) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Restricts the token to `statements` and returns self.
+    pub fn with_scope<S: IntoIterator<Item = Statement>>(mut self, statements: S) -> Self {
+        self.scope = Some(statements.into_iter().collect());
+        self
+    }
+}
+
+/// A newly created [`AuthenticationToken`]. This is the only time the private
+/// token is made available.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CreatedAuthenticationToken {
+    /// The unique id of the token.
+    pub id: u64,
+    /// The private token used to authenticate.
+    pub token: SensitiveString,
+}
+
+/// Information about an [`AuthenticationToken`], excluding its private token.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AuthenticationTokenSummary {
+    /// The unique id of the token.
+    pub id: u64,
+    /// The identity the token authenticates as.
+    pub identity: IdentityId,
+    /// A human-readable description of the token's purpose.
+    pub label: Option<String>,
+    /// When the token was created.
+    pub created_at: TimestampAsNanoseconds,
+    /// When the token expires, if ever.
+    pub expires_at: Option<TimestampAsNanoseconds>,
+    /// The last time the token was used to authenticate successfully.
+    pub last_used_at: Option<TimestampAsNanoseconds>,
+    /// The statements the token is restricted to, if any.
+    pub scope: Option<Vec<Statement>>,
+}
+
+impl From<CollectionDocument<AuthenticationToken>> for AuthenticationTokenSummary {
+    fn from(token: CollectionDocument<AuthenticationToken>) -> Self {
+        Self {
+            id: token.header.id,
+            identity: token.contents.identity,
+            label: token.contents.label,
+            created_at: token.contents.created_at,
+            expires_at: token.contents.expires_at,
+            last_used_at: token.contents.last_used_at,
+            scope: token.contents.scope,
+        }
+    }
 }
 
 #[cfg(feature = "token-authentication")]
@@ -18,7 +155,7 @@ mod implementation {
     use rand::{thread_rng, Rng};
     use zeroize::Zeroize;
 
-    use super::AuthenticationToken;
+    use super::{AuthenticationToken, AuthenticationTokenOptions};
     use crate::connection::{
         AsyncConnection, Connection, IdentityId, IdentityReference, SensitiveString,
         TokenChallengeAlgorithm,
@@ -28,7 +165,7 @@ mod implementation {
     use crate::schema::SerializedCollection;
 
     impl AuthenticationToken {
-        fn random(identity: IdentityId) -> (u64, Self) {
+        fn random(identity: IdentityId, options: &AuthenticationTokenOptions) -> (u64, Self) {
             const ALPHABET: &[u8] =
                 b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-.+/#";
             let mut rng = thread_rng();
@@ -45,6 +182,10 @@ mod implementation {
                     identity,
                     token,
                     created_at: TimestampAsNanoseconds::now(),
+                    label: options.label.clone(),
+                    expires_at: options.expires_at,
+                    last_used_at: None,
+                    scope: options.scope.clone(),
                 },
             )
         }
@@ -52,12 +193,20 @@ mod implementation {
         pub fn create<C: Connection>(
             identity: &IdentityReference<'_>,
             database: &C,
+        ) -> Result<CollectionDocument<Self>, crate::Error> {
+            Self::create_with_options(identity, &AuthenticationTokenOptions::default(), database)
+        }
+
+        pub fn create_with_options<C: Connection>(
+            identity: &IdentityReference<'_>,
+            options: &AuthenticationTokenOptions,
+            database: &C,
         ) -> Result<CollectionDocument<Self>, crate::Error> {
             let identity_id = identity
                 .resolve(database)?
                 .ok_or(crate::Error::InvalidCredentials)?;
             loop {
-                let (id, token) = Self::random(identity_id);
+                let (id, token) = Self::random(identity_id, options);
                 match token.insert_into(&id, database) {
                     Err(err) if err.error.conflicting_document::<Self>().is_some() => continue,
                     other => break other.map_err(|err| err.error),
@@ -68,13 +217,26 @@ mod implementation {
         pub async fn create_async<C: AsyncConnection>(
             identity: IdentityReference<'_>,
             database: &C,
+        ) -> Result<CollectionDocument<Self>, crate::Error> {
+            Self::create_with_options_async(
+                identity,
+                &AuthenticationTokenOptions::default(),
+                database,
+            )
+            .await
+        }
+
+        pub async fn create_with_options_async<C: AsyncConnection>(
+            identity: IdentityReference<'_>,
+            options: &AuthenticationTokenOptions,
+            database: &C,
         ) -> Result<CollectionDocument<Self>, crate::Error> {
             let identity_id = identity
                 .resolve_async(database)
                 .await?
                 .ok_or(crate::Error::InvalidCredentials)?;
             loop {
-                let (id, token) = Self::random(identity_id);
+                let (id, token) = Self::random(identity_id, options);
                 match token.insert_into_async(&id, database).await {
                     Err(err) if err.error.conflicting_document::<Self>().is_some() => continue,
                     other => break other.map_err(|err| err.error),
@@ -0,0 +1,80 @@
+use std::fmt::{Display, Write};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::connection::IdentityId;
+use crate::define_basic_unique_mapped_view;
+use crate::document::{CollectionDocument, Emit};
+use crate::schema::Collection;
+
+/// A mapping from a client certificate to the identity that connections
+/// presenting the certificate are authenticated as.
+#[derive(Collection, Clone, Serialize, Deserialize, Debug)]
+#[collection(name = "client-certificates", authority = "khonsulabs", views = [ByMatcher], core = crate)]
+#[must_use]
+pub struct ClientCertificate {
+    /// How certificates are matched against this mapping. Must be unique.
+    pub matcher: CertificateMatcher,
+    /// The identity connections presenting a matching certificate are
+    /// authenticated as.
+    pub identity: IdentityId,
+}
+
+impl ClientCertificate {
+    /// Returns a mapping of certificates matching `matcher` to `identity`.
+    pub const fn new(matcher: CertificateMatcher, identity: IdentityId) -> Self {
+        Self { matcher, identity }
+    }
+}
+
+/// Identifies one or more client certificates.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum CertificateMatcher {
+    /// Matches a certificate whose SHA-256 fingerprint of its DER encoding
+    /// matches the lowercase hexadecimal string contained.
+    Fingerprint(String),
+    /// Matches any certificate whose subject's distinguished name matches the
+    /// string contained, in the form `CN=client, O=Example`.
+    Subject(String),
+}
+
+impl CertificateMatcher {
+    /// Returns a matcher for the fingerprint of the DER-encoded `certificate`.
+    #[must_use]
+    pub fn fingerprint_of(certificate: &[u8]) -> Self {
+        let digest: [u8; 32] = Sha256::digest(certificate).into();
+        let mut fingerprint = String::with_capacity(digest.len() * 2);
+        for byte in digest {
+            write!(fingerprint, "{byte:02x}").expect("writing to a string is infallible");
+        }
+        Self::Fingerprint(fingerprint)
+    }
+
+    /// Returns a matcher for certificates with the subject `subject`.
+    pub fn subject(subject: impl Into<String>) -> Self {
+        Self::Subject(subject.into())
+    }
+}
+
+impl Display for CertificateMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fingerprint(fingerprint) => write!(f, "sha256:{}", fingerprint.to_lowercase()),
+            Self::Subject(subject) => write!(f, "subject:{subject}"),
+        }
+    }
+}
+
+define_basic_unique_mapped_view!(
+    ByMatcher,
+    ClientCertificate,
+    1,
+    "by-matcher",
+    String,
+    |document: CollectionDocument<ClientCertificate>| {
+        document
+            .header
+            .emit_key(document.contents.matcher.to_string())
+    }
+);
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::define_basic_unique_mapped_view;
 use crate::document::{CollectionDocument, Emit};
-use crate::permissions::Statement;
+use crate::permissions::{ConditionalStatement, Statement};
 use crate::schema::{Collection, NamedCollection};
 
 /// A named group of permissions statements.
@@ -14,6 +14,11 @@ pub struct PermissionGroup {
     pub name: String,
     /// The permission statements.
     pub statements: Vec<Statement>,
+    /// Permission statements that only apply while their conditions are met.
+    /// Deny statements in this list take precedence over all other
+    /// statements.
+    #[serde(default)]
+    pub conditional_statements: Vec<ConditionalStatement>,
 }
 
 impl PermissionGroup {
@@ -22,6 +27,7 @@ impl PermissionGroup {
         Self {
             name: name.into(),
             statements: Vec::new(),
+            conditional_statements: Vec::new(),
         }
     }
 
@@ -30,6 +36,16 @@ impl PermissionGroup {
         self.statements = statements.into_iter().collect();
         self
     }
+
+    /// Builder-style method. Returns self after replacing the current
+    /// conditional statements with `statements`.
+    pub fn with_conditional_statements<I: IntoIterator<Item = ConditionalStatement>>(
+        mut self,
+        statements: I,
+    ) -> Self {
+        self.conditional_statements = statements.into_iter().collect();
+        self
+    }
 }
 
 impl NamedCollection for PermissionGroup {
@@ -1,8 +1,12 @@
 use crate::schema::Schema;
 
+#[doc(hidden)]
+pub mod audit;
 #[doc(hidden)]
 pub mod authentication_token;
 #[doc(hidden)]
+pub mod client_certificate;
+#[doc(hidden)]
 pub mod database;
 #[doc(hidden)]
 pub mod group;
@@ -11,7 +15,12 @@ pub mod role;
 #[doc(hidden)]
 pub mod user;
 
-pub use self::authentication_token::AuthenticationToken;
+pub use self::audit::AuditEntry;
+pub use self::authentication_token::{
+    AuthenticationToken, AuthenticationTokenOptions, AuthenticationTokenSummary,
+    CreatedAuthenticationToken,
+};
+pub use self::client_certificate::{CertificateMatcher, ClientCertificate};
 pub use self::database::Database;
 pub use self::group::PermissionGroup;
 pub use self::role::Role;
@@ -19,7 +28,7 @@ pub use self::user::User;
 
 /// The BonsaiDb administration schema.
 #[derive(Debug, Schema)]
-#[schema(name = "bonsaidb-admin", authority = "khonsulabs", collections = [Database, PermissionGroup, Role, User, AuthenticationToken], core = crate)]
+#[schema(name = "bonsaidb-admin", authority = "khonsulabs", collections = [Database, PermissionGroup, Role, User, AuthenticationToken, AuditEntry, ClientCertificate], core = crate)]
 pub struct Admin;
 
 /// The name of the admin database.
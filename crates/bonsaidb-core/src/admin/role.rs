@@ -2,9 +2,14 @@ use actionable::Permissions;
 use serde::{Deserialize, Serialize};
 
 use crate::admin::group;
-use crate::connection::{AsyncStorageConnection, Connection, IdentityReference, StorageConnection};
+use crate::connection::{
+    AsyncStorageConnection, Connection, IdentityReference, Session, StorageConnection,
+};
 use crate::define_basic_unique_mapped_view;
 use crate::document::{CollectionDocument, Emit};
+use crate::permissions::{
+    Action, ClientContext, ConditionalPermissions, Identifier, PermissionExplanation,
+};
 use crate::schema::{Collection, Nameable, NamedCollection, SerializedCollection};
 
 /// An assignable role, which grants permissions based on the associated [`PermissionGroup`](crate::admin::PermissionGroup)s.
@@ -69,6 +74,52 @@ impl Role {
 
         Ok(merged_permissions)
     }
+
+    /// Collects the conditional statements from the groups this role is
+    /// assigned.
+    pub fn conditional_permissions<C: Connection>(
+        &self,
+        admin: &C,
+    ) -> Result<ConditionalPermissions, crate::Error> {
+        Ok(group::PermissionGroup::get_multiple(&self.groups, admin)?
+            .into_iter()
+            .flat_map(|group| group.contents.conditional_statements)
+            .collect())
+    }
+
+    /// Explains whether this role is permitted to perform `action` on
+    /// `resource_name` when connecting as `client`. The decision is made using
+    /// [`Self::effective_permissions`] and
+    /// [`Self::conditional_permissions`], and the explanation lists each
+    /// statement from this role's groups that matches the action.
+    pub fn explain_permission<'a, C: Connection, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        admin: &C,
+        inherit_permissions: &Permissions,
+        resource_name: R,
+        action: &P,
+        client: &ClientContext,
+    ) -> Result<PermissionExplanation, crate::Error> {
+        let resource_name = resource_name.as_ref();
+        let session = Session {
+            permissions: self.effective_permissions(admin, inherit_permissions)?,
+            conditional_permissions: self.conditional_permissions(admin)?,
+            client: client.clone(),
+            ..Session::default()
+        };
+        let groups = group::PermissionGroup::get_multiple(&self.groups, admin)?
+            .into_iter()
+            .map(|group| (None, group));
+
+        Ok(PermissionExplanation::new(
+            session.allowed_to(resource_name, action),
+            groups,
+            inherit_permissions,
+            resource_name,
+            action,
+            client,
+        ))
+    }
 }
 
 impl NamedCollection for Role {
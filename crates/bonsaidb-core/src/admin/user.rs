@@ -3,11 +3,14 @@ use serde::{Deserialize, Serialize};
 
 use crate::admin::{group, role};
 use crate::connection::{
-    AsyncStorageConnection, Connection, IdentityReference, SensitiveString, StorageConnection,
+    AsyncStorageConnection, Connection, IdentityReference, SensitiveString, Session,
+    StorageConnection,
 };
 use crate::define_basic_unique_mapped_view;
 use crate::document::{CollectionDocument, Emit, KeyId};
-use crate::permissions::Permissions;
+use crate::permissions::{
+    Action, ClientContext, ConditionalPermissions, Identifier, PermissionExplanation, Permissions,
+};
 use crate::schema::{Collection, Nameable, NamedCollection, SerializedCollection};
 
 /// A user that can authenticate with BonsaiDb.
@@ -63,11 +66,84 @@ impl User {
         admin: &C,
         inherit_permissions: &Permissions,
     ) -> Result<Permissions, crate::Error> {
+        let groups = self.permission_groups(admin)?;
+
+        // Combine the permissions from all the groups into one.
+        let merged_permissions = Permissions::merged(
+            groups
+                .into_iter()
+                .map(|group| Permissions::from(group.contents.statements))
+                .collect::<Vec<_>>()
+                .iter()
+                .chain(std::iter::once(inherit_permissions)),
+        );
+
+        Ok(merged_permissions)
+    }
+
+    /// Collects the conditional statements from the groups and roles this
+    /// user is assigned.
+    pub fn conditional_permissions<C: Connection>(
+        &self,
+        admin: &C,
+    ) -> Result<ConditionalPermissions, crate::Error> {
+        Ok(self
+            .permission_groups(admin)?
+            .into_iter()
+            .flat_map(|group| group.contents.conditional_statements)
+            .collect())
+    }
+
+    /// Explains whether this user is permitted to perform `action` on
+    /// `resource_name` when connecting as `client`. The decision is made using
+    /// [`Self::effective_permissions`] and
+    /// [`Self::conditional_permissions`], and the explanation lists each
+    /// statement from this user's groups and roles that matches the action.
+    pub fn explain_permission<'a, C: Connection, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        admin: &C,
+        inherit_permissions: &Permissions,
+        resource_name: R,
+        action: &P,
+        client: &ClientContext,
+    ) -> Result<PermissionExplanation, crate::Error> {
+        let resource_name = resource_name.as_ref();
+        let session = Session {
+            permissions: self.effective_permissions(admin, inherit_permissions)?,
+            conditional_permissions: self.conditional_permissions(admin)?,
+            client: client.clone(),
+            ..Session::default()
+        };
+
+        let mut groups = group::PermissionGroup::get_multiple(self.groups.iter(), admin)?
+            .into_iter()
+            .map(|group| (None, group))
+            .collect::<Vec<_>>();
+        for role in role::Role::get_multiple(self.roles.iter(), admin)? {
+            for group in group::PermissionGroup::get_multiple(&role.contents.groups, admin)? {
+                groups.push((Some(role.contents.name.clone()), group));
+            }
+        }
+
+        Ok(PermissionExplanation::new(
+            session.allowed_to(resource_name, action),
+            groups,
+            inherit_permissions,
+            resource_name,
+            action,
+            client,
+        ))
+    }
+
+    fn permission_groups<C: Connection>(
+        &self,
+        admin: &C,
+    ) -> Result<Vec<CollectionDocument<group::PermissionGroup>>, crate::Error> {
         // List all of the groups that this user belongs to because of role associations.
         let role_groups = if self.roles.is_empty() {
             Vec::default()
         } else {
-            let roles = role::Role::get_multiple(self.groups.iter(), admin)?;
+            let roles = role::Role::get_multiple(self.roles.iter(), admin)?;
             roles
                 .into_iter()
                 .flat_map(|doc| doc.contents.groups)
@@ -75,26 +151,14 @@ impl User {
                 .collect::<Vec<_>>()
         };
         // Retrieve all of the groups.
-        let groups = if role_groups.is_empty() {
-            group::PermissionGroup::get_multiple(self.groups.iter(), admin)?
+        if role_groups.is_empty() {
+            group::PermissionGroup::get_multiple(self.groups.iter(), admin)
         } else {
             let mut all_groups = role_groups;
             all_groups.extend(self.groups.iter().copied());
             all_groups.dedup();
-            group::PermissionGroup::get_multiple(&all_groups, admin)?
-        };
-
-        // Combine the permissions from all the groups into one.
-        let merged_permissions = Permissions::merged(
-            groups
-                .into_iter()
-                .map(|group| Permissions::from(group.contents.statements))
-                .collect::<Vec<_>>()
-                .iter()
-                .chain(std::iter::once(inherit_permissions)),
-        );
-
-        Ok(merged_permissions)
+            group::PermissionGroup::get_multiple(&all_groups, admin)
+        }
     }
 }
 
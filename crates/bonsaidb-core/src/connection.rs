@@ -3,6 +3,7 @@ use std::convert::Infallible;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::string::FromUtf8Error;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use actionable::{Action, Identifier};
@@ -13,12 +14,15 @@ use futures::{Future, FutureExt};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroize;
 
-use crate::admin::{Role, User};
+use crate::admin::{AuthenticationTokenSummary, PermissionGroup, Role, User};
 use crate::document::{
     CollectionDocument, CollectionHeader, Document, HasHeader, Header, OwnedDocument,
 };
 use crate::key::{ByteSource, IntoPrefixRange, Key, KeyEncoding, KeyKind, KeyVisitor};
-use crate::permissions::Permissions;
+use crate::permissions::{
+    ActionName, ClientContext, ConditionalPermissions, PermissionExplanation, Permissions,
+    ResourceName,
+};
 use crate::schema::view::map::{MappedDocuments, ViewMappings as ViewMappingsCurrent};
 use crate::schema::{
     self, MappedValue, Nameable, NamedReference, Schema, SchemaName, SchemaSummary,
@@ -3143,6 +3147,128 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
         user: U,
         role: R,
     ) -> Result<(), crate::Error>;
+
+    /// Creates a [`PermissionGroup`], returning its id.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UniqueKeyViolation`]: a group named `group.name` already
+    ///   exists.
+    fn create_permission_group(&self, group: PermissionGroup) -> Result<u64, crate::Error>;
+
+    /// Replaces the name and statements of `permission_group` with the
+    /// contents of `updated`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::PermissionGroupNotFound`]: `permission_group` does not exist.
+    /// * [`Error::UniqueKeyViolation`]: another group is named `updated.name`.
+    fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), crate::Error>;
+
+    /// Deletes a [`PermissionGroup`]. The group is also removed from all users
+    /// and roles that it was assigned to.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::PermissionGroupNotFound`]: `permission_group` does not exist.
+    fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), crate::Error>;
+
+    /// Creates a [`Role`], returning its id.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UniqueKeyViolation`]: a role named `role.name` already
+    ///   exists.
+    fn create_role(&self, role: Role) -> Result<u64, crate::Error>;
+
+    /// Replaces the name and permission groups of `role` with the contents of
+    /// `updated`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::RoleNotFound`]: `role` does not exist.
+    /// * [`Error::UniqueKeyViolation`]: another role is named `updated.name`.
+    fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), crate::Error>;
+
+    /// Deletes a [`Role`]. The role is also removed from all users that it was
+    /// assigned to.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::RoleNotFound`]: `role` does not exist.
+    fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), crate::Error>;
+
+    /// Returns information about the
+    /// [`AuthenticationToken`s](crate::admin::AuthenticationToken) that
+    /// authenticate as `user`. The private tokens are not included.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `user` does not exist.
+    fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, crate::Error>;
+
+    /// Creates an [`AuthenticationToken`](crate::admin::AuthenticationToken)
+    /// that authenticates as `identity`, configured using `options`. The
+    /// returned private token can't be retrieved again.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `identity` refers to a user that does not
+    ///   exist.
+    /// * [`Error::RoleNotFound`]: `identity` refers to a role that does not
+    ///   exist.
+    #[cfg(feature = "token-authentication")]
+    fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: crate::admin::AuthenticationTokenOptions,
+    ) -> Result<crate::admin::CreatedAuthenticationToken, crate::Error>;
+
+    /// Revokes the [`AuthenticationToken`](crate::admin::AuthenticationToken)
+    /// with `token_id`, preventing it from being used to authenticate.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::AuthenticationTokenNotFound`]: `token_id` does not exist.
+    fn revoke_authentication_token(&self, token_id: u64) -> Result<(), crate::Error>;
+
+    /// Explains whether `identity` is permitted to perform the action named
+    /// `action` on `resource_name` when connecting as `client`. The
+    /// explanation contains the decision and each statement from the
+    /// identity's permission groups and roles that matches the action. If
+    /// `resource_name` names a document owned by `identity`, the explanation
+    /// also notes the allowance granted to owners.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `identity` refers to a user that does not
+    ///   exist.
+    /// * [`Error::RoleNotFound`]: `identity` refers to a role that does not
+    ///   exist.
+    fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, crate::Error>;
 }
 
 /// Functions for interacting with a multi-database BonsaiDb instance.
@@ -3338,6 +3464,128 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
         user: U,
         role: R,
     ) -> Result<(), crate::Error>;
+
+    /// Creates a [`PermissionGroup`], returning its id.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UniqueKeyViolation`]: a group named `group.name` already
+    ///   exists.
+    async fn create_permission_group(&self, group: PermissionGroup) -> Result<u64, crate::Error>;
+
+    /// Replaces the name and statements of `permission_group` with the
+    /// contents of `updated`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::PermissionGroupNotFound`]: `permission_group` does not exist.
+    /// * [`Error::UniqueKeyViolation`]: another group is named `updated.name`.
+    async fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), crate::Error>;
+
+    /// Deletes a [`PermissionGroup`]. The group is also removed from all users
+    /// and roles that it was assigned to.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::PermissionGroupNotFound`]: `permission_group` does not exist.
+    async fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), crate::Error>;
+
+    /// Creates a [`Role`], returning its id.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UniqueKeyViolation`]: a role named `role.name` already
+    ///   exists.
+    async fn create_role(&self, role: Role) -> Result<u64, crate::Error>;
+
+    /// Replaces the name and permission groups of `role` with the contents of
+    /// `updated`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::RoleNotFound`]: `role` does not exist.
+    /// * [`Error::UniqueKeyViolation`]: another role is named `updated.name`.
+    async fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), crate::Error>;
+
+    /// Deletes a [`Role`]. The role is also removed from all users that it was
+    /// assigned to.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::RoleNotFound`]: `role` does not exist.
+    async fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), crate::Error>;
+
+    /// Returns information about the
+    /// [`AuthenticationToken`s](crate::admin::AuthenticationToken) that
+    /// authenticate as `user`. The private tokens are not included.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `user` does not exist.
+    async fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, crate::Error>;
+
+    /// Creates an [`AuthenticationToken`](crate::admin::AuthenticationToken)
+    /// that authenticates as `identity`, configured using `options`. The
+    /// returned private token can't be retrieved again.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `identity` refers to a user that does not
+    ///   exist.
+    /// * [`Error::RoleNotFound`]: `identity` refers to a role that does not
+    ///   exist.
+    #[cfg(feature = "token-authentication")]
+    async fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: crate::admin::AuthenticationTokenOptions,
+    ) -> Result<crate::admin::CreatedAuthenticationToken, crate::Error>;
+
+    /// Revokes the [`AuthenticationToken`](crate::admin::AuthenticationToken)
+    /// with `token_id`, preventing it from being used to authenticate.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::AuthenticationTokenNotFound`]: `token_id` does not exist.
+    async fn revoke_authentication_token(&self, token_id: u64) -> Result<(), crate::Error>;
+
+    /// Explains whether `identity` is permitted to perform the action named
+    /// `action` on `resource_name` when connecting as `client`. The
+    /// explanation contains the decision and each statement from the
+    /// identity's permission groups and roles that matches the action. If
+    /// `resource_name` names a document owned by `identity`, the explanation
+    /// also notes the allowance granted to owners.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `identity` refers to a user that does not
+    ///   exist.
+    /// * [`Error::RoleNotFound`]: `identity` refers to a role that does not
+    ///   exist.
+    async fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, crate::Error>;
 }
 
 /// A database stored in BonsaiDb.
@@ -3494,6 +3742,10 @@ pub enum Authentication {
         /// The password of the user.
         password: SensitiveString,
     },
+    /// Authenticate using a credential that is verified by the storage's
+    /// external authentication provider, such as a signed JWT issued by an
+    /// identity service.
+    External(SensitiveBytes),
 }
 
 impl Authentication {
@@ -3509,6 +3761,12 @@ impl Authentication {
         })
     }
 
+    /// Returns an authentication instance for an opaque `credential` that is
+    /// verified by the storage's external authentication provider.
+    pub fn external(credential: impl Into<Bytes>) -> Self {
+        Self::External(SensitiveBytes(credential.into()))
+    }
+
     /// Returns a token authentication initialization instance for this token.
     #[cfg(feature = "token-authentication")]
     pub fn token(id: u64, token: &SensitiveString) -> Result<Self, crate::Error> {
@@ -3625,6 +3883,67 @@ pub struct Session {
     pub authentication: SessionAuthentication,
     /// The effective permissions of the session.
     pub permissions: Permissions,
+    /// If set, actions must also be allowed by these permissions. This is used
+    /// to restrict sessions authenticated with a scoped
+    /// [`AuthenticationToken`](crate::admin::AuthenticationToken).
+    #[serde(default)]
+    pub scope: Option<Permissions>,
+    /// Statements whose effect depends on conditions such as the current time
+    /// or [`Self::client`](Self#structfield.client). These are evaluated
+    /// before [`Self::permissions`](Self#structfield.permissions), and any
+    /// applicable deny statement takes precedence.
+    #[serde(default)]
+    pub conditional_permissions: ConditionalPermissions,
+    /// Information about the client using this session.
+    #[serde(default)]
+    pub client: ClientContext,
+    /// The id of the [`AuthenticationToken`](crate::admin::AuthenticationToken)
+    /// this session was authenticated with, if any. The session is only valid
+    /// while the token exists and has not expired.
+    #[serde(default)]
+    pub authentication_token: Option<u64>,
+    /// The validity of [`Self::authentication_token`](Self#structfield.authentication_token),
+    /// tracked by the storage that authenticated this session. While the token
+    /// is not valid, every action is denied.
+    #[serde(skip)]
+    pub authentication_token_validity: Option<AuthenticationTokenValidity>,
+}
+
+/// Tracks whether the
+/// [`AuthenticationToken`](crate::admin::AuthenticationToken) a [`Session`]
+/// was authenticated with is still valid. Clones share their revocation
+/// state, allowing every session authenticated with a token to be invalidated
+/// at once.
+#[derive(Clone, Debug)]
+pub struct AuthenticationTokenValidity {
+    expires_at: Option<crate::key::time::TimestampAsNanoseconds>,
+    revoked: Arc<AtomicBool>,
+}
+
+impl AuthenticationTokenValidity {
+    /// Returns a new validity for a token that expires at `expires_at`, if
+    /// set.
+    #[must_use]
+    pub fn new(expires_at: Option<crate::key::time::TimestampAsNanoseconds>) -> Self {
+        Self {
+            expires_at,
+            revoked: Arc::default(),
+        }
+    }
+
+    /// Marks the token as revoked.
+    pub fn revoke(&self) {
+        self.revoked.store(true, Ordering::Release);
+    }
+
+    /// Returns true if the token has not been revoked and has not expired.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        !self.revoked.load(Ordering::Acquire)
+            && self.expires_at.map_or(true, |expires_at| {
+                expires_at > crate::key::time::TimestampAsNanoseconds::now()
+            })
+    }
 }
 
 /// The authentication state of a [`Session`].
@@ -3701,6 +4020,9 @@ pub enum AuthenticationMethod {
     Token,
     /// Authenticate a user using password hashing (Argon2).
     PasswordHash,
+    /// Authenticate using a credential verified by an external
+    /// authentication provider.
+    External,
 }
 
 /// A unique session ID.
@@ -3709,26 +4031,84 @@ pub enum AuthenticationMethod {
 pub struct SessionId(pub u64);
 
 impl Session {
+    /// Returns false if this session was authenticated with an
+    /// [`AuthenticationToken`](crate::admin::AuthenticationToken) that has
+    /// since been revoked or has expired.
+    #[must_use]
+    pub fn authentication_token_is_valid(&self) -> bool {
+        self.authentication_token_validity
+            .as_ref()
+            .map_or(true, AuthenticationTokenValidity::is_valid)
+    }
+
     /// Checks if `action` is permitted against `resource_name`.
     pub fn allowed_to<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
         &self,
         resource_name: R,
         action: &P,
     ) -> bool {
-        self.permissions.allowed_to(resource_name, action)
+        let resource_name = resource_name.as_ref();
+        self.authentication_token_is_valid()
+            && self
+                .conditional_permissions
+                .evaluate(resource_name, action, &self.client)
+                .unwrap_or_else(|| self.permissions.allowed_to(resource_name, action))
+            && self
+                .scope
+                .as_ref()
+                .map_or(true, |scope| scope.allowed_to(resource_name, action))
+    }
+
+    /// Returns true if `action` is explicitly denied on `resource_name`,
+    /// either by an applicable deny statement, by the session's
+    /// [`scope`](Self#structfield.scope), or because the session's
+    /// authentication token is no longer valid. Unlike [`Self::allowed_to`],
+    /// this returns false when no statement permits the action.
+    #[must_use]
+    pub fn denies<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> bool {
+        let resource_name = resource_name.as_ref();
+        !self.authentication_token_is_valid()
+            || self
+                .conditional_permissions
+                .evaluate(resource_name, action, &self.client)
+                == Some(false)
+            || self
+                .scope
+                .as_ref()
+                .map_or(false, |scope| !scope.allowed_to(resource_name, action))
     }
 
     /// Checks if `action` is permitted against `resource_name`. If permission
     /// is denied, returns a [`PermissionDenied`](Error::PermissionDenied)
-    /// error.
+    /// error. If the session's authentication token is no longer valid,
+    /// returns [`Error::InvalidCredentials`].
     pub fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
         &self,
         resource_name: R,
         action: &P,
     ) -> Result<(), Error> {
-        self.permissions
-            .check(resource_name, action)
-            .map_err(Error::from)
+        if !self.authentication_token_is_valid() {
+            return Err(Error::InvalidCredentials);
+        }
+        let resource_name = resource_name.as_ref();
+        match self
+            .conditional_permissions
+            .evaluate(resource_name, action, &self.client)
+        {
+            Some(true) => {}
+            // An empty set of permissions denies everything, producing the
+            // same error a static denial would.
+            Some(false) => Permissions::default().check(resource_name, action)?,
+            None => self.permissions.check(resource_name, action)?,
+        }
+        if let Some(scope) = &self.scope {
+            scope.check(resource_name, action)?;
+        }
+        Ok(())
     }
 
     /// Returns the identity that this session is authenticated as, if any.
@@ -3776,6 +4156,17 @@ pub enum Identity {
     },
 }
 
+impl Identity {
+    /// Returns the unique id of this identity.
+    #[must_use]
+    pub const fn id(&self) -> IdentityId {
+        match self {
+            Self::User { id, .. } => IdentityId::User(*id),
+            Self::Role { id, .. } => IdentityId::Role(*id),
+        }
+    }
+}
+
 impl Eq for Identity {}
 
 impl PartialEq for Identity {
@@ -3860,7 +4251,7 @@ impl<'name> IdentityReference<'name> {
 }
 
 /// An identity from the connected BonsaiDb instance.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum IdentityId {
     /// A [`User`](crate::admin::User) id.
@@ -1,5 +1,6 @@
 /// [`Key`] implementations for time types.
 pub mod time;
+mod dynamic;
 mod varint;
 
 mod deprecated;
@@ -0,0 +1,408 @@
+use ordered_varint::Variable;
+use pot::Value;
+
+use crate::key::{CompositeKind, KeyDescription, KeyKind};
+use crate::Error;
+
+impl KeyDescription {
+    /// Decodes `bytes`, which were encoded by the [`Key`](crate::key::Key)
+    /// this is a description of, into a [`Value`]. This allows inspecting keys
+    /// without access to the Rust type that encoded them.
+    ///
+    /// Tuples and multi-field structs are decoded as a [`Value::Sequence`],
+    /// [`Option`]s as their contained value or [`Value::None`], and
+    /// [`Result`]s as a mapping of `Ok` or `Err` to their contained value.
+    /// Single-field structs are decoded as their field. Keys with a custom
+    /// encoding ([`KeyDescription::Other`]) can't be interpreted and are
+    /// returned as [`Value::Bytes`].
+    pub fn decode_value(&self, bytes: &[u8]) -> Result<Value<'static>, Error> {
+        match self {
+            KeyDescription::Basic(kind) => decode_basic(kind, bytes),
+            KeyDescription::Composite(composite) => match &composite.kind {
+                CompositeKind::Option => match bytes.first() {
+                    None | Some(0) => Ok(Value::None),
+                    Some(_) => field(&composite.fields, 0)?.decode_value(&bytes[1..]),
+                },
+                CompositeKind::Result => {
+                    let (variant, field) = match bytes.first() {
+                        Some(0) => ("Ok", field(&composite.fields, 0)?),
+                        Some(_) => ("Err", field(&composite.fields, 1)?),
+                        None => return Err(invalid_key("missing result discriminant")),
+                    };
+                    Ok(Value::Mappings(vec![(
+                        Value::from(variant),
+                        field.decode_value(&bytes[1..])?,
+                    )]))
+                }
+                CompositeKind::Struct(_) if composite.fields.len() == 1 => {
+                    field(&composite.fields, 0)?.decode_value(bytes)
+                }
+                CompositeKind::Tuple => decode_composite(&composite.fields, bytes),
+                // Structs are free to choose their own encoding, so fall back
+                // to the raw bytes if they weren't encoded as a composite key.
+                CompositeKind::Struct(_) => decode_composite(&composite.fields, bytes)
+                    .or_else(|_| Ok(Value::Bytes(bytes.to_vec().into()))),
+            },
+            KeyDescription::Other(_) => Ok(Value::Bytes(bytes.to_vec().into())),
+        }
+    }
+
+    /// Encodes `value` using the same encoding as the [`Key`](crate::key::Key)
+    /// this is a description of. This is the inverse of
+    /// [`Self::decode_value()`], allowing keys to be constructed without
+    /// access to the Rust type.
+    pub fn encode_value(&self, value: &Value<'_>) -> Result<Vec<u8>, Error> {
+        match self {
+            KeyDescription::Basic(kind) => encode_basic(kind, value),
+            KeyDescription::Composite(composite) => match &composite.kind {
+                CompositeKind::Option => match value {
+                    Value::None | Value::Unit => Ok(vec![0]),
+                    value => {
+                        let mut encoded = vec![1];
+                        encoded.extend(field(&composite.fields, 0)?.encode_value(value)?);
+                        Ok(encoded)
+                    }
+                },
+                CompositeKind::Result => match value {
+                    Value::Mappings(mappings) if mappings.len() == 1 => {
+                        let (variant, value) = &mappings[0];
+                        let (mut encoded, field) = match variant {
+                            Value::String(variant) if variant == "Ok" => {
+                                (vec![0], field(&composite.fields, 0)?)
+                            }
+                            Value::String(variant) if variant == "Err" => {
+                                (vec![1], field(&composite.fields, 1)?)
+                            }
+                            _ => return Err(invalid_key("expected `Ok` or `Err`")),
+                        };
+                        encoded.extend(field.encode_value(value)?);
+                        Ok(encoded)
+                    }
+                    _ => Err(invalid_key("expected a mapping of `Ok` or `Err`")),
+                },
+                CompositeKind::Struct(_) if composite.fields.len() == 1 => {
+                    field(&composite.fields, 0)?.encode_value(value)
+                }
+                CompositeKind::Tuple | CompositeKind::Struct(_) => {
+                    encode_composite(&composite.fields, value)
+                }
+            },
+            KeyDescription::Other(_) => match value {
+                Value::Bytes(bytes) => Ok(bytes.to_vec()),
+                _ => Err(invalid_key("expected bytes for a custom key encoding")),
+            },
+        }
+    }
+
+    /// Returns the length of the encoded key, `Some(None)` if it is variable,
+    /// or `None` if it can't be determined from the description.
+    fn encoded_length(&self) -> Option<Option<usize>> {
+        match self {
+            KeyDescription::Basic(kind) => Some(fixed_length(kind)),
+            KeyDescription::Composite(composite) => match composite.kind {
+                CompositeKind::Option | CompositeKind::Result => Some(None),
+                CompositeKind::Struct(_) if composite.fields.len() == 1 => {
+                    composite.fields[0].encoded_length()
+                }
+                CompositeKind::Tuple => {
+                    let mut total = Some(0);
+                    for field in &composite.fields {
+                        total = match (total, field.encoded_length()?) {
+                            (Some(total), Some(length)) => Some(total + length),
+                            _ => None,
+                        };
+                    }
+                    Some(total)
+                }
+                CompositeKind::Struct(_) => None,
+            },
+            KeyDescription::Other(_) => None,
+        }
+    }
+}
+
+fn fixed_length(kind: &KeyKind) -> Option<usize> {
+    match kind {
+        KeyKind::Unit => Some(0),
+        KeyKind::U8 | KeyKind::I8 | KeyKind::Bool => Some(1),
+        KeyKind::U16 | KeyKind::I16 => Some(2),
+        KeyKind::U32 | KeyKind::I32 => Some(4),
+        KeyKind::U64 | KeyKind::I64 | KeyKind::Usize | KeyKind::Isize => Some(8),
+        KeyKind::U128 | KeyKind::I128 => Some(16),
+        KeyKind::Signed | KeyKind::Unsigned | KeyKind::String | KeyKind::Bytes => None,
+    }
+}
+
+fn decode_basic(kind: &KeyKind, bytes: &[u8]) -> Result<Value<'static>, Error> {
+    macro_rules! be_bytes {
+        ($type:ty) => {
+            Value::from(<$type>::from_be_bytes(
+                bytes
+                    .try_into()
+                    .map_err(|_| invalid_key("incorrect length"))?,
+            ))
+        };
+    }
+
+    Ok(match kind {
+        KeyKind::Unit => Value::Unit,
+        KeyKind::U8 => be_bytes!(u8),
+        KeyKind::U16 => be_bytes!(u16),
+        KeyKind::U32 => be_bytes!(u32),
+        KeyKind::U64 | KeyKind::Usize => be_bytes!(u64),
+        KeyKind::U128 => be_bytes!(u128),
+        KeyKind::I8 => be_bytes!(i8),
+        KeyKind::I16 => be_bytes!(i16),
+        KeyKind::I32 => be_bytes!(i32),
+        KeyKind::I64 | KeyKind::Isize => be_bytes!(i64),
+        KeyKind::I128 => be_bytes!(i128),
+        KeyKind::Signed => Value::from(i128::decode_variable(bytes).map_err(invalid_key)?),
+        KeyKind::Unsigned => Value::from(u128::decode_variable(bytes).map_err(invalid_key)?),
+        KeyKind::Bool => match bytes {
+            [value] => Value::Bool(*value != 0),
+            _ => return Err(invalid_key("incorrect length")),
+        },
+        KeyKind::String => Value::from(String::from_utf8(bytes.to_vec()).map_err(invalid_key)?),
+        KeyKind::Bytes => Value::Bytes(bytes.to_vec().into()),
+    })
+}
+
+fn encode_basic(kind: &KeyKind, value: &Value<'_>) -> Result<Vec<u8>, Error> {
+    macro_rules! be_bytes {
+        ($as:ident) => {
+            match value {
+                Value::Integer(integer) => {
+                    integer.$as().map_err(invalid_key)?.to_be_bytes().to_vec()
+                }
+                _ => return Err(invalid_key("expected an integer")),
+            }
+        };
+    }
+
+    Ok(match kind {
+        KeyKind::Unit => Vec::new(),
+        KeyKind::U8 => be_bytes!(as_u8),
+        KeyKind::U16 => be_bytes!(as_u16),
+        KeyKind::U32 => be_bytes!(as_u32),
+        KeyKind::U64 | KeyKind::Usize => be_bytes!(as_u64),
+        KeyKind::U128 => be_bytes!(as_u128),
+        KeyKind::I8 => be_bytes!(as_i8),
+        KeyKind::I16 => be_bytes!(as_i16),
+        KeyKind::I32 => be_bytes!(as_i32),
+        KeyKind::I64 | KeyKind::Isize => be_bytes!(as_i64),
+        KeyKind::I128 => be_bytes!(as_i128),
+        KeyKind::Signed => match value {
+            Value::Integer(integer) => integer
+                .as_i128()
+                .map_err(invalid_key)?
+                .to_variable_vec()
+                .map_err(invalid_key)?,
+            _ => return Err(invalid_key("expected an integer")),
+        },
+        KeyKind::Unsigned => match value {
+            Value::Integer(integer) => integer
+                .as_u128()
+                .map_err(invalid_key)?
+                .to_variable_vec()
+                .map_err(invalid_key)?,
+            _ => return Err(invalid_key("expected an integer")),
+        },
+        KeyKind::Bool => match value {
+            Value::Bool(value) => vec![u8::from(*value)],
+            _ => return Err(invalid_key("expected a bool")),
+        },
+        KeyKind::String => match value {
+            Value::String(value) => value.as_bytes().to_vec(),
+            _ => return Err(invalid_key("expected a string")),
+        },
+        KeyKind::Bytes => match value {
+            Value::Bytes(value) => value.to_vec(),
+            Value::String(value) => value.as_bytes().to_vec(),
+            _ => return Err(invalid_key("expected bytes")),
+        },
+    })
+}
+
+/// Encodes `value`'s fields the same way
+/// [`CompositeKeyEncoder`](crate::key::CompositeKeyEncoder) does, escaping
+/// null bytes.
+fn encode_composite(fields: &[KeyDescription], value: &Value<'_>) -> Result<Vec<u8>, Error> {
+    let Value::Sequence(values) = value else {
+        return Err(invalid_key("expected a sequence"));
+    };
+    if values.len() != fields.len() {
+        return Err(invalid_key(format!("expected {} fields", fields.len())));
+    }
+
+    let mut encoded = Vec::new();
+    let mut encoded_lengths = Vec::new();
+    for (field, value) in fields.iter().zip(values) {
+        let length = field
+            .encoded_length()
+            .ok_or_else(|| invalid_key("field length can't be determined"))?;
+        let field_bytes = field.encode_value(value)?;
+        if length.is_some() {
+            encoded.extend(field_bytes);
+        } else {
+            let escaped = escape_nulls(&field_bytes);
+            encoded_lengths.push(u16::try_from(escaped.len()).map_err(invalid_key)?);
+            encoded.extend(escaped);
+            encoded.push(0);
+        }
+    }
+
+    for length in encoded_lengths.into_iter().rev() {
+        match length {
+            0..=0x7F => {
+                encoded.push(u8::try_from(length).unwrap());
+            }
+            0x80..=0x3FFF => {
+                encoded.push(u8::try_from(length >> 7).unwrap());
+                encoded.push(u8::try_from((length & 0x7F) | 0x80).unwrap());
+            }
+            0x4000.. => {
+                encoded.push(u8::try_from(length >> 14).unwrap());
+                encoded.push(u8::try_from(((length >> 7) & 0x7F) | 0x80).unwrap());
+                encoded.push(u8::try_from((length & 0x7F) | 0x80).unwrap());
+            }
+        }
+    }
+    Ok(encoded)
+}
+
+fn escape_nulls(unescaped: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(unescaped.len());
+    for byte in unescaped {
+        escaped.push(*byte);
+        if *byte == 0 {
+            escaped.push(0);
+        }
+    }
+    escaped
+}
+
+/// Decodes fields encoded with
+/// [`CompositeKeyEncoder`](crate::key::CompositeKeyEncoder), assuming the
+/// default null byte escaping.
+fn decode_composite(fields: &[KeyDescription], bytes: &[u8]) -> Result<Value<'static>, Error> {
+    let mut offset = 0;
+    let mut end = bytes.len();
+    let mut values = Vec::with_capacity(fields.len());
+    for field in fields {
+        let length = field
+            .encoded_length()
+            .ok_or_else(|| invalid_key("field length can't be determined"))?;
+        let value = if let Some(length) = length {
+            let field_end = offset + length;
+            if field_end > end {
+                return Err(invalid_key("unexpected end of key"));
+            }
+            let value = field.decode_value(&bytes[offset..field_end])?;
+            offset = field_end;
+            value
+        } else {
+            // Variable length fields have their lengths encoded in reverse
+            // order at the end of the key.
+            let mut length = 0;
+            let mut found_end = false;
+            for (index, byte) in bytes[offset..end].iter().rev().enumerate() {
+                length |= usize::from(byte & 0x7f) << (index * 7);
+                if byte & 0x80 == 0 {
+                    end = end - index - 1;
+                    found_end = true;
+                    break;
+                }
+            }
+            let field_end = offset + length;
+            if !found_end || field_end > end {
+                return Err(invalid_key("unexpected end of key"));
+            }
+            let value = field.decode_value(&unescape_nulls(&bytes[offset..field_end]))?;
+            // Variable length fields are followed by a null delimiter.
+            offset = field_end + 1;
+            value
+        };
+        values.push(value);
+    }
+
+    if offset == end {
+        Ok(Value::Sequence(values))
+    } else {
+        Err(invalid_key("trailing bytes"))
+    }
+}
+
+fn unescape_nulls(escaped: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(escaped.len());
+    let mut bytes = escaped.iter().copied();
+    while let Some(byte) = bytes.next() {
+        unescaped.push(byte);
+        if byte == 0 {
+            // Null bytes are escaped by repeating them.
+            bytes.next();
+        }
+    }
+    unescaped
+}
+
+fn invalid_key(error: impl std::fmt::Display) -> Error {
+    Error::other("key", error)
+}
+
+/// Returns the description of the field at `index`, or an error if the
+/// description doesn't contain enough fields for its kind.
+fn field(fields: &[KeyDescription], index: usize) -> Result<&KeyDescription, Error> {
+    fields
+        .get(index)
+        .ok_or_else(|| invalid_key(format!("key description is missing field {index}")))
+}
+
+#[test]
+fn decode_value_tests() {
+    use crate::key::KeyEncoding;
+
+    fn round_trip<K: for<'k> crate::key::Key<'k>>(key: &K) -> Value<'static> {
+        let description = KeyDescription::for_key::<K>();
+        let encoded = key.as_ord_bytes().unwrap();
+        let decoded = description.decode_value(&encoded).unwrap();
+        assert_eq!(
+            description.encode_value(&decoded).unwrap(),
+            encoded.as_ref()
+        );
+        decoded
+    }
+
+    assert_eq!(round_trip(&42_u64), Value::from(42_u64));
+    assert_eq!(round_trip(&-1_i32), Value::from(-1_i32));
+    assert_eq!(round_trip(&true), Value::Bool(true));
+    assert_eq!(round_trip(&String::from("hello")), Value::from("hello"));
+    assert_eq!(round_trip(&Some(1_u8)), Value::from(1_u8));
+    assert_eq!(round_trip(&Option::<u8>::None), Value::None);
+    assert_eq!(
+        round_trip(&(String::from("a\0b"), 7_u16, vec![0_u8, 1])),
+        Value::Sequence(vec![
+            Value::from("a\0b"),
+            Value::from(7_u16),
+            Value::Bytes(vec![0_u8, 1].into()),
+        ])
+    );
+    assert_eq!(
+        round_trip(&Result::<u8, String>::Err(String::from("oops"))),
+        Value::Mappings(vec![(Value::from("Err"), Value::from("oops"))])
+    );
+
+    // Descriptions missing fields for their kind produce errors instead of
+    // panicking.
+    let truncated = KeyDescription::Composite(crate::key::CompositeKeyDescription {
+        kind: CompositeKind::Result,
+        fields: vec![KeyDescription::Basic(KeyKind::U8)],
+        attributes: std::collections::HashMap::new(),
+    });
+    assert!(truncated.decode_value(&[1, 0]).is_err());
+    assert!(truncated
+        .encode_value(&Value::Mappings(vec![(
+            Value::from("Err"),
+            Value::from(0_u8)
+        )]))
+        .is_err());
+}
@@ -47,11 +47,12 @@ pub mod pubsub;
 
 use std::fmt::Display;
 use std::string::FromUtf8Error;
+use std::time::Duration;
 
 use schema::{view, CollectionName, SchemaName, ViewName};
 use serde::{Deserialize, Serialize};
 pub use {
-    actionable, arc_bytes, async_trait, circulate, num_traits, ordered_varint, transmog,
+    actionable, arc_bytes, async_trait, circulate, num_traits, ordered_varint, pot, transmog,
     transmog_pot,
 };
 
@@ -178,6 +179,26 @@ pub enum Error {
     #[error("user not found")]
     UserNotFound,
 
+    /// The permission group specified was not found.
+    #[error("permission group not found")]
+    PermissionGroupNotFound,
+
+    /// The role specified was not found.
+    #[error("role not found")]
+    RoleNotFound,
+
+    /// The authentication token specified was not found.
+    #[error("authentication token not found")]
+    AuthenticationTokenNotFound,
+
+    /// The session specified was not found.
+    #[error("session not found")]
+    SessionNotFound,
+
+    /// The connected client specified was not found.
+    #[error("client not found")]
+    ClientNotFound,
+
     /// An error occurred converting from bytes to Utf-8.
     #[error("invalid string: {0}")]
     InvalidUnicode(String),
@@ -186,6 +207,35 @@ pub enum Error {
     #[error("invalid credentials")]
     InvalidCredentials,
 
+    /// The password provided is shorter than the configured minimum length.
+    #[error("password must be at least {minimum_length} characters long")]
+    PasswordTooShort {
+        /// The minimum number of characters a password must contain.
+        minimum_length: usize,
+    },
+
+    /// The password provided is a commonly used password.
+    #[error("password is too common")]
+    CommonPassword,
+
+    /// The user is temporarily locked out due to too many failed login
+    /// attempts. Logging in may be attempted again after `retry_after` has
+    /// elapsed.
+    #[error("user is locked out, retry after {retry_after:?}")]
+    UserLockedOut {
+        /// The amount of time until the lockout ends.
+        retry_after: Duration,
+    },
+
+    /// Too many failed authentication attempts have been made from the
+    /// client's address. Authentication may be attempted again after
+    /// `retry_after` has elapsed.
+    #[error("too many failed authentication attempts, retry after {retry_after:?}")]
+    AuthenticationThrottled {
+        /// The amount of time to wait before authenticating again.
+        retry_after: Duration,
+    },
+
     /// Returned when the a view's reduce() function is unimplemented.
     #[error("reduce is unimplemented")]
     ReduceUnimplemented,
@@ -198,6 +248,14 @@ pub enum Error {
     #[error("time error: {0}")]
     Time(#[from] TimeError),
 
+    /// The request was rejected because a rate limit was exceeded. The request
+    /// may be retried after `retry_after` has elapsed.
+    #[error("rate limit exceeded, retry after {retry_after:?}")]
+    RateLimited {
+        /// The amount of time to wait before retrying the request.
+        retry_after: Duration,
+    },
+
     /// An error from another crate.
     #[error("error from {origin}: {error}")]
     Other {
@@ -1,12 +1,20 @@
+use std::net::SocketAddr;
+
 use arc_bytes::serde::Bytes;
 use serde::{Deserialize, Serialize};
 
+use crate::admin::{AuthenticationTokenSummary, PermissionGroup, Role};
 use crate::api::{Api, ApiName};
 use crate::connection::{
-    AccessPolicy, Database, IdentityReference, Range, SerializedQueryKey, Session, SessionId, Sort,
+    AccessPolicy, Database, Identity, IdentityReference, Range, SerializedQueryKey, Session,
+    SessionId, Sort,
 };
 use crate::document::{DocumentId, Header, OwnedDocument};
+use crate::key::time::TimestampAsNanoseconds;
 use crate::keyvalue::{KeyOperation, Output};
+use crate::permissions::{
+    ActionName, ClientContext, ClientTransport, PermissionExplanation, ResourceName,
+};
 use crate::schema::view::map::{self, MappedSerializedDocuments};
 use crate::schema::{CollectionName, NamedReference, Qualified, SchemaSummary, ViewName};
 use crate::transaction::{Executed, OperationResult, Transaction};
@@ -183,6 +191,65 @@ impl Api for LogOutSession {
     }
 }
 
+/// Lists the sessions of every client connected to the server.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListSessions;
+
+impl Api for ListSessions {
+    type Error = crate::Error;
+    type Response = Vec<ActiveSession>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListSessions")
+    }
+}
+
+/// A session of a client connected to a server.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ActiveSession {
+    /// The unique id of the client the session belongs to.
+    pub client_id: u32,
+    /// The id of the session, or `None` for the client's unauthenticated
+    /// session.
+    pub session_id: Option<SessionId>,
+    /// The identity the session is authenticated as, if any.
+    pub identity: Option<Identity>,
+    /// The transport the client is connected with.
+    pub transport: ClientTransport,
+    /// The address of the client.
+    pub address: SocketAddr,
+    /// When the client connected.
+    pub connected_at: TimestampAsNanoseconds,
+    /// When the client last sent a request.
+    pub last_activity_at: TimestampAsNanoseconds,
+}
+
+/// Ends another client's authenticated session.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct TerminateSession(pub SessionId);
+
+impl Api for TerminateSession {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "TerminateSession")
+    }
+}
+
+/// Closes a connected client's connection.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct DisconnectClient(pub u32);
+
+impl Api for DisconnectClient {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "DisconnectClient")
+    }
+}
+
 /// Alter's a user's membership in a permission group.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct AlterUserPermissionGroupMembership {
@@ -227,6 +294,182 @@ impl Api for AlterUserRoleMembership {
     }
 }
 
+/// Creates a permission group.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct CreatePermissionGroup {
+    /// The group to create.
+    pub group: PermissionGroup,
+}
+
+impl Api for CreatePermissionGroup {
+    type Error = crate::Error;
+    type Response = u64;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "CreatePermissionGroup")
+    }
+}
+
+/// Updates a permission group's name and statements.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct UpdatePermissionGroup {
+    /// The name or id of the group.
+    pub group: NamedReference<'static, u64>,
+
+    /// The new contents of the group.
+    pub updated: PermissionGroup,
+}
+
+impl Api for UpdatePermissionGroup {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "UpdatePermissionGroup")
+    }
+}
+
+/// Deletes a permission group.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct DeletePermissionGroup {
+    /// The name or id of the group.
+    pub group: NamedReference<'static, u64>,
+}
+
+impl Api for DeletePermissionGroup {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "DeletePermissionGroup")
+    }
+}
+
+/// Creates a role.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct CreateRole {
+    /// The role to create.
+    pub role: Role,
+}
+
+impl Api for CreateRole {
+    type Error = crate::Error;
+    type Response = u64;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "CreateRole")
+    }
+}
+
+/// Updates a role's name and permission groups.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct UpdateRole {
+    /// The name or id of the role.
+    pub role: NamedReference<'static, u64>,
+
+    /// The new contents of the role.
+    pub updated: Role,
+}
+
+impl Api for UpdateRole {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "UpdateRole")
+    }
+}
+
+/// Deletes a role.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct DeleteRole {
+    /// The name or id of the role.
+    pub role: NamedReference<'static, u64>,
+}
+
+impl Api for DeleteRole {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "DeleteRole")
+    }
+}
+
+/// Lists the authentication tokens of a user.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListAuthenticationTokens {
+    /// The name or id of the user.
+    pub user: NamedReference<'static, u64>,
+}
+
+impl Api for ListAuthenticationTokens {
+    type Error = crate::Error;
+    type Response = Vec<AuthenticationTokenSummary>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListAuthenticationTokens")
+    }
+}
+
+/// Explains whether an identity is permitted to perform an action.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ExplainPermission {
+    /// The user or role whose permissions to explain.
+    pub identity: IdentityReference<'static>,
+    /// The resource the action is performed on.
+    pub resource_name: ResourceName<'static>,
+    /// The name of the action.
+    pub action: ActionName,
+    /// The client the identity is connecting as.
+    pub client: ClientContext,
+}
+
+impl Api for ExplainPermission {
+    type Error = crate::Error;
+    type Response = PermissionExplanation;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ExplainPermission")
+    }
+}
+
+/// Creates an authentication token.
+#[cfg(feature = "token-authentication")]
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct CreateAuthenticationToken {
+    /// The user or role the token authenticates as.
+    pub identity: IdentityReference<'static>,
+    /// The options of the token.
+    pub options: crate::admin::AuthenticationTokenOptions,
+}
+
+#[cfg(feature = "token-authentication")]
+impl Api for CreateAuthenticationToken {
+    type Error = crate::Error;
+    type Response = crate::admin::CreatedAuthenticationToken;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "CreateAuthenticationToken")
+    }
+}
+
+/// Revokes an authentication token.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct RevokeAuthenticationToken {
+    /// The id of the token to revoke.
+    pub token_id: u64,
+}
+
+impl Api for RevokeAuthenticationToken {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "RevokeAuthenticationToken")
+    }
+}
+
 /// Retrieve a single document.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct Get {
@@ -91,6 +91,22 @@ pub fn user_resource_name<'a>(user_id: u64) -> ResourceName<'a> {
     bonsaidb_resource_name().and("user").and(user_id)
 }
 
+/// Creates a resource name for `permission_group_id`.
+#[must_use]
+pub fn permission_group_resource_name<'a>(permission_group_id: u64) -> ResourceName<'a> {
+    bonsaidb_resource_name()
+        .and("permission-group")
+        .and(permission_group_id)
+}
+
+/// Creates a resource name for `token_id`.
+#[must_use]
+pub fn authentication_token_resource_name<'a>(token_id: u64) -> ResourceName<'a> {
+    bonsaidb_resource_name()
+        .and("authentication-token")
+        .and(token_id)
+}
+
 /// Creates a resource name for `role_id`.
 #[must_use]
 pub fn role_resource_name<'a>(role_id: u64) -> ResourceName<'a> {
@@ -146,6 +162,34 @@ pub enum ServerAction {
     /// Permits .
     /// Permits [`StorageConnection::add_role_to_user`](crate::connection::StorageConnection::add_role_to_user) and [`StorageConnection::remove_role_from_user`](crate::connection::StorageConnection::remove_role_from_user).
     ModifyUserRoles,
+    /// Permits [`StorageConnection::create_permission_group`](crate::connection::StorageConnection::create_permission_group).
+    CreatePermissionGroup,
+    /// Permits [`StorageConnection::update_permission_group`](crate::connection::StorageConnection::update_permission_group).
+    ModifyPermissionGroup,
+    /// Permits [`StorageConnection::delete_permission_group`](crate::connection::StorageConnection::delete_permission_group).
+    DeletePermissionGroup,
+    /// Permits [`StorageConnection::create_role`](crate::connection::StorageConnection::create_role).
+    CreateRole,
+    /// Permits [`StorageConnection::update_role`](crate::connection::StorageConnection::update_role).
+    ModifyRole,
+    /// Permits [`StorageConnection::delete_role`](crate::connection::StorageConnection::delete_role).
+    DeleteRole,
+    /// Permits [`StorageConnection::list_authentication_tokens`](crate::connection::StorageConnection::list_authentication_tokens).
+    ListAuthenticationTokens,
+    /// Permits [`StorageConnection::create_authentication_token`](crate::connection::StorageConnection::create_authentication_token).
+    CreateAuthenticationToken,
+    /// Permits [`StorageConnection::revoke_authentication_token`](crate::connection::StorageConnection::revoke_authentication_token).
+    RevokeAuthenticationToken,
+    /// Permits viewing the server's metrics through its HTTP endpoint.
+    ViewMetrics,
+    /// Permits listing the sessions of all connected clients.
+    ListSessions,
+    /// Permits ending another client's session.
+    TerminateSession,
+    /// Permits closing a connected client's connection.
+    DisconnectClient,
+    /// Permits [`StorageConnection::explain_permission`](crate::connection::StorageConnection::explain_permission).
+    ExplainPermission,
 }
 
 /// Actions that operate on a specific database.
@@ -153,6 +197,9 @@ pub enum ServerAction {
 pub enum DatabaseAction {
     /// The ability to compact data to reclaim space.
     Compact,
+    /// The ability to apply or preview pending schema migrations through
+    /// `Database::migrate()` and `Database::migrate_dry_run()`.
+    Migrate,
     /// Actions that operate on a document.
     Document(DocumentAction),
     /// Actions that operate on a view.
@@ -0,0 +1,336 @@
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use actionable::{Action, Identifier, Permissions, Statement};
+use serde::{Deserialize, Serialize};
+
+use crate::key::time::TimestampAsNanoseconds;
+
+/// A [`Statement`] that only applies while its [`Conditions`] are met.
+///
+/// Conditional statements are evaluated before a session's static
+/// permissions. If any applicable [`Effect::Deny`] statement matches an
+/// action, the action is denied, even if other statements allow it. Otherwise,
+/// if any applicable [`Effect::Allow`] statement matches, the action is
+/// allowed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[must_use]
+pub struct ConditionalStatement {
+    /// The resources and actions this statement applies to.
+    pub statement: Statement,
+    /// Whether matching actions are allowed or denied.
+    pub effect: Effect,
+    /// The conditions that must be met for this statement to apply.
+    pub conditions: Conditions,
+}
+
+impl ConditionalStatement {
+    /// Returns a statement allowing the actions in `statement` while
+    /// `conditions` are met.
+    pub const fn allow(statement: Statement, conditions: Conditions) -> Self {
+        Self {
+            statement,
+            effect: Effect::Allow,
+            conditions,
+        }
+    }
+
+    /// Returns a statement denying the actions in `statement` while
+    /// `conditions` are met.
+    pub const fn deny(statement: Statement, conditions: Conditions) -> Self {
+        Self {
+            statement,
+            effect: Effect::Deny,
+            conditions,
+        }
+    }
+
+    /// Returns true if this statement applies to `client` at `now`.
+    ///
+    /// Conditions that depend on information `client` doesn't have, such as
+    /// its address, are treated as met by deny statements and unmet by allow
+    /// statements. Missing information can therefore never grant access.
+    #[must_use]
+    pub fn applies(&self, client: &ClientContext, now: TimestampAsNanoseconds) -> bool {
+        self.conditions
+            .evaluate(client, now, self.effect == Effect::Deny)
+    }
+}
+
+/// The effect of a [`ConditionalStatement`].
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    /// Matching actions are allowed.
+    #[default]
+    Allow,
+    /// Matching actions are denied. Deny statements take precedence over all
+    /// other statements.
+    Deny,
+}
+
+/// Conditions that restrict when a [`ConditionalStatement`] applies. A
+/// statement applies only if all of its conditions are met.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[must_use]
+pub struct Conditions {
+    /// If set, the statement does not apply before this time.
+    pub not_before: Option<TimestampAsNanoseconds>,
+    /// If set, the statement does not apply at or after this time.
+    pub not_after: Option<TimestampAsNanoseconds>,
+    /// If set, the statement only applies to clients connected using one of
+    /// these transports.
+    pub transports: Option<Vec<ClientTransport>>,
+    /// If set, the statement only applies to clients connecting from an
+    /// address within one of these ranges.
+    pub addresses: Option<Vec<AddressRange>>,
+}
+
+impl Conditions {
+    /// Sets [`Self::not_before`](Self#structfield.not_before) to `timestamp`
+    /// and returns self.
+    pub fn not_before(mut self, timestamp: TimestampAsNanoseconds) -> Self {
+        self.not_before = Some(timestamp);
+        self
+    }
+
+    /// Sets [`Self::not_after`](Self#structfield.not_after) to `timestamp`
+    /// and returns self.
+    pub fn not_after(mut self, timestamp: TimestampAsNanoseconds) -> Self {
+        self.not_after = Some(timestamp);
+        self
+    }
+
+    /// Adds `transport` to [`Self::transports`](Self#structfield.transports)
+    /// and returns self.
+    pub fn with_transport(mut self, transport: ClientTransport) -> Self {
+        self.transports.get_or_insert_with(Vec::new).push(transport);
+        self
+    }
+
+    /// Adds `range` to [`Self::addresses`](Self#structfield.addresses) and
+    /// returns self.
+    pub fn with_address_range(mut self, range: AddressRange) -> Self {
+        self.addresses.get_or_insert_with(Vec::new).push(range);
+        self
+    }
+
+    /// Returns true if these conditions are met by `client` at `now`.
+    /// Conditions that depend on information `client` doesn't have are not
+    /// met.
+    #[must_use]
+    pub fn are_met(&self, client: &ClientContext, now: TimestampAsNanoseconds) -> bool {
+        self.evaluate(client, now, false)
+    }
+
+    /// Evaluates these conditions for `client` at `now`. Conditions that
+    /// depend on information `client` doesn't have evaluate to `if_unknown`.
+    fn evaluate(
+        &self,
+        client: &ClientContext,
+        now: TimestampAsNanoseconds,
+        if_unknown: bool,
+    ) -> bool {
+        self.not_before.map_or(true, |not_before| now >= not_before)
+            && self.not_after.map_or(true, |not_after| now < not_after)
+            && self.transports.as_ref().map_or(true, |transports| {
+                client
+                    .transport
+                    .map_or(if_unknown, |transport| transports.contains(&transport))
+            })
+            && self.addresses.as_ref().map_or(true, |ranges| {
+                client.address.map_or(if_unknown, |address| {
+                    ranges.iter().any(|range| range.contains(address))
+                })
+            })
+    }
+}
+
+/// A range of IP addresses, expressed as an address and the number of leading
+/// bits that must match.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AddressRange {
+    /// The address of the network.
+    pub address: IpAddr,
+    /// The number of leading bits of [`Self::address`](Self#structfield.address)
+    /// that addresses within this range share.
+    pub prefix_length: u8,
+}
+
+impl AddressRange {
+    /// Returns a range containing only `address`.
+    #[must_use]
+    pub const fn single(address: IpAddr) -> Self {
+        let prefix_length = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        Self {
+            address,
+            prefix_length,
+        }
+    }
+
+    /// Returns a range of the addresses sharing the first `prefix_length` bits
+    /// of `address`.
+    #[must_use]
+    pub const fn new(address: IpAddr, prefix_length: u8) -> Self {
+        Self {
+            address,
+            prefix_length,
+        }
+    }
+
+    /// Returns true if `address` is within this range.
+    #[must_use]
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (canonical_address(self.address), canonical_address(address)) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => prefix_matches(
+                u128::from(u32::from(network)) << 96,
+                u128::from(u32::from(address)) << 96,
+                self.prefix_length.min(32),
+            ),
+            (IpAddr::V6(network), IpAddr::V6(address)) => prefix_matches(
+                u128::from(network),
+                u128::from(address),
+                self.prefix_length.min(128),
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the IPv4 address `address` maps to if it is an IPv4-mapped IPv6
+/// address (`::ffff:a.b.c.d`), otherwise returns `address` unchanged.
+///
+/// Listeners bound to an unspecified IPv6 address report IPv4 clients using
+/// IPv4-mapped addresses. This function is equivalent to
+/// `IpAddr::to_canonical`, which isn't available in this crate's minimum
+/// supported Rust version.
+#[must_use]
+pub fn canonical_address(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(address, IpAddr::V4),
+        IpAddr::V4(_) => address,
+    }
+}
+
+fn prefix_matches(network: u128, address: u128, prefix_length: u8) -> bool {
+    let mask = u128::MAX
+        .checked_shl(128 - u32::from(prefix_length))
+        .unwrap_or(0);
+    network & mask == address & mask
+}
+
+/// The transport a client is connected to a server with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ClientTransport {
+    /// BonsaiDb's QUIC-based protocol.
+    Bonsai,
+    /// WebSockets.
+    WebSocket,
+    /// HTTP requests, such as the REST API.
+    Http,
+}
+
+impl Display for ClientTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bonsai => f.write_str("bonsai"),
+            Self::WebSocket => f.write_str("websocket"),
+            Self::Http => f.write_str("http"),
+        }
+    }
+}
+
+impl FromStr for ClientTransport {
+    type Err = UnknownTransport;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bonsai" => Ok(Self::Bonsai),
+            "websocket" => Ok(Self::WebSocket),
+            "http" => Ok(Self::Http),
+            other => Err(UnknownTransport(other.to_string())),
+        }
+    }
+}
+
+/// An error parsing a [`ClientTransport`].
+#[derive(thiserror::Error, Debug)]
+#[error("unknown transport '{0}', expected bonsai, websocket, or http")]
+pub struct UnknownTransport(pub String);
+
+/// Information about the client a [`Session`](crate::connection::Session) is
+/// being used by, used when evaluating [`Conditions`].
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ClientContext {
+    /// The transport the client is connected with.
+    pub transport: Option<ClientTransport>,
+    /// The address the client is connecting from.
+    pub address: Option<IpAddr>,
+}
+
+/// A set of [`ConditionalStatement`]s.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ConditionalPermissions {
+    /// The conditional statements.
+    pub statements: Vec<ConditionalStatement>,
+}
+
+impl ConditionalPermissions {
+    /// Evaluates these statements for `action` on `resource_name` by `client`.
+    ///
+    /// Returns `Some(false)` if an applicable deny statement matches,
+    /// `Some(true)` if an applicable allow statement matches, and `None` if no
+    /// applicable statements match.
+    #[must_use]
+    pub fn evaluate<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+        client: &ClientContext,
+    ) -> Option<bool> {
+        if self.statements.is_empty() {
+            return None;
+        }
+
+        let resource_name = resource_name.as_ref();
+        let now = TimestampAsNanoseconds::now();
+        let mut allowed = None;
+        for statement in &self.statements {
+            if !statement.applies(client, now)
+                || !Permissions::from(vec![statement.statement.clone()])
+                    .allowed_to(resource_name, action)
+            {
+                continue;
+            }
+
+            match statement.effect {
+                Effect::Deny => return Some(false),
+                Effect::Allow => allowed = Some(true),
+            }
+        }
+        allowed
+    }
+
+    /// Returns true if there are no statements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+impl From<Vec<ConditionalStatement>> for ConditionalPermissions {
+    fn from(statements: Vec<ConditionalStatement>) -> Self {
+        Self { statements }
+    }
+}
+
+impl FromIterator<ConditionalStatement> for ConditionalPermissions {
+    fn from_iter<T: IntoIterator<Item = ConditionalStatement>>(iter: T) -> Self {
+        Self {
+            statements: iter.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use actionable::{Action, Identifier, Permissions, Statement};
+use serde::{Deserialize, Serialize};
+
+use crate::admin::PermissionGroup;
+use crate::document::CollectionDocument;
+use crate::key::time::TimestampAsNanoseconds;
+use crate::permissions::{ClientContext, Conditions, Effect};
+
+/// An explanation of whether an identity is permitted to perform an action,
+/// returned from
+/// [`StorageConnection::explain_permission`](crate::connection::StorageConnection::explain_permission).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PermissionExplanation {
+    /// True if the action is permitted.
+    pub allowed: bool,
+    /// Every statement that matches the action, in the order they were
+    /// found. Statements whose conditions aren't met are included, but do not
+    /// contribute to the decision.
+    pub statements: Vec<ExplainedStatement>,
+}
+
+impl PermissionExplanation {
+    pub(crate) fn new<'a, P: Action>(
+        allowed: bool,
+        groups: impl IntoIterator<Item = (Option<String>, CollectionDocument<PermissionGroup>)>,
+        inherit_permissions: &Permissions,
+        resource_name: &[Identifier<'a>],
+        action: &P,
+        client: &ClientContext,
+    ) -> Self {
+        let now = TimestampAsNanoseconds::now();
+        let matches = |statement: &Statement| {
+            Permissions::from(vec![statement.clone()]).allowed_to(resource_name, action)
+        };
+
+        let mut statements = Vec::new();
+        for (role, group) in groups {
+            let source = StatementSource::Group {
+                id: group.header.id,
+                name: group.contents.name,
+                role,
+            };
+            for statement in group.contents.statements {
+                if matches(&statement) {
+                    statements.push(ExplainedStatement {
+                        source: source.clone(),
+                        effect: Effect::Allow,
+                        statement: Some(statement),
+                        conditions: None,
+                        applies: true,
+                    });
+                }
+            }
+            for conditional in group.contents.conditional_statements {
+                if matches(&conditional.statement) {
+                    statements.push(ExplainedStatement {
+                        source: source.clone(),
+                        effect: conditional.effect,
+                        applies: conditional.applies(client, now),
+                        statement: Some(conditional.statement),
+                        conditions: Some(conditional.conditions),
+                    });
+                }
+            }
+        }
+
+        if inherit_permissions.allowed_to(resource_name, action) {
+            statements.push(ExplainedStatement {
+                source: StatementSource::Inherited,
+                effect: Effect::Allow,
+                statement: None,
+                conditions: None,
+                applies: true,
+            });
+        }
+
+        Self {
+            allowed,
+            statements,
+        }
+    }
+}
+
+/// A statement that matches the action being explained in a
+/// [`PermissionExplanation`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExplainedStatement {
+    /// Where the statement came from.
+    pub source: StatementSource,
+    /// Whether the statement allows or denies the action.
+    pub effect: Effect,
+    /// The statement. This is `None` for [`StatementSource::Inherited`] and
+    /// [`StatementSource::Owner`], as these sources are not made up of
+    /// inspectable statements.
+    pub statement: Option<Statement>,
+    /// The statement's conditions, if it is a
+    /// [`ConditionalStatement`](crate::permissions::ConditionalStatement).
+    pub conditions: Option<Conditions>,
+    /// True if the statement's conditions are met.
+    pub applies: bool,
+}
+
+/// The source of an [`ExplainedStatement`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StatementSource {
+    /// The statement belongs to a [`PermissionGroup`].
+    Group {
+        /// The id of the group.
+        id: u64,
+        /// The name of the group.
+        name: String,
+        /// The name of the role the group was assigned through, or `None` if
+        /// the group was assigned directly.
+        role: Option<String>,
+    },
+    /// The action is allowed by the permissions every authenticated identity
+    /// inherits, configured using `StorageConfiguration::authenticated_permissions`.
+    Inherited,
+    /// The identity owns the document being accessed. Owners of documents in
+    /// collections with document ownership enabled using
+    /// [`Schematic::define_document_ownership()`](crate::schema::Schematic::define_document_ownership)
+    /// can get, update, overwrite, and delete their documents unless the
+    /// action is denied.
+    Owner,
+}
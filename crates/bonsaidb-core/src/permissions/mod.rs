@@ -1,7 +1,12 @@
 /// Types used for granting permissions within BonsaiDb.
 pub mod bonsai;
+mod conditional;
+mod explain;
 
 pub use actionable::{
     Action, ActionName, ActionNameList, Actionable, Dispatcher, Identifier, PermissionDenied,
     Permissions, ResourceName, Statement,
 };
+
+pub use self::conditional::*;
+pub use self::explain::*;
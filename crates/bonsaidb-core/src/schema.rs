@@ -1,4 +1,5 @@
 mod collection;
+mod migration;
 mod names;
 mod schematic;
 mod summary;
@@ -11,12 +12,16 @@ pub use self::collection::{
     AsyncEntry, AsyncList, Collection, DefaultSerialization, Entry, EntryInsert, EntryUpdate,
     InsertError, List, Nameable, NamedCollection, NamedReference, SerializedCollection,
 };
+pub use self::migration::{MigrateFn, Migration};
 pub use self::names::{
     Authority, CollectionName, InvalidNameError, Name, Qualified, QualifiedName, SchemaName,
     ViewName,
 };
 pub use self::schematic::Schematic;
-pub use self::summary::{CollectionSummary, SchemaSummary, ViewSummary};
+pub use self::summary::{
+    CollectionSummary, DynamicDocument, DynamicMapping, SchemaSummary, SerializationFormat,
+    ViewSummary,
+};
 pub use self::view::map::{Map, MappedValue, ViewMappedValue};
 pub use self::view::{
     CollectionMapReduce, DefaultViewSerialization, MapReduce, ReduceResult, SerializedView, View,
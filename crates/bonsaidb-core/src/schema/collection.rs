@@ -17,7 +17,7 @@ use crate::document::{
     OwnedDocument, OwnedDocuments, Revision,
 };
 use crate::key::{IntoPrefixRange, Key, KeyEncoding};
-use crate::schema::{CollectionName, Schematic};
+use crate::schema::{CollectionName, Schematic, SerializationFormat};
 use crate::transaction::{Operation, OperationResult, Transaction};
 use crate::Error;
 
@@ -252,6 +252,16 @@ pub trait Collection: Send + Sync {
     fn encryption_key() -> Option<KeyId> {
         None
     }
+
+    /// Returns a description of how this collection's documents are
+    /// serialized, which allows tools without access to this type to decode
+    /// the documents. The derive macro implements this function, but manual
+    /// implementations of this trait will report
+    /// [`SerializationFormat::Unknown`] unless this function is implemented.
+    #[must_use]
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Unknown
+    }
 }
 
 /// A collection that knows how to serialize and deserialize documents to an associated type.
@@ -0,0 +1,59 @@
+use std::fmt::Debug;
+
+use crate::document::OwnedDocument;
+use crate::schema::CollectionName;
+use crate::Error;
+
+/// A function that migrates a single stored document. Returning `Ok(None)`
+/// deletes the document.
+pub type MigrateFn =
+    dyn Fn(OwnedDocument) -> Result<Option<OwnedDocument>, Error> + Send + Sync + 'static;
+
+/// A migration of the documents stored in a collection, defined using
+/// [`Schematic::define_migration()`](crate::schema::Schematic::define_migration).
+///
+/// Migrations are applied in the order they were defined. Each collection
+/// tracks the version of the last migration applied to it, where the first
+/// migration defined for a collection is version 1.
+pub struct Migration {
+    /// The collection this migration applies to.
+    pub collection: CollectionName,
+    /// The version of the collection's contents after this migration has been
+    /// applied.
+    pub version: u64,
+    /// A description of this migration.
+    pub name: String,
+    migrate: Box<MigrateFn>,
+}
+
+impl Migration {
+    pub(crate) fn new(
+        collection: CollectionName,
+        version: u64,
+        name: String,
+        migrate: Box<MigrateFn>,
+    ) -> Self {
+        Self {
+            collection,
+            version,
+            name,
+            migrate,
+        }
+    }
+
+    /// Migrates `document`, returning the updated document or `None` if the
+    /// document should be deleted.
+    pub fn migrate(&self, document: OwnedDocument) -> Result<Option<OwnedDocument>, Error> {
+        (self.migrate)(document)
+    }
+}
+
+impl Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migration")
+            .field("collection", &self.collection)
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,18 +1,19 @@
 use std::any::TypeId;
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
 use derive_where::derive_where;
 
-use crate::document::{BorrowedDocument, DocumentId, KeyId};
+use crate::document::{BorrowedDocument, DocumentId, KeyId, OwnedDocument};
 use crate::key::{ByteSource, Key, KeyDescription};
 use crate::schema::collection::Collection;
+use crate::schema::migration::Migration;
 use crate::schema::view::map::{self, MappedValue};
 use crate::schema::view::{
     self, MapReduce, Serialized, SerializedView, ViewSchema, ViewUpdatePolicy,
 };
-use crate::schema::{CollectionName, Schema, SchemaName, View, ViewName};
+use crate::schema::{CollectionName, Schema, SchemaName, SerializationFormat, View, ViewName};
 use crate::Error;
 
 /// A collection of defined collections and views.
@@ -20,6 +21,7 @@ pub struct Schematic {
     /// The name of the schema this was built from.
     pub name: SchemaName,
     contained_collections: HashMap<CollectionName, KeyDescription>,
+    collection_formats: HashMap<CollectionName, SerializationFormat>,
     collections_by_type_id: HashMap<TypeId, CollectionName>,
     collection_encryption_keys: HashMap<CollectionName, KeyId>,
     collection_id_generators: HashMap<CollectionName, Box<dyn IdGenerator>>,
@@ -27,6 +29,8 @@ pub struct Schematic {
     views_by_name: HashMap<ViewName, TypeId>,
     views_by_collection: HashMap<CollectionName, Vec<TypeId>>,
     eager_views_by_collection: HashMap<CollectionName, Vec<TypeId>>,
+    migrations: HashMap<CollectionName, Vec<Migration>>,
+    owned_collections: HashSet<CollectionName>,
 }
 
 impl Schematic {
@@ -35,6 +39,7 @@ impl Schematic {
         let mut schematic = Self {
             name: S::schema_name(),
             contained_collections: HashMap::new(),
+            collection_formats: HashMap::new(),
             collections_by_type_id: HashMap::new(),
             collection_encryption_keys: HashMap::new(),
             collection_id_generators: HashMap::new(),
@@ -42,6 +47,8 @@ impl Schematic {
             views_by_name: HashMap::new(),
             views_by_collection: HashMap::new(),
             eager_views_by_collection: HashMap::new(),
+            migrations: HashMap::new(),
+            owned_collections: HashSet::new(),
         };
         S::define_collections(&mut schematic)?;
         Ok(schematic)
@@ -57,6 +64,8 @@ impl Schematic {
                 if let Some(key) = C::encryption_key() {
                     self.collection_encryption_keys.insert(name.clone(), key);
                 }
+                self.collection_formats
+                    .insert(name.clone(), C::serialization_format());
                 self.collection_id_generators
                     .insert(name, Box::<KeyIdGenerator<C>>::default());
                 entry.insert(KeyDescription::for_key::<C::PrimaryKey>());
@@ -107,6 +116,51 @@ impl Schematic {
         Ok(())
     }
 
+    /// Adds a migration for the stored documents of collection `C`. `migrate`
+    /// is invoked for each document in the collection, and can return the
+    /// updated document or `None` to delete it.
+    ///
+    /// Migrations for a collection are applied in the order they are defined,
+    /// so new migrations must always be defined after existing ones. This can
+    /// be called from [`Schema::define_collections()`] or
+    /// [`Collection::define_views()`].
+    pub fn define_migration<C, F>(&mut self, name: impl Into<String>, migrate: F)
+    where
+        C: Collection + 'static,
+        F: Fn(OwnedDocument) -> Result<Option<OwnedDocument>, Error> + Send + Sync + 'static,
+    {
+        let collection = C::collection_name();
+        let migrations = self.migrations.entry(collection.clone()).or_default();
+        let version = migrations.len() as u64 + 1;
+        migrations.push(Migration::new(
+            collection,
+            version,
+            name.into(),
+            Box::new(migrate),
+        ));
+    }
+
+    /// Enables ownership-based access control for the documents of collection
+    /// `C`.
+    ///
+    /// When enabled, the identity that inserts a document is recorded as its
+    /// owner. Reading, listing, counting, and querying views over the
+    /// collection is filtered to the documents the session owns or is allowed
+    /// to [`DocumentAction::Get`](crate::permissions::bonsai::DocumentAction::Get),
+    /// and owners are always allowed to update and delete their documents.
+    /// This can be called from [`Schema::define_collections()`] or
+    /// [`Collection::define_views()`].
+    pub fn define_document_ownership<C: Collection + 'static>(&mut self) {
+        self.owned_collections.insert(C::collection_name());
+    }
+
+    /// Returns `true` if `collection` has ownership-based access control
+    /// enabled. See [`Self::define_document_ownership()`].
+    #[must_use]
+    pub fn collection_has_document_ownership(&self, collection: &CollectionName) -> bool {
+        self.owned_collections.contains(collection)
+    }
+
     /// Returns `true` if this schema contains the collection `C`.
     #[must_use]
     pub fn contains_collection<C: Collection + 'static>(&self) -> bool {
@@ -123,6 +177,16 @@ impl Schematic {
         self.contained_collections.get(collection)
     }
 
+    /// Returns the description of how documents in the collection with the
+    /// given name are serialized, or `None` if the collection can't be found.
+    #[must_use]
+    pub fn collection_serialization_format<'a>(
+        &'a self,
+        collection: &CollectionName,
+    ) -> Option<&'a SerializationFormat> {
+        self.collection_formats.get(collection)
+    }
+
     /// Returns the next id in sequence for the collection, if the primary key
     /// type supports the operation and the next id would not overflow.
     pub fn next_id_for_collection(
@@ -200,6 +264,18 @@ impl Schematic {
     pub fn collections(&self) -> impl Iterator<Item = &CollectionName> {
         self.contained_collections.keys()
     }
+
+    /// Returns the migrations defined for `collection`, in the order they are
+    /// applied.
+    #[must_use]
+    pub fn migrations_in_collection(&self, collection: &CollectionName) -> &[Migration] {
+        self.migrations.get(collection).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns a list of all collections that have migrations defined.
+    pub fn migrated_collections(&self) -> impl Iterator<Item = &CollectionName> {
+        self.migrations.keys()
+    }
 }
 
 impl Debug for Schematic {
@@ -214,6 +290,7 @@ impl Debug for Schematic {
         f.debug_struct("Schematic")
             .field("name", &self.name)
             .field("contained_collections", &self.contained_collections)
+            .field("collection_formats", &self.collection_formats)
             .field("collections_by_type_id", &self.collections_by_type_id)
             .field(
                 "collection_encryption_keys",
@@ -224,6 +301,8 @@ impl Debug for Schematic {
             .field("views_by_name", &self.views_by_name)
             .field("views_by_collection", &self.views_by_collection)
             .field("eager_views_by_collection", &self.eager_views_by_collection)
+            .field("migrations", &self.migrations)
+            .field("owned_collections", &self.owned_collections)
             .finish()
     }
 }
@@ -247,6 +326,10 @@ where
         KeyDescription::for_key::<<V as View>::Key>()
     }
 
+    fn value_format(&self) -> SerializationFormat {
+        <V as SerializedView>::serialization_format()
+    }
+
     fn update_policy(&self) -> ViewUpdatePolicy {
         self.schema.update_policy()
     }
@@ -333,3 +416,26 @@ fn schema_tests() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn migration_tests() {
+    use crate::test_util::Basic;
+    let mut schema = Schematic::from_schema::<Basic>().unwrap();
+    assert!(schema
+        .migrations_in_collection(&Basic::collection_name())
+        .is_empty());
+
+    schema.define_migration::<Basic, _>("first", |doc| Ok(Some(doc)));
+    schema.define_migration::<Basic, _>("second", |_| Ok(None));
+
+    let migrations = schema.migrations_in_collection(&Basic::collection_name());
+    assert_eq!(migrations.len(), 2);
+    assert_eq!(migrations[0].name, "first");
+    assert_eq!(migrations[0].version, 1);
+    assert_eq!(migrations[1].name, "second");
+    assert_eq!(migrations[1].version, 2);
+    assert_eq!(
+        schema.migrated_collections().collect::<Vec<_>>(),
+        vec![&Basic::collection_name()]
+    );
+}
@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 
+use pot::Value;
 use serde::{Deserialize, Serialize};
 
+use crate::document::{DocumentId, Header, OwnedDocument};
 use crate::key::KeyDescription;
-use crate::schema::view::ViewUpdatePolicy;
+use crate::schema::view::{map, ViewUpdatePolicy};
 use crate::schema::{CollectionName, SchemaName, Schematic, ViewName};
+use crate::Error;
 
 /// A summary of a [`Schema`](crate::schema::Schema)/[`Schematic`].
 ///
@@ -49,6 +52,10 @@ impl<'a> From<&'a Schematic> for SchemaSummary {
                         .collection_primary_key_description(collection_name)
                         .expect("invalid schematic")
                         .clone(),
+                    format: schematic
+                        .collection_serialization_format(collection_name)
+                        .expect("invalid schematic")
+                        .clone(),
                     views: HashMap::new(),
                 });
             for view in schematic.views_in_collection(collection_name) {
@@ -58,6 +65,7 @@ impl<'a> From<&'a Schematic> for SchemaSummary {
                     ViewSummary {
                         name,
                         key: view.key_description(),
+                        value_format: view.value_format(),
                         policy: view.update_policy(),
                         version: view.version(),
                     },
@@ -76,6 +84,9 @@ pub struct CollectionSummary {
     pub name: CollectionName,
     /// The description of [`Collection::PrimaryKey`](crate::schema::Collection::PrimaryKey).
     pub primary_key: KeyDescription,
+    /// The format the collection's documents are serialized with.
+    #[serde(default)]
+    pub format: SerializationFormat,
     views: HashMap<ViewName, ViewSummary>,
 }
 
@@ -90,6 +101,22 @@ impl CollectionSummary {
     pub fn views(&self) -> impl Iterator<Item = &ViewSummary> {
         self.views.values()
     }
+
+    /// Decodes `id` using this collection's primary key description.
+    pub fn decode_id(&self, id: &DocumentId) -> Result<Value<'static>, Error> {
+        self.primary_key.decode_value(id)
+    }
+
+    /// Decodes `document`'s id and contents. Documents retrieved using
+    /// [`LowLevelConnection`](crate::connection::LowLevelConnection) can be
+    /// decoded without access to the collection's Rust type.
+    pub fn decode_document(&self, document: &OwnedDocument) -> Result<DynamicDocument, Error> {
+        Ok(DynamicDocument {
+            id: self.decode_id(&document.header.id)?,
+            contents: self.format.decode(&document.contents)?,
+            header: document.header.clone(),
+        })
+    }
 }
 
 /// A summary of a [`ViewSchema`](crate::schema::ViewSchema).
@@ -100,6 +127,9 @@ pub struct ViewSummary {
     pub name: ViewName,
     /// The description of [`View::Key`](crate::schema::View::Key).
     pub key: KeyDescription,
+    /// The format the view's values are serialized with.
+    #[serde(default)]
+    pub value_format: SerializationFormat,
     /// The result of
     /// [`ViewSchema::update_policy()`](crate::schema::ViewSchema::update_policy)
     /// for this view.
@@ -109,3 +139,79 @@ pub struct ViewSummary {
     /// view.
     pub version: u64,
 }
+
+impl ViewSummary {
+    /// Decodes `key` using this view's key description.
+    pub fn decode_key(&self, key: &[u8]) -> Result<Value<'static>, Error> {
+        self.key.decode_value(key)
+    }
+
+    /// Decodes `value` using this view's value format.
+    pub fn decode_value(&self, value: &[u8]) -> Result<Value<'static>, Error> {
+        self.value_format.decode(value)
+    }
+
+    /// Decodes the key and value of `mapping`. Mappings retrieved using
+    /// [`LowLevelConnection::query_by_name`](crate::connection::LowLevelConnection::query_by_name)
+    /// can be decoded without access to the view's Rust type.
+    pub fn decode_mapping(&self, mapping: &map::Serialized) -> Result<DynamicMapping, Error> {
+        Ok(DynamicMapping {
+            source: mapping.source.clone(),
+            key: self.decode_key(&mapping.key)?,
+            value: self.decode_value(&mapping.value)?,
+        })
+    }
+}
+
+/// A description of how serialized data is encoded.
+///
+/// This is reported in [`CollectionSummary::format`] and
+/// [`ViewSummary::value_format`], allowing stored data to be decoded without
+/// access to the Rust types.
+#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize, Clone)]
+pub enum SerializationFormat {
+    /// The data is encoded using [`pot`].
+    Pot,
+    /// The data is encoded using the described [`Key`](crate::key::Key)
+    /// implementation.
+    Key(KeyDescription),
+    /// The format isn't known. This is reported for custom formats.
+    #[default]
+    Unknown,
+}
+
+impl SerializationFormat {
+    /// Decodes `bytes` into a [`Value`]. Data with an
+    /// [`Unknown`](Self::Unknown) format is returned as [`Value::Bytes`].
+    pub fn decode(&self, bytes: &[u8]) -> Result<Value<'static>, Error> {
+        match self {
+            Self::Pot => pot::from_slice::<Value<'_>>(bytes)
+                .map(Value::into_static)
+                .map_err(|err| Error::other("serialization", err)),
+            Self::Key(description) => description.decode_value(bytes),
+            Self::Unknown => Ok(Value::Bytes(bytes.to_vec().into())),
+        }
+    }
+}
+
+/// A document decoded using a [`CollectionSummary`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DynamicDocument {
+    /// The header of the document.
+    pub header: Header,
+    /// The decoded primary key of the document.
+    pub id: Value<'static>,
+    /// The decoded contents of the document.
+    pub contents: Value<'static>,
+}
+
+/// A view entry decoded using a [`ViewSummary`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DynamicMapping {
+    /// The header of the document that emitted this entry.
+    pub source: Header,
+    /// The decoded key of the entry.
+    pub key: Value<'static>,
+    /// The decoded value of the entry.
+    pub value: Value<'static>,
+}
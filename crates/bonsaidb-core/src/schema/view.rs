@@ -9,7 +9,9 @@ use crate::connection::{self, AsyncConnection, Connection};
 use crate::document::{BorrowedDocument, CollectionDocument};
 use crate::key::{ByteSource, Key, KeyDescription};
 use crate::schema::view::map::{MappedValue, Mappings, ViewMappedValue};
-use crate::schema::{Collection, CollectionName, Name, SerializedCollection, ViewName};
+use crate::schema::{
+    Collection, CollectionName, Name, SerializationFormat, SerializedCollection, ViewName,
+};
 use crate::AnyError;
 
 /// Types for defining a `Map` within a `View`.
@@ -274,6 +276,14 @@ pub trait SerializedView: View {
     // TODO allow configuration to be passed here, such as max allocation bytes.
     fn format() -> Self::Format;
 
+    /// Returns a description of [`Self::Format`], which allows tools without
+    /// access to this type to decode the view's values. Views using
+    /// [`DefaultViewSerialization`] report [`SerializationFormat::Pot`].
+    #[must_use]
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Unknown
+    }
+
     /// Deserialize `data` as `Self::Value` using this views's format.
     fn deserialize(data: &[u8]) -> Result<Self::Value, crate::Error> {
         Self::format()
@@ -317,6 +327,10 @@ where
     fn format() -> Self::Format {
         Pot::default()
     }
+
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Pot
+    }
 }
 
 /// A [`MapReduce`] implementation that automatically serializes/deserializes
@@ -378,6 +392,8 @@ pub trait Serialized: Send + Sync {
     fn collection(&self) -> CollectionName;
     /// Returns the description of the view's `Key`.
     fn key_description(&self) -> KeyDescription;
+    /// Wraps [`SerializedView::serialization_format`]
+    fn value_format(&self) -> SerializationFormat;
     /// Wraps [`ViewSchema::update_policy`]
     fn update_policy(&self) -> ViewUpdatePolicy;
 
@@ -7,6 +7,8 @@ use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+#[cfg(feature = "token-authentication")]
+use std::time::SystemTime;
 use std::time::{Duration, Instant};
 
 use itertools::Itertools;
@@ -22,18 +24,21 @@ use crate::document::{
 };
 use crate::keyvalue::{AsyncKeyValue, KeyValue};
 use crate::limits::{LIST_TRANSACTIONS_DEFAULT_RESULT_COUNT, LIST_TRANSACTIONS_MAX_RESULTS};
+use crate::permissions::bonsai::{BonsaiAction, ServerAction};
+use crate::permissions::Statement;
 use crate::schema::view::map::{Mappings, ViewMappedValue};
 use crate::schema::view::{MapReduce, ReduceResult, SerializedView, ViewUpdatePolicy};
 use crate::schema::{
     Collection, CollectionName, MappedValue, NamedCollection, Qualified, Schema, SchemaName,
-    Schematic, SerializedCollection, View, ViewMapResult, ViewSchema,
+    Schematic, SerializationFormat, SerializedCollection, View, ViewMapResult, ViewSchema,
 };
 use crate::transaction::{Operation, OperationResult, Transaction};
 use crate::Error;
 #[cfg(feature = "token-authentication")]
 use crate::{
-    admin::AuthenticationToken,
+    admin::{AuthenticationToken, AuthenticationTokenOptions},
     connection::{HasSession, Identity, IdentityReference, Session},
+    key::time::TimestampAsNanoseconds,
 };
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Default, Clone, Collection)]
@@ -425,6 +430,10 @@ impl Collection for BasicCollectionWithNoViews {
     fn define_views(_schema: &mut Schematic) -> Result<(), Error> {
         Ok(())
     }
+
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Pot
+    }
 }
 
 impl SerializedCollection for BasicCollectionWithNoViews {
@@ -449,6 +458,10 @@ impl Collection for BasicCollectionWithOnlyBrokenParentId {
     fn define_views(schema: &mut Schematic) -> Result<(), Error> {
         schema.define_view(BasicByBrokenParentId)
     }
+
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Pot
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Collection)]
@@ -2818,6 +2831,76 @@ pub async fn user_management_tests<C: AsyncConnection, S: AsyncStorageConnection
 
     assert!(User::get_async(&user_id, admin).await.unwrap().is_none());
 
+    // Manage a permission group and a role through the storage connection.
+    let group_name = format!("managed-group-{server_name}");
+    let group_id = server
+        .create_permission_group(PermissionGroup::named(&group_name))
+        .await?;
+    let role_name = format!("managed-role-{server_name}");
+    let role_id = server
+        .create_role(Role::named(&role_name).with_group_ids([group_id]))
+        .await?;
+    server
+        .update_permission_group(
+            &group_name,
+            PermissionGroup::named(&group_name).with_group_ids([
+                Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect))
+            ]),
+        )
+        .await?;
+    {
+        let group = PermissionGroup::get_async(&group_id, admin)
+            .await?
+            .expect("group not found");
+        assert_eq!(group.contents.statements.len(), 1);
+    }
+
+    let user_id = server.create_user(&username).await?;
+    server
+        .add_permission_group_to_user(user_id, group_id)
+        .await?;
+    server.add_role_to_user(&username, &role_name).await?;
+
+    // Deleting the group removes it from the role and the user.
+    server.delete_permission_group(&group_name).await?;
+    assert!(PermissionGroup::get_async(&group_id, admin)
+        .await?
+        .is_none());
+    {
+        let role = Role::get_async(&role_id, admin)
+            .await?
+            .expect("role not found");
+        assert!(role.contents.groups.is_empty());
+        let user = User::get_async(&user_id, admin)
+            .await?
+            .expect("user not found");
+        assert!(user.contents.groups.is_empty());
+        assert_eq!(user.contents.roles, vec![role_id]);
+    }
+
+    // Rename the role, then delete it, which removes it from the user.
+    let renamed_role = format!("renamed-role-{server_name}");
+    server
+        .update_role(role_id, Role::named(&renamed_role))
+        .await?;
+    assert!(Role::load_async(&role_name, admin).await?.is_none());
+    server.delete_role(&renamed_role).await?;
+    {
+        let user = User::get_async(&user_id, admin)
+            .await?
+            .expect("user not found");
+        assert!(user.contents.roles.is_empty());
+    }
+    assert!(matches!(
+        server.delete_role(role_id).await,
+        Err(Error::RoleNotFound)
+    ));
+    assert!(matches!(
+        server.delete_permission_group(group_id).await,
+        Err(Error::PermissionGroupNotFound)
+    ));
+    server.delete_user(user_id).await?;
+
     Ok(())
 }
 
@@ -2890,6 +2973,56 @@ pub fn blocking_user_management_tests<C: Connection, S: StorageConnection>(
 
     assert!(User::get(&user_id, admin).unwrap().is_none());
 
+    // Manage a permission group and a role through the storage connection.
+    let group_name = format!("managed-group-{server_name}");
+    let group_id = server.create_permission_group(PermissionGroup::named(&group_name))?;
+    let role_name = format!("managed-role-{server_name}");
+    let role_id = server.create_role(Role::named(&role_name).with_group_ids([group_id]))?;
+    server.update_permission_group(
+        &group_name,
+        PermissionGroup::named(&group_name).with_group_ids([
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect))
+        ]),
+    )?;
+    {
+        let group = PermissionGroup::get(&group_id, admin)?.expect("group not found");
+        assert_eq!(group.contents.statements.len(), 1);
+    }
+
+    let user_id = server.create_user(&username)?;
+    server.add_permission_group_to_user(user_id, group_id)?;
+    server.add_role_to_user(&username, &role_name)?;
+
+    // Deleting the group removes it from the role and the user.
+    server.delete_permission_group(&group_name)?;
+    assert!(PermissionGroup::get(&group_id, admin)?.is_none());
+    {
+        let role = Role::get(&role_id, admin)?.expect("role not found");
+        assert!(role.contents.groups.is_empty());
+        let user = User::get(&user_id, admin)?.expect("user not found");
+        assert!(user.contents.groups.is_empty());
+        assert_eq!(user.contents.roles, vec![role_id]);
+    }
+
+    // Rename the role, then delete it, which removes it from the user.
+    let renamed_role = format!("renamed-role-{server_name}");
+    server.update_role(role_id, Role::named(&renamed_role))?;
+    assert!(Role::load(&role_name, admin)?.is_none());
+    server.delete_role(&renamed_role)?;
+    {
+        let user = User::get(&user_id, admin)?.expect("user not found");
+        assert!(user.contents.roles.is_empty());
+    }
+    assert!(matches!(
+        server.delete_role(role_id),
+        Err(Error::RoleNotFound)
+    ));
+    assert!(matches!(
+        server.delete_permission_group(group_id),
+        Err(Error::PermissionGroupNotFound)
+    ));
+    server.delete_user(user_id)?;
+
     Ok(())
 }
 
@@ -2927,6 +3060,114 @@ pub async fn token_authentication_tests<C: AsyncConnection, S: AsyncStorageConne
         assert_eq!(*id, role.header.id);
     }
 
+    // Successful authentication records when the token was last used.
+    let tokens = server.list_authentication_tokens(&username).await?;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].id, user_token.header.id);
+    assert!(tokens[0].last_used_at.is_some());
+
+    // Expired tokens can't be used.
+    let expired = AuthenticationToken::create_with_options_async(
+        IdentityReference::user(&username)?,
+        &AuthenticationTokenOptions::default()
+            .with_label("expired")
+            .expiring_at(TimestampAsNanoseconds::now()),
+        admin,
+    )
+    .await?;
+    assert!(server
+        .authenticate_with_token(expired.header.id, &expired.contents.token)
+        .await
+        .is_err());
+
+    // Scoped tokens are limited to their scope, even if the user is allowed
+    // to do more.
+    let group_id = server
+        .create_permission_group(
+            PermissionGroup::named(format!("token-group-{server_name}")).with_group_ids([
+                Statement::for_any().allowing(&BonsaiAction::Server(
+                    ServerAction::ListAuthenticationTokens,
+                )),
+            ]),
+        )
+        .await?;
+    server
+        .add_permission_group_to_user(user_id, group_id)
+        .await?;
+    let scoped = AuthenticationToken::create_with_options_async(
+        IdentityReference::user(&username)?,
+        &AuthenticationTokenOptions::default().with_scope([
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect))
+        ]),
+        admin,
+    )
+    .await?;
+    let as_user = server
+        .authenticate_with_token(user_token.header.id, &user_token.contents.token)
+        .await?;
+    assert_eq!(as_user.list_authentication_tokens(user_id).await?.len(), 3);
+    let as_scoped = server
+        .authenticate_with_token(scoped.header.id, &scoped.contents.token)
+        .await?;
+    assert!(matches!(
+        as_scoped.list_authentication_tokens(user_id).await,
+        Err(Error::PermissionDenied(_))
+    ));
+
+    // Sessions authenticated with a token end once it expires.
+    let expiring = AuthenticationToken::create_with_options_async(
+        IdentityReference::user(&username)?,
+        &AuthenticationTokenOptions::default().expiring_at(TimestampAsNanoseconds::try_from(
+            SystemTime::now() + Duration::from_secs(1),
+        )?),
+        admin,
+    )
+    .await?;
+    let as_expiring = server
+        .authenticate_with_token(expiring.header.id, &expiring.contents.token)
+        .await?;
+    as_expiring.list_authentication_tokens(user_id).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(as_expiring
+        .list_authentication_tokens(user_id)
+        .await
+        .is_err());
+    expiring.delete_async(admin).await?;
+
+    // Tokens can be created through the storage connection.
+    let created = server
+        .create_authentication_token(
+            IdentityReference::user(&username)?,
+            AuthenticationTokenOptions::default().with_label("created"),
+        )
+        .await?;
+    server
+        .authenticate_with_token(created.id, &created.token)
+        .await?;
+    assert!(server
+        .list_authentication_tokens(user_id)
+        .await?
+        .iter()
+        .any(|token| token.id == created.id && token.label.as_deref() == Some("created")));
+    server.revoke_authentication_token(created.id).await?;
+
+    // Revoked tokens can't be used, and sessions authenticated with them end.
+    server
+        .revoke_authentication_token(user_token.header.id)
+        .await?;
+    assert!(server
+        .authenticate_with_token(user_token.header.id, &user_token.contents.token)
+        .await
+        .is_err());
+    assert!(as_user.list_authentication_tokens(user_id).await.is_err());
+    assert!(matches!(
+        server
+            .revoke_authentication_token(user_token.header.id)
+            .await,
+        Err(Error::AuthenticationTokenNotFound)
+    ));
+    assert_eq!(server.list_authentication_tokens(user_id).await?.len(), 2);
+
     Ok(())
 }
 
@@ -2959,6 +3200,89 @@ pub fn blocking_token_authentication_tests<C: Connection, S: StorageConnection>(
         assert_eq!(*id, role.header.id);
     }
 
+    // Successful authentication records when the token was last used.
+    let tokens = server.list_authentication_tokens(&username)?;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].id, user_token.header.id);
+    assert!(tokens[0].last_used_at.is_some());
+
+    // Expired tokens can't be used.
+    let expired = AuthenticationToken::create_with_options(
+        &IdentityReference::user(&username)?,
+        &AuthenticationTokenOptions::default()
+            .with_label("expired")
+            .expiring_at(TimestampAsNanoseconds::now()),
+        admin,
+    )?;
+    assert!(server
+        .authenticate_with_token(expired.header.id, &expired.contents.token)
+        .is_err());
+
+    // Scoped tokens are limited to their scope, even if the user is allowed
+    // to do more.
+    let group_id = server.create_permission_group(
+        PermissionGroup::named(format!("blocking-token-group-{server_name}")).with_group_ids([
+            Statement::for_any().allowing(&BonsaiAction::Server(
+                ServerAction::ListAuthenticationTokens,
+            )),
+        ]),
+    )?;
+    server.add_permission_group_to_user(user_id, group_id)?;
+    let scoped = AuthenticationToken::create_with_options(
+        &IdentityReference::user(&username)?,
+        &AuthenticationTokenOptions::default().with_scope([
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect))
+        ]),
+        admin,
+    )?;
+    let as_user =
+        server.authenticate_with_token(user_token.header.id, &user_token.contents.token)?;
+    assert_eq!(as_user.list_authentication_tokens(user_id)?.len(), 3);
+    let as_scoped = server.authenticate_with_token(scoped.header.id, &scoped.contents.token)?;
+    assert!(matches!(
+        as_scoped.list_authentication_tokens(user_id),
+        Err(Error::PermissionDenied(_))
+    ));
+
+    // Sessions authenticated with a token end once it expires.
+    let expiring = AuthenticationToken::create_with_options(
+        &IdentityReference::user(&username)?,
+        &AuthenticationTokenOptions::default().expiring_at(TimestampAsNanoseconds::try_from(
+            SystemTime::now() + Duration::from_secs(1),
+        )?),
+        admin,
+    )?;
+    let as_expiring =
+        server.authenticate_with_token(expiring.header.id, &expiring.contents.token)?;
+    as_expiring.list_authentication_tokens(user_id)?;
+    std::thread::sleep(Duration::from_secs(1));
+    assert!(as_expiring.list_authentication_tokens(user_id).is_err());
+    expiring.delete(admin)?;
+
+    // Tokens can be created through the storage connection.
+    let created = server.create_authentication_token(
+        IdentityReference::user(&username)?,
+        AuthenticationTokenOptions::default().with_label("created"),
+    )?;
+    server.authenticate_with_token(created.id, &created.token)?;
+    assert!(server
+        .list_authentication_tokens(user_id)?
+        .iter()
+        .any(|token| token.id == created.id && token.label.as_deref() == Some("created")));
+    server.revoke_authentication_token(created.id)?;
+
+    // Revoked tokens can't be used, and sessions authenticated with them end.
+    server.revoke_authentication_token(user_token.header.id)?;
+    assert!(server
+        .authenticate_with_token(user_token.header.id, &user_token.contents.token)
+        .is_err());
+    assert!(as_user.list_authentication_tokens(user_id).is_err());
+    assert!(matches!(
+        server.revoke_authentication_token(user_token.header.id),
+        Err(Error::AuthenticationTokenNotFound)
+    ));
+    assert_eq!(server.list_authentication_tokens(user_id)?.len(), 2);
+
     Ok(())
 }
 
@@ -0,0 +1,108 @@
+#[cfg(feature = "async")]
+use bonsaidb_core::connection::AsyncConnection;
+use bonsaidb_core::connection::Connection;
+use clap::Subcommand;
+
+use crate::{Error, FileConfig, Verification};
+
+/// Commands for administering the files stored using a [`FileConfig`].
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Verifies the contents of each file against its stored checksum.
+    Verify {
+        /// Only verify files whose path starts with this path.
+        #[clap(long, default_value = "/")]
+        path: String,
+    },
+    /// Recomputes and stores the checksum of each file from its contents.
+    RecomputeChecksums {
+        /// Only recompute checksums for files whose path starts with this
+        /// path.
+        #[clap(long, default_value = "/")]
+        path: String,
+    },
+}
+
+impl Command {
+    /// Executes the command on the files stored in `database` using `Config`.
+    pub fn execute<Config: FileConfig, Database: Connection + Clone>(
+        self,
+        database: &Database,
+    ) -> Result<(), Error> {
+        match self {
+            Command::Verify { path } => {
+                let mut failed = 0;
+                for file in Config::list_recursive(&path, database)? {
+                    if !report_verification(&file.path(), file.verify()?) {
+                        failed += 1;
+                    }
+                }
+                verification_result(failed)
+            }
+            Command::RecomputeChecksums { path } => {
+                for file in Config::list_recursive(&path, database)? {
+                    let checksum = file.recompute_checksum()?;
+                    println!("{}: {checksum}", file.path());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Executes the command on the files stored in `database` using `Config`.
+    #[cfg(feature = "async")]
+    pub async fn execute_async<
+        Config: FileConfig,
+        Database: AsyncConnection + Clone + Unpin + 'static,
+    >(
+        self,
+        database: &Database,
+    ) -> Result<(), Error> {
+        match self {
+            Command::Verify { path } => {
+                let mut failed = 0;
+                for file in Config::list_recursive_async(&path, database).await? {
+                    if !report_verification(&file.path(), file.verify().await?) {
+                        failed += 1;
+                    }
+                }
+                verification_result(failed)
+            }
+            Command::RecomputeChecksums { path } => {
+                for file in Config::list_recursive_async(&path, database).await? {
+                    let checksum = file.recompute_checksum().await?;
+                    println!("{}: {checksum}", file.path());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn report_verification(path: &str, verification: Verification) -> bool {
+    match verification.stored {
+        Some(stored) if verification.is_valid() => {
+            println!("{path}: ok ({stored})");
+            true
+        }
+        Some(stored) => {
+            eprintln!(
+                "{path}: checksum mismatch (stored {stored}, computed {})",
+                verification.computed
+            );
+            false
+        }
+        None => {
+            eprintln!("{path}: no stored checksum");
+            false
+        }
+    }
+}
+
+fn verification_result(failed: usize) -> Result<(), Error> {
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(Error::VerificationFailed(failed))
+    }
+}
@@ -1,26 +1,28 @@
-#[cfg(feature = "async")]
-use std::collections::BTreeMap;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
+use std::sync::Arc;
 #[cfg(feature = "async")]
 use std::task::Poll;
 
 use bonsaidb_core::connection::Connection;
-use bonsaidb_core::document::{CollectionDocument, CollectionHeader};
+use bonsaidb_core::document::{CollectionDocument, CollectionHeader, Revision};
 use bonsaidb_core::key::time::TimestampAsNanoseconds;
 use bonsaidb_core::schema::SerializedCollection;
+use bonsaidb_core::transaction::Transaction;
 #[cfg(feature = "async")]
 use bonsaidb_core::{circulate::flume, connection::AsyncConnection};
 use derive_where::derive_where;
 #[cfg(feature = "async")]
-use futures::{future::BoxFuture, ready, FutureExt};
+use futures::{future::BoxFuture, ready, FutureExt, StreamExt};
 #[cfg(feature = "async")]
 use tokio::io::AsyncWriteExt;
 
 use crate::schema::block::BlockAppendInfo;
+use crate::schema::block_contents::{BlockContents, BlockHash};
+use crate::schema::version::FileVersion;
 use crate::schema::{self};
-use crate::{BonsaiFiles, Error, FileConfig, Statistics, Truncate};
+use crate::{BonsaiFiles, Checksum, Error, FileConfig, Prune, Statistics, Truncate, Verification};
 
 /// A handle to a file stored in a database.
 #[derive_where(Debug, Clone)]
@@ -173,8 +175,7 @@ where
 
     /// Deletes the file.
     pub fn delete(&self) -> Result<(), Error> {
-        schema::block::Block::<Config>::delete_for_file(self.doc.header.id, &self.database.0)?;
-        self.doc.delete(&self.database.0)?;
+        schema::block::Block::<Config>::delete_for_file(&self.doc, &self.database.0)?;
         Ok(())
     }
 
@@ -217,17 +218,11 @@ where
     /// [`Config::BLOCK_SIZE`](FileConfig::BLOCK_SIZE).
     pub fn contents(&self) -> Result<Contents<Blocking<Database>, Config>, bonsaidb_core::Error> {
         let blocks = schema::block::Block::<Config>::for_file(self.id(), &self.database.0)?;
-        Ok(Contents {
-            database: self.database.clone(),
+        Ok(Contents::new(
+            self.database.clone(),
             blocks,
-            loaded: VecDeque::default(),
-            current_block: 0,
-            offset: 0,
-            buffer_size: Config::BLOCK_SIZE * 10,
-            #[cfg(feature = "async")]
-            async_blocks: None,
-            _config: PhantomData,
-        })
+            BlockSource::File,
+        ))
     }
 
     /// Truncates the file, removing data from either the start or end of the
@@ -258,10 +253,120 @@ where
         }
     }
 
+    /// Writes `data` to the file starting at `offset`, overwriting any
+    /// existing bytes in that range. If the range extends past the end of the
+    /// file, the file is extended. If `offset` is past the end of the file, the
+    /// gap is filled with zeroes. An error is returned if the gap is larger
+    /// than [`Config::MAX_WRITE_GAP`](FileConfig::MAX_WRITE_GAP).
+    ///
+    /// Only the blocks that overlap the written range are rewritten, and all
+    /// changes are applied in a single transaction.
+    pub fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), bonsaidb_core::Error> {
+        schema::block::Block::<Config>::write_at(data, offset, self.doc.header.id, &self.database.0)
+    }
+
+    /// Returns a writer that will buffer writes to the file starting at the
+    /// beginning of the file. The returned writer implements
+    /// [`std::io::Seek`], allowing any range of the file to be overwritten.
+    pub fn write_buffered(&mut self) -> BufferedWrite<'_, Config, Database> {
+        BufferedWrite {
+            file: self,
+            position: 0,
+            buffer: Vec::new(),
+            _config: PhantomData,
+        }
+    }
+
     /// Stores changes to the metadata of this document.
     pub fn update_metadata(&mut self) -> Result<(), bonsaidb_core::Error> {
         self.doc.update(&self.database.0)
     }
+
+    /// Returns the stored checksum of this file's contents. Returns `None` if
+    /// the file was written before checksums were introduced and has not been
+    /// modified since, unless its checksum has been
+    /// [recomputed](Self::recompute_checksum).
+    pub fn checksum(&self) -> Result<Option<Checksum>, bonsaidb_core::Error> {
+        schema::checksum::FileChecksum::<Config>::get(self.id(), &self.database.0)
+    }
+
+    /// Reads the entire contents of this file and compares their checksum
+    /// against the stored checksum.
+    ///
+    /// If the file is modified while being verified, the result may report a
+    /// mismatch.
+    pub fn verify(&self) -> Result<Verification, bonsaidb_core::Error> {
+        let stored = self.checksum()?;
+        Ok(Verification {
+            stored,
+            computed: self.compute_checksum()?,
+        })
+    }
+
+    /// Reads the entire contents of this file and replaces the stored checksum
+    /// with the checksum of the contents. If the file is modified while its
+    /// checksum is being recomputed, a conflict error is returned.
+    pub fn recompute_checksum(&self) -> Result<Checksum, bonsaidb_core::Error> {
+        let existing = self
+            .database
+            .0
+            .collection::<schema::checksum::FileChecksum<Config>>()
+            .get(&self.id())?;
+        let checksum = self.compute_checksum()?;
+        Transaction::from(schema::checksum::FileChecksum::<Config>::replace_operation(
+            self.id(),
+            existing,
+            checksum,
+        )?)
+        .apply(&self.database.0)?;
+        Ok(checksum)
+    }
+
+    fn compute_checksum(&self) -> Result<Checksum, bonsaidb_core::Error> {
+        let mut hashes = Vec::new();
+        for block in self.contents()? {
+            let block = block.map_err(|err| bonsaidb_core::Error::other("bonsaidb-files", err))?;
+            hashes.push(schema::block_contents::BlockContents::<Config>::hash(
+                &block,
+            ));
+        }
+        Ok(Checksum::of_blocks(&hashes))
+    }
+
+    /// Returns the previous versions of this file, oldest first. Versions are
+    /// only stored when [`FileConfig::VERSIONED`] is enabled.
+    pub fn versions(&self) -> Result<Vec<Version>, bonsaidb_core::Error> {
+        FileVersion::<Config>::for_file(self.id(), &self.database.0)
+            .map(|versions| versions.iter().map(Version::from_document).collect())
+    }
+
+    /// Returns the contents of this file as of the version `version_id`.
+    ///
+    /// A [`DocumentNotFound`](bonsaidb_core::Error::DocumentNotFound) error is
+    /// returned if the version doesn't exist or belongs to another file.
+    pub fn version_contents(
+        &self,
+        version_id: u64,
+    ) -> Result<Contents<Blocking<Database>, Config>, bonsaidb_core::Error> {
+        let version = FileVersion::<Config>::get_for_file(self.id(), version_id, &self.database.0)?;
+        Ok(Contents::for_version(
+            self.database.clone(),
+            version.contents,
+        ))
+    }
+
+    /// Replaces the contents of this file with the contents of the version
+    /// `version_id`. The file's current contents are preserved as a new
+    /// version, allowing the restore to be undone.
+    pub fn restore_version(&self, version_id: u64) -> Result<(), bonsaidb_core::Error> {
+        schema::block::Block::<Config>::restore(self.id(), version_id, &self.database.0)
+    }
+
+    /// Removes the previous versions of this file selected by `prune`,
+    /// returning the number of versions removed.
+    pub fn prune_versions(&self, prune: Prune) -> Result<usize, bonsaidb_core::Error> {
+        FileVersion::<Config>::prune(self.id(), prune, &self.database.0)
+    }
 }
 
 #[cfg(feature = "async")]
@@ -404,9 +509,7 @@ where
 
     /// Deletes the file.
     pub async fn delete(&self) -> Result<(), Error> {
-        schema::block::Block::<Config>::delete_for_file_async(self.doc.header.id, &self.database.0)
-            .await?;
-        self.doc.delete_async(&self.database.0).await?;
+        schema::block::Block::<Config>::delete_for_file_async(&self.doc, &self.database.0).await?;
         Ok(())
     }
 
@@ -455,17 +558,11 @@ where
     ) -> Result<Contents<Async<Database>, Config>, bonsaidb_core::Error> {
         let blocks =
             schema::block::Block::<Config>::for_file_async(self.id(), &self.database.0).await?;
-        Ok(Contents {
-            database: self.database.clone(),
+        Ok(Contents::new(
+            self.database.clone(),
             blocks,
-            loaded: VecDeque::default(),
-            current_block: 0,
-            offset: 0,
-            buffer_size: Config::BLOCK_SIZE * 10,
-            #[cfg(feature = "async")]
-            async_blocks: None,
-            _config: PhantomData,
-        })
+            BlockSource::File,
+        ))
     }
 
     /// Truncates the file, removing data from either the start or end of the
@@ -503,10 +600,145 @@ where
         }
     }
 
+    /// Writes `data` to the file starting at `offset`, overwriting any
+    /// existing bytes in that range. If the range extends past the end of the
+    /// file, the file is extended. If `offset` is past the end of the file, the
+    /// gap is filled with zeroes. An error is returned if the gap is larger
+    /// than [`Config::MAX_WRITE_GAP`](FileConfig::MAX_WRITE_GAP).
+    ///
+    /// Only the blocks that overlap the written range are rewritten, and all
+    /// changes are applied in a single transaction.
+    pub async fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), bonsaidb_core::Error> {
+        schema::block::Block::<Config>::write_at_async(
+            data,
+            offset,
+            self.doc.header.id,
+            &self.database.0,
+        )
+        .await
+    }
+
+    /// Returns a writer that will buffer writes to the file starting at the
+    /// beginning of the file. The returned writer implements
+    /// [`tokio::io::AsyncSeek`], allowing any range of the file to be
+    /// overwritten.
+    pub fn write_buffered(&mut self) -> AsyncBufferedWrite<'_, Config, Database> {
+        AsyncBufferedWrite {
+            file: self,
+            position: 0,
+            buffer: Vec::new(),
+            flush_future: None,
+            pending_seek: None,
+            seek_future: None,
+            _config: PhantomData,
+        }
+    }
+
     /// Stores changes to the metadata of this document.
     pub async fn update_metadata(&mut self) -> Result<(), bonsaidb_core::Error> {
         self.doc.update_async(&self.database.0).await
     }
+
+    /// Returns the stored checksum of this file's contents. Returns `None` if
+    /// the file was written before checksums were introduced and has not been
+    /// modified since, unless its checksum has been
+    /// [recomputed](Self::recompute_checksum).
+    pub async fn checksum(&self) -> Result<Option<Checksum>, bonsaidb_core::Error> {
+        schema::checksum::FileChecksum::<Config>::get_async(self.id(), &self.database.0).await
+    }
+
+    /// Reads the entire contents of this file and compares their checksum
+    /// against the stored checksum.
+    ///
+    /// If the file is modified while being verified, the result may report a
+    /// mismatch.
+    pub async fn verify(&self) -> Result<Verification, bonsaidb_core::Error>
+    where
+        Database: Unpin + 'static,
+    {
+        let stored = self.checksum().await?;
+        Ok(Verification {
+            stored,
+            computed: self.compute_checksum().await?,
+        })
+    }
+
+    /// Reads the entire contents of this file and replaces the stored checksum
+    /// with the checksum of the contents. If the file is modified while its
+    /// checksum is being recomputed, a conflict error is returned.
+    pub async fn recompute_checksum(&self) -> Result<Checksum, bonsaidb_core::Error>
+    where
+        Database: Unpin + 'static,
+    {
+        let existing = self
+            .database
+            .0
+            .collection::<schema::checksum::FileChecksum<Config>>()
+            .get(&self.id())
+            .await?;
+        let checksum = self.compute_checksum().await?;
+        Transaction::from(schema::checksum::FileChecksum::<Config>::replace_operation(
+            self.id(),
+            existing,
+            checksum,
+        )?)
+        .apply_async(&self.database.0)
+        .await?;
+        Ok(checksum)
+    }
+
+    async fn compute_checksum(&self) -> Result<Checksum, bonsaidb_core::Error>
+    where
+        Database: Unpin + 'static,
+    {
+        let mut hashes = Vec::new();
+        let mut contents = self.contents().await?;
+        while let Some(block) = contents.next().await {
+            let block = block.map_err(|err| bonsaidb_core::Error::other("bonsaidb-files", err))?;
+            hashes.push(schema::block_contents::BlockContents::<Config>::hash(
+                &block,
+            ));
+        }
+        Ok(Checksum::of_blocks(&hashes))
+    }
+
+    /// Returns the previous versions of this file, oldest first. Versions are
+    /// only stored when [`FileConfig::VERSIONED`] is enabled.
+    pub async fn versions(&self) -> Result<Vec<Version>, bonsaidb_core::Error> {
+        FileVersion::<Config>::for_file_async(self.id(), &self.database.0)
+            .await
+            .map(|versions| versions.iter().map(Version::from_document).collect())
+    }
+
+    /// Returns the contents of this file as of the version `version_id`.
+    ///
+    /// A [`DocumentNotFound`](bonsaidb_core::Error::DocumentNotFound) error is
+    /// returned if the version doesn't exist or belongs to another file.
+    pub async fn version_contents(
+        &self,
+        version_id: u64,
+    ) -> Result<Contents<Async<Database>, Config>, bonsaidb_core::Error> {
+        let version =
+            FileVersion::<Config>::get_for_file_async(self.id(), version_id, &self.database.0)
+                .await?;
+        Ok(Contents::for_version(
+            self.database.clone(),
+            version.contents,
+        ))
+    }
+
+    /// Replaces the contents of this file with the contents of the version
+    /// `version_id`. The file's current contents are preserved as a new
+    /// version, allowing the restore to be undone.
+    pub async fn restore_version(&self, version_id: u64) -> Result<(), bonsaidb_core::Error> {
+        schema::block::Block::<Config>::restore_async(self.id(), version_id, &self.database.0).await
+    }
+
+    /// Removes the previous versions of this file selected by `prune`,
+    /// returning the number of versions removed.
+    pub async fn prune_versions(&self, prune: Prune) -> Result<usize, bonsaidb_core::Error> {
+        FileVersion::<Config>::prune_async(self.id(), prune, &self.database.0).await
+    }
 }
 
 impl<Database, Config> File<Database, Config>
@@ -593,6 +825,35 @@ where
     }
 }
 
+/// A previous version of a [`File`]'s contents, which is stored each time a
+/// file is modified when [`FileConfig::VERSIONED`] is enabled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Version {
+    /// The unique id of this version. Versions of a file with larger ids
+    /// were created more recently.
+    pub id: u64,
+    /// The length of the file's contents in this version.
+    pub length: u64,
+    /// The timestamp of the last data written to the file before this
+    /// version was replaced.
+    pub last_appended_at: Option<TimestampAsNanoseconds>,
+    /// The timestamp the file was modified, replacing this version.
+    pub replaced_at: TimestampAsNanoseconds,
+}
+
+impl Version {
+    fn from_document<Config: FileConfig>(
+        version: &CollectionDocument<FileVersion<Config>>,
+    ) -> Self {
+        Self {
+            id: version.header.id,
+            length: version.contents.length(),
+            last_appended_at: version.contents.last_appended_at(),
+            replaced_at: version.contents.replaced_at,
+        }
+    }
+}
+
 /// A builder to create a [`File`].
 #[derive(Debug, Clone)]
 #[must_use]
@@ -691,6 +952,7 @@ impl<'a, Config: FileConfig> FileBuilder<'a, Config> {
 pub struct Contents<Database: Clone, Config: FileConfig> {
     database: Database,
     blocks: Vec<BlockInfo>,
+    source: BlockSource,
     loaded: VecDeque<LoadedBlock>,
     current_block: usize,
     offset: usize,
@@ -713,6 +975,7 @@ impl<Database: Clone, Config: FileConfig> Clone for Contents<Database, Config> {
         Self {
             database: self.database.clone(),
             blocks: self.blocks.clone(),
+            source: self.source.clone(),
             loaded: VecDeque::new(),
             current_block: self.current_block,
             offset: self.offset,
@@ -724,6 +987,79 @@ impl<Database: Clone, Config: FileConfig> Clone for Contents<Database, Config> {
     }
 }
 
+/// Where the data of the blocks of [`Contents`] is loaded from.
+#[derive(Clone)]
+enum BlockSource {
+    /// The blocks are stored in the file's block collection.
+    File,
+    /// The blocks are the contents referenced by a [`Version`]. Each block's
+    /// id is the index of its hash in this list.
+    Version(Arc<Vec<BlockHash>>),
+}
+
+impl BlockSource {
+    fn load<Config: FileConfig, Database: Connection>(
+        &self,
+        block_ids: &[u64],
+        database: &Database,
+    ) -> Result<BTreeMap<u64, Vec<u8>>, bonsaidb_core::Error> {
+        match self {
+            Self::File => schema::block::Block::<Config>::load(block_ids, database),
+            Self::Version(hashes) => {
+                let contents = BlockContents::<Config>::load(
+                    block_ids
+                        .iter()
+                        .map(|id| &hashes[usize::try_from(*id).unwrap()]),
+                    database,
+                )?;
+                Self::resolve(block_ids, hashes, &contents)
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn load_async<Config: FileConfig, Database: AsyncConnection>(
+        &self,
+        block_ids: &[u64],
+        database: &Database,
+    ) -> Result<BTreeMap<u64, Vec<u8>>, bonsaidb_core::Error> {
+        match self {
+            Self::File => schema::block::Block::<Config>::load_async(block_ids, database).await,
+            Self::Version(hashes) => {
+                let contents = BlockContents::<Config>::load_async(
+                    block_ids
+                        .iter()
+                        .map(|id| &hashes[usize::try_from(*id).unwrap()]),
+                    database,
+                )
+                .await?;
+                Self::resolve(block_ids, hashes, &contents)
+            }
+        }
+    }
+
+    fn resolve(
+        block_ids: &[u64],
+        hashes: &[BlockHash],
+        contents: &BTreeMap<BlockHash, Vec<u8>>,
+    ) -> Result<BTreeMap<u64, Vec<u8>>, bonsaidb_core::Error> {
+        block_ids
+            .iter()
+            .map(|&id| {
+                contents
+                    .get(&hashes[usize::try_from(id).unwrap()])
+                    .map(|data| (id, data.clone()))
+                    .ok_or_else(|| {
+                        bonsaidb_core::Error::other(
+                            "bonsaidb-files",
+                            format!("contents of block {id} are missing"),
+                        )
+                    })
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone)]
 struct LoadedBlock {
     index: usize,
@@ -762,11 +1098,12 @@ impl<Database: Connection + Clone, Config: FileConfig> Contents<Blocking<Databas
 
     fn load_blocks(&mut self) -> std::io::Result<()> {
         self.loaded.clear();
-        for (index, (_, contents)) in
-            schema::block::Block::<Config>::load(&self.next_blocks(), &self.database.0)
-                .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?
-                .into_iter()
-                .enumerate()
+        for (index, (_, contents)) in self
+            .source
+            .load::<Config, _>(&self.next_blocks(), &self.database.0)
+            .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?
+            .into_iter()
+            .enumerate()
         {
             self.loaded.push_back(LoadedBlock {
                 index: self.current_block + index,
@@ -820,12 +1157,13 @@ impl<
             let (request_sender, request_receiver) = flume::unbounded();
 
             let task_database = self.database.0.clone();
+            let source = self.source.clone();
             tokio::task::spawn(async move {
                 while let Ok(doc_ids) = request_receiver.recv_async().await {
-                    let blocks =
-                        schema::block::Block::<Config>::load_async(&doc_ids, &task_database)
-                            .await
-                            .map_err(|err| std::io::Error::new(ErrorKind::Other, err));
+                    let blocks = source
+                        .load_async::<Config, _>(&doc_ids, &task_database)
+                        .await
+                        .map_err(|err| std::io::Error::new(ErrorKind::Other, err));
                     if block_sender.send(blocks).is_err() {
                         break;
                     }
@@ -891,6 +1229,47 @@ impl<
 }
 
 impl<Database: Clone, Config: FileConfig> Contents<Database, Config> {
+    fn new(database: Database, blocks: Vec<BlockInfo>, source: BlockSource) -> Self {
+        Self {
+            database,
+            blocks,
+            source,
+            loaded: VecDeque::default(),
+            current_block: 0,
+            offset: 0,
+            buffer_size: Config::BLOCK_SIZE * 10,
+            #[cfg(feature = "async")]
+            async_blocks: None,
+            _config: PhantomData,
+        }
+    }
+
+    fn for_version(database: Database, version: FileVersion<Config>) -> Self {
+        let mut offset = 0;
+        let mut hashes = Vec::with_capacity(version.blocks.len());
+        let blocks = version
+            .blocks
+            .into_iter()
+            .enumerate()
+            .map(|(index, block)| {
+                let info = BlockInfo {
+                    offset,
+                    length: usize::try_from(block.length).unwrap(),
+                    timestamp: block.timestamp,
+                    header: CollectionHeader {
+                        id: u64::try_from(index).unwrap(),
+                        revision: Revision::new(&block.hash),
+                    },
+                    hash: block.hash,
+                };
+                offset += block.length;
+                hashes.push(block.hash);
+                info
+            })
+            .collect();
+        Self::new(database, blocks, BlockSource::Version(Arc::new(hashes)))
+    }
+
     fn next_blocks(&self) -> Vec<u64> {
         let mut last_block = self.current_block;
         let mut requesting_size = 0;
@@ -1194,6 +1573,7 @@ pub(crate) struct BlockInfo {
     pub length: usize,
     pub timestamp: TimestampAsNanoseconds,
     pub header: CollectionHeader<u64>,
+    pub hash: BlockHash,
 }
 
 /// A buffered [`std::io::Write`] and [`std::io::Seek`] implementor for a
@@ -1392,3 +1772,298 @@ impl<'a, Config: FileConfig, Database: AsyncConnection + Clone + 'static> Drop
         }
     }
 }
+
+/// A buffered [`std::io::Write`] and [`std::io::Seek`] implementor that
+/// overwrites the contents of a [`File`].
+///
+/// Each flush of the buffer is applied in a single transaction. Seeking
+/// flushes any buffered data before moving the write position.
+pub struct BufferedWrite<'a, Config: FileConfig, Database: Connection + Clone> {
+    file: &'a mut File<Blocking<Database>, Config>,
+    position: u64,
+    pub(crate) buffer: Vec<u8>,
+    _config: PhantomData<Config>,
+}
+
+impl<'a, Config: FileConfig, Database: Connection + Clone> BufferedWrite<'a, Config, Database> {
+    /// Sets the size of the buffer. For optimal use, this should be a multiple
+    /// of [`Config::BLOCK_SIZE`](FileConfig::BLOCK_SIZE).
+    ///
+    /// If any data is already buffered, it will be flushed before the buffer is
+    /// resized.
+    pub fn set_buffer_size(&mut self, capacity: usize) -> std::io::Result<()> {
+        if self.buffer.capacity() > 0 {
+            self.flush()?;
+        }
+        self.buffer = Vec::with_capacity(capacity);
+        Ok(())
+    }
+}
+
+impl<'a, Config: FileConfig, Database: Connection + Clone> Write
+    for BufferedWrite<'a, Config, Database>
+{
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if self.buffer.capacity() == 0 {
+            const ONE_MEGABYTE: usize = 1024 * 1024;
+            // By default, reserve the largest multiple of BLOCK_SIZE that is
+            // less than or equal to 1 megabyte.
+            self.buffer
+                .reserve_exact(ONE_MEGABYTE / Config::BLOCK_SIZE * Config::BLOCK_SIZE);
+        } else if self.buffer.capacity() == self.buffer.len() {
+            self.flush()?;
+        }
+
+        if data.is_empty() {
+            Ok(0)
+        } else {
+            let bytes_to_write = data.len().min(self.buffer.capacity() - self.buffer.len());
+            self.buffer.extend(&data[..bytes_to_write]);
+            Ok(bytes_to_write)
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file
+            .write_at(self.position, &self.buffer)
+            .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?;
+        self.position += u64::try_from(self.buffer.len()).unwrap();
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<'a, Config: FileConfig, Database: Connection + Clone> Seek
+    for BufferedWrite<'a, Config, Database>
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.flush()?;
+        self.position = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(delta) => offset_position(self.position, delta)?,
+            SeekFrom::End(delta) => offset_position(
+                self.file
+                    .len()
+                    .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?,
+                delta,
+            )?,
+        };
+        Ok(self.position)
+    }
+}
+
+impl<'a, Config: FileConfig, Database: Connection + Clone> Drop
+    for BufferedWrite<'a, Config, Database>
+{
+    fn drop(&mut self) {
+        drop(self.flush());
+    }
+}
+
+fn offset_position(position: u64, delta: i64) -> std::io::Result<u64> {
+    position.checked_add_signed(delta).ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        )
+    })
+}
+
+/// A buffered [`tokio::io::AsyncWrite`] and [`tokio::io::AsyncSeek`]
+/// implementor that overwrites the contents of a [`File`].
+///
+/// Each flush of the buffer is applied in a single transaction. Seeking
+/// flushes any buffered data before moving the write position.
+#[cfg(feature = "async")]
+pub struct AsyncBufferedWrite<'a, Config: FileConfig, Database: AsyncConnection + Clone + 'static> {
+    file: &'a mut File<Async<Database>, Config>,
+    position: u64,
+    pub(crate) buffer: Vec<u8>,
+    flush_future: Option<BoxFuture<'a, Result<(), std::io::Error>>>,
+    pending_seek: Option<SeekFrom>,
+    seek_future: Option<BoxFuture<'a, Result<u64, std::io::Error>>>,
+    _config: PhantomData<Config>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, Config: FileConfig, Database: AsyncConnection + Clone + 'static>
+    AsyncBufferedWrite<'a, Config, Database>
+{
+    /// Sets the size of the buffer. For optimal use, this should be a multiple
+    /// of [`Config::BLOCK_SIZE`](FileConfig::BLOCK_SIZE).
+    ///
+    /// If any data is already buffered, it will be flushed before the buffer is
+    /// resized.
+    pub async fn set_buffer_size(&mut self, capacity: usize) -> std::io::Result<()> {
+        if self.buffer.capacity() > 0 {
+            self.flush().await?;
+        }
+        self.buffer = Vec::with_capacity(capacity);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, Config: FileConfig, Database: AsyncConnection + Clone + 'static> tokio::io::AsyncWrite
+    for AsyncBufferedWrite<'a, Config, Database>
+{
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        data: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        if self.buffer.capacity() == 0 {
+            const ONE_MEGABYTE: usize = 1024 * 1024;
+            // By default, reserve the largest multiple of BLOCK_SIZE that is
+            // less than or equal to 1 megabyte.
+            self.buffer
+                .reserve_exact(ONE_MEGABYTE / Config::BLOCK_SIZE * Config::BLOCK_SIZE);
+        }
+
+        if self.flush_future.is_some() || self.buffer.capacity() == self.buffer.len() {
+            if let Err(err) = ready!(std::pin::Pin::new(&mut self).poll_flush(cx)) {
+                return Poll::Ready(Err(err));
+            }
+        }
+
+        if data.is_empty() {
+            Poll::Ready(Ok(0))
+        } else {
+            let bytes_to_write = data.len().min(self.buffer.capacity() - self.buffer.len());
+            self.buffer.extend(&data[..bytes_to_write]);
+            Poll::Ready(Ok(bytes_to_write))
+        }
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        if let Some(flush_future) = &mut self.flush_future {
+            let result = ready!(flush_future.poll_unpin(cx));
+            self.flush_future = None;
+            Poll::Ready(result)
+        } else if self.buffer.is_empty() {
+            Poll::Ready(Ok(()))
+        } else {
+            let file = self.file.clone();
+            let position = self.position;
+
+            let mut buffer = Vec::with_capacity(self.buffer.capacity());
+            std::mem::swap(&mut buffer, &mut self.buffer);
+            self.position += u64::try_from(buffer.len()).unwrap();
+
+            let mut flush_task = async move {
+                file.write_at(position, &buffer)
+                    .await
+                    .map_err(|err| std::io::Error::new(ErrorKind::Other, err))
+            }
+            .boxed();
+            let poll_result = flush_task.poll_unpin(cx);
+            if poll_result.is_pending() {
+                self.flush_future = Some(flush_task);
+            }
+            poll_result
+        }
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, Config: FileConfig, Database: AsyncConnection + Clone + 'static> tokio::io::AsyncSeek
+    for AsyncBufferedWrite<'a, Config, Database>
+{
+    fn start_seek(mut self: std::pin::Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        if self.pending_seek.is_some() || self.seek_future.is_some() {
+            return Err(std::io::Error::new(
+                ErrorKind::Other,
+                "a seek is already in progress",
+            ));
+        }
+        self.pending_seek = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<std::io::Result<u64>> {
+        if let Some(seek_future) = &mut self.seek_future {
+            let result = ready!(seek_future.poll_unpin(cx));
+            self.seek_future = None;
+            return Poll::Ready(result.map(|position| {
+                self.position = position;
+                position
+            }));
+        }
+
+        let Some(seek) = self.pending_seek else {
+            return Poll::Ready(Ok(self.position + u64::try_from(self.buffer.len()).unwrap()));
+        };
+
+        // Any buffered data must be written before the position changes.
+        if let Err(err) = ready!(tokio::io::AsyncWrite::poll_flush(
+            std::pin::Pin::new(&mut *self),
+            cx
+        )) {
+            self.pending_seek = None;
+            return Poll::Ready(Err(err));
+        }
+        self.pending_seek = None;
+
+        match seek {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                Poll::Ready(Ok(offset))
+            }
+            SeekFrom::Current(delta) => {
+                let result = offset_position(self.position, delta);
+                if let Ok(position) = result {
+                    self.position = position;
+                }
+                Poll::Ready(result)
+            }
+            SeekFrom::End(delta) => {
+                let mut file = self.file.clone();
+                self.seek_future = Some(
+                    async move {
+                        let length = file
+                            .len()
+                            .await
+                            .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?;
+                        offset_position(length, delta)
+                    }
+                    .boxed(),
+                );
+                tokio::io::AsyncSeek::poll_complete(self, cx)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, Config: FileConfig, Database: AsyncConnection + Clone + 'static> Drop
+    for AsyncBufferedWrite<'a, Config, Database>
+{
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            assert!(
+                self.flush_future.is_none(),
+                "flush() was started but not completed before dropped"
+            );
+            let buffer = std::mem::take(&mut self.buffer);
+            let position = self.position;
+            let file = self.file.clone();
+
+            tokio::runtime::Handle::current().spawn(async move {
+                drop(file.write_at(position, &buffer).await);
+            });
+        }
+    }
+}
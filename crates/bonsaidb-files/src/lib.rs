@@ -20,7 +20,7 @@
 //! ```rust
 #![doc = include_str!("../examples/basic-files.rs")]
 //! ```
-//! 
+//!
 //! # Async Support
 //!
 //! This crate adds implementations of `tokio::io::AsyncRead` and
@@ -45,6 +45,7 @@
 
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 #[cfg(feature = "async")]
 use bonsaidb_core::async_trait::async_trait;
@@ -67,6 +68,10 @@ mod schema;
 /// part of accessing/updating the underlying collections.
 pub mod direct;
 
+/// Command-line commands for administering stored files.
+#[cfg(feature = "cli")]
+pub mod cli;
+
 /// A configuration for a set of [stored files](direct::File).
 #[cfg_attr(feature = "async", async_trait)]
 pub trait FileConfig: Sized + Send + Sync + Unpin + 'static {
@@ -78,15 +83,86 @@ pub trait FileConfig: Sized + Send + Sync + Unpin + 'static {
     /// stored by breaking the data written into chunks no larger than
     /// `BLOCK_SIZE`.
     const BLOCK_SIZE: usize;
+
+    /// When true, file contents are split into blocks using content-defined
+    /// chunking, and each unique block is stored only once across all files
+    /// using this configuration. Blocks are reference counted, and a block's
+    /// contents are removed once no file references them.
+    ///
+    /// Changing this value for a configuration that already has files stored
+    /// is not supported.
+    const DEDUPLICATE_BLOCKS: bool = false;
+
+    /// When true, each modification of a file preserves the file's previous
+    /// contents as an immutable [version](direct::Version). Versions share
+    /// unchanged blocks with the file and with each other, which requires
+    /// deduplicated blocks: enabling versioning implies
+    /// [`Self::DEDUPLICATE_BLOCKS`].
+    ///
+    /// Changing this value for a configuration that already has files stored
+    /// is not supported.
+    const VERSIONED: bool = false;
+
+    /// The maximum number of bytes past the end of a file that
+    /// [`File::write_at()`](direct::File::write_at) can begin writing at.
+    /// Skipped bytes are stored as blocks of zeroes, so this limits how much
+    /// data a single write can create. Writes beyond this limit return an
+    /// error.
+    ///
+    /// By default, this is 64 megabytes.
+    const MAX_WRITE_GAP: u64 = 64 * 1024 * 1024;
+
     /// Returns the unique collection name to use to store [`File`s][direct::File].
     fn files_name() -> CollectionName;
     /// Returns the unique collection name to use to store file blocks.
     fn blocks_name() -> CollectionName;
 
+    /// Returns the unique collection name to use to store the contents of
+    /// deduplicated blocks. This collection is only used when
+    /// [`Self::DEDUPLICATE_BLOCKS`] or [`Self::VERSIONED`] is true.
+    ///
+    /// By default, this is [`Self::blocks_name()`] with `-contents` appended to
+    /// its name.
+    fn block_contents_name() -> CollectionName {
+        let blocks = Self::blocks_name();
+        CollectionName::new(
+            blocks.authority.clone(),
+            format!("{}-contents", blocks.name),
+        )
+    }
+
+    /// Returns the unique collection name to use to store each file's
+    /// [`Checksum`].
+    ///
+    /// By default, this is [`Self::files_name()`] with `-checksums` appended to
+    /// its name.
+    fn checksums_name() -> CollectionName {
+        let files = Self::files_name();
+        CollectionName::new(files.authority.clone(), format!("{}-checksums", files.name))
+    }
+
+    /// Returns the unique collection name to use to store previous versions
+    /// of files. This collection is only used when [`Self::VERSIONED`] is
+    /// true.
+    ///
+    /// By default, this is [`Self::files_name()`] with `-versions` appended to
+    /// its name.
+    fn versions_name() -> CollectionName {
+        let files = Self::files_name();
+        CollectionName::new(files.authority.clone(), format!("{}-versions", files.name))
+    }
+
     /// Registers the collections for this configuration into `schema`.
     fn register_collections(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
         schema.define_collection::<schema::file::File<Self>>()?;
         schema.define_collection::<schema::block::Block<Self>>()?;
+        schema.define_collection::<schema::checksum::FileChecksum<Self>>()?;
+        if deduplicates_blocks::<Self>() {
+            schema.define_collection::<schema::block_contents::BlockContents<Self>>()?;
+        }
+        if Self::VERSIONED {
+            schema.define_collection::<schema::version::FileVersion<Self>>()?;
+        }
 
         Ok(())
     }
@@ -440,6 +516,12 @@ pub trait FileConfig: Sized + Send + Sync + Unpin + 'static {
     }
 }
 
+/// Returns true if blocks stored using `Config` reference deduplicated
+/// contents.
+pub(crate) const fn deduplicates_blocks<Config: FileConfig>() -> bool {
+    Config::DEDUPLICATE_BLOCKS || Config::VERSIONED
+}
+
 /// A default configuration for storing files within BonsaiDb.
 #[derive(Debug)]
 pub struct BonsaiFiles;
@@ -493,6 +575,9 @@ pub enum Error {
     /// The file was deleted during the operation.
     #[error("the file was deleted during the operation")]
     Deleted,
+    /// One or more files did not match their stored checksums.
+    #[error("{0} file(s) failed verification")]
+    VerificationFailed(usize),
 }
 
 impl<T> From<InsertError<T>> for Error {
@@ -528,6 +613,16 @@ pub enum Truncate {
     RemovingEnd,
 }
 
+/// Controls which [versions](direct::Version) of a file are removed when
+/// pruning.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Prune {
+    /// Remove all but the newest `usize` versions.
+    KeepingNewest(usize),
+    /// Remove versions that were replaced longer ago than the duration given.
+    OlderThan(Duration),
+}
+
 /// Statistics about a set of files contained in a collection.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct Statistics {
@@ -542,5 +637,83 @@ pub struct Statistics {
     pub last_appended_at: Option<TimestampAsNanoseconds>,
 }
 
+/// A checksum of the contents of a [`File`](direct::File).
+///
+/// The checksum chains the BLAKE3 hashes of each of the file's blocks, in
+/// order: starting with the BLAKE3 hash of no data, the checksum is replaced by
+/// the BLAKE3 hash of the checksum followed by the next block's hash. This
+/// allows the checksum to be updated as blocks are appended without reading
+/// the rest of the file. Because it is computed from blocks, two files with
+/// identical contents can have different checksums if their contents were
+/// written in different chunks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Checksum([u8; 32]);
+
+impl Checksum {
+    /// Returns the checksum from its byte representation.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the byte representation of this checksum.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Returns the checksum of a file whose blocks have the contents hashes
+    /// `hashes`, in order.
+    pub(crate) fn of_blocks<'a>(hashes: impl IntoIterator<Item = &'a [u8; 32]>) -> Self {
+        Self::default().appending(hashes)
+    }
+
+    /// Returns the checksum of this file after appending blocks whose
+    /// contents hashes are `hashes`, in order.
+    pub(crate) fn appending<'a>(self, hashes: impl IntoIterator<Item = &'a [u8; 32]>) -> Self {
+        hashes.into_iter().fold(self, |checksum, hash| {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&checksum.0);
+            hasher.update(hash);
+            Self(*hasher.finalize().as_bytes())
+        })
+    }
+}
+
+impl Default for Checksum {
+    /// Returns the checksum of an empty file.
+    fn default() -> Self {
+        Self(*blake3::Hasher::new().finalize().as_bytes())
+    }
+}
+
+impl std::fmt::Display for Checksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of verifying a file's contents against its stored [`Checksum`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Verification {
+    /// The checksum stored for the file. This is `None` for files written
+    /// before checksums were introduced, until the file is modified or its
+    /// checksum is recomputed.
+    pub stored: Option<Checksum>,
+    /// The checksum computed from the file's current contents.
+    pub computed: Checksum,
+}
+
+impl Verification {
+    /// Returns true if the stored checksum matches the file's contents.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.stored == Some(self.computed)
+    }
+}
+
 #[cfg(test)]
 mod tests;
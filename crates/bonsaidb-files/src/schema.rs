@@ -1,2 +1,5 @@
 pub mod block;
+pub mod block_contents;
+pub mod checksum;
 pub mod file;
+pub mod version;
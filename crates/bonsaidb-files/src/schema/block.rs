@@ -1,11 +1,13 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::marker::PhantomData;
 use std::mem::size_of;
 
 #[cfg(feature = "async")]
 use bonsaidb_core::connection::AsyncConnection;
 use bonsaidb_core::connection::Connection;
-use bonsaidb_core::document::{BorrowedDocument, Emit};
+use bonsaidb_core::document::{
+    BorrowedDocument, CollectionDocument, Emit, HasHeader, Header, OwnedDocument,
+};
 use bonsaidb_core::key::time::TimestampAsNanoseconds;
 use bonsaidb_core::key::KeyEncoding;
 use bonsaidb_core::schema::{
@@ -16,8 +18,11 @@ use derive_where::derive_where;
 use serde::{Deserialize, Serialize};
 
 use crate::direct::BlockInfo;
+use crate::schema::block_contents::{BlockContents, BlockHash, ContentChunks, ReferenceChanges};
+use crate::schema::checksum::FileChecksum;
 use crate::schema::file::File;
-use crate::FileConfig;
+use crate::schema::version::{FileVersion, VersionBlock};
+use crate::{deduplicates_blocks, Checksum, FileConfig};
 
 #[derive_where(Debug, Default)]
 pub struct Block<Config>(PhantomData<Config>)
@@ -34,23 +39,9 @@ where
         database: &Database,
     ) -> Result<(), bonsaidb_core::Error> {
         if !data.is_empty() {
-            let mut tx = Transaction::new();
-            let now = TimestampAsNanoseconds::now();
-            // Verify the file exists as part of appending. If the file was
-            // deleted out from underneath the appender, this will ensure no
-            // blocks are orphaned.
-            tx.push(Operation::check_document_exists::<File<Config>>(&file_id)?);
-
-            let block_collection = Self::collection_name();
-            for chunk in data.chunks(Config::BLOCK_SIZE) {
-                let mut block =
-                    Vec::with_capacity(chunk.len() + size_of::<u32>() + size_of::<i64>());
-                block.extend(chunk);
-                block.extend(file_id.to_be_bytes());
-                block.extend(now.representation().to_be_bytes());
-                tx.push(Operation::insert(block_collection.clone(), None, block));
-            }
-
+            let (mut tx, mut changes) = Self::create_append_transaction(data, file_id)?;
+            changes.preserve(Self::snapshot(file_id, database)?);
+            changes.apply(file_id, &mut tx, database)?;
             tx.apply(database)?;
         }
         Ok(())
@@ -63,28 +54,285 @@ where
         database: &Database,
     ) -> Result<(), bonsaidb_core::Error> {
         if !data.is_empty() {
-            let mut tx = Transaction::new();
-            let now = TimestampAsNanoseconds::now();
-            // Verify the file exists as part of appending. If the file was
-            // deleted out from underneath the appender, this will ensure no
-            // blocks are orphaned.
-            tx.push(Operation::check_document_exists::<File<Config>>(&file_id)?);
-
-            let block_collection = Self::collection_name();
+            let (mut tx, mut changes) = Self::create_append_transaction(data, file_id)?;
+            changes.preserve(Self::snapshot_async(file_id, database).await?);
+            changes.apply_async(file_id, &mut tx, database).await?;
+            tx.apply_async(database).await?;
+        }
+        Ok(())
+    }
+
+    fn create_append_transaction(
+        data: &[u8],
+        file_id: u32,
+    ) -> Result<(Transaction, BlockChanges<Config>), bonsaidb_core::Error> {
+        let mut tx = Transaction::new();
+        let now = TimestampAsNanoseconds::now();
+        // Verify the file exists as part of appending. If the file was
+        // deleted out from underneath the appender, this will ensure no
+        // blocks are orphaned.
+        tx.push(Operation::check_document_exists::<File<Config>>(&file_id)?);
+
+        let mut changes = BlockChanges::default();
+        Self::push_new_blocks(data, file_id, now, &mut changes, &mut tx);
+
+        Ok((tx, changes))
+    }
+
+    /// Pushes inserts for new blocks containing `data`. When deduplication is
+    /// enabled, the data is split using content-defined chunking and each
+    /// block references its contents by hash.
+    fn push_new_blocks(
+        data: &[u8],
+        file_id: u32,
+        timestamp: TimestampAsNanoseconds,
+        changes: &mut BlockChanges<Config>,
+        tx: &mut Transaction,
+    ) {
+        let block_collection = Self::collection_name();
+        if deduplicates_blocks::<Config>() {
+            for chunk in ContentChunks::new(data, Config::BLOCK_SIZE) {
+                tx.push(Operation::insert(
+                    block_collection.clone(),
+                    None,
+                    Self::encode(chunk, file_id, timestamp, changes),
+                ));
+            }
+        } else {
             for chunk in data.chunks(Config::BLOCK_SIZE) {
-                let mut block =
-                    Vec::with_capacity(chunk.len() + size_of::<u32>() + size_of::<i64>());
-                block.extend(chunk);
-                block.extend(file_id.to_be_bytes());
-                block.extend(now.representation().to_be_bytes());
-                tx.push(Operation::insert(block_collection.clone(), None, block));
+                tx.push(Operation::insert(
+                    block_collection.clone(),
+                    None,
+                    Self::encode(chunk, file_id, timestamp, changes),
+                ));
             }
+        }
+    }
 
+    /// Encodes a block document containing `data`, recording the new block in
+    /// `changes`. When deduplication is enabled, the block stores the hash and
+    /// length of `data` in place of the data itself.
+    fn encode(
+        data: &[u8],
+        file_id: u32,
+        timestamp: TimestampAsNanoseconds,
+        changes: &mut BlockChanges<Config>,
+    ) -> Vec<u8> {
+        let hash = changes.added(data);
+        if deduplicates_blocks::<Config>() {
+            Self::encode_reference(
+                &hash,
+                u64::try_from(data.len()).unwrap(),
+                file_id,
+                timestamp,
+            )
+        } else {
+            let mut block = Vec::with_capacity(data.len() + size_of::<u32>() + size_of::<i64>());
+            block.extend(data);
+            block.extend(file_id.to_be_bytes());
+            block.extend(timestamp.representation().to_be_bytes());
+            block
+        }
+    }
+
+    /// Encodes a block document referencing the deduplicated contents with
+    /// `hash`.
+    fn encode_reference(
+        hash: &BlockHash,
+        length: u64,
+        file_id: u32,
+        timestamp: TimestampAsNanoseconds,
+    ) -> Vec<u8> {
+        let mut block = Vec::with_capacity(
+            size_of::<BlockHash>() + size_of::<u64>() + size_of::<u32>() + size_of::<i64>(),
+        );
+        block.extend(hash);
+        block.extend(length.to_be_bytes());
+        block.extend(file_id.to_be_bytes());
+        block.extend(timestamp.representation().to_be_bytes());
+        block
+    }
+
+    /// Writes `data` into the file at `offset`, replacing any existing bytes
+    /// in that range and extending the file if needed.
+    pub fn write_at<Database: Connection>(
+        data: &[u8],
+        offset: u64,
+        file_id: u32,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        if !data.is_empty() {
+            check_write_range(offset, data)?;
+            let blocks = Self::for_file(file_id, database)?;
+            let loaded = Self::load(
+                &Self::blocks_to_rewrite(&blocks, offset, data.len()),
+                database,
+            )?;
+            let (mut tx, mut changes) =
+                Self::create_write_transaction(&blocks, loaded, offset, data, file_id)?;
+            changes.preserve(Self::snapshot_of(&blocks, database)?);
+            changes.apply(file_id, &mut tx, database)?;
+            tx.apply(database)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn write_at_async<Database: AsyncConnection>(
+        data: &[u8],
+        offset: u64,
+        file_id: u32,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        if !data.is_empty() {
+            check_write_range(offset, data)?;
+            let blocks = Self::for_file_async(file_id, database).await?;
+            let loaded = Self::load_async(
+                &Self::blocks_to_rewrite(&blocks, offset, data.len()),
+                database,
+            )
+            .await?;
+            let (mut tx, mut changes) =
+                Self::create_write_transaction(&blocks, loaded, offset, data, file_id)?;
+            changes.preserve(Self::snapshot_of_async(&blocks, database).await?);
+            changes.apply_async(file_id, &mut tx, database).await?;
             tx.apply_async(database).await?;
         }
         Ok(())
     }
 
+    /// Returns the ids of the blocks that overlap the range being written,
+    /// along with the last block if the write extends past the end of the
+    /// file and the last block has room to grow.
+    fn blocks_to_rewrite(blocks: &[BlockInfo], offset: u64, length: usize) -> Vec<u64> {
+        let end = offset + u64::try_from(length).unwrap();
+        let last_index = blocks.len().checked_sub(1);
+        blocks
+            .iter()
+            .enumerate()
+            .filter(|(index, block)| {
+                let block_end = block.offset + u64::try_from(block.length).unwrap();
+                (block.offset < end && block_end > offset)
+                    || (Some(*index) == last_index
+                        && end > block_end
+                        && block.length < Config::BLOCK_SIZE)
+            })
+            .map(|(_, block)| block.header.id)
+            .collect()
+    }
+
+    fn create_write_transaction(
+        blocks: &[BlockInfo],
+        mut loaded: BTreeMap<u64, Vec<u8>>,
+        offset: u64,
+        data: &[u8],
+        file_id: u32,
+    ) -> Result<(Transaction, BlockChanges<Config>), bonsaidb_core::Error> {
+        let mut tx = Transaction::new();
+        let now = TimestampAsNanoseconds::now();
+        // Like appending, verify the file still exists so that no blocks are
+        // orphaned if it is deleted while being written to.
+        tx.push(Operation::check_document_exists::<File<Config>>(&file_id)?);
+
+        let file_length = blocks
+            .last()
+            .map(|b| b.offset + u64::try_from(b.length).unwrap())
+            .unwrap_or_default();
+        check_write_gap::<Config>(file_length, offset)?;
+
+        // Gather the contents of the blocks being rewritten. These blocks are
+        // always contiguous.
+        let mut changes = BlockChanges::default();
+        let mut region_start = None;
+        let mut contents = Vec::new();
+        let mut headers = Vec::new();
+        for block in blocks {
+            if let Some(block_contents) = loaded.remove(&block.header.id) {
+                changes.removed(
+                    block.header.id,
+                    BlockContents::<Config>::hash(&block_contents),
+                );
+                region_start.get_or_insert(block.offset);
+                contents.extend(block_contents);
+                headers.push(Header::try_from(block.header).expect("u64 serialization can't fail"));
+            }
+        }
+        let region_start = region_start.unwrap_or(file_length);
+        let region_end = region_start + u64::try_from(contents.len()).unwrap();
+
+        // Overwrite the bytes within the rewritten blocks.
+        let mut remaining = data;
+        if offset < region_end {
+            let start = usize::try_from(offset - region_start).unwrap();
+            let bytes_to_write = remaining.len().min(contents.len() - start);
+            contents[start..start + bytes_to_write].copy_from_slice(&remaining[..bytes_to_write]);
+            remaining = &remaining[bytes_to_write..];
+        }
+
+        // Writing past the end of the file fills the gap with zeroes. When
+        // extending the file, the last block is filled up to BLOCK_SIZE before
+        // creating new blocks.
+        let mut gap = offset.saturating_sub(region_end);
+        let unfilled =
+            (Config::BLOCK_SIZE - contents.len() % Config::BLOCK_SIZE) % Config::BLOCK_SIZE;
+        let zeroes = usize::try_from(gap).map_or(unfilled, |gap| gap.min(unfilled));
+        contents.resize(contents.len() + zeroes, 0);
+        gap -= u64::try_from(zeroes).unwrap();
+        if gap == 0 {
+            let bytes_to_write = remaining.len().min(unfilled - zeroes);
+            contents.extend(&remaining[..bytes_to_write]);
+            remaining = &remaining[bytes_to_write..];
+        }
+
+        // Store the rewritten contents in as few blocks as possible, merging
+        // blocks smaller than BLOCK_SIZE and splitting at BLOCK_SIZE
+        // boundaries. Because every block is at most BLOCK_SIZE, this never
+        // needs more blocks than are being rewritten, which keeps the ordering
+        // of the file's blocks intact.
+        let block_collection = Self::collection_name();
+        let mut headers = headers.into_iter();
+        for chunk in contents.chunks(Config::BLOCK_SIZE) {
+            let block = Self::encode(chunk, file_id, now, &mut changes);
+            tx.push(match headers.next() {
+                Some(header) => Operation::update(block_collection.clone(), header, block),
+                None => Operation::insert(block_collection.clone(), None, block),
+            });
+        }
+        for header in headers {
+            tx.push(Operation::delete(block_collection.clone(), header));
+        }
+
+        // Fill the remainder of the gap with blocks of zeroes, completing the
+        // last one with the start of the data being written.
+        if gap > 0 {
+            let zeroes = vec![0; Config::BLOCK_SIZE];
+            let block_size = u64::try_from(Config::BLOCK_SIZE).unwrap();
+            while gap >= block_size {
+                tx.push(Operation::insert(
+                    block_collection.clone(),
+                    None,
+                    Self::encode(&zeroes, file_id, now, &mut changes),
+                ));
+                gap -= block_size;
+            }
+            if gap > 0 {
+                let mut block = vec![0; usize::try_from(gap).unwrap()];
+                let bytes_to_write = remaining.len().min(Config::BLOCK_SIZE - block.len());
+                block.extend(&remaining[..bytes_to_write]);
+                remaining = &remaining[bytes_to_write..];
+                tx.push(Operation::insert(
+                    block_collection.clone(),
+                    None,
+                    Self::encode(&block, file_id, now, &mut changes),
+                ));
+            }
+        }
+
+        Self::push_new_blocks(remaining, file_id, now, &mut changes, &mut tx);
+
+        Ok((tx, changes))
+    }
+
     pub fn load<
         'a,
         DocumentIds: IntoIterator<Item = &'a PrimaryKey, IntoIter = I> + Send + Sync,
@@ -95,16 +343,19 @@ where
         block_ids: DocumentIds,
         database: &Database,
     ) -> Result<BTreeMap<u64, Vec<u8>>, bonsaidb_core::Error> {
-        database
-            .collection::<Self>()
-            .get_multiple(block_ids)?
-            .into_iter()
-            .map(|block| {
-                let mut contents = block.contents.into_vec();
-                contents.truncate(contents.len() - size_of::<u32>() - size_of::<i64>());
-                block.header.id.deserialize().map(|id| (id, contents))
-            })
-            .collect()
+        let blocks = Self::decode_all(database.collection::<Self>().get_multiple(block_ids)?)?;
+        if deduplicates_blocks::<Config>() {
+            let contents = BlockContents::<Config>::load(
+                blocks.values().filter_map(BlockData::hash),
+                database,
+            )?;
+            Self::resolve_references(blocks, &contents)
+        } else {
+            Ok(blocks
+                .into_iter()
+                .filter_map(|(id, block)| block.into_data().map(|data| (id, data)))
+                .collect())
+        }
     }
 
     #[cfg(feature = "async")]
@@ -118,19 +369,99 @@ where
         block_ids: DocumentIds,
         database: &Database,
     ) -> Result<BTreeMap<u64, Vec<u8>>, bonsaidb_core::Error> {
-        database
-            .collection::<Self>()
-            .get_multiple(block_ids)
-            .await?
+        let blocks = Self::decode_all(
+            database
+                .collection::<Self>()
+                .get_multiple(block_ids)
+                .await?,
+        )?;
+        if deduplicates_blocks::<Config>() {
+            let contents = BlockContents::<Config>::load_async(
+                blocks.values().filter_map(BlockData::hash),
+                database,
+            )
+            .await?;
+            Self::resolve_references(blocks, &contents)
+        } else {
+            Ok(blocks
+                .into_iter()
+                .filter_map(|(id, block)| block.into_data().map(|data| (id, data)))
+                .collect())
+        }
+    }
+
+    fn decode_all(
+        documents: Vec<OwnedDocument>,
+    ) -> Result<BTreeMap<u64, BlockData>, bonsaidb_core::Error> {
+        documents
             .into_iter()
             .map(|block| {
                 let mut contents = block.contents.into_vec();
                 contents.truncate(contents.len() - size_of::<u32>() - size_of::<i64>());
-                block.header.id.deserialize().map(|id| (id, contents))
+                let data = if deduplicates_blocks::<Config>() {
+                    let mut hash = BlockHash::default();
+                    hash.copy_from_slice(&contents[..size_of::<BlockHash>()]);
+                    BlockData::Reference(hash)
+                } else {
+                    BlockData::Stored(contents)
+                };
+                block.header.id.deserialize().map(|id| (id, data))
             })
             .collect()
     }
 
+    fn resolve_references(
+        blocks: BTreeMap<u64, BlockData>,
+        contents: &BTreeMap<BlockHash, Vec<u8>>,
+    ) -> Result<BTreeMap<u64, Vec<u8>>, bonsaidb_core::Error> {
+        blocks
+            .into_iter()
+            .map(|(id, block)| {
+                block
+                    .hash()
+                    .and_then(|hash| contents.get(hash))
+                    .map(|data| (id, data.clone()))
+                    .ok_or_else(|| {
+                        bonsaidb_core::Error::other(
+                            "bonsaidb-files",
+                            format!("contents of block {id} are missing"),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the changes that removing the blocks with `block_ids` requires.
+    pub(crate) fn removed_blocks<Database: Connection>(
+        block_ids: &[u64],
+        database: &Database,
+    ) -> Result<BlockChanges<Config>, bonsaidb_core::Error> {
+        if deduplicates_blocks::<Config>() && !block_ids.is_empty() {
+            let blocks = Self::decode_all(database.collection::<Self>().get_multiple(block_ids)?)?;
+            Ok(BlockChanges::removing(&blocks))
+        } else {
+            Ok(BlockChanges::removing_ids(block_ids))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn removed_blocks_async<Database: AsyncConnection>(
+        block_ids: &[u64],
+        database: &Database,
+    ) -> Result<BlockChanges<Config>, bonsaidb_core::Error> {
+        if deduplicates_blocks::<Config>() && !block_ids.is_empty() {
+            let blocks = Self::decode_all(
+                database
+                    .collection::<Self>()
+                    .get_multiple(block_ids)
+                    .await?,
+            )?;
+            Ok(BlockChanges::removing(&blocks))
+        } else {
+            Ok(BlockChanges::removing_ids(block_ids))
+        }
+    }
+
     pub(crate) fn for_file<Database: Connection>(
         file_id: u32,
         database: &Database,
@@ -144,6 +475,7 @@ where
                 header: mapping.source,
                 length: usize::try_from(mapping.value.length).unwrap(),
                 timestamp: mapping.value.timestamp.unwrap(),
+                hash: mapping.value.hash.unwrap(),
                 offset: 0,
             })
             .collect::<Vec<_>>();
@@ -219,6 +551,7 @@ where
                 header: mapping.source,
                 length: usize::try_from(mapping.value.length).unwrap(),
                 timestamp: mapping.value.timestamp.unwrap(),
+                hash: mapping.value.hash.unwrap(),
                 offset: 0,
             })
             .collect::<Vec<_>>();
@@ -231,29 +564,525 @@ where
         Ok(blocks)
     }
 
+    /// Deletes `file` along with its blocks in a single transaction.
     pub fn delete_for_file<Database: Connection>(
-        file_id: u32,
+        file: &CollectionDocument<File<Config>>,
         database: &Database,
     ) -> Result<(), bonsaidb_core::Error> {
-        database
-            .view::<ByFile<Config>>()
-            .with_key(&file_id)
-            .delete_docs()?;
+        let file_id = file.header.id;
+        let blocks = Self::for_file(file_id, database)?;
+        let mut tx = Self::create_delete_transaction(&blocks);
+        tx.push(Operation::delete(
+            File::<Config>::collection_name(),
+            file.header()?,
+        ));
+        let mut changes = Self::removed_blocks(
+            &blocks.iter().map(|b| b.header.id).collect::<Vec<_>>(),
+            database,
+        )?;
+        if deduplicates_blocks::<Config>() {
+            changes.discard(&FileVersion::for_file(file_id, database)?, &mut tx)?;
+        }
+        changes.remove_file();
+        changes.apply(file_id, &mut tx, database)?;
+        tx.apply(database)?;
         Ok(())
     }
 
     #[cfg(feature = "async")]
     pub async fn delete_for_file_async<Database: AsyncConnection>(
+        file: &CollectionDocument<File<Config>>,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let file_id = file.header.id;
+        let blocks = Self::for_file_async(file_id, database).await?;
+        let mut tx = Self::create_delete_transaction(&blocks);
+        tx.push(Operation::delete(
+            File::<Config>::collection_name(),
+            file.header()?,
+        ));
+        let mut changes = Self::removed_blocks_async(
+            &blocks.iter().map(|b| b.header.id).collect::<Vec<_>>(),
+            database,
+        )
+        .await?;
+        if deduplicates_blocks::<Config>() {
+            changes.discard(
+                &FileVersion::for_file_async(file_id, database).await?,
+                &mut tx,
+            )?;
+        }
+        changes.remove_file();
+        changes.apply_async(file_id, &mut tx, database).await?;
+        tx.apply_async(database).await?;
+        Ok(())
+    }
+
+    fn create_delete_transaction(blocks: &[BlockInfo]) -> Transaction {
+        let block_collection = Self::collection_name();
+        let mut tx = Transaction::new();
+        for block in blocks {
+            tx.push(Operation::delete(
+                block_collection.clone(),
+                Header::try_from(block.header).expect("u64 serialization can't fail"),
+            ));
+        }
+        tx
+    }
+
+    /// Returns the current contents of `file_id`, to be preserved as a
+    /// version before the file is modified. This is always empty unless
+    /// [`FileConfig::VERSIONED`] is enabled.
+    pub(crate) fn snapshot<Database: Connection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Vec<VersionBlock>, bonsaidb_core::Error> {
+        if Config::VERSIONED {
+            Self::snapshot_of(&Self::for_file(file_id, database)?, database)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn snapshot_async<Database: AsyncConnection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Vec<VersionBlock>, bonsaidb_core::Error> {
+        if Config::VERSIONED {
+            Self::snapshot_of_async(&Self::for_file_async(file_id, database).await?, database).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the current contents of a file made up of `blocks`, to be
+    /// preserved as a version before the file is modified. This is always
+    /// empty unless [`FileConfig::VERSIONED`] is enabled.
+    pub(crate) fn snapshot_of<Database: Connection>(
+        blocks: &[BlockInfo],
+        database: &Database,
+    ) -> Result<Vec<VersionBlock>, bonsaidb_core::Error> {
+        if Config::VERSIONED && !blocks.is_empty() {
+            Self::decode_snapshot(
+                database
+                    .collection::<Self>()
+                    .get_multiple(&blocks.iter().map(|b| b.header.id).collect::<Vec<_>>())?,
+            )
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn snapshot_of_async<Database: AsyncConnection>(
+        blocks: &[BlockInfo],
+        database: &Database,
+    ) -> Result<Vec<VersionBlock>, bonsaidb_core::Error> {
+        if Config::VERSIONED && !blocks.is_empty() {
+            Self::decode_snapshot(
+                database
+                    .collection::<Self>()
+                    .get_multiple(&blocks.iter().map(|b| b.header.id).collect::<Vec<_>>())
+                    .await?,
+            )
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn decode_snapshot(
+        documents: Vec<OwnedDocument>,
+    ) -> Result<Vec<VersionBlock>, bonsaidb_core::Error> {
+        let blocks = documents
+            .into_iter()
+            .map(|doc| {
+                let (hash, length, timestamp) = decode_reference(&doc.contents);
+                doc.header.id.deserialize::<u64>().map(|id| {
+                    (
+                        id,
+                        VersionBlock {
+                            hash,
+                            length,
+                            timestamp,
+                        },
+                    )
+                })
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        Ok(blocks.into_values().collect())
+    }
+
+    /// Replaces the contents of `file_id` with the contents of the version
+    /// `version_id`. The file's current contents are preserved as a new
+    /// version.
+    pub fn restore<Database: Connection>(
         file_id: u32,
+        version_id: u64,
         database: &Database,
     ) -> Result<(), bonsaidb_core::Error> {
-        database
-            .view::<ByFile<Config>>()
-            .with_key(&file_id)
-            .delete_docs()
-            .await?;
+        let version = FileVersion::<Config>::get_for_file(file_id, version_id, database)?;
+        let blocks = Self::for_file(file_id, database)?;
+        let mut changes = Self::removed_blocks(
+            &blocks.iter().map(|b| b.header.id).collect::<Vec<_>>(),
+            database,
+        )?;
+        changes.preserve(Self::snapshot_of(&blocks, database)?);
+        let mut tx = Self::create_restore_transaction(&blocks, &version, &mut changes)?;
+        changes.apply(file_id, &mut tx, database)?;
+        tx.apply(database)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn restore_async<Database: AsyncConnection>(
+        file_id: u32,
+        version_id: u64,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let version =
+            FileVersion::<Config>::get_for_file_async(file_id, version_id, database).await?;
+        let blocks = Self::for_file_async(file_id, database).await?;
+        let mut changes = Self::removed_blocks_async(
+            &blocks.iter().map(|b| b.header.id).collect::<Vec<_>>(),
+            database,
+        )
+        .await?;
+        changes.preserve(Self::snapshot_of_async(&blocks, database).await?);
+        let mut tx = Self::create_restore_transaction(&blocks, &version, &mut changes)?;
+        changes.apply_async(file_id, &mut tx, database).await?;
+        tx.apply_async(database).await?;
+        Ok(())
+    }
+
+    fn create_restore_transaction(
+        blocks: &[BlockInfo],
+        version: &CollectionDocument<FileVersion<Config>>,
+        changes: &mut BlockChanges<Config>,
+    ) -> Result<Transaction, bonsaidb_core::Error> {
+        let file_id = version.contents.file_id;
+        let mut tx = Self::create_delete_transaction(blocks);
+        tx.push(Operation::check_document_exists::<File<Config>>(&file_id)?);
+        // The version's contents are only guaranteed to be stored while the
+        // version exists, so ensure it isn't pruned concurrently.
+        tx.push(Operation::check_document_is_current::<
+            FileVersion<Config>,
+            _,
+        >(version)?);
+
+        let block_collection = Self::collection_name();
+        let now = TimestampAsNanoseconds::now();
+        for block in &version.contents.blocks {
+            changes.referenced(block.hash);
+            tx.push(Operation::insert(
+                block_collection.clone(),
+                None,
+                Self::encode_reference(&block.hash, block.length, file_id, now),
+            ));
+        }
+        Ok(tx)
+    }
+}
+
+/// Returns an error if writing `data` at `offset` would extend the file past
+/// the maximum length of a file.
+fn check_write_range(offset: u64, data: &[u8]) -> Result<(), bonsaidb_core::Error> {
+    u64::try_from(data.len())
+        .ok()
+        .and_then(|length| offset.checked_add(length))
+        .map(|_| ())
+        .ok_or_else(|| {
+            bonsaidb_core::Error::other(
+                "bonsaidb-files",
+                "write extends past the maximum length of a file",
+            )
+        })
+}
+
+/// Returns an error if writing at `offset` would fill more than
+/// [`FileConfig::MAX_WRITE_GAP`] bytes past the end of a file of
+/// `file_length` bytes with zeroes.
+fn check_write_gap<Config: FileConfig>(
+    file_length: u64,
+    offset: u64,
+) -> Result<(), bonsaidb_core::Error> {
+    let gap = offset.saturating_sub(file_length);
+    if gap > Config::MAX_WRITE_GAP {
+        Err(bonsaidb_core::Error::other(
+            "bonsaidb-files",
+            format!(
+                "write begins {gap} bytes past the end of the file, exceeding the maximum of {}",
+                Config::MAX_WRITE_GAP
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Decodes the hash, length, and timestamp of a block that references
+/// deduplicated contents.
+fn decode_reference(contents: &[u8]) -> (BlockHash, u64, TimestampAsNanoseconds) {
+    let mut hash = BlockHash::default();
+    hash.copy_from_slice(&contents[..size_of::<BlockHash>()]);
+
+    let length_offset = size_of::<BlockHash>();
+    let mut length = [0; size_of::<u64>()];
+    length.copy_from_slice(&contents[length_offset..length_offset + size_of::<u64>()]);
+
+    let mut timestamp = [0; size_of::<i64>()];
+    timestamp.copy_from_slice(&contents[contents.len() - size_of::<i64>()..]);
+
+    (
+        hash,
+        u64::from_be_bytes(length),
+        TimestampAsNanoseconds::from_representation(i64::from_be_bytes(timestamp)),
+    )
+}
+
+/// Changes that must be applied in the same transaction as changes to a
+/// file's blocks: the reference counts of deduplicated contents, the file's
+/// checksum, and the version preserving the file's previous contents.
+#[derive_where(Default)]
+pub(crate) struct BlockChanges<Config>
+where
+    Config: FileConfig,
+{
+    references: ReferenceChanges,
+    /// The ids of the blocks being removed or rewritten. These blocks are
+    /// always contiguous.
+    removed_blocks: BTreeSet<u64>,
+    /// The hashes of the blocks taking the place of `removed_blocks`, in
+    /// order. When no blocks are removed, these blocks are appended.
+    added_blocks: Vec<BlockHash>,
+    removes_file: bool,
+    version: Vec<VersionBlock>,
+    _config: PhantomData<Config>,
+}
+
+impl<Config> BlockChanges<Config>
+where
+    Config: FileConfig,
+{
+    fn removing(blocks: &BTreeMap<u64, BlockData>) -> Self {
+        let mut changes = Self::default();
+        for (id, block) in blocks {
+            changes.removed(
+                *id,
+                match block {
+                    BlockData::Stored(data) => BlockContents::<Config>::hash(data),
+                    BlockData::Reference(hash) => *hash,
+                },
+            );
+        }
+        changes
+    }
+
+    /// Returns the changes removing `block_ids`, for blocks that store their
+    /// own contents.
+    fn removing_ids(block_ids: &[u64]) -> Self {
+        Self {
+            removed_blocks: block_ids.iter().copied().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Records a new block containing `data`, returning the hash of `data`.
+    fn added(&mut self, data: &[u8]) -> BlockHash {
+        let hash = if deduplicates_blocks::<Config>() {
+            self.references.add::<Config>(data)
+        } else {
+            BlockContents::<Config>::hash(data)
+        };
+        self.added_blocks.push(hash);
+        hash
+    }
+
+    /// Records the removal of the block `id`, whose contents hash to `hash`.
+    fn removed(&mut self, id: u64, hash: BlockHash) {
+        if deduplicates_blocks::<Config>() {
+            self.references.remove(hash);
+        }
+        self.removed_blocks.insert(id);
+    }
+
+    /// Records a new block referencing the stored contents with `hash`.
+    fn referenced(&mut self, hash: BlockHash) {
+        self.references.retain(hash);
+        self.added_blocks.push(hash);
+    }
+
+    /// Records that the file is being deleted, which removes its checksum.
+    /// Contents that are already missing are treated as already deleted.
+    pub fn remove_file(&mut self) {
+        self.removes_file = true;
+        self.references.ignore_missing();
+    }
+
+    /// Returns the checksum of the file currently made up of `blocks` once
+    /// these changes are applied.
+    fn checksum(&self, blocks: &[BlockInfo]) -> Checksum {
+        let mut added = Some(&self.added_blocks);
+        let mut hashes = Vec::with_capacity(blocks.len() + self.added_blocks.len());
+        for block in blocks {
+            if self.removed_blocks.contains(&block.header.id) {
+                if let Some(added) = added.take() {
+                    hashes.extend(added);
+                }
+            } else {
+                hashes.push(&block.hash);
+            }
+        }
+        if let Some(added) = added {
+            hashes.extend(added);
+        }
+        Checksum::of_blocks(hashes)
+    }
+
+    /// Returns true if these changes modify the file's blocks.
+    fn changes_blocks(&self) -> bool {
+        self.removes_file || !self.removed_blocks.is_empty() || !self.added_blocks.is_empty()
+    }
+
+    /// Returns true if the file's blocks are needed to compute its new
+    /// checksum. When blocks are only appended to a file with a stored
+    /// checksum, the new checksum is computed from the stored checksum.
+    fn checksum_requires_blocks(&self, existing: Option<&OwnedDocument>) -> bool {
+        !self.removes_file && (existing.is_none() || !self.removed_blocks.is_empty())
+    }
+
+    /// Pushes the operation storing the file's new checksum, or removing it
+    /// if the file is being deleted. `existing` must be loaded before
+    /// `blocks`, ensuring a conflict is returned if the file is modified
+    /// concurrently. `blocks` is only used if
+    /// [`Self::checksum_requires_blocks()`] returns true.
+    fn push_checksum(
+        &self,
+        file_id: u32,
+        existing: Option<OwnedDocument>,
+        blocks: &[BlockInfo],
+        tx: &mut Transaction,
+    ) -> Result<(), bonsaidb_core::Error> {
+        if self.removes_file {
+            if let Some(operation) = FileChecksum::<Config>::delete_operation(existing) {
+                tx.push(operation);
+            }
+        } else {
+            let checksum = match &existing {
+                Some(existing) if self.removed_blocks.is_empty() => {
+                    FileChecksum::<Config>::decode(existing).appending(&self.added_blocks)
+                }
+                _ => self.checksum(blocks),
+            };
+            tx.push(FileChecksum::<Config>::replace_operation(
+                file_id, existing, checksum,
+            )?);
+        }
         Ok(())
     }
+
+    /// Preserves `blocks`, the contents of the file before these changes, as
+    /// a new version of the file. Empty contents are not preserved.
+    pub fn preserve(&mut self, blocks: Vec<VersionBlock>) {
+        for block in &blocks {
+            self.references.retain(block.hash);
+        }
+        self.version = blocks;
+    }
+
+    /// Removes `versions`, releasing their references to block contents.
+    pub fn discard(
+        &mut self,
+        versions: &[CollectionDocument<FileVersion<Config>>],
+        tx: &mut Transaction,
+    ) -> Result<(), bonsaidb_core::Error> {
+        for version in versions {
+            tx.push(Operation::delete(
+                FileVersion::<Config>::collection_name(),
+                version.header()?,
+            ));
+            for block in &version.contents.blocks {
+                self.references.remove(block.hash);
+            }
+        }
+        Ok(())
+    }
+
+    fn push_version(
+        &mut self,
+        file_id: u32,
+        tx: &mut Transaction,
+    ) -> Result<(), bonsaidb_core::Error> {
+        if !self.version.is_empty() {
+            tx.push(Operation::push_serialized::<FileVersion<Config>>(
+                &FileVersion::new(file_id, std::mem::take(&mut self.version)),
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn apply<Database: Connection>(
+        mut self,
+        file_id: u32,
+        tx: &mut Transaction,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.push_version(file_id, tx)?;
+        if self.changes_blocks() {
+            let existing = FileChecksum::<Config>::load(file_id, database)?;
+            let blocks = if self.checksum_requires_blocks(existing.as_ref()) {
+                Block::<Config>::for_file(file_id, database)?
+            } else {
+                Vec::new()
+            };
+            self.push_checksum(file_id, existing, &blocks, tx)?;
+        }
+        BlockContents::<Config>::apply(self.references, tx, database)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn apply_async<Database: AsyncConnection>(
+        mut self,
+        file_id: u32,
+        tx: &mut Transaction,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.push_version(file_id, tx)?;
+        if self.changes_blocks() {
+            let existing = FileChecksum::<Config>::load_async(file_id, database).await?;
+            let blocks = if self.checksum_requires_blocks(existing.as_ref()) {
+                Block::<Config>::for_file_async(file_id, database).await?
+            } else {
+                Vec::new()
+            };
+            self.push_checksum(file_id, existing, &blocks, tx)?;
+        }
+        BlockContents::<Config>::apply_async(self.references, tx, database).await
+    }
+}
+
+/// The decoded contents of a block document.
+enum BlockData {
+    /// The block's data is stored within the block.
+    Stored(Vec<u8>),
+    /// The block's data is stored in [`BlockContents`], keyed by this hash.
+    Reference(BlockHash),
+}
+
+impl BlockData {
+    fn hash(&self) -> Option<&BlockHash> {
+        match self {
+            Self::Reference(hash) => Some(hash),
+            Self::Stored(_) => None,
+        }
+    }
+
+    fn into_data(self) -> Option<Vec<u8>> {
+        match self {
+            Self::Stored(data) => Some(data),
+            Self::Reference(_) => None,
+        }
+    }
 }
 
 impl<Config> Collection for Block<Config>
@@ -279,7 +1108,7 @@ where
 #[derive(View, ViewSchema)]
 #[view(name = "by-file", collection = Block<Config>, key = u32, value = BlockAppendInfo)]
 #[view(core = bonsaidb_core)]
-#[view_schema(version = 2)]
+#[view_schema(version = 3)]
 #[view_schema(core = bonsaidb_core)]
 struct ByFile<Config>(PhantomData<Config>)
 where
@@ -301,13 +1130,23 @@ where
         timestamp.copy_from_slice(&doc.contents[timestamp_offset..]);
         let timestamp = TimestampAsNanoseconds::from_representation(i64::from_be_bytes(timestamp));
 
-        let length = u64::try_from(file_id_offset).unwrap();
+        let (hash, length) = if deduplicates_blocks::<Config>() {
+            // Deduplicated blocks store the hash and length of their contents.
+            let (hash, length, _) = decode_reference(&doc.contents);
+            (hash, length)
+        } else {
+            (
+                BlockContents::<Config>::hash(&doc.contents[..file_id_offset]),
+                u64::try_from(file_id_offset).unwrap(),
+            )
+        };
 
         doc.header.emit_key_and_value(
             file_id,
             BlockAppendInfo {
                 length,
                 timestamp: Some(timestamp),
+                hash: Some(hash),
             },
         )
     }
@@ -323,6 +1162,7 @@ where
                 .iter()
                 .filter_map(|info| info.value.timestamp)
                 .max(),
+            hash: None,
         })
     }
 }
@@ -331,4 +1171,8 @@ where
 pub struct BlockAppendInfo {
     pub length: u64,
     pub timestamp: Option<TimestampAsNanoseconds>,
+    /// The hash of the block's contents. This is `None` when multiple blocks
+    /// are reduced.
+    #[serde(default)]
+    pub hash: Option<BlockHash>,
 }
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+#[cfg(feature = "async")]
+use bonsaidb_core::connection::AsyncConnection;
+use bonsaidb_core::connection::Connection;
+use bonsaidb_core::document::{DocumentId, OwnedDocument};
+use bonsaidb_core::schema::{Collection, CollectionName};
+use bonsaidb_core::transaction::{Operation, Transaction};
+use derive_where::derive_where;
+
+use crate::FileConfig;
+
+/// The BLAKE3 hash of a deduplicated block's contents.
+pub type BlockHash = [u8; 32];
+
+/// Stores the contents of blocks when [`FileConfig::DEDUPLICATE_BLOCKS`] or
+/// [`FileConfig::VERSIONED`] is enabled. Each document is keyed by the hash of
+/// its contents, and tracks how many blocks and versions reference it.
+#[derive_where(Debug, Default)]
+pub struct BlockContents<Config>(PhantomData<Config>)
+where
+    Config: FileConfig;
+
+impl<Config> BlockContents<Config>
+where
+    Config: FileConfig,
+{
+    pub fn hash(data: &[u8]) -> BlockHash {
+        *blake3::hash(data).as_bytes()
+    }
+
+    /// Loads the contents for each hash in `hashes`.
+    pub fn load<
+        'a,
+        Hashes: IntoIterator<Item = &'a BlockHash, IntoIter = I> + Send + Sync,
+        I: Iterator<Item = &'a BlockHash> + Send + Sync,
+        Database: Connection,
+    >(
+        hashes: Hashes,
+        database: &Database,
+    ) -> Result<BTreeMap<BlockHash, Vec<u8>>, bonsaidb_core::Error> {
+        Self::decode_all(database.collection::<Self>().get_multiple(hashes)?)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn load_async<
+        'a,
+        Hashes: IntoIterator<Item = &'a BlockHash, IntoIter = I> + Send + Sync,
+        I: Iterator<Item = &'a BlockHash> + Send + Sync,
+        Database: AsyncConnection,
+    >(
+        hashes: Hashes,
+        database: &Database,
+    ) -> Result<BTreeMap<BlockHash, Vec<u8>>, bonsaidb_core::Error> {
+        Self::decode_all(database.collection::<Self>().get_multiple(hashes).await?)
+    }
+
+    /// Loads the currently stored documents for the hashes in `references`
+    /// and pushes the operations needed to apply `references` into `tx`.
+    pub fn apply<Database: Connection>(
+        references: ReferenceChanges,
+        tx: &mut Transaction,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        if !references.is_empty() {
+            let existing = database
+                .collection::<Self>()
+                .get_multiple(&references.hashes())?;
+            references.push_operations::<Config>(existing, tx)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn apply_async<Database: AsyncConnection>(
+        references: ReferenceChanges,
+        tx: &mut Transaction,
+        database: &Database,
+    ) -> Result<(), bonsaidb_core::Error> {
+        if !references.is_empty() {
+            let existing = database
+                .collection::<Self>()
+                .get_multiple(&references.hashes())
+                .await?;
+            references.push_operations::<Config>(existing, tx)?;
+        }
+        Ok(())
+    }
+
+    fn decode_all(
+        documents: Vec<OwnedDocument>,
+    ) -> Result<BTreeMap<BlockHash, Vec<u8>>, bonsaidb_core::Error> {
+        documents
+            .into_iter()
+            .map(|doc| {
+                let (contents, _) = decode(doc.contents.into_vec());
+                doc.header.id.deserialize().map(|hash| (hash, contents))
+            })
+            .collect()
+    }
+}
+
+impl<Config> Collection for BlockContents<Config>
+where
+    Config: FileConfig,
+{
+    type PrimaryKey = BlockHash;
+
+    fn collection_name() -> CollectionName {
+        Config::block_contents_name()
+    }
+
+    fn define_views(
+        _schema: &mut bonsaidb_core::schema::Schematic,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(())
+    }
+}
+
+/// Splits a stored document into the block's contents and its reference
+/// count.
+fn decode(mut contents: Vec<u8>) -> (Vec<u8>, u64) {
+    let count_offset = contents.len() - size_of::<u64>();
+    let mut references = [0; size_of::<u64>()];
+    references.copy_from_slice(&contents[count_offset..]);
+    contents.truncate(count_offset);
+    (contents, u64::from_be_bytes(references))
+}
+
+fn encode(data: &[u8], references: u64) -> Vec<u8> {
+    let mut contents = Vec::with_capacity(data.len() + size_of::<u64>());
+    contents.extend(data);
+    contents.extend(references.to_be_bytes());
+    contents
+}
+
+/// A set of pending changes to the reference counts of deduplicated blocks.
+#[derive(Default)]
+pub struct ReferenceChanges {
+    changes: BTreeMap<BlockHash, ReferenceChange>,
+    ignores_missing: bool,
+}
+
+#[derive(Default)]
+struct ReferenceChange {
+    delta: i64,
+    data: Option<Vec<u8>>,
+}
+
+impl ReferenceChanges {
+    /// Records a new reference to `data`, returning its hash.
+    pub fn add<Config: FileConfig>(&mut self, data: &[u8]) -> BlockHash {
+        let hash = BlockContents::<Config>::hash(data);
+        let change = self.changes.entry(hash).or_default();
+        change.delta += 1;
+        if change.data.is_none() {
+            change.data = Some(data.to_vec());
+        }
+        hash
+    }
+
+    /// Records a new reference to contents that are already stored.
+    pub fn retain(&mut self, hash: BlockHash) {
+        self.changes.entry(hash).or_default().delta += 1;
+    }
+
+    /// Records that a reference to the contents with `hash` was removed.
+    pub fn remove(&mut self, hash: BlockHash) {
+        self.changes.entry(hash).or_default().delta -= 1;
+    }
+
+    /// Treats removed references to contents that are no longer stored as
+    /// already released instead of failing.
+    pub fn ignore_missing(&mut self) {
+        self.ignores_missing = true;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.values().all(|change| change.delta == 0)
+    }
+
+    fn hashes(&self) -> Vec<BlockHash> {
+        self.changes
+            .iter()
+            .filter(|(_, change)| change.delta != 0)
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
+    /// Pushes the inserts, updates, and deletes needed to apply these changes
+    /// on top of the `existing` documents. Updates and deletes include the
+    /// revision that was read, causing the transaction to fail with a conflict
+    /// if another writer changed the same contents concurrently.
+    fn push_operations<Config: FileConfig>(
+        mut self,
+        existing: Vec<OwnedDocument>,
+        tx: &mut Transaction,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let collection = Config::block_contents_name();
+        for doc in existing {
+            let hash = doc.header.id.deserialize::<BlockHash>()?;
+            let Some(change) = self.changes.remove(&hash) else {
+                continue;
+            };
+            if change.delta == 0 {
+                continue;
+            }
+
+            let (data, references) = decode(doc.contents.into_vec());
+            let references = references.saturating_add_signed(change.delta);
+            if references == 0 {
+                tx.push(Operation::delete(collection.clone(), doc.header));
+            } else {
+                tx.push(Operation::update(
+                    collection.clone(),
+                    doc.header,
+                    encode(&data, references),
+                ));
+            }
+        }
+
+        // Anything left over is either newly referenced content or content
+        // that is no longer stored.
+        let ignores_missing = self.ignores_missing;
+        for (hash, change) in self.changes {
+            if change.delta == 0 {
+                continue;
+            }
+            match (u64::try_from(change.delta), change.data) {
+                (Ok(references), Some(data)) => {
+                    tx.push(Operation::insert(
+                        collection.clone(),
+                        Some(DocumentId::new(&hash)?),
+                        encode(&data, references),
+                    ));
+                }
+                (Err(_), _) if ignores_missing => {}
+                _ => {
+                    // The contents were referenced without providing their
+                    // data, or references to them were removed, but they
+                    // aren't stored. Either another writer removed them after
+                    // they were read, or the database is inconsistent.
+                    return Err(bonsaidb_core::Error::DocumentNotFound(
+                        collection,
+                        Box::new(DocumentId::new(&hash)?),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits data into chunks using content-defined chunking, which places chunk
+/// boundaries based on the data itself rather than at fixed offsets. Inserting
+/// or removing bytes only changes the chunks near the edit, allowing the rest
+/// of the chunks to be deduplicated against previously stored data.
+///
+/// Chunks are never larger than `max_size`, and are at least a quarter of
+/// `max_size` unless they are at the end of `data`.
+pub struct ContentChunks<'a> {
+    data: &'a [u8],
+    min_size: usize,
+    max_size: usize,
+    mask: u64,
+}
+
+impl<'a> ContentChunks<'a> {
+    pub fn new(data: &'a [u8], max_size: usize) -> Self {
+        let max_size = max_size.max(1);
+        // Target an average chunk size of half of the maximum size.
+        let average_bits = (max_size / 2).max(1).next_power_of_two().trailing_zeros();
+        Self {
+            data,
+            min_size: max_size / 4,
+            max_size,
+            // Use the most significant bits of the rolling hash, as they are
+            // influenced by the largest window of input.
+            mask: (!0_u64).checked_shl(64 - average_bits).unwrap_or_default(),
+        }
+    }
+}
+
+impl<'a> Iterator for ContentChunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let limit = self.data.len().min(self.max_size);
+        let mut boundary = limit;
+        let mut hash = 0_u64;
+        for (index, byte) in self.data[..limit].iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[usize::from(*byte)]);
+            if index + 1 >= self.min_size && (hash & self.mask) == 0 {
+                boundary = index + 1;
+                break;
+            }
+        }
+
+        let (chunk, remaining) = self.data.split_at(boundary);
+        self.data = remaining;
+        Some(chunk)
+    }
+}
+
+/// A table of random values used by the gear rolling hash.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // Fill the table using SplitMix64 so that the values are stable across
+    // builds and platforms.
+    let mut table = [0; 256];
+    let mut state = 0_u64;
+    let mut index = 0;
+    while index < table.len() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[index] = value ^ (value >> 31);
+        index += 1;
+    }
+    table
+}
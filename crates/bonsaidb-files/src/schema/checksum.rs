@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+
+#[cfg(feature = "async")]
+use bonsaidb_core::connection::AsyncConnection;
+use bonsaidb_core::connection::Connection;
+use bonsaidb_core::document::{DocumentId, OwnedDocument};
+use bonsaidb_core::schema::{Collection, CollectionName};
+use bonsaidb_core::transaction::Operation;
+use derive_where::derive_where;
+
+use crate::{Checksum, FileConfig};
+
+/// Stores the [`Checksum`] of each file, keyed by the file's id.
+#[derive_where(Debug, Default)]
+pub struct FileChecksum<Config>(PhantomData<Config>)
+where
+    Config: FileConfig;
+
+impl<Config> FileChecksum<Config>
+where
+    Config: FileConfig,
+{
+    pub fn get<Database: Connection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Option<Checksum>, bonsaidb_core::Error> {
+        Ok(database
+            .collection::<Self>()
+            .get(&file_id)?
+            .map(|doc| Self::decode(&doc)))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_async<Database: AsyncConnection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Option<Checksum>, bonsaidb_core::Error> {
+        Ok(database
+            .collection::<Self>()
+            .get(&file_id)
+            .await?
+            .map(|doc| Self::decode(&doc)))
+    }
+
+    /// Returns the operation that stores the checksum of a newly created file.
+    pub fn insert(file_id: u32, checksum: Checksum) -> Result<Operation, bonsaidb_core::Error> {
+        Ok(Operation::insert(
+            Self::collection_name(),
+            Some(DocumentId::new(&file_id)?),
+            checksum.as_bytes().to_vec(),
+        ))
+    }
+
+    /// Returns the document storing the checksum for `file_id`, if present.
+    pub fn load<Database: Connection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Option<OwnedDocument>, bonsaidb_core::Error> {
+        database.collection::<Self>().get(&file_id)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn load_async<Database: AsyncConnection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Option<OwnedDocument>, bonsaidb_core::Error> {
+        database.collection::<Self>().get(&file_id).await
+    }
+
+    /// Stores `checksum` for `file_id`, replacing any existing checksum.
+    /// `existing` must be the document that was loaded before computing
+    /// `checksum`, ensuring a conflict is returned if the file was modified in
+    /// the meantime.
+    pub fn replace_operation(
+        file_id: u32,
+        existing: Option<OwnedDocument>,
+        checksum: Checksum,
+    ) -> Result<Operation, bonsaidb_core::Error> {
+        match existing {
+            Some(doc) => Ok(Operation::update(
+                Self::collection_name(),
+                doc.header,
+                checksum.as_bytes().to_vec(),
+            )),
+            None => Self::insert(file_id, checksum),
+        }
+    }
+
+    /// Returns the operation that removes the `existing` checksum, if one is
+    /// stored.
+    pub fn delete_operation(existing: Option<OwnedDocument>) -> Option<Operation> {
+        existing.map(|doc| Operation::delete(Self::collection_name(), doc.header))
+    }
+
+    /// Returns the checksum stored in `doc`.
+    pub fn decode(doc: &OwnedDocument) -> Checksum {
+        let mut checksum = [0; 32];
+        let length = doc.contents.len().min(checksum.len());
+        checksum[..length].copy_from_slice(&doc.contents[..length]);
+        Checksum::from_bytes(checksum)
+    }
+}
+
+impl<Config> Collection for FileChecksum<Config>
+where
+    Config: FileConfig,
+{
+    type PrimaryKey = u32;
+
+    fn collection_name() -> CollectionName {
+        Config::checksums_name()
+    }
+
+    fn define_views(
+        _schema: &mut bonsaidb_core::schema::Schematic,
+    ) -> Result<(), bonsaidb_core::Error> {
+        Ok(())
+    }
+}
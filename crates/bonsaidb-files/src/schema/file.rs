@@ -12,8 +12,8 @@ use bonsaidb_core::key::{
 };
 use bonsaidb_core::schema::view::map::ViewMappings;
 use bonsaidb_core::schema::{
-    Collection, CollectionMapReduce, CollectionName, DefaultSerialization, SerializedCollection,
-    View, ViewMapResult,
+    Collection, CollectionMapReduce, CollectionName, DefaultSerialization, SerializationFormat,
+    SerializedCollection, View, ViewMapResult,
 };
 use bonsaidb_core::transaction::{Operation, Transaction};
 use bonsaidb_macros::ViewSchema;
@@ -23,7 +23,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::direct::BlockInfo;
 use crate::schema::block::Block;
-use crate::{BonsaiFiles, Error, FileConfig, Statistics, Truncate};
+use crate::schema::checksum::FileChecksum;
+use crate::{BonsaiFiles, Checksum, Error, FileConfig, Statistics, Truncate};
 
 #[derive_where(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
@@ -72,6 +73,11 @@ where
             _name: PhantomData,
         }
         .push_into(database)?;
+        Transaction::from(FileChecksum::<Config>::insert(
+            file.header.id,
+            Checksum::default(),
+        )?)
+        .apply(database)?;
         Block::<Config>::append(contents, file.header.id, database)?;
         Ok(file)
     }
@@ -105,6 +111,12 @@ where
         }
         .push_into_async(database)
         .await?;
+        Transaction::from(FileChecksum::<Config>::insert(
+            file.header.id,
+            Checksum::default(),
+        )?)
+        .apply_async(database)
+        .await?;
         Block::<Config>::append_async(contents, file.header.id, database).await?;
         Ok(file)
     }
@@ -298,11 +310,13 @@ where
         from: Truncate,
         database: &Database,
     ) -> Result<(), bonsaidb_core::Error> {
-        let tx = Self::create_truncate_transaction(
-            Block::<Config>::for_file(file.header.id, database)?,
-            new_length,
-            from,
-        );
+        let blocks = Block::<Config>::for_file(file.header.id, database)?;
+        let (mut tx, removed) = Self::create_truncate_transaction(blocks.clone(), new_length, from);
+        let mut changes = Block::<Config>::removed_blocks(&removed, database)?;
+        if !removed.is_empty() {
+            changes.preserve(Block::<Config>::snapshot_of(&blocks, database)?);
+        }
+        changes.apply(file.header.id, &mut tx, database)?;
 
         tx.apply(database)?;
         Ok(())
@@ -312,12 +326,13 @@ where
         mut blocks: Vec<BlockInfo>,
         new_length: u64,
         from: Truncate,
-    ) -> Transaction {
+    ) -> (Transaction, Vec<u64>) {
         let total_length: u64 = blocks
             .iter()
             .map(|b| u64::try_from(b.length).unwrap())
             .sum();
         let mut tx = Transaction::new();
+        let mut removed = Vec::new();
         if let Some(mut bytes_to_remove) = total_length.checked_sub(new_length) {
             let block_collection = Config::blocks_name();
             while bytes_to_remove > 0 && !blocks.is_empty() {
@@ -332,7 +347,7 @@ where
                         Header::try_from(blocks[offset].header)
                             .expect("u64 serialization can't fail"),
                     ));
-                    blocks.remove(offset);
+                    removed.push(blocks.remove(offset).header.id);
                     bytes_to_remove -= block_length;
                 } else {
                     // Partial removal. For now, we're just not going to support
@@ -341,7 +356,7 @@ where
                 }
             }
         }
-        tx
+        (tx, removed)
     }
 
     #[cfg(feature = "async")]
@@ -351,11 +366,15 @@ where
         from: Truncate,
         database: &Database,
     ) -> Result<(), bonsaidb_core::Error> {
-        let tx = Self::create_truncate_transaction(
-            Block::<Config>::for_file_async(file.header.id, database).await?,
-            new_length,
-            from,
-        );
+        let blocks = Block::<Config>::for_file_async(file.header.id, database).await?;
+        let (mut tx, removed) = Self::create_truncate_transaction(blocks.clone(), new_length, from);
+        let mut changes = Block::<Config>::removed_blocks_async(&removed, database).await?;
+        if !removed.is_empty() {
+            changes.preserve(Block::<Config>::snapshot_of_async(&blocks, database).await?);
+        }
+        changes
+            .apply_async(file.header.id, &mut tx, database)
+            .await?;
 
         tx.apply_async(database).await?;
         Ok(())
@@ -379,6 +398,10 @@ where
 
         Ok(())
     }
+
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Pot
+    }
 }
 
 impl<Config> DefaultSerialization for File<Config> where Config: FileConfig {}
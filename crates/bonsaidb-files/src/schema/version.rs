@@ -0,0 +1,250 @@
+use std::marker::PhantomData;
+
+#[cfg(feature = "async")]
+use bonsaidb_core::connection::AsyncConnection;
+use bonsaidb_core::connection::Connection;
+use bonsaidb_core::document::{CollectionDocument, DocumentId, Emit};
+use bonsaidb_core::key::time::TimestampAsNanoseconds;
+use bonsaidb_core::schema::{
+    Collection, CollectionMapReduce, CollectionName, DefaultSerialization, SerializationFormat,
+    SerializedCollection, View, ViewMapResult,
+};
+use bonsaidb_core::transaction::Transaction;
+use bonsaidb_macros::ViewSchema;
+use derive_where::derive_where;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::block::BlockChanges;
+use crate::schema::block_contents::BlockHash;
+use crate::{FileConfig, Prune};
+
+/// A previous version of a file's contents, stored when
+/// [`FileConfig::VERSIONED`] is enabled. Versions reference the contents of
+/// their blocks by hash, sharing them with the file and with other versions.
+#[derive_where(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct FileVersion<Config>
+where
+    Config: FileConfig,
+{
+    pub file_id: u32,
+    pub replaced_at: TimestampAsNanoseconds,
+    pub blocks: Vec<VersionBlock>,
+
+    #[serde(skip)]
+    #[derive_where(skip)]
+    _config: PhantomData<Config>,
+}
+
+/// A block of a [`FileVersion`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VersionBlock {
+    pub hash: BlockHash,
+    pub length: u64,
+    pub timestamp: TimestampAsNanoseconds,
+}
+
+impl<Config> FileVersion<Config>
+where
+    Config: FileConfig,
+{
+    pub fn new(file_id: u32, blocks: Vec<VersionBlock>) -> Self {
+        Self {
+            file_id,
+            replaced_at: TimestampAsNanoseconds::now(),
+            blocks,
+            _config: PhantomData,
+        }
+    }
+
+    pub fn length(&self) -> u64 {
+        self.blocks.iter().map(|block| block.length).sum()
+    }
+
+    pub fn last_appended_at(&self) -> Option<TimestampAsNanoseconds> {
+        self.blocks.iter().map(|block| block.timestamp).max()
+    }
+
+    /// Returns the versions of `file_id`, oldest first.
+    pub fn for_file<Database: Connection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Vec<CollectionDocument<Self>>, bonsaidb_core::Error> {
+        if !Config::VERSIONED {
+            return Ok(Vec::new());
+        }
+
+        Ok(database
+            .view::<ByFile<Config>>()
+            .with_key(&file_id)
+            .query_with_collection_docs()?
+            .documents
+            .into_values()
+            .collect())
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn for_file_async<Database: AsyncConnection>(
+        file_id: u32,
+        database: &Database,
+    ) -> Result<Vec<CollectionDocument<Self>>, bonsaidb_core::Error> {
+        if !Config::VERSIONED {
+            return Ok(Vec::new());
+        }
+
+        Ok(database
+            .view::<ByFile<Config>>()
+            .with_key(&file_id)
+            .query_with_collection_docs()
+            .await?
+            .documents
+            .into_values()
+            .collect())
+    }
+
+    /// Returns the version `version_id` of `file_id`. A
+    /// [`DocumentNotFound`](bonsaidb_core::Error::DocumentNotFound) error is
+    /// returned if the version doesn't exist or belongs to another file.
+    pub fn get_for_file<Database: Connection>(
+        file_id: u32,
+        version_id: u64,
+        database: &Database,
+    ) -> Result<CollectionDocument<Self>, bonsaidb_core::Error> {
+        let version = if Config::VERSIONED {
+            Self::get(&version_id, database)?
+        } else {
+            None
+        };
+        Self::for_file_or_not_found(file_id, version_id, version)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_for_file_async<Database: AsyncConnection>(
+        file_id: u32,
+        version_id: u64,
+        database: &Database,
+    ) -> Result<CollectionDocument<Self>, bonsaidb_core::Error> {
+        let version = if Config::VERSIONED {
+            Self::get_async(&version_id, database).await?
+        } else {
+            None
+        };
+        Self::for_file_or_not_found(file_id, version_id, version)
+    }
+
+    fn for_file_or_not_found(
+        file_id: u32,
+        version_id: u64,
+        version: Option<CollectionDocument<Self>>,
+    ) -> Result<CollectionDocument<Self>, bonsaidb_core::Error> {
+        version
+            .filter(|version| version.contents.file_id == file_id)
+            .ok_or_else(|| {
+                bonsaidb_core::Error::DocumentNotFound(
+                    Self::collection_name(),
+                    Box::new(DocumentId::from_u64(version_id)),
+                )
+            })
+    }
+
+    /// Removes the versions of `file_id` selected by `prune`, returning the
+    /// number of versions removed.
+    pub fn prune<Database: Connection>(
+        file_id: u32,
+        prune: Prune,
+        database: &Database,
+    ) -> Result<usize, bonsaidb_core::Error> {
+        let versions = Self::select_pruned(Self::for_file(file_id, database)?, prune);
+        if !versions.is_empty() {
+            let mut tx = Transaction::new();
+            let mut changes = BlockChanges::<Config>::default();
+            changes.discard(&versions, &mut tx)?;
+            changes.apply(file_id, &mut tx, database)?;
+            tx.apply(database)?;
+        }
+        Ok(versions.len())
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn prune_async<Database: AsyncConnection>(
+        file_id: u32,
+        prune: Prune,
+        database: &Database,
+    ) -> Result<usize, bonsaidb_core::Error> {
+        let versions = Self::select_pruned(Self::for_file_async(file_id, database).await?, prune);
+        if !versions.is_empty() {
+            let mut tx = Transaction::new();
+            let mut changes = BlockChanges::<Config>::default();
+            changes.discard(&versions, &mut tx)?;
+            changes.apply_async(file_id, &mut tx, database).await?;
+            tx.apply_async(database).await?;
+        }
+        Ok(versions.len())
+    }
+
+    /// Returns the versions from `versions`, which must be ordered oldest
+    /// first, that `prune` removes.
+    fn select_pruned(
+        mut versions: Vec<CollectionDocument<Self>>,
+        prune: Prune,
+    ) -> Vec<CollectionDocument<Self>> {
+        match prune {
+            Prune::KeepingNewest(count) => {
+                versions.truncate(versions.len().saturating_sub(count));
+            }
+            Prune::OlderThan(age) => {
+                let now = TimestampAsNanoseconds::now();
+                versions.retain(|version| {
+                    matches!(
+                        now.duration_since(&version.contents.replaced_at),
+                        Ok(Some(elapsed)) if elapsed > age
+                    )
+                });
+            }
+        }
+        versions
+    }
+}
+
+impl<Config> Collection for FileVersion<Config>
+where
+    Config: FileConfig,
+{
+    type PrimaryKey = u64;
+
+    fn collection_name() -> CollectionName {
+        Config::versions_name()
+    }
+
+    fn define_views(
+        schema: &mut bonsaidb_core::schema::Schematic,
+    ) -> Result<(), bonsaidb_core::Error> {
+        schema.define_view(ByFile::<Config>::default())?;
+
+        Ok(())
+    }
+
+    fn serialization_format() -> SerializationFormat {
+        SerializationFormat::Pot
+    }
+}
+
+impl<Config> DefaultSerialization for FileVersion<Config> where Config: FileConfig {}
+
+#[derive_where(Clone, Debug, Default)]
+#[derive(View, ViewSchema)]
+#[view(name = "by-file", collection = FileVersion<Config>, key = u32)]
+#[view(core = bonsaidb_core)]
+#[view_schema(core = bonsaidb_core)]
+struct ByFile<Config>(PhantomData<Config>)
+where
+    Config: FileConfig;
+
+impl<Config> CollectionMapReduce for ByFile<Config>
+where
+    Config: FileConfig,
+{
+    fn map<'doc>(&self, doc: CollectionDocument<FileVersion<Config>>) -> ViewMapResult<'doc, Self> {
+        doc.header.emit_key(doc.contents.file_id)
+    }
+}
@@ -1,8 +1,12 @@
 use std::io::{Read, Seek, Write};
 use std::mem::size_of;
+use std::time::Duration;
 
+use bonsaidb_core::connection::Connection;
+use bonsaidb_core::document::Header;
 use bonsaidb_core::key::time::TimestampAsNanoseconds;
 use bonsaidb_core::test_util::TestDirectory;
+use bonsaidb_core::transaction::{Operation, Transaction};
 use bonsaidb_local::config::{Builder, StorageConfiguration};
 #[cfg(feature = "async")]
 use bonsaidb_local::AsyncDatabase;
@@ -10,9 +14,13 @@ use bonsaidb_local::Database;
 #[cfg(feature = "async")]
 use futures::StreamExt;
 #[cfg(feature = "async")]
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
-use crate::{BonsaiFiles, Error, FileConfig, FilesSchema, Truncate};
+use crate::schema::block::Block;
+use crate::schema::block_contents::BlockContents;
+use crate::schema::checksum::FileChecksum;
+use crate::schema::version::FileVersion;
+use crate::{BonsaiFiles, Error, FileConfig, FilesSchema, Prune, Truncate};
 
 #[test]
 fn simple_file_test() {
@@ -408,6 +416,7 @@ impl FileConfig for SmallBlocks {
     type Metadata = usize;
 
     const BLOCK_SIZE: usize = 8;
+    const MAX_WRITE_GAP: u64 = 64;
 
     fn files_name() -> bonsaidb_core::schema::CollectionName {
         BonsaiFiles::files_name()
@@ -558,6 +567,573 @@ async fn async_blocked_file_test() {
     assert_eq!(contents.to_vec().await.unwrap(), data_written);
 }
 
+#[test]
+fn random_write_test() {
+    let directory = TestDirectory::new("random-write");
+    let database =
+        Database::open::<FilesSchema<SmallBlocks>>(StorageConfiguration::new(&directory)).unwrap();
+
+    let mut expected = (0..SmallBlocks::BLOCK_SIZE * 3)
+        .map(|i| u8::try_from(i).unwrap())
+        .collect::<Vec<_>>();
+    let mut file = SmallBlocks::build("hello.bin")
+        .contents(&expected)
+        .create(&database)
+        .unwrap();
+
+    // Overwrite a range spanning the first two blocks.
+    file.write_at(4, b"abcdefgh").unwrap();
+    expected[4..12].copy_from_slice(b"abcdefgh");
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+
+    // Write a range that extends past the end of the file.
+    file.write_at(u64::try_from(expected.len() - 2).unwrap(), b"0123456789")
+        .unwrap();
+    expected.truncate(expected.len() - 2);
+    expected.extend(b"0123456789");
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+
+    // Overwriting data within full blocks doesn't change the file's layout.
+    let block_count = file.contents().unwrap().count();
+    let mut writer = file.write_buffered();
+    writer.seek(std::io::SeekFrom::Start(2)).unwrap();
+    writer.write_all(b"xyz").unwrap();
+    writer.seek(std::io::SeekFrom::End(-3)).unwrap();
+    writer.write_all(b"end").unwrap();
+    drop(writer);
+    expected[2..5].copy_from_slice(b"xyz");
+    let end = expected.len() - 3;
+    expected[end..].copy_from_slice(b"end");
+    assert_eq!(file.contents().unwrap().count(), block_count);
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+
+    // Seeking past the end of the file fills the gap with zeroes.
+    let mut writer = file.write_buffered();
+    writer.seek(std::io::SeekFrom::End(4)).unwrap();
+    writer.write_all(b"!").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+    expected.extend([0, 0, 0, 0, b'!']);
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+    assert!(file
+        .contents()
+        .unwrap()
+        .all(|block| block.unwrap().len() <= SmallBlocks::BLOCK_SIZE));
+
+    // Large gaps are filled with blocks of zeroes.
+    let gap = SmallBlocks::BLOCK_SIZE * 2 + 5;
+    file.write_at(u64::try_from(expected.len() + gap).unwrap(), b"?")
+        .unwrap();
+    expected.resize(expected.len() + gap, 0);
+    expected.push(b'?');
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+    assert!(file
+        .contents()
+        .unwrap()
+        .all(|block| block.unwrap().len() <= SmallBlocks::BLOCK_SIZE));
+
+    // Writes that would extend the file past its maximum length fail.
+    assert!(file.write_at(u64::MAX, b"!").is_err());
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+
+    // Writes that would fill more than MAX_WRITE_GAP bytes with zeroes fail.
+    let too_far = u64::try_from(expected.len()).unwrap() + SmallBlocks::MAX_WRITE_GAP + 1;
+    assert!(file.write_at(too_far, b"!").is_err());
+    assert_eq!(file.contents().unwrap().into_vec().unwrap(), expected);
+
+    // Overwriting a range spanning blocks smaller than BLOCK_SIZE merges them.
+    let small = SmallBlocks::build("small.bin").create(&database).unwrap();
+    let mut expected = Vec::new();
+    for chunk in [b"abc", b"def", b"ghi", b"jkl"] {
+        small.append(chunk).unwrap();
+        expected.extend(chunk);
+    }
+    assert_eq!(small.contents().unwrap().count(), 4);
+    small.write_at(1, b"1234567").unwrap();
+    expected[1..8].copy_from_slice(b"1234567");
+    assert_eq!(small.contents().unwrap().count(), 3);
+    assert_eq!(small.contents().unwrap().into_vec().unwrap(), expected);
+
+    // Extending the file fills the last block before splitting the remaining
+    // data into new blocks.
+    small.write_at(11, b"0123456789").unwrap();
+    expected.truncate(11);
+    expected.extend(b"0123456789");
+    assert_eq!(small.contents().unwrap().into_vec().unwrap(), expected);
+    assert_eq!(
+        small
+            .contents()
+            .unwrap()
+            .map(|block| block.unwrap().len())
+            .collect::<Vec<_>>(),
+        vec![8, 1, 8, 4]
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_random_write_test() {
+    let directory = TestDirectory::new("random-write-async");
+    let database =
+        AsyncDatabase::open::<FilesSchema<SmallBlocks>>(StorageConfiguration::new(&directory))
+            .await
+            .unwrap();
+
+    let mut expected = (0..SmallBlocks::BLOCK_SIZE * 3)
+        .map(|i| u8::try_from(i).unwrap())
+        .collect::<Vec<_>>();
+    let mut file = SmallBlocks::build("hello.bin")
+        .contents(&expected)
+        .create_async(&database)
+        .await
+        .unwrap();
+
+    // Overwrite a range spanning the first two blocks.
+    file.write_at(4, b"abcdefgh").await.unwrap();
+    expected[4..12].copy_from_slice(b"abcdefgh");
+    assert_eq!(
+        file.contents().await.unwrap().into_vec().await.unwrap(),
+        expected
+    );
+
+    // Write a range that extends past the end of the file.
+    file.write_at(u64::try_from(expected.len() - 2).unwrap(), b"0123456789")
+        .await
+        .unwrap();
+    expected.truncate(expected.len() - 2);
+    expected.extend(b"0123456789");
+    assert_eq!(
+        file.contents().await.unwrap().into_vec().await.unwrap(),
+        expected
+    );
+
+    let mut writer = file.write_buffered();
+    writer.seek(std::io::SeekFrom::Start(2)).await.unwrap();
+    writer.write_all(b"xyz").await.unwrap();
+    writer.seek(std::io::SeekFrom::End(-3)).await.unwrap();
+    writer.write_all(b"end").await.unwrap();
+    writer.seek(std::io::SeekFrom::End(4)).await.unwrap();
+    writer.write_all(b"!").await.unwrap();
+    writer.flush().await.unwrap();
+    drop(writer);
+    expected[2..5].copy_from_slice(b"xyz");
+    let end = expected.len() - 3;
+    expected[end..].copy_from_slice(b"end");
+    expected.extend([0, 0, 0, 0, b'!']);
+    assert_eq!(
+        file.contents().await.unwrap().into_vec().await.unwrap(),
+        expected
+    );
+}
+
+enum DeduplicatedBlocks {}
+impl FileConfig for DeduplicatedBlocks {
+    type Metadata = ();
+
+    const BLOCK_SIZE: usize = 64;
+    const DEDUPLICATE_BLOCKS: bool = true;
+
+    fn files_name() -> bonsaidb_core::schema::CollectionName {
+        BonsaiFiles::files_name()
+    }
+
+    fn blocks_name() -> bonsaidb_core::schema::CollectionName {
+        BonsaiFiles::blocks_name()
+    }
+}
+
+#[test]
+fn deduplicated_blocks_test() {
+    let directory = TestDirectory::new("deduplicated-blocks");
+    let database =
+        Database::open::<FilesSchema<DeduplicatedBlocks>>(StorageConfiguration::new(&directory))
+            .unwrap();
+    let stored_contents = || {
+        database
+            .collection::<BlockContents<DeduplicatedBlocks>>()
+            .all()
+            .count()
+            .unwrap()
+    };
+
+    // Generate data that doesn't repeat so that every chunk is unique.
+    let mut state = 1_u32;
+    let data = (0..1024)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            state.to_be_bytes()[0]
+        })
+        .collect::<Vec<_>>();
+
+    let first = DeduplicatedBlocks::build("first.bin")
+        .contents(&data)
+        .create(&database)
+        .unwrap();
+    let unique_blocks = stored_contents();
+    assert!(unique_blocks > 0);
+    assert!(first
+        .contents()
+        .unwrap()
+        .all(|block| block.unwrap().len() <= DeduplicatedBlocks::BLOCK_SIZE));
+
+    // Storing the same data again doesn't store any new contents.
+    let second = DeduplicatedBlocks::build("second.bin")
+        .contents(&data)
+        .create(&database)
+        .unwrap();
+    assert_eq!(stored_contents(), unique_blocks);
+    assert_eq!(second.contents().unwrap().into_vec().unwrap(), data);
+
+    // Modifying one file doesn't affect the other.
+    let mut modified = data.clone();
+    modified[10] = !modified[10];
+    second.write_at(10, &modified[10..11]).unwrap();
+    assert_eq!(first.contents().unwrap().into_vec().unwrap(), data);
+    assert_eq!(second.contents().unwrap().into_vec().unwrap(), modified);
+    assert_eq!(stored_contents(), unique_blocks + 1);
+
+    // Contents are only removed once no file references them.
+    first.delete().unwrap();
+    assert_eq!(stored_contents(), unique_blocks);
+    assert_eq!(second.contents().unwrap().into_vec().unwrap(), modified);
+    second.truncate(0, Truncate::RemovingEnd).unwrap();
+    assert_eq!(stored_contents(), 0);
+    second.delete().unwrap();
+}
+
+#[test]
+fn missing_block_contents_test() {
+    let directory = TestDirectory::new("missing-block-contents");
+    let database =
+        Database::open::<FilesSchema<DeduplicatedBlocks>>(StorageConfiguration::new(&directory))
+            .unwrap();
+
+    let file = DeduplicatedBlocks::build("hello.bin")
+        .contents(b"hello, world!")
+        .create(&database)
+        .unwrap();
+
+    // Remove the file's contents out from underneath it.
+    for contents in database
+        .collection::<BlockContents<DeduplicatedBlocks>>()
+        .all()
+        .query()
+        .unwrap()
+    {
+        database
+            .collection::<BlockContents<DeduplicatedBlocks>>()
+            .delete(&contents)
+            .unwrap();
+    }
+
+    // Other changes that release the missing contents fail, leaving the file
+    // untouched.
+    assert!(file.truncate(0, Truncate::RemovingEnd).is_err());
+    assert!(!Block::<DeduplicatedBlocks>::for_file(file.id(), &database)
+        .unwrap()
+        .is_empty());
+
+    // Deleting the file treats the missing contents as already deleted.
+    file.delete().unwrap();
+    assert!(DeduplicatedBlocks::load("hello.bin", &database)
+        .unwrap()
+        .is_none());
+    assert!(Block::<DeduplicatedBlocks>::for_file(file.id(), &database)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn checksum_test() {
+    let directory = TestDirectory::new("checksums");
+    let database =
+        Database::open::<FilesSchema<SmallBlocks>>(StorageConfiguration::new(&directory)).unwrap();
+
+    let file = SmallBlocks::build("hello.bin")
+        .contents(b"hello, world!")
+        .create(&database)
+        .unwrap();
+    let initial = file.checksum().unwrap().unwrap();
+    assert!(file.verify().unwrap().is_valid());
+
+    // Each modification keeps the stored checksum up to date.
+    file.append(b" more data").unwrap();
+    let appended = file.verify().unwrap();
+    assert!(appended.is_valid());
+    assert_ne!(appended.computed, initial);
+    file.write_at(2, b"y").unwrap();
+    assert!(file.verify().unwrap().is_valid());
+    file.truncate(8, Truncate::RemovingStart).unwrap();
+    assert!(file.verify().unwrap().is_valid());
+
+    // Corrupt the first block directly.
+    let block = &Block::<SmallBlocks>::for_file(file.id(), &database).unwrap()[0];
+    let mut contents = database
+        .collection::<Block<SmallBlocks>>()
+        .get(&block.header.id)
+        .unwrap()
+        .unwrap()
+        .contents
+        .into_vec();
+    contents[0] = !contents[0];
+    Transaction::from(Operation::update(
+        SmallBlocks::blocks_name(),
+        Header::try_from(block.header).unwrap(),
+        contents,
+    ))
+    .apply(&database)
+    .unwrap();
+    let verification = file.verify().unwrap();
+    assert!(!verification.is_valid());
+
+    // Recomputing accepts the current contents.
+    assert_eq!(file.recompute_checksum().unwrap(), verification.computed);
+    assert!(file.verify().unwrap().is_valid());
+
+    // Reordering blocks changes the checksum.
+    let reordered = SmallBlocks::build("reordered.bin")
+        .contents(b"aaaaaaaabbbbbbbb")
+        .create(&database)
+        .unwrap();
+    let blocks = Block::<SmallBlocks>::for_file(reordered.id(), &database).unwrap();
+    let contents = blocks
+        .iter()
+        .map(|block| {
+            database
+                .collection::<Block<SmallBlocks>>()
+                .get(&block.header.id)
+                .unwrap()
+                .unwrap()
+                .contents
+                .into_vec()
+        })
+        .collect::<Vec<_>>();
+    let mut tx = Transaction::new();
+    for (block, contents) in blocks.iter().zip(contents.into_iter().rev()) {
+        tx.push(Operation::update(
+            SmallBlocks::blocks_name(),
+            Header::try_from(block.header).unwrap(),
+            contents,
+        ));
+    }
+    tx.apply(&database).unwrap();
+    assert_eq!(
+        reordered.contents().unwrap().into_vec().unwrap(),
+        b"bbbbbbbbaaaaaaaa"
+    );
+    assert!(!reordered.verify().unwrap().is_valid());
+
+    // Deleting the files removes their checksums.
+    file.delete().unwrap();
+    reordered.delete().unwrap();
+    assert_eq!(
+        database
+            .collection::<FileChecksum<SmallBlocks>>()
+            .all()
+            .count()
+            .unwrap(),
+        0
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_checksum_test() {
+    let directory = TestDirectory::new("checksums-async");
+    let database =
+        AsyncDatabase::open::<FilesSchema<SmallBlocks>>(StorageConfiguration::new(&directory))
+            .await
+            .unwrap();
+
+    let file = SmallBlocks::build("hello.bin")
+        .contents(b"hello, world!")
+        .create_async(&database)
+        .await
+        .unwrap();
+    assert!(file.verify().await.unwrap().is_valid());
+    file.append(b" more data").await.unwrap();
+    file.write_at(2, b"y").await.unwrap();
+    file.truncate(8, Truncate::RemovingEnd).await.unwrap();
+    let verification = file.verify().await.unwrap();
+    assert!(verification.is_valid());
+    assert_eq!(
+        file.recompute_checksum().await.unwrap(),
+        verification.computed
+    );
+}
+
+enum VersionedBlocks {}
+impl FileConfig for VersionedBlocks {
+    type Metadata = ();
+
+    const BLOCK_SIZE: usize = 16;
+    const VERSIONED: bool = true;
+
+    fn files_name() -> bonsaidb_core::schema::CollectionName {
+        BonsaiFiles::files_name()
+    }
+
+    fn blocks_name() -> bonsaidb_core::schema::CollectionName {
+        BonsaiFiles::blocks_name()
+    }
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn versioned_file_test() {
+    let directory = TestDirectory::new("versioned-files");
+    let database =
+        Database::open::<FilesSchema<VersionedBlocks>>(StorageConfiguration::new(&directory))
+            .unwrap();
+    let stored_contents = || {
+        database
+            .collection::<BlockContents<VersionedBlocks>>()
+            .all()
+            .count()
+            .unwrap()
+    };
+
+    // Creating a file doesn't create a version.
+    let file = VersionedBlocks::build("hello.txt")
+        .contents(b"hello, world!")
+        .create(&database)
+        .unwrap();
+    assert!(file.versions().unwrap().is_empty());
+
+    // Each modification preserves the previous contents.
+    file.append(b" more").unwrap();
+    file.write_at(0, b"J").unwrap();
+    assert_eq!(
+        file.contents().unwrap().into_string().unwrap(),
+        "Jello, world! more"
+    );
+    let versions = file.versions().unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0].length, 13);
+    assert_eq!(
+        file.version_contents(versions[0].id)
+            .unwrap()
+            .into_string()
+            .unwrap(),
+        "hello, world!"
+    );
+    assert_eq!(
+        file.version_contents(versions[1].id)
+            .unwrap()
+            .into_string()
+            .unwrap(),
+        "hello, world! more"
+    );
+
+    // Restoring a version preserves the current contents as a new version.
+    file.restore_version(versions[0].id).unwrap();
+    assert_eq!(
+        file.contents().unwrap().into_string().unwrap(),
+        "hello, world!"
+    );
+    assert!(file.verify().unwrap().is_valid());
+    let versions = file.versions().unwrap();
+    assert_eq!(versions.len(), 3);
+    assert_eq!(
+        file.version_contents(versions[2].id)
+            .unwrap()
+            .into_string()
+            .unwrap(),
+        "Jello, world! more"
+    );
+
+    // Versions keep their contents stored after the file no longer
+    // references them.
+    file.truncate(0, Truncate::RemovingEnd).unwrap();
+    assert!(file.contents().unwrap().is_empty());
+    assert_eq!(file.versions().unwrap().len(), 4);
+    assert!(stored_contents() > 0);
+
+    assert_eq!(
+        file.prune_versions(Prune::OlderThan(Duration::from_secs(3600)))
+            .unwrap(),
+        0
+    );
+    assert_eq!(file.prune_versions(Prune::KeepingNewest(1)).unwrap(), 3);
+    let versions = file.versions().unwrap();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(
+        file.version_contents(versions[0].id)
+            .unwrap()
+            .into_string()
+            .unwrap(),
+        "hello, world!"
+    );
+
+    // Versions can't be accessed through other files.
+    let other = VersionedBlocks::build("other.txt")
+        .create(&database)
+        .unwrap();
+    assert!(matches!(
+        other.version_contents(versions[0].id),
+        Err(bonsaidb_core::Error::DocumentNotFound(..))
+    ));
+    assert!(matches!(
+        other.restore_version(versions[0].id),
+        Err(bonsaidb_core::Error::DocumentNotFound(..))
+    ));
+
+    // Deleting the file removes its versions and their contents.
+    file.delete().unwrap();
+    assert_eq!(stored_contents(), 0);
+    assert_eq!(
+        database
+            .collection::<FileVersion<VersionedBlocks>>()
+            .all()
+            .count()
+            .unwrap(),
+        0
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_versioned_file_test() {
+    let directory = TestDirectory::new("versioned-files-async");
+    let database =
+        AsyncDatabase::open::<FilesSchema<VersionedBlocks>>(StorageConfiguration::new(&directory))
+            .await
+            .unwrap();
+
+    let file = VersionedBlocks::build("hello.txt")
+        .contents(b"hello, world!")
+        .create_async(&database)
+        .await
+        .unwrap();
+    file.truncate(0, Truncate::RemovingEnd).await.unwrap();
+    file.append(b"goodbye").await.unwrap();
+    let versions = file.versions().await.unwrap();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(
+        file.version_contents(versions[0].id)
+            .await
+            .unwrap()
+            .into_string()
+            .await
+            .unwrap(),
+        "hello, world!"
+    );
+
+    file.restore_version(versions[0].id).await.unwrap();
+    assert_eq!(
+        file.contents().await.unwrap().into_string().await.unwrap(),
+        "hello, world!"
+    );
+    assert_eq!(file.versions().await.unwrap().len(), 2);
+    assert_eq!(
+        file.prune_versions(Prune::OlderThan(Duration::ZERO))
+            .await
+            .unwrap(),
+        2
+    );
+    assert!(file.versions().await.unwrap().is_empty());
+}
+
 #[test]
 fn seek_read_test() {
     let mut file_contents = Vec::with_capacity(BonsaiFiles::BLOCK_SIZE * 3);
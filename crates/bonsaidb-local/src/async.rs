@@ -1,6 +1,8 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use bonsaidb_core::admin::{AuthenticationTokenSummary, PermissionGroup, Role};
 use bonsaidb_core::connection::{
     self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection,
     Connection, HasSchema, HasSession, IdentityReference, LowLevelConnection, Range,
@@ -8,7 +10,10 @@ use bonsaidb_core::connection::{
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::{AsyncKeyValue, KeyOperation, KeyValue, Output};
-use bonsaidb_core::permissions::Permissions;
+use bonsaidb_core::permissions::{
+    Action, ActionName, ClientContext, ClientTransport, Identifier, PermissionExplanation,
+    Permissions, ResourceName,
+};
 use bonsaidb_core::pubsub::{self, AsyncPubSub, AsyncSubscriber, PubSub, Receiver};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
 use bonsaidb_core::schema::{
@@ -18,8 +23,9 @@ use bonsaidb_core::transaction::{self, OperationResult, Transaction};
 
 use crate::config::StorageConfiguration;
 use crate::database::DatabaseNonBlocking;
+use crate::metrics::StorageMetrics;
 use crate::storage::{AnyBackupLocation, StorageNonBlocking};
-use crate::{Database, Error, Storage, Subscriber};
+use crate::{CollectionMigration, Database, Error, Storage, Subscriber};
 
 /// A file-based, multi-database, multi-user database engine. This type is
 /// designed for use with [Tokio](https://tokio.rs). For blocking
@@ -163,6 +169,66 @@ impl AsyncStorage {
             })
     }
 
+    /// Returns a snapshot of the metrics collected since this storage was
+    /// opened.
+    #[must_use]
+    pub fn metrics(&self) -> StorageMetrics {
+        self.storage.metrics()
+    }
+
+    /// Returns a copy of this storage that records `address` as the client
+    /// address of its [`AuditEntry`s](bonsaidb_core::admin::AuditEntry). The
+    /// address is also used when evaluating the address conditions of
+    /// [`ConditionalStatement`s](bonsaidb_core::permissions::ConditionalStatement).
+    pub fn with_client_address(&self, address: SocketAddr) -> Self {
+        Self {
+            storage: self.storage.with_client_address(address),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Returns a copy of this storage whose session reports `transport` as
+    /// the transport the client is connected with. The transport is used when
+    /// evaluating the transport conditions of
+    /// [`ConditionalStatement`s](bonsaidb_core::permissions::ConditionalStatement).
+    pub fn with_client_transport(&self, transport: ClientTransport) -> Self {
+        Self {
+            storage: self.storage.with_client_transport(transport),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Returns the client address set by
+    /// [`with_client_address()`](Self::with_client_address), if any.
+    #[must_use]
+    pub const fn client_address(&self) -> Option<SocketAddr> {
+        self.storage.client_address()
+    }
+
+    /// Removes all [`AuditEntry`s](bonsaidb_core::admin::AuditEntry) older
+    /// than the configured
+    /// [`AuditLog::retention`](crate::config::AuditLog::retention).
+    ///
+    /// Returns the number of entries removed.
+    pub async fn prune_audit_log(&self) -> Result<u64, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.prune_audit_log())
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Waits until every [`AuditEntry`](bonsaidb_core::admin::AuditEntry)
+    /// recorded before this call has been written to the admin database.
+    pub async fn flush_audit_log(&self) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.flush_audit_log())
+            .await
+            .map_err(Error::from)?;
+        Ok(())
+    }
+
     #[cfg(feature = "internal-apis")]
     #[doc(hidden)]
     pub async fn database_without_schema(&self, name: &str) -> Result<AsyncDatabase, Error> {
@@ -301,6 +367,24 @@ impl AsyncDatabase {
             })
     }
 
+    /// Applies all pending migrations defined in this database's schema. See
+    /// [`Database::migrate()`] for more information.
+    pub async fn migrate(&self) -> Result<Vec<CollectionMigration>, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.migrate())
+            .await?
+    }
+
+    /// Returns the changes [`Self::migrate()`] would make, without modifying
+    /// the database.
+    pub async fn migrate_dry_run(&self) -> Result<Vec<CollectionMigration>, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.migrate_dry_run())
+            .await?
+    }
+
     /// Converts this instance into its blocking version, which is able to be
     /// used without async.
     #[must_use]
@@ -345,6 +429,14 @@ impl HasSession for AsyncStorage {
     fn session(&self) -> Option<&Session> {
         self.storage.session()
     }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.check_permission(resource_name, action)
+    }
 }
 
 #[async_trait]
@@ -573,12 +665,146 @@ impl AsyncStorageConnection for AsyncStorage {
             .await
             .map_err(Error::from)?
     }
+
+    async fn create_permission_group(
+        &self,
+        group: PermissionGroup,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.create_permission_group(group))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let group = permission_group.name()?.into_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.update_permission_group(group, updated))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let group = permission_group.name()?.into_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.delete_permission_group(group))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn create_role(&self, role: Role) -> Result<u64, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.create_role(role))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let role = role.name()?.into_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.update_role(role, updated))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let role = role.name()?.into_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.delete_role(role))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let user = user.name()?.into_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.list_authentication_tokens(user))
+            .await
+            .map_err(Error::from)?
+    }
+
+    #[cfg(feature = "token-authentication")]
+    async fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: bonsaidb_core::admin::AuthenticationTokenOptions,
+    ) -> Result<bonsaidb_core::admin::CreatedAuthenticationToken, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let identity = identity.into_owned();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .storage
+                    .create_authentication_token(identity, options)
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.revoke_authentication_token(token_id))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let identity = identity.into_owned();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .storage
+                    .explain_permission(identity, resource_name, action, client)
+            })
+            .await
+            .map_err(Error::from)?
+    }
 }
 
 impl HasSession for AsyncDatabase {
     fn session(&self) -> Option<&Session> {
         self.database.session()
     }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.database.check_permission(resource_name, action)
+    }
 }
 
 #[async_trait]
@@ -8,6 +8,8 @@ use crate::{Error, Storage};
 
 /// Commands for administering the bonsaidb server.
 pub mod admin;
+/// Commands for migrating databases.
+pub mod migrate;
 /// Commands for querying the schemas.
 pub mod schema;
 
@@ -25,6 +27,8 @@ pub enum StorageCommand {
     Admin(admin::Command),
     /// Executes a schema query.
     Schema(schema::Command),
+    /// Applies pending schema migrations to a database.
+    Migrate(migrate::Command),
 }
 
 /// A backup location.
@@ -39,7 +43,19 @@ pub enum Location {
 
 impl StorageCommand {
     /// Executes the command after opening a [`Storage`] instance using `config`.
-    pub fn execute(self, config: StorageConfiguration) -> Result<(), Error> {
+    pub fn execute(self, mut config: StorageConfiguration) -> Result<(), Error> {
+        match &self {
+            StorageCommand::Migrate(_) => {
+                // Migrating on open would leave nothing for the command to
+                // report.
+                config.migrations.run_on_open = false;
+            }
+            StorageCommand::Schema(_) => {
+                // Drift must not prevent opening the database to report it.
+                config.schema_drift.deny_on_open = false;
+            }
+            _ => {}
+        }
         let storage = Storage::open(config)?;
         self.execute_on(&storage)
     }
@@ -50,7 +66,8 @@ impl StorageCommand {
             StorageCommand::Backup(location) => location.backup(storage),
             StorageCommand::Restore(location) => location.restore(storage),
             StorageCommand::Admin(admin) => admin.execute(storage),
-            StorageCommand::Schema(schema) => schema.execute(storage),
+            StorageCommand::Schema(schema) => schema.execute_local(storage),
+            StorageCommand::Migrate(migrate) => migrate.execute(storage),
         }
     }
 
@@ -61,7 +78,8 @@ impl StorageCommand {
             StorageCommand::Backup(location) => location.backup_async(storage).await,
             StorageCommand::Restore(location) => location.restore_async(storage).await,
             StorageCommand::Admin(admin) => admin.execute_async(storage).await,
-            StorageCommand::Schema(schema) => schema.execute_async(storage).await,
+            StorageCommand::Schema(schema) => schema.execute_local_async(storage).await,
+            StorageCommand::Migrate(migrate) => migrate.execute_async(storage).await,
         }
     }
 }
@@ -1,12 +1,48 @@
-use bonsaidb_core::connection::{AsyncStorageConnection, StorageConnection};
+use std::borrow::Cow;
+use std::net::IpAddr;
+
+use bonsaidb_core::admin::audit::ByTimestamp;
+#[cfg(feature = "token-authentication")]
+use bonsaidb_core::admin::AuthenticationTokenOptions;
+use bonsaidb_core::admin::{AuditEntry, AuthenticationTokenSummary, PermissionGroup, Role};
+use bonsaidb_core::connection::{
+    AsyncConnection, AsyncStorageConnection, Connection, IdentityReference, Range, RangeRef,
+    StorageConnection,
+};
+use bonsaidb_core::key::time::TimestampAsNanoseconds;
+#[cfg(feature = "token-authentication")]
+use bonsaidb_core::permissions::Statement;
+use bonsaidb_core::permissions::{
+    ActionName, ClientContext, ClientTransport, Effect, ExplainedStatement, Identifier,
+    PermissionExplanation, ResourceName, StatementSource,
+};
+use bonsaidb_core::schema::NamedCollection;
 use clap::Subcommand;
 
+#[cfg(feature = "token-authentication")]
+use crate::storage::NamedAction;
+
 /// An administrative command-line command.
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// A command operating on [`User`s](bonsaidb_core::admin::User).
     #[clap(subcommand)]
     User(UserCommand),
+    /// A command operating on [`PermissionGroup`s](PermissionGroup).
+    #[clap(subcommand)]
+    Group(GroupCommand),
+    /// A command operating on [`Role`s](Role).
+    #[clap(subcommand)]
+    Role(RoleCommand),
+    /// A command operating on the audit log.
+    #[clap(subcommand)]
+    Audit(AuditCommand),
+    /// A command operating on
+    /// [`AuthenticationToken`s](bonsaidb_core::admin::AuthenticationToken).
+    #[clap(subcommand)]
+    Token(TokenCommand),
+    /// Explains whether a user or role is permitted to perform an action.
+    Explain(ExplainCommand),
 }
 
 /// A command operating on [`User`s](bonsaidb_core::admin::User).
@@ -59,6 +95,144 @@ pub enum UserCommand {
     },
 }
 
+/// A command operating on [`PermissionGroup`s](PermissionGroup).
+#[derive(Subcommand, Debug)]
+pub enum GroupCommand {
+    /// Creates a new permission group with no statements.
+    Create {
+        /// The name of the group to create.
+        name: String,
+    },
+    /// Renames a permission group.
+    Rename {
+        /// The name of the group to rename.
+        group: String,
+        /// The new name of the group.
+        new_name: String,
+    },
+    /// Deletes a permission group, removing it from all users and roles.
+    Delete {
+        /// The name of the group to delete.
+        group: String,
+    },
+}
+
+/// A command operating on [`Role`s](Role).
+#[derive(Subcommand, Debug)]
+pub enum RoleCommand {
+    /// Creates a new role with no permission groups.
+    Create {
+        /// The name of the role to create.
+        name: String,
+    },
+    /// Renames a role.
+    Rename {
+        /// The name of the role to rename.
+        role: String,
+        /// The new name of the role.
+        new_name: String,
+    },
+    /// Deletes a role, removing it from all users.
+    Delete {
+        /// The name of the role to delete.
+        role: String,
+    },
+    /// Adds a permission group to a role.
+    AddGroup {
+        /// The name of the role to add the group to.
+        role: String,
+        /// The name of the permission group to add.
+        group: String,
+    },
+    /// Removes a permission group from a role.
+    RemoveGroup {
+        /// The name of the role to remove the group from.
+        role: String,
+        /// The name of the permission group to remove.
+        group: String,
+    },
+}
+
+/// A command operating on the audit log of [`AuditEntry`s](AuditEntry).
+#[derive(Subcommand, Debug)]
+pub enum AuditCommand {
+    /// Prints the recorded audit entries, oldest first, one per line.
+    Export {
+        /// Only exports entries recorded at or after this timestamp, in
+        /// seconds since the Unix epoch.
+        #[clap(long)]
+        since: Option<TimestampAsNanoseconds>,
+        /// Only exports entries recorded before this timestamp, in seconds
+        /// since the Unix epoch.
+        #[clap(long)]
+        until: Option<TimestampAsNanoseconds>,
+    },
+}
+
+/// A command operating on
+/// [`AuthenticationToken`s](bonsaidb_core::admin::AuthenticationToken).
+#[derive(Subcommand, Debug)]
+pub enum TokenCommand {
+    /// Lists a user's authentication tokens, one per line.
+    List {
+        /// The username of the user whose tokens to list.
+        username: String,
+    },
+    /// Creates an authentication token and prints its id and private token.
+    /// The private token can't be retrieved again.
+    #[cfg(feature = "token-authentication")]
+    Create {
+        /// The username of the user the token authenticates as.
+        #[clap(long, conflicts_with = "role", required_unless_present = "role")]
+        user: Option<String>,
+        /// The name of the role the token authenticates as.
+        #[clap(long)]
+        role: Option<String>,
+        /// A human-readable description of the token's purpose.
+        #[clap(long)]
+        label: Option<String>,
+        /// The time the token expires, in seconds since the Unix epoch.
+        #[clap(long)]
+        expires_at: Option<TimestampAsNanoseconds>,
+        /// Restricts the token to an action, optionally on a resource, in the
+        /// form `ACTION[@RESOURCE]`. For example,
+        /// `Database.Document.Get@bonsaidb.my-database`. May be specified
+        /// more than once.
+        #[clap(long = "allow")]
+        scope: Vec<String>,
+    },
+    /// Revokes an authentication token.
+    Revoke {
+        /// The id of the token to revoke.
+        id: u64,
+    },
+}
+
+/// Explains whether a user or role is permitted to perform an action,
+/// printing the decision followed by each matching statement, one per line.
+#[derive(clap::Args, Debug)]
+pub struct ExplainCommand {
+    /// The username of the user to explain.
+    #[clap(long, conflicts_with = "role", required_unless_present = "role")]
+    pub user: Option<String>,
+    /// The name of the role to explain.
+    #[clap(long)]
+    pub role: Option<String>,
+    /// The name of the action, for example `Database.Document.Get`.
+    pub action: String,
+    /// The name of the resource, with each identifier separated by `.`, for
+    /// example `bonsaidb.my-database`. Numeric identifiers are treated as
+    /// integers.
+    pub resource: String,
+    /// The transport the identity connects with: `bonsai`, `websocket`, or
+    /// `http`.
+    #[clap(long)]
+    pub transport: Option<ClientTransport>,
+    /// The address the identity connects from.
+    #[clap(long)]
+    pub address: Option<IpAddr>,
+}
+
 impl Command {
     /// Executes the command on `storage`.
     pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
@@ -109,6 +283,11 @@ impl Command {
                     Ok(())
                 }
             },
+            Command::Group(group) => group.execute(storage),
+            Command::Role(role) => role.execute(storage),
+            Command::Audit(audit) => audit.execute(storage),
+            Command::Token(token) => token.execute(storage),
+            Command::Explain(explain) => explain.execute(storage),
         }
     }
 
@@ -168,6 +347,500 @@ impl Command {
                     Ok(())
                 }
             },
+            Command::Group(group) => group.execute_async(storage).await,
+            Command::Role(role) => role.execute_async(storage).await,
+            Command::Audit(audit) => audit.execute_async(storage).await,
+            Command::Token(token) => token.execute_async(storage).await,
+            Command::Explain(explain) => explain.execute_async(storage).await,
         }
     }
 }
+
+impl GroupCommand {
+    /// Executes the command on `storage`.
+    pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
+        match self {
+            GroupCommand::Create { name } => {
+                let group_id = storage.create_permission_group(PermissionGroup::named(&name))?;
+                println!("Group #{group_id} {name} created");
+            }
+            GroupCommand::Rename { group, new_name } => {
+                let mut contents = PermissionGroup::load(&group, &storage.admin())?
+                    .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?
+                    .contents;
+                contents.name = new_name.clone();
+                storage.update_permission_group(&group, contents)?;
+                println!("Group {group} renamed to {new_name}");
+            }
+            GroupCommand::Delete { group } => {
+                storage.delete_permission_group(&group)?;
+                println!("Group {group} deleted");
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    pub async fn execute_async<SC: AsyncStorageConnection>(
+        self,
+        storage: &SC,
+    ) -> Result<(), crate::Error> {
+        match self {
+            GroupCommand::Create { name } => {
+                let group_id = storage
+                    .create_permission_group(PermissionGroup::named(&name))
+                    .await?;
+                println!("Group #{group_id} {name} created");
+            }
+            GroupCommand::Rename { group, new_name } => {
+                let mut contents = PermissionGroup::load_async(&group, &storage.admin().await)
+                    .await?
+                    .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?
+                    .contents;
+                contents.name = new_name.clone();
+                storage.update_permission_group(&group, contents).await?;
+                println!("Group {group} renamed to {new_name}");
+            }
+            GroupCommand::Delete { group } => {
+                storage.delete_permission_group(&group).await?;
+                println!("Group {group} deleted");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RoleCommand {
+    /// Executes the command on `storage`.
+    pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
+        match self {
+            RoleCommand::Create { name } => {
+                let role_id = storage.create_role(Role::named(&name))?;
+                println!("Role #{role_id} {name} created");
+            }
+            RoleCommand::Delete { role } => {
+                storage.delete_role(&role)?;
+                println!("Role {role} deleted");
+            }
+            RoleCommand::Rename { role, new_name } => {
+                let mut contents = Role::load(&role, &storage.admin())?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?
+                    .contents;
+                contents.name = new_name.clone();
+                storage.update_role(&role, contents)?;
+                println!("Role {role} renamed to {new_name}");
+            }
+            RoleCommand::AddGroup { role, group } => {
+                let admin = storage.admin();
+                let mut contents = Role::load(&role, &admin)?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?
+                    .contents;
+                let group_id = PermissionGroup::load(&group, &admin)?
+                    .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?
+                    .header
+                    .id;
+                if !contents.groups.contains(&group_id) {
+                    contents.groups.push(group_id);
+                    storage.update_role(&role, contents)?;
+                }
+                println!("Group {group} added to role {role}");
+            }
+            RoleCommand::RemoveGroup { role, group } => {
+                let admin = storage.admin();
+                let mut contents = Role::load(&role, &admin)?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?
+                    .contents;
+                let group_id = PermissionGroup::load(&group, &admin)?
+                    .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?
+                    .header
+                    .id;
+                contents.groups.retain(|id| id != &group_id);
+                storage.update_role(&role, contents)?;
+                println!("Group {group} removed from role {role}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    pub async fn execute_async<SC: AsyncStorageConnection>(
+        self,
+        storage: &SC,
+    ) -> Result<(), crate::Error> {
+        match self {
+            RoleCommand::Create { name } => {
+                let role_id = storage.create_role(Role::named(&name)).await?;
+                println!("Role #{role_id} {name} created");
+            }
+            RoleCommand::Delete { role } => {
+                storage.delete_role(&role).await?;
+                println!("Role {role} deleted");
+            }
+            RoleCommand::Rename { role, new_name } => {
+                let mut contents = Role::load_async(&role, &storage.admin().await)
+                    .await?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?
+                    .contents;
+                contents.name = new_name.clone();
+                storage.update_role(&role, contents).await?;
+                println!("Role {role} renamed to {new_name}");
+            }
+            RoleCommand::AddGroup { role, group } => {
+                let admin = storage.admin().await;
+                let mut contents = Role::load_async(&role, &admin)
+                    .await?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?
+                    .contents;
+                let group_id = PermissionGroup::load_async(&group, &admin)
+                    .await?
+                    .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?
+                    .header
+                    .id;
+                if !contents.groups.contains(&group_id) {
+                    contents.groups.push(group_id);
+                    storage.update_role(&role, contents).await?;
+                }
+                println!("Group {group} added to role {role}");
+            }
+            RoleCommand::RemoveGroup { role, group } => {
+                let admin = storage.admin().await;
+                let mut contents = Role::load_async(&role, &admin)
+                    .await?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?
+                    .contents;
+                let group_id = PermissionGroup::load_async(&group, &admin)
+                    .await?
+                    .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?
+                    .header
+                    .id;
+                contents.groups.retain(|id| id != &group_id);
+                storage.update_role(&role, contents).await?;
+                println!("Group {group} removed from role {role}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AuditCommand {
+    /// Executes the command on `storage`.
+    pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
+        match self {
+            AuditCommand::Export { since, until } => {
+                let entries = storage
+                    .admin()
+                    .view::<ByTimestamp>()
+                    .with_key_range(timestamp_range(since, until))
+                    .query_with_collection_docs()?;
+                for mapping in &entries {
+                    println!("{}", format_audit_entry(&mapping.document.contents));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    pub async fn execute_async<SC: AsyncStorageConnection>(
+        self,
+        storage: &SC,
+    ) -> Result<(), crate::Error> {
+        match self {
+            AuditCommand::Export { since, until } => {
+                let entries = storage
+                    .admin()
+                    .await
+                    .view::<ByTimestamp>()
+                    .with_key_range(timestamp_range(since, until))
+                    .query_with_collection_docs()
+                    .await?;
+                for mapping in &entries {
+                    println!("{}", format_audit_entry(&mapping.document.contents));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TokenCommand {
+    /// Executes the command on `storage`.
+    pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
+        match self {
+            TokenCommand::List { username } => {
+                for token in storage.list_authentication_tokens(&username)? {
+                    println!("{}", format_token_summary(&token));
+                }
+            }
+            #[cfg(feature = "token-authentication")]
+            TokenCommand::Create {
+                user,
+                role,
+                label,
+                expires_at,
+                scope,
+            } => {
+                let created = storage.create_authentication_token(
+                    identity_reference(user, role)?,
+                    token_options(label, expires_at, &scope),
+                )?;
+                println!("Token #{} created: {}", created.id, created.token.0);
+            }
+            TokenCommand::Revoke { id } => {
+                storage.revoke_authentication_token(id)?;
+                println!("Token #{id} revoked");
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    pub async fn execute_async<SC: AsyncStorageConnection>(
+        self,
+        storage: &SC,
+    ) -> Result<(), crate::Error> {
+        match self {
+            TokenCommand::List { username } => {
+                for token in storage.list_authentication_tokens(&username).await? {
+                    println!("{}", format_token_summary(&token));
+                }
+            }
+            #[cfg(feature = "token-authentication")]
+            TokenCommand::Create {
+                user,
+                role,
+                label,
+                expires_at,
+                scope,
+            } => {
+                let created = storage
+                    .create_authentication_token(
+                        identity_reference(user, role)?,
+                        token_options(label, expires_at, &scope),
+                    )
+                    .await?;
+                println!("Token #{} created: {}", created.id, created.token.0);
+            }
+            TokenCommand::Revoke { id } => {
+                storage.revoke_authentication_token(id).await?;
+                println!("Token #{id} revoked");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExplainCommand {
+    /// Executes the command on `storage`.
+    pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
+        let (identity, resource_name, action, client) = self.into_request()?;
+        let explanation = storage.explain_permission(identity, resource_name, action, client)?;
+        print_explanation(&explanation);
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    pub async fn execute_async<SC: AsyncStorageConnection>(
+        self,
+        storage: &SC,
+    ) -> Result<(), crate::Error> {
+        let (identity, resource_name, action, client) = self.into_request()?;
+        let explanation = storage
+            .explain_permission(identity, resource_name, action, client)
+            .await?;
+        print_explanation(&explanation);
+        Ok(())
+    }
+
+    fn into_request(
+        self,
+    ) -> Result<
+        (
+            IdentityReference<'static>,
+            ResourceName<'static>,
+            ActionName,
+            ClientContext,
+        ),
+        crate::Error,
+    > {
+        let identity = identity_reference(self.user, self.role)?;
+        let resource_name = parse_resource_name(&self.resource);
+        let action = parse_action_name(&self.action);
+        let client = ClientContext {
+            transport: self.transport,
+            address: self.address,
+        };
+        Ok((identity, resource_name, action, client))
+    }
+}
+
+fn identity_reference(
+    user: Option<String>,
+    role: Option<String>,
+) -> Result<IdentityReference<'static>, crate::Error> {
+    let identity = match (user, role) {
+        (Some(user), _) => IdentityReference::user(user)?,
+        (None, Some(role)) => IdentityReference::role(role)?,
+        (None, None) => return Err(bonsaidb_core::Error::UserNotFound.into()),
+    };
+    Ok(identity.into_owned())
+}
+
+/// Parses a resource name with each identifier separated by `.`. Numeric
+/// identifiers are treated as integers.
+fn parse_resource_name(resource: &str) -> ResourceName<'static> {
+    let mut identifiers = resource.split('.').map(|identifier| {
+        identifier.parse::<u64>().map_or_else(
+            |_| Identifier::String(Cow::Owned(identifier.to_string())),
+            Identifier::Integer,
+        )
+    });
+    let mut resource_name = ResourceName::named(identifiers.next().unwrap_or(Identifier::Any));
+    for identifier in identifiers {
+        resource_name = resource_name.and(identifier);
+    }
+    resource_name
+}
+
+/// Parses an action name with each segment separated by `.`.
+fn parse_action_name(action: &str) -> ActionName {
+    ActionName(
+        action
+            .split('.')
+            .map(|segment| Cow::Owned(segment.to_string()))
+            .collect(),
+    )
+}
+
+/// Builds the options for a new token. Each entry of `scope` is in the form
+/// `ACTION[@RESOURCE]`, and entries without a resource apply to any resource.
+#[cfg(feature = "token-authentication")]
+fn token_options(
+    label: Option<String>,
+    expires_at: Option<TimestampAsNanoseconds>,
+    scope: &[String],
+) -> AuthenticationTokenOptions {
+    let mut options = AuthenticationTokenOptions::default();
+    if let Some(label) = label {
+        options = options.with_label(label);
+    }
+    if let Some(expires_at) = expires_at {
+        options = options.expiring_at(expires_at);
+    }
+    if !scope.is_empty() {
+        options = options.with_scope(scope.iter().map(|allowed| {
+            let (action, statement) = match allowed.split_once('@') {
+                Some((action, resource)) => (
+                    action,
+                    Statement::for_resource(parse_resource_name(resource)),
+                ),
+                None => (allowed.as_str(), Statement::for_any()),
+            };
+            statement.allowing(&NamedAction(parse_action_name(action)))
+        }));
+    }
+    options
+}
+
+fn print_explanation(explanation: &PermissionExplanation) {
+    println!(
+        "{}",
+        if explanation.allowed {
+            "allowed"
+        } else {
+            "denied"
+        }
+    );
+    for statement in &explanation.statements {
+        println!("{}", format_explained_statement(statement));
+    }
+}
+
+/// Formats `statement` as a tab-separated line of its source, effect, whether
+/// its conditions are met, and the statement itself.
+fn format_explained_statement(statement: &ExplainedStatement) -> String {
+    let source = match &statement.source {
+        StatementSource::Group { id, name, role } => match role {
+            Some(role) => format!("group #{id} {name} (role {role})"),
+            None => format!("group #{id} {name}"),
+        },
+        StatementSource::Inherited => String::from("inherited"),
+        StatementSource::Owner => String::from("owner"),
+    };
+    format!(
+        "{}\t{}\t{}\t{}",
+        source,
+        match statement.effect {
+            Effect::Allow => "allow",
+            Effect::Deny => "deny",
+        },
+        if statement.applies {
+            "applies"
+        } else {
+            "conditions-unmet"
+        },
+        statement
+            .statement
+            .as_ref()
+            .map_or_else(|| String::from("-"), |statement| format!("{statement:?}")),
+    )
+}
+
+fn timestamp_range(
+    since: Option<TimestampAsNanoseconds>,
+    until: Option<TimestampAsNanoseconds>,
+) -> RangeRef<'static, TimestampAsNanoseconds> {
+    let mut range = Range::default();
+    if let Some(since) = since {
+        range = range.start_at(since);
+    }
+    if let Some(until) = until {
+        range = range.before(until);
+    }
+    RangeRef::owned(range)
+}
+
+/// Formats `entry` as a tab-separated line of its timestamp, result,
+/// identity, session, client address, action, and resource name.
+fn format_audit_entry(entry: &AuditEntry) -> String {
+    let missing = || String::from("-");
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        entry.timestamp,
+        if entry.allowed { "allowed" } else { "denied" },
+        entry
+            .identity
+            .map_or_else(missing, |identity| format!("{identity:?}")),
+        entry
+            .session
+            .map_or_else(missing, |session| session.0.to_string()),
+        entry
+            .address
+            .map_or_else(missing, |address| address.to_string()),
+        entry.action,
+        entry.resource,
+    )
+}
+
+/// Formats `token` as a tab-separated line of its id, label, creation time,
+/// expiration, last use, and whether it is scoped.
+fn format_token_summary(token: &AuthenticationTokenSummary) -> String {
+    let missing = || String::from("-");
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        token.id,
+        token.label.as_deref().unwrap_or("-"),
+        token.created_at,
+        token
+            .expires_at
+            .map_or_else(missing, |expires_at| expires_at.to_string()),
+        token
+            .last_used_at
+            .map_or_else(missing, |last_used_at| last_used_at.to_string()),
+        if token.scope.is_some() {
+            "scoped"
+        } else {
+            "unscoped"
+        },
+    )
+}
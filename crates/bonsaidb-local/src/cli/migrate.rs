@@ -0,0 +1,58 @@
+use clap::Parser;
+
+use crate::{CollectionMigration, Error, Storage};
+
+/// Applies pending schema migrations to a database.
+#[derive(Parser, Debug)]
+pub struct Command {
+    /// The name of the database to migrate.
+    pub database: String,
+
+    /// If this flag is provided, the changes migrating would make are printed
+    /// without modifying the database.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl Command {
+    /// Executes the command on `storage`.
+    pub fn execute(self, storage: &Storage) -> Result<(), Error> {
+        let database =
+            storage
+                .instance
+                .database_without_schema(&self.database, Some(storage), None)?;
+        let migrations = if self.dry_run {
+            database.migrate_dry_run()?
+        } else {
+            database.migrate()?
+        };
+        print_migrations(&migrations, self.dry_run);
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    #[cfg(feature = "async")]
+    pub async fn execute_async(self, storage: &crate::AsyncStorage) -> Result<(), Error> {
+        let storage = storage.to_blocking();
+        tokio::task::spawn_blocking(move || self.execute(&storage)).await?
+    }
+}
+
+fn print_migrations(migrations: &[CollectionMigration], dry_run: bool) {
+    if migrations.is_empty() {
+        println!("No pending migrations");
+        return;
+    }
+
+    for migration in migrations {
+        println!(
+            "{}{}: version {} -> {}, {} updated, {} deleted",
+            if dry_run { "(dry run) " } else { "" },
+            migration.collection,
+            migration.from_version,
+            migration.to_version,
+            migration.updated,
+            migration.deleted
+        );
+    }
+}
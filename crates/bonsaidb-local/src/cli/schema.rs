@@ -4,11 +4,18 @@ use bonsaidb_core::connection::{AsyncStorageConnection, StorageConnection};
 use bonsaidb_core::schema::{
     CollectionName, InvalidNameError, SchemaName, SchemaSummary, ViewName,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::Storage;
 
 /// A schema query against a storage instance.
 #[derive(Parser, Debug)]
+#[clap(args_conflicts_with_subcommands = true)]
 pub struct Command {
+    /// An action to perform instead of querying a schema.
+    #[clap(subcommand)]
+    pub action: Option<Action>,
+
     /// The name of the schema to query.
     pub name: Option<SchemaName>,
 
@@ -16,22 +23,74 @@ pub struct Command {
     pub item: Option<CollectionOrView>,
 }
 
+/// An action operating on a database's schema.
+#[derive(Subcommand, Debug)]
+pub enum Action {
+    /// Compares a database's registered schema against its stored data.
+    Diff(Diff),
+}
+
+/// Compares a database's registered schema against its stored collections,
+/// views, and primary keys.
+#[derive(Parser, Debug)]
+pub struct Diff {
+    /// The name of the database to compare.
+    pub database: String,
+
+    /// If this flag is provided, orphaned collections, views, and trees are
+    /// deleted, and changed primary keys are recorded as current.
+    #[clap(long)]
+    pub clean: bool,
+}
+
 impl Command {
-    /// Executes the command on `storage`.
+    /// Executes the command on `storage`. [`Action::Diff`] requires local
+    /// storage, and is only supported by [`Self::execute_local()`].
     pub fn execute<SC: StorageConnection>(self, storage: &SC) -> Result<(), crate::Error> {
+        Self::require_no_action(self.action.as_ref())?;
         let schemas = storage.list_available_schemas()?;
         self.handle_schema_command(schemas)
     }
 
-    /// Executes the command on `storage`.
+    /// Executes the command on `storage`. [`Action::Diff`] requires local
+    /// storage, and is only supported by [`Self::execute_local_async()`].
     pub async fn execute_async<SC: AsyncStorageConnection>(
         self,
         storage: &SC,
     ) -> Result<(), crate::Error> {
+        Self::require_no_action(self.action.as_ref())?;
         let schemas = storage.list_available_schemas().await?;
         self.handle_schema_command(schemas)
     }
 
+    /// Executes the command on local `storage`.
+    pub fn execute_local(mut self, storage: &Storage) -> Result<(), crate::Error> {
+        match self.action.take() {
+            Some(Action::Diff(diff)) => diff.execute(storage),
+            None => self.execute(storage),
+        }
+    }
+
+    /// Executes the command on local `storage`.
+    #[cfg(feature = "async")]
+    pub async fn execute_local_async(
+        self,
+        storage: &crate::AsyncStorage,
+    ) -> Result<(), crate::Error> {
+        let storage = storage.to_blocking();
+        tokio::task::spawn_blocking(move || self.execute_local(&storage)).await?
+    }
+
+    fn require_no_action(action: Option<&Action>) -> Result<(), crate::Error> {
+        match action {
+            Some(Action::Diff(_)) => Err(crate::Error::other(
+                "bonsaidb-local",
+                "`schema diff` requires local storage",
+            )),
+            None => Ok(()),
+        }
+    }
+
     fn handle_schema_command(self, schemas: Vec<SchemaSummary>) -> Result<(), crate::Error> {
         if let Some(name) = self.name {
             let Some(schema) = schemas.into_iter().find(|s| s.name == name) else {
@@ -80,6 +139,30 @@ impl Command {
     }
 }
 
+impl Diff {
+    /// Executes the command on `storage`.
+    pub fn execute(self, storage: &Storage) -> Result<(), crate::Error> {
+        let database =
+            storage
+                .instance
+                .database_without_schema(&self.database, Some(storage), None)?;
+        let drift = if self.clean {
+            database.clean_schema_drift()?
+        } else {
+            database.schema_drift()?
+        };
+        println!("{drift}");
+        if !drift.is_empty() {
+            if self.clean {
+                println!("Orphaned data has been removed.");
+            } else {
+                println!("Run again with --clean to remove orphaned data.");
+            }
+        }
+        Ok(())
+    }
+}
+
 fn print_schema_list(mut schemas: Vec<SchemaSummary>) {
     schemas.sort_by(|s1, s2| s1.name.cmp(&s2.name));
 
@@ -18,6 +18,12 @@ use crate::Error;
 mod argon;
 #[cfg(feature = "password-hashing")]
 pub use argon::*;
+mod authentication;
+pub use authentication::*;
+#[cfg(feature = "password-hashing")]
+mod password;
+#[cfg(feature = "password-hashing")]
+pub use password::*;
 
 /// Configuration options for [`Storage`](crate::storage::Storage).
 #[derive(Clone)]
@@ -62,6 +68,15 @@ pub struct StorageConfiguration {
     /// Configuration options related to views.
     pub views: Views,
 
+    /// Configuration options related to schema migrations.
+    pub migrations: Migrations,
+
+    /// Configuration options related to detecting schema drift.
+    pub schema_drift: SchemaDriftChecks,
+
+    /// Configuration options related to the audit log.
+    pub audit_log: AuditLog,
+
     /// Controls how the key-value store persists keys, on a per-database basis.
     pub key_value_persistence: KeyValuePersistence,
 
@@ -76,6 +91,21 @@ pub struct StorageConfiguration {
     #[cfg(feature = "password-hashing")]
     pub argon: ArgonConfiguration,
 
+    /// The rules passwords must satisfy when being set.
+    #[cfg(feature = "password-hashing")]
+    pub password_policy: PasswordPolicy,
+
+    /// Configuration for locking out users after repeated failed password
+    /// authentication attempts.
+    #[cfg(feature = "password-hashing")]
+    pub login_lockout: LoginLockout,
+
+    /// The provider used to verify credentials provided using
+    /// [`Authentication::External`](bonsaidb_core::connection::Authentication::External).
+    /// If not specified, external authentication always fails with
+    /// [`bonsaidb_core::Error::InvalidCredentials`].
+    pub authentication_provider: Option<Arc<dyn AuthenticationProvider>>,
+
     pub(crate) initial_schemas: HashMap<SchemaName, Arc<dyn DatabaseOpener>>,
 }
 
@@ -98,10 +128,18 @@ impl Default for StorageConfiguration {
             default_compression: None,
             workers: Tasks::default_for(&system),
             views: Views::default(),
+            migrations: Migrations::default(),
+            schema_drift: SchemaDriftChecks::default(),
+            audit_log: AuditLog::default(),
             key_value_persistence: KeyValuePersistence::default(),
             authenticated_permissions: Permissions::default(),
             #[cfg(feature = "password-hashing")]
             argon: ArgonConfiguration::default_for(&system),
+            #[cfg(feature = "password-hashing")]
+            password_policy: PasswordPolicy::default(),
+            #[cfg(feature = "password-hashing")]
+            login_lockout: LoginLockout::default(),
+            authentication_provider: None,
             initial_schemas: HashMap::default(),
         }
     }
@@ -117,8 +155,12 @@ impl std::fmt::Debug for StorageConfiguration {
             .field("unique_id", &self.unique_id)
             .field("workers", &self.workers)
             .field("views", &self.views)
+            .field("migrations", &self.migrations)
+            .field("schema_drift", &self.schema_drift)
+            .field("audit_log", &self.audit_log)
             .field("key_value_persistence", &self.key_value_persistence)
             .field("authenticated_permissions", &self.authenticated_permissions)
+            .field("authentication_provider", &self.authentication_provider)
             .field("initial_schemas", &schemas);
 
         #[cfg(feature = "encryption")]
@@ -129,7 +171,9 @@ impl std::fmt::Debug for StorageConfiguration {
         f.field("default_compression", &self.default_compression);
 
         #[cfg(feature = "password-hashing")]
-        f.field("argon", &self.argon);
+        f.field("argon", &self.argon)
+            .field("password_policy", &self.password_policy)
+            .field("login_lockout", &self.login_lockout);
 
         f.finish()
     }
@@ -184,6 +228,41 @@ pub struct Views {
     pub check_integrity_on_open: bool,
 }
 
+/// Configuration options for schema migrations.
+#[derive(Clone, Debug, Default)]
+pub struct Migrations {
+    /// If true, pending migrations defined using
+    /// [`Schematic::define_migration()`](bonsaidb_core::schema::Schematic::define_migration)
+    /// are applied the first time each database is opened. When false,
+    /// migrations are only applied by calling
+    /// [`Database::migrate()`](crate::Database::migrate) or by using the
+    /// `migrate` command. Default value is `false`.
+    pub run_on_open: bool,
+}
+
+/// Configuration options for detecting schema drift.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaDriftChecks {
+    /// If true, opening a database whose stored collections, views, or primary
+    /// keys don't match its registered schema returns
+    /// [`Error::SchemaDrift`](crate::Error::SchemaDrift). The `schema diff`
+    /// command reports drift and can remove orphaned data. Default value is
+    /// `false`.
+    pub deny_on_open: bool,
+}
+
+/// Configuration options for the audit log.
+#[derive(Clone, Debug, Default)]
+pub struct AuditLog {
+    /// If true, every permission check performed on behalf of a session is
+    /// recorded as an [`AuditEntry`](bonsaidb_core::admin::AuditEntry) in the
+    /// admin database. Default value is `false`.
+    pub enabled: bool,
+    /// If set, audit entries older than this duration are periodically
+    /// removed. Default value is `None`, which keeps entries forever.
+    pub retention: Option<Duration>,
+}
+
 /// Rules for persisting key-value changes. Default persistence is to
 /// immediately persist all changes. While this ensures data integrity, the
 /// overhead of the key-value store can be significantly reduced by utilizing
@@ -380,6 +459,18 @@ pub trait Builder: Sized {
     /// Sets [`Views::check_integrity_on_open`] to `check` and returns self.
     #[must_use]
     fn check_view_integrity_on_open(self, check: bool) -> Self;
+    /// Sets [`Migrations::run_on_open`] to `run` and returns self.
+    #[must_use]
+    fn run_migrations_on_open(self, run: bool) -> Self;
+    /// Sets [`SchemaDriftChecks::deny_on_open`] to `deny` and returns self.
+    #[must_use]
+    fn deny_schema_drift_on_open(self, deny: bool) -> Self;
+    /// Sets [`AuditLog::enabled`] to `enabled` and returns self.
+    #[must_use]
+    fn audit_log(self, enabled: bool) -> Self;
+    /// Sets [`AuditLog::retention`] to `retention` and returns self.
+    #[must_use]
+    fn audit_log_retention(self, retention: Duration) -> Self;
     /// Sets [`StorageConfiguration::default_compression`](StorageConfiguration#structfield.default_compression) to `path` and returns self.
     #[cfg(feature = "compression")]
     #[must_use]
@@ -394,6 +485,22 @@ pub trait Builder: Sized {
     #[cfg(feature = "password-hashing")]
     #[must_use]
     fn argon(self, argon: ArgonConfiguration) -> Self;
+    /// Sets [`PasswordPolicy::minimum_length`] to `minimum_length` and returns self.
+    #[cfg(feature = "password-hashing")]
+    #[must_use]
+    fn password_minimum_length(self, minimum_length: usize) -> Self;
+    /// Sets [`PasswordPolicy::reject_common_passwords`] to `reject` and returns self.
+    #[cfg(feature = "password-hashing")]
+    #[must_use]
+    fn reject_common_passwords(self, reject: bool) -> Self;
+    /// Sets [`LoginLockout::max_failed_attempts`] to `max_failed_attempts` and
+    /// [`LoginLockout::duration`] to `duration` and returns self.
+    #[cfg(feature = "password-hashing")]
+    #[must_use]
+    fn login_lockout(self, max_failed_attempts: u32, duration: Duration) -> Self;
+    /// Sets [`StorageConfiguration::authentication_provider`](StorageConfiguration#structfield.authentication_provider) to `provider` and returns self.
+    #[must_use]
+    fn authentication_provider<Provider: AuthenticationProvider>(self, provider: Provider) -> Self;
 }
 
 impl Builder for StorageConfiguration {
@@ -453,6 +560,26 @@ impl Builder for StorageConfiguration {
         self
     }
 
+    fn run_migrations_on_open(mut self, run: bool) -> Self {
+        self.migrations.run_on_open = run;
+        self
+    }
+
+    fn deny_schema_drift_on_open(mut self, deny: bool) -> Self {
+        self.schema_drift.deny_on_open = deny;
+        self
+    }
+
+    fn audit_log(mut self, enabled: bool) -> Self {
+        self.audit_log.enabled = enabled;
+        self
+    }
+
+    fn audit_log_retention(mut self, retention: Duration) -> Self {
+        self.audit_log.retention = Some(retention);
+        self
+    }
+
     fn key_value_persistence(mut self, persistence: KeyValuePersistence) -> Self {
         self.key_value_persistence = persistence;
         self
@@ -471,6 +598,33 @@ impl Builder for StorageConfiguration {
         self.argon = argon;
         self
     }
+
+    #[cfg(feature = "password-hashing")]
+    fn password_minimum_length(mut self, minimum_length: usize) -> Self {
+        self.password_policy.minimum_length = minimum_length;
+        self
+    }
+
+    #[cfg(feature = "password-hashing")]
+    fn reject_common_passwords(mut self, reject: bool) -> Self {
+        self.password_policy.reject_common_passwords = reject;
+        self
+    }
+
+    #[cfg(feature = "password-hashing")]
+    fn login_lockout(mut self, max_failed_attempts: u32, duration: Duration) -> Self {
+        self.login_lockout.max_failed_attempts = Some(max_failed_attempts);
+        self.login_lockout.duration = duration;
+        self
+    }
+
+    fn authentication_provider<Provider: AuthenticationProvider>(
+        mut self,
+        provider: Provider,
+    ) -> Self {
+        self.authentication_provider = Some(Arc::new(provider));
+        self
+    }
 }
 
 pub(crate) trait SystemDefault: Sized {
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use bonsaidb_core::connection::SensitiveBytes;
+
+/// Verifies credentials provided using
+/// [`Authentication::External`](bonsaidb_core::connection::Authentication::External).
+///
+/// This allows sessions to be backed by an external identity service. A
+/// provider is given the opaque credential the client authenticated with, such
+/// as a signed JWT, and returns the identity the session should assume.
+///
+/// Providers are invoked from a blocking context, so it is safe to perform
+/// blocking operations while verifying a credential.
+pub trait AuthenticationProvider: Send + Sync + Debug + 'static {
+    /// Verifies `credential`, returning the identity it authenticates as.
+    ///
+    /// If [`ExternalIdentity::User`] is returned and no user with the username
+    /// exists, the user is created before the session is authenticated.
+    ///
+    /// ## Errors
+    ///
+    /// Implementors should return
+    /// [`bonsaidb_core::Error::InvalidCredentials`] if `credential` cannot be
+    /// verified.
+    fn authenticate(
+        &self,
+        credential: &SensitiveBytes,
+    ) -> Result<ExternalIdentity, bonsaidb_core::Error>;
+}
+
+/// An identity returned by an [`AuthenticationProvider`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ExternalIdentity {
+    /// A [`User`](bonsaidb_core::admin::User) with the contained username.
+    User(String),
+    /// A [`Role`](bonsaidb_core::admin::Role) with the contained name. Unlike
+    /// users, roles are not created automatically.
+    Role(String),
+}
+
+/// An [`AuthenticationProvider`] that accepts a fixed set of credentials.
+///
+/// This provider is a stand-in for an external identity service, and is
+/// primarily intended for testing.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct StaticAuthenticationProvider {
+    credentials: HashMap<Vec<u8>, ExternalIdentity>,
+}
+
+impl StaticAuthenticationProvider {
+    /// Accepts `credential` as authenticating as `identity` and returns self.
+    pub fn with_credential(
+        mut self,
+        credential: impl Into<Vec<u8>>,
+        identity: ExternalIdentity,
+    ) -> Self {
+        self.credentials.insert(credential.into(), identity);
+        self
+    }
+}
+
+impl AuthenticationProvider for StaticAuthenticationProvider {
+    fn authenticate(
+        &self,
+        credential: &SensitiveBytes,
+    ) -> Result<ExternalIdentity, bonsaidb_core::Error> {
+        self.credentials
+            .get(&credential[..])
+            .cloned()
+            .ok_or(bonsaidb_core::Error::InvalidCredentials)
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+/// Rules that passwords must satisfy when being set using
+/// [`StorageConnection::set_user_password()`](bonsaidb_core::connection::StorageConnection::set_user_password).
+///
+/// By default, all passwords are accepted.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct PasswordPolicy {
+    /// The minimum number of characters a password must contain. Default
+    /// value is `0`.
+    pub minimum_length: usize,
+    /// If true, passwords that appear in a built-in list of commonly used
+    /// passwords are rejected, ignoring case. Default value is `false`.
+    pub reject_common_passwords: bool,
+}
+
+impl PasswordPolicy {
+    /// Checks `password` against this policy.
+    ///
+    /// ## Errors
+    ///
+    /// - [`bonsaidb_core::Error::PasswordTooShort`]: `password` contains fewer
+    ///   than [`Self::minimum_length`](Self#structfield.minimum_length)
+    ///   characters.
+    /// - [`bonsaidb_core::Error::CommonPassword`]: `password` is a commonly
+    ///   used password and
+    ///   [`Self::reject_common_passwords`](Self#structfield.reject_common_passwords)
+    ///   is true.
+    pub fn validate(&self, password: &str) -> Result<(), bonsaidb_core::Error> {
+        if password.chars().count() < self.minimum_length {
+            return Err(bonsaidb_core::Error::PasswordTooShort {
+                minimum_length: self.minimum_length,
+            });
+        }
+
+        if self.reject_common_passwords
+            && COMMON_PASSWORDS
+                .iter()
+                .any(|common| common.eq_ignore_ascii_case(password))
+        {
+            return Err(bonsaidb_core::Error::CommonPassword);
+        }
+
+        Ok(())
+    }
+}
+
+/// Configuration for temporarily locking out users after repeated failed
+/// password authentication attempts.
+///
+/// Failed attempts are tracked in memory, and are reset when the storage is
+/// reopened or the user successfully authenticates.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LoginLockout {
+    /// The number of consecutive failed password authentication attempts
+    /// after which a user is locked out. `None` disables lockouts. Default
+    /// value is `None`.
+    pub max_failed_attempts: Option<u32>,
+    /// How long a user is locked out for. While locked out, authenticating as
+    /// the user returns
+    /// [`Error::UserLockedOut`](bonsaidb_core::Error::UserLockedOut), even if
+    /// the correct password is provided. Default value is 15 minutes.
+    pub duration: Duration,
+}
+
+impl Default for LoginLockout {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: None,
+            duration: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Frequently used passwords, based on publicly available password breach
+/// statistics.
+const COMMON_PASSWORDS: &[&str] = &[
+    "000000",
+    "111111",
+    "112233",
+    "121212",
+    "123123",
+    "123321",
+    "1234",
+    "12345",
+    "123456",
+    "1234567",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "123qwe",
+    "1q2w3e",
+    "1q2w3e4r",
+    "1qaz2wsx",
+    "222222",
+    "555555",
+    "654321",
+    "666666",
+    "696969",
+    "7777777",
+    "888888",
+    "987654321",
+    "abc123",
+    "access",
+    "admin",
+    "admin123",
+    "andrew",
+    "ashley",
+    "asdfgh",
+    "asdfghjkl",
+    "azerty",
+    "bailey",
+    "baseball",
+    "batman",
+    "charlie",
+    "dragon",
+    "football",
+    "freedom",
+    "hello",
+    "hunter",
+    "hunter2",
+    "iloveyou",
+    "jennifer",
+    "jordan",
+    "letmein",
+    "login",
+    "master",
+    "michael",
+    "monkey",
+    "mustang",
+    "passw0rd",
+    "password",
+    "password1",
+    "password123",
+    "princess",
+    "qazwsx",
+    "qwerty",
+    "qwerty123",
+    "qwertyuiop",
+    "root",
+    "secret",
+    "shadow",
+    "starwars",
+    "sunshine",
+    "superman",
+    "trustno1",
+    "welcome",
+    "whatever",
+    "zaq12wsx",
+];
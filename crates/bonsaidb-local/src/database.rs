@@ -5,7 +5,8 @@ use std::ops::{self, Deref};
 use std::sync::Arc;
 use std::u8;
 
-use bonsaidb_core::arc_bytes::serde::CowBytes;
+use bonsaidb_core::admin::ADMIN_DATABASE_NAME;
+use bonsaidb_core::arc_bytes::serde::{Bytes, CowBytes};
 use bonsaidb_core::arc_bytes::ArcBytes;
 use bonsaidb_core::connection::{
     self, AccessPolicy, Connection, HasSchema, HasSession, LowLevelConnection, Range,
@@ -23,7 +24,7 @@ use bonsaidb_core::permissions::bonsai::{
     view_resource_name, BonsaiAction, DatabaseAction, DocumentAction, TransactionAction,
     ViewAction,
 };
-use bonsaidb_core::permissions::Permissions;
+use bonsaidb_core::permissions::{Action, Identifier, Permissions};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
 use bonsaidb_core::schema::view::{self};
 use bonsaidb_core::schema::{self, CollectionName, Schema, Schematic, ViewName};
@@ -44,7 +45,9 @@ use watchable::Watchable;
 
 use crate::config::{Builder, KeyValuePersistence, StorageConfiguration};
 use crate::database::keyvalue::BackgroundWorkerProcessTarget;
+use crate::database::migrations::MIGRATIONS_TREE;
 use crate::error::Error;
+use crate::metrics::MetricsCollector;
 use crate::open_trees::OpenTrees;
 use crate::storage::StorageLock;
 #[cfg(feature = "encryption")]
@@ -55,8 +58,12 @@ use crate::views::{
 };
 use crate::Storage;
 
+pub(crate) mod drift;
 pub mod keyvalue;
 
+pub(crate) mod migrations;
+pub(crate) mod ownership;
+
 pub(crate) mod compat;
 pub mod pubsub;
 
@@ -141,6 +148,18 @@ impl Database {
             }),
         };
 
+        // The admin database is managed by BonsaiDb, so drift is only reported
+        // for it through `schema_drift()`.
+        db.check_schema_if_needed(
+            storage.instance.deny_schema_drift_on_database_open()
+                && db.data.name.as_ref() != ADMIN_DATABASE_NAME,
+        )?;
+
+        db.record_new_collection_versions_if_needed()?;
+        if storage.instance.run_migrations_on_database_open() {
+            db.migrate_if_needed()?;
+        }
+
         if storage.instance.check_view_integrity_on_database_open() {
             for view in db.data.schema.views() {
                 storage.instance.tasks().spawn_integrity_check(view, &db);
@@ -303,11 +322,63 @@ impl Database {
         Ok(open_trees)
     }
 
+    /// Waits for the eager views affected by `transaction` to be up-to-date
+    /// before applying it. If `migration` is provided, the collection's
+    /// migration version is recorded in the same transaction.
+    fn apply_transaction_after_eager_views(
+        &self,
+        transaction: &Transaction,
+        migration: Option<(&CollectionName, u64)>,
+    ) -> Result<Vec<OperationResult>, Error> {
+        let mut eager_view_tasks = Vec::new();
+        for collection_name in transaction
+            .operations
+            .iter()
+            .map(|op| &op.collection)
+            .collect::<HashSet<_>>()
+        {
+            for view in self.data.schema.eager_views_in_collection(collection_name) {
+                if let Some(task) = self
+                    .storage
+                    .instance
+                    .tasks()
+                    .spawn_integrity_check(view, self)
+                {
+                    eager_view_tasks.push(task);
+                }
+            }
+        }
+
+        let mut eager_view_mapping_tasks = Vec::new();
+        for task in eager_view_tasks {
+            if let Some(spawned_task) = task.receive().map_err(Error::from)?.map_err(Error::from)? {
+                eager_view_mapping_tasks.push(spawned_task);
+            }
+        }
+
+        for task in eager_view_mapping_tasks {
+            let mut task = task.lock();
+            if let Some(task) = task.take() {
+                task.receive().map_err(Error::from)?.map_err(Error::from)?;
+            }
+        }
+
+        self.apply_transaction_to_roots(transaction, migration)
+    }
+
     fn apply_transaction_to_roots(
         &self,
         transaction: &Transaction,
+        migration: Option<(&CollectionName, u64)>,
     ) -> Result<Vec<OperationResult>, Error> {
-        let open_trees = self.open_trees_for_transaction(transaction)?;
+        let mut open_trees = self.open_trees_for_transaction(transaction)?;
+        if migration.is_some() {
+            open_trees.open_tree::<Unversioned>(
+                MIGRATIONS_TREE,
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                None,
+            );
+        }
 
         let mut roots_transaction = self
             .data
@@ -363,6 +434,16 @@ impl Database {
             &changed_documents,
         )?;
 
+        if let Some((collection, version)) = migration {
+            let mut versions = roots_transaction
+                .tree::<Unversioned>(open_trees.trees_index_by_name[MIGRATIONS_TREE])
+                .unwrap();
+            versions.set(
+                collection.to_string().into_bytes(),
+                version.to_be_bytes().to_vec(),
+            )?;
+        }
+
         roots_transaction
             .entry_mut()
             .set_data(compat::serialize_executed_transaction_changes(
@@ -473,6 +554,7 @@ impl Database {
         let document_id = ArcBytes::from(id.to_vec());
         let mut result = None;
         let mut updated = false;
+        let mut created = false;
         documents.modify(
             vec![document_id.clone()],
             nebari::tree::Operation::CompareSwap(CompareSwap::new(&mut |_key,
@@ -535,6 +617,7 @@ impl Database {
                                 header: doc.header,
                             }));
                             updated = true;
+                            created = true;
                             return nebari::tree::KeyOperation::Set(ArcBytes::from(serialized));
                         }
                         Err(err) => {
@@ -552,6 +635,15 @@ impl Database {
         )?;
         drop(documents);
 
+        if created {
+            self.record_document_owner(
+                &operation.collection,
+                transaction,
+                tree_index_map,
+                &document_id,
+            )?;
+        }
+
         if updated {
             self.update_eager_views(&document_id, operation, transaction, tree_index_map)?;
         }
@@ -606,6 +698,12 @@ impl Database {
             )))
         } else {
             drop(documents);
+            self.record_document_owner(
+                &operation.collection,
+                transaction,
+                tree_index_map,
+                &document_id,
+            )?;
             self.update_eager_views(&document_id, operation, transaction, tree_index_map)?;
 
             Ok(OperationResult::DocumentUpdated {
@@ -638,12 +736,14 @@ impl Database {
             drop(documents);
             let doc = deserialize_document(&vec)?;
             if &doc.header == header {
-                self.update_eager_views(
-                    &ArcBytes::from(doc.header.id.to_vec()),
-                    operation,
+                let document_id = ArcBytes::from(doc.header.id.to_vec());
+                self.remove_document_owner(
+                    &operation.collection,
                     transaction,
                     tree_index_map,
+                    &document_id,
                 )?;
+                self.update_eager_views(&document_id, operation, transaction, tree_index_map)?;
 
                 Ok(OperationResult::DocumentDeleted {
                     collection: operation.collection.clone(),
@@ -991,6 +1091,14 @@ impl HasSession for Database {
     fn session(&self) -> Option<&Session> {
         self.storage.session()
     }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.check_permission(resource_name, action)
+    }
 }
 
 impl Connection for Database {
@@ -1130,65 +1238,31 @@ impl LowLevelConnection for Database {
         transaction: Transaction,
     ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
         for op in &transaction.operations {
-            let (resource, action) = match &op.command {
-                Command::Insert { .. } => (
+            match &op.command {
+                Command::Insert { .. } => self.check_permission(
                     collection_resource_name(self.name(), &op.collection),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert)),
-                ),
-                Command::Update { header, .. } => (
-                    document_resource_name(self.name(), &op.collection, &header.id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Update)),
-                ),
-                Command::Overwrite { id, .. } => (
-                    document_resource_name(self.name(), &op.collection, id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Overwrite)),
-                ),
-                Command::Delete { header } => (
-                    document_resource_name(self.name(), &op.collection, &header.id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete)),
-                ),
-                Command::Check { id, .. } => (
-                    document_resource_name(self.name(), &op.collection, id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
-                ),
-            };
-            self.check_permission(resource, &action)?;
-        }
-
-        let mut eager_view_tasks = Vec::new();
-        for collection_name in transaction
-            .operations
-            .iter()
-            .map(|op| &op.collection)
-            .collect::<HashSet<_>>()
-        {
-            for view in self.data.schema.eager_views_in_collection(collection_name) {
-                if let Some(task) = self
-                    .storage
-                    .instance
-                    .tasks()
-                    .spawn_integrity_check(view, self)
-                {
-                    eager_view_tasks.push(task);
+                    &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert)),
+                )?,
+                Command::Update { header, .. } => self.check_document_permission(
+                    &op.collection,
+                    &header.id,
+                    DocumentAction::Update,
+                )?,
+                Command::Overwrite { id, .. } => {
+                    self.check_document_permission(&op.collection, id, DocumentAction::Overwrite)?;
+                }
+                Command::Delete { header } => self.check_document_permission(
+                    &op.collection,
+                    &header.id,
+                    DocumentAction::Delete,
+                )?,
+                Command::Check { id, .. } => {
+                    self.check_document_permission(&op.collection, id, DocumentAction::Get)?;
                 }
             }
         }
 
-        let mut eager_view_mapping_tasks = Vec::new();
-        for task in eager_view_tasks {
-            if let Some(spawned_task) = task.receive().map_err(Error::from)?.map_err(Error::from)? {
-                eager_view_mapping_tasks.push(spawned_task);
-            }
-        }
-
-        for task in eager_view_mapping_tasks {
-            let mut task = task.lock();
-            if let Some(task) = task.take() {
-                task.receive().map_err(Error::from)?.map_err(Error::from)?;
-            }
-        }
-
-        self.apply_transaction_to_roots(&transaction)
+        self.apply_transaction_after_eager_views(&transaction, None)
             .map_err(bonsaidb_core::Error::from)
     }
 
@@ -1206,10 +1280,13 @@ impl LowLevelConnection for Database {
         id: DocumentId,
         collection: &CollectionName,
     ) -> Result<Option<OwnedDocument>, bonsaidb_core::Error> {
-        self.check_permission(
-            document_resource_name(self.name(), collection, &id),
-            &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
-        )?;
+        let filter = self.document_filter(collection)?;
+        if filter.is_none() {
+            self.check_permission(
+                document_resource_name(self.name(), collection, &id),
+                &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
+            )?;
+        }
         let tree = self
             .data
             .context
@@ -1217,6 +1294,11 @@ impl LowLevelConnection for Database {
             .tree(self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?)
             .map_err(Error::from)?;
         if let Some(vec) = tree.get(id.as_ref()).map_err(Error::from)? {
+            if let Some(filter) = &filter {
+                if !filter.allows(id.as_ref())? {
+                    return Ok(None);
+                }
+            }
             Ok(Some(deserialize_document(&vec)?.into_owned()))
         } else {
             Ok(None)
@@ -1243,6 +1325,7 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), collection),
             &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::List)),
         )?;
+        let filter = self.document_filter(collection)?;
         let tree = self
             .data
             .context
@@ -1251,6 +1334,7 @@ impl LowLevelConnection for Database {
             .map_err(Error::from)?;
         let mut found_docs = Vec::new();
         let mut keys_read = 0;
+        let mut filter_error = None;
         let ids = DocumentIdRange(ids);
         tree.scan(
             &ids.borrow_as_bytes(),
@@ -1259,7 +1343,18 @@ impl LowLevelConnection for Database {
                 Sort::Descending => false,
             },
             |_, _, _| ScanEvaluation::ReadData,
-            |_, _| {
+            |key, _| {
+                if let Some(filter) = &filter {
+                    match filter.allows(key) {
+                        Ok(true) => {}
+                        Ok(false) => return ScanEvaluation::Skip,
+                        Err(err) => {
+                            filter_error = Some(err);
+                            return ScanEvaluation::Stop;
+                        }
+                    }
+                }
+
                 if let Some(limit) = limit {
                     if keys_read >= limit {
                         return ScanEvaluation::Stop;
@@ -1282,6 +1377,9 @@ impl LowLevelConnection for Database {
             AbortError::Other(err) => err,
             AbortError::Nebari(err) => crate::Error::from(err),
         })?;
+        if let Some(err) = filter_error {
+            return Err(bonsaidb_core::Error::from(err));
+        }
 
         Ok(found_docs)
     }
@@ -1306,6 +1404,7 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), collection),
             &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::ListHeaders)),
         )?;
+        let filter = self.document_filter(collection)?;
         let tree = self
             .data
             .context
@@ -1314,6 +1413,7 @@ impl LowLevelConnection for Database {
             .map_err(Error::from)?;
         let mut found_headers = Vec::new();
         let mut keys_read = 0;
+        let mut filter_error = None;
         let ids = DocumentIdRange(ids);
         tree.scan(
             &ids.borrow_as_bytes(),
@@ -1322,7 +1422,18 @@ impl LowLevelConnection for Database {
                 Sort::Descending => false,
             },
             |_, _, _| ScanEvaluation::ReadData,
-            |_, _| {
+            |key, _| {
+                if let Some(filter) = &filter {
+                    match filter.allows(key) {
+                        Ok(true) => {}
+                        Ok(false) => return ScanEvaluation::Skip,
+                        Err(err) => {
+                            filter_error = Some(err);
+                            return ScanEvaluation::Stop;
+                        }
+                    }
+                }
+
                 if let Some(limit) = limit {
                     if keys_read >= limit {
                         return ScanEvaluation::Stop;
@@ -1345,6 +1456,9 @@ impl LowLevelConnection for Database {
             AbortError::Other(err) => err,
             AbortError::Nebari(err) => crate::Error::from(err),
         })?;
+        if let Some(err) = filter_error {
+            return Err(bonsaidb_core::Error::from(err));
+        }
 
         Ok(found_headers)
     }
@@ -1374,6 +1488,34 @@ impl LowLevelConnection for Database {
             .tree(self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?)
             .map_err(Error::from)?;
         let ids = DocumentIdRange(ids);
+        if let Some(filter) = self.document_filter(collection)? {
+            let mut count = 0;
+            let mut filter_error = None;
+            tree.scan(
+                &ids.borrow_as_bytes(),
+                true,
+                |_, _, _| ScanEvaluation::ReadData,
+                |key, _| {
+                    match filter.allows(key) {
+                        Ok(true) => count += 1,
+                        Ok(false) => {}
+                        Err(err) => {
+                            filter_error = Some(err);
+                            return ScanEvaluation::Stop;
+                        }
+                    }
+                    ScanEvaluation::Skip
+                },
+                |_, _, _| Ok::<_, AbortError<Infallible>>(()),
+            )
+            .map_err(Error::from)?;
+            if let Some(err) = filter_error {
+                return Err(bonsaidb_core::Error::from(err));
+            }
+
+            return Ok(count);
+        }
+
         let stats = tree.reduce(&ids.borrow_as_bytes()).map_err(Error::from)?;
 
         Ok(stats.alive_keys)
@@ -1393,11 +1535,14 @@ impl LowLevelConnection for Database {
         ids: &[DocumentId],
         collection: &CollectionName,
     ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
-        for id in ids {
-            self.check_permission(
-                document_resource_name(self.name(), collection, id),
-                &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
-            )?;
+        let filter = self.document_filter(collection)?;
+        if filter.is_none() {
+            for id in ids {
+                self.check_permission(
+                    document_resource_name(self.name(), collection, id),
+                    &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
+                )?;
+            }
         }
         let mut ids = ids.to_vec();
         let collection = collection.clone();
@@ -1414,11 +1559,17 @@ impl LowLevelConnection for Database {
             .get_multiple(ids.iter().map(|id| id.as_ref()))
             .map_err(Error::from)?;
 
-        keys_and_values
-            .into_iter()
-            .map(|(_, value)| deserialize_document(&value).map(BorrowedDocument::into_owned))
-            .collect::<Result<Vec<_>, Error>>()
-            .map_err(bonsaidb_core::Error::from)
+        let mut documents = Vec::with_capacity(keys_and_values.len());
+        for (key, value) in keys_and_values {
+            if let Some(filter) = &filter {
+                if !filter.allows(&key)? {
+                    continue;
+                }
+            }
+            documents.push(deserialize_document(&value)?.into_owned());
+        }
+
+        Ok(documents)
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(
@@ -1468,17 +1619,48 @@ impl LowLevelConnection for Database {
             view_resource_name(self.name(), &view.view_name()),
             &BonsaiAction::Database(DatabaseAction::View(ViewAction::Query)),
         )?;
+        let collection = view.collection();
+        let filter = self.document_filter(&collection)?;
         let mut results = Vec::new();
-        self.for_each_in_view(view, key, order, limit, access_policy, |entry| {
-            for mapping in entry.mappings {
-                results.push(bonsaidb_core::schema::view::map::Serialized {
-                    source: mapping.source,
-                    key: entry.key.clone(),
-                    value: mapping.value,
-                });
-            }
-            Ok(())
-        })?;
+        if let Some(filter) = filter {
+            // The limit applies to the entries visible to this session, so
+            // it can't be passed along to the view's iterator.
+            let mut entries_read = 0;
+            self.for_each_in_view(view, key, order, None, access_policy, |entry| {
+                if let Some(limit) = limit {
+                    if entries_read >= limit {
+                        return Ok(());
+                    }
+                }
+
+                let mut visible = false;
+                for mapping in entry.mappings {
+                    if filter.allows(&mapping.source.id)? {
+                        visible = true;
+                        results.push(bonsaidb_core::schema::view::map::Serialized {
+                            source: mapping.source,
+                            key: entry.key.clone(),
+                            value: mapping.value,
+                        });
+                    }
+                }
+                if visible {
+                    entries_read += 1;
+                }
+                Ok(())
+            })?;
+        } else {
+            self.for_each_in_view(view, key, order, limit, access_policy, |entry| {
+                for mapping in entry.mappings {
+                    results.push(bonsaidb_core::schema::view::map::Serialized {
+                        source: mapping.source,
+                        key: entry.key.clone(),
+                        value: mapping.value,
+                    });
+                }
+                Ok(())
+            })?;
+        }
 
         Ok(results)
     }
@@ -1580,11 +1762,32 @@ impl LowLevelConnection for Database {
             view_resource_name(self.name(), &view.view_name()),
             &BonsaiAction::Database(DatabaseAction::View(ViewAction::Reduce)),
         )?;
+        let collection = view.collection();
+        let filter = self.document_filter(&collection)?;
         let mut mappings = Vec::new();
         self.for_each_in_view(view, key, Sort::Ascending, None, access_policy, |entry| {
+            let value = if let Some(filter) = &filter {
+                // Only the mappings from documents this session can read are
+                // reduced.
+                let mut visible = Vec::with_capacity(entry.mappings.len());
+                for mapping in &entry.mappings {
+                    if filter.allows(&mapping.source.id)? {
+                        visible.push((entry.key.as_slice(), mapping.value.as_slice()));
+                    }
+                }
+                if visible.is_empty() {
+                    return Ok(());
+                } else if visible.len() == entry.mappings.len() {
+                    entry.reduced_value
+                } else {
+                    Bytes::from(view.reduce(&visible, false).map_err(Error::from)?)
+                }
+            } else {
+                entry.reduced_value
+            };
             mappings.push(MappedSerializedValue {
                 key: entry.key,
-                value: entry.reduced_value,
+                value,
             });
             Ok(())
         })?;
@@ -1610,9 +1813,15 @@ impl LowLevelConnection for Database {
     ) -> Result<u64, bonsaidb_core::Error> {
         let view = self.data.schema.view_by_name(view)?;
         let collection = view.collection();
+        let filter = self.document_filter(&collection)?;
         let mut transaction = Transaction::default();
         self.for_each_in_view(view, key, Sort::Ascending, None, access_policy, |entry| {
             for mapping in entry.mappings {
+                if let Some(filter) = &filter {
+                    if !filter.allows(&mapping.source.id)? {
+                        continue;
+                    }
+                }
                 transaction.push(Operation::delete(collection.clone(), mapping.source));
             }
 
@@ -1666,6 +1875,14 @@ impl Deref for Context {
 pub(crate) struct ContextData {
     pub(crate) roots: Roots<AnyFile>,
     key_value_state: Arc<Mutex<keyvalue::KeyValueState>>,
+    /// Set once pending migrations have been applied. The lock is held while
+    /// migrations are running.
+    migrated: Mutex<bool>,
+    /// Set once the migration versions of new collections have been
+    /// recorded.
+    new_collection_versions_recorded: Mutex<bool>,
+    /// Set once the schema has been checked against the stored data.
+    schema_checked: Mutex<bool>,
 }
 
 impl Borrow<Roots<AnyFile>> for Context {
@@ -1678,6 +1895,7 @@ impl Context {
     pub(crate) fn new(
         roots: Roots<AnyFile>,
         key_value_persistence: KeyValuePersistence,
+        metrics: Arc<MetricsCollector>,
         storage_lock: Option<StorageLock>,
     ) -> Self {
         let background_worker_target = Watchable::new(BackgroundWorkerProcessTarget::Never);
@@ -1686,12 +1904,16 @@ impl Context {
             key_value_persistence,
             roots.clone(),
             background_worker_target,
+            metrics,
         )));
         let background_worker_state = Arc::downgrade(&key_value_state);
         let context = Self {
             data: Arc::new(ContextData {
                 roots,
                 key_value_state,
+                migrated: Mutex::new(false),
+                new_collection_versions_recorded: Mutex::new(false),
+                schema_checked: Mutex::new(false),
             }),
         };
         std::thread::Builder::new()
@@ -0,0 +1,269 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+
+use bonsaidb_core::key::KeyDescription;
+use bonsaidb_core::schema::{CollectionName, Qualified, SchemaSummary, ViewName};
+use nebari::tree::{Root, Unversioned};
+
+use crate::database::keyvalue::KEY_TREE;
+use crate::database::migrations::MIGRATIONS_TREE;
+use crate::database::ownership::document_owners_tree_name;
+use crate::database::{document_tree_name, Database};
+use crate::views::{
+    view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
+    view_versions_tree_name,
+};
+use crate::Error;
+
+/// The tree storing the [`KeyDescription`] of each collection's primary key,
+/// keyed by the collection's encoded name. Descriptions are recorded the first
+/// time a database is opened with a schema containing the collection.
+pub(crate) const COLLECTION_KEYS_TREE: &str = "collection-keys";
+
+/// The differences between a database's registered schema and the data stored
+/// on disk, returned from [`Database::schema_drift()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDrift {
+    /// Collections with stored data that aren't part of the schema.
+    pub orphaned_collections: Vec<CollectionName>,
+    /// Views with stored data that aren't part of the schema.
+    pub orphaned_views: Vec<ViewName>,
+    /// Collections whose primary key type differs from the one recorded when
+    /// the collection was first stored.
+    pub changed_primary_keys: Vec<ChangedPrimaryKey>,
+    /// The names of stored trees that don't belong to the schema.
+    pub orphaned_trees: Vec<String>,
+    orphaned_view_versions: Vec<(CollectionName, String)>,
+    orphaned_collection_keys: Vec<String>,
+}
+
+impl SchemaDrift {
+    /// Returns true if the stored data matches the schema.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.orphaned_collections.is_empty()
+            && self.orphaned_views.is_empty()
+            && self.changed_primary_keys.is_empty()
+            && self.orphaned_trees.is_empty()
+            && self.orphaned_view_versions.is_empty()
+            && self.orphaned_collection_keys.is_empty()
+    }
+}
+
+impl Display for SchemaDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no schema drift");
+        }
+
+        let mut lines = Vec::new();
+        for collection in &self.orphaned_collections {
+            lines.push(format!("orphaned collection: {collection}"));
+        }
+        for view in &self.orphaned_views {
+            lines.push(format!("orphaned view: {view}"));
+        }
+        for changed in &self.changed_primary_keys {
+            lines.push(format!(
+                "primary key of {} changed from {:?} to {:?}",
+                changed.collection, changed.stored, changed.current
+            ));
+        }
+        for tree in &self.orphaned_trees {
+            lines.push(format!("orphaned tree: {tree}"));
+        }
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+/// A collection whose primary key type has changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedPrimaryKey {
+    /// The collection whose primary key changed.
+    pub collection: CollectionName,
+    /// The description of the primary key recorded on disk.
+    pub stored: KeyDescription,
+    /// The description of the primary key in the registered schema.
+    pub current: KeyDescription,
+}
+
+impl Database {
+    /// Compares this database's registered schema against the collections,
+    /// views, and primary keys stored on disk.
+    pub fn schema_drift(&self) -> Result<SchemaDrift, Error> {
+        let summary = SchemaSummary::from(self.schematic());
+        let mut expected_trees = HashSet::from([
+            KEY_TREE.to_string(),
+            MIGRATIONS_TREE.to_string(),
+            COLLECTION_KEYS_TREE.to_string(),
+        ]);
+        let mut expected_views = HashSet::new();
+        for collection in summary.collections() {
+            expected_trees.insert(document_tree_name(&collection.name));
+            expected_trees.insert(view_versions_tree_name(&collection.name));
+            if self
+                .schematic()
+                .collection_has_document_ownership(&collection.name)
+            {
+                expected_trees.insert(document_owners_tree_name(&collection.name));
+            }
+            for view in collection.views() {
+                expected_trees.insert(view_entries_tree_name(&view.name));
+                expected_trees.insert(view_document_map_tree_name(&view.name));
+                expected_trees.insert(view_invalidated_docs_tree_name(&view.name));
+                expected_views.insert(view.name.to_string());
+            }
+        }
+
+        let mut drift = SchemaDrift::default();
+        let mut orphaned_collections = BTreeSet::new();
+        let mut orphaned_views = BTreeSet::new();
+        let mut tree_names = self.roots().tree_names()?;
+        tree_names.sort();
+        for tree in &tree_names {
+            if expected_trees.contains(tree) {
+                continue;
+            }
+
+            if let Some(collection) = tree
+                .strip_prefix("collection.")
+                .or_else(|| tree.strip_prefix("view-versions."))
+                .or_else(|| tree.strip_prefix("owners."))
+            {
+                if let Ok(collection) = CollectionName::parse_encoded(collection) {
+                    orphaned_collections.insert(collection);
+                }
+            } else if let Some(view) = tree.strip_prefix("view.") {
+                let view = [".document-map", ".invalidated", ".omitted"]
+                    .iter()
+                    .find_map(|suffix| view.strip_suffix(suffix))
+                    .unwrap_or(view);
+                if let Ok(view) = view.parse::<ViewName>() {
+                    if !expected_views.contains(&view.to_string()) {
+                        orphaned_views.insert(view);
+                    }
+                }
+            }
+            drift.orphaned_trees.push(tree.clone());
+        }
+
+        // Views removed from a collection that is still in the schema leave
+        // their version behind in the collection's view versions.
+        for collection in summary.collections() {
+            let tree_name = view_versions_tree_name(&collection.name);
+            if !tree_names.contains(&tree_name) {
+                continue;
+            }
+            let view_versions = self
+                .roots()
+                .tree(self.collection_tree::<Unversioned, _>(&collection.name, tree_name)?)?;
+            for (view, _) in view_versions.get_range(&(..))? {
+                let view = String::from_utf8_lossy(&view).into_owned();
+                if !expected_views.contains(&view) {
+                    if let Ok(view) = view.parse::<ViewName>() {
+                        orphaned_views.insert(view);
+                    }
+                    drift
+                        .orphaned_view_versions
+                        .push((collection.name.clone(), view));
+                }
+            }
+        }
+
+        if tree_names.iter().any(|tree| tree == COLLECTION_KEYS_TREE) {
+            let collections = summary
+                .collections()
+                .map(|collection| (collection.name.encoded(), collection))
+                .collect::<HashMap<_, _>>();
+            let collection_keys = self.roots().tree(Unversioned::tree(COLLECTION_KEYS_TREE))?;
+            for (name, stored) in collection_keys.get_range(&(..))? {
+                let name = String::from_utf8_lossy(&name).into_owned();
+                if let Some(collection) = collections.get(&name) {
+                    let stored = pot::from_slice::<KeyDescription>(&stored)?;
+                    if stored != collection.primary_key {
+                        drift.changed_primary_keys.push(ChangedPrimaryKey {
+                            collection: collection.name.clone(),
+                            stored,
+                            current: collection.primary_key.clone(),
+                        });
+                    }
+                } else {
+                    if let Ok(collection) = CollectionName::parse_encoded(&name) {
+                        orphaned_collections.insert(collection);
+                    }
+                    drift.orphaned_collection_keys.push(name);
+                }
+            }
+        }
+
+        drift.orphaned_collections = orphaned_collections.into_iter().collect();
+        drift.orphaned_views = orphaned_views.into_iter().collect();
+        Ok(drift)
+    }
+
+    /// Removes the orphaned data reported by [`Self::schema_drift()`] and
+    /// records the current primary key of each collection whose primary key
+    /// changed. Returns the drift that was cleaned up.
+    pub fn clean_schema_drift(&self) -> Result<SchemaDrift, Error> {
+        let drift = self.schema_drift()?;
+        for tree in &drift.orphaned_trees {
+            self.roots().delete_tree(tree.clone())?;
+        }
+
+        for (collection, view) in &drift.orphaned_view_versions {
+            let view_versions = self.roots().tree(self.collection_tree::<Unversioned, _>(
+                collection,
+                view_versions_tree_name(collection),
+            )?)?;
+            view_versions.remove(view.as_bytes())?;
+        }
+
+        if !drift.orphaned_collection_keys.is_empty() || !drift.changed_primary_keys.is_empty() {
+            let collection_keys = self.roots().tree(Unversioned::tree(COLLECTION_KEYS_TREE))?;
+            for name in &drift.orphaned_collection_keys {
+                collection_keys.remove(name.as_bytes())?;
+            }
+            for changed in &drift.changed_primary_keys {
+                collection_keys.set(
+                    changed.collection.encoded().into_bytes(),
+                    pot::to_vec(&changed.current)?,
+                )?;
+            }
+        }
+
+        Ok(drift)
+    }
+
+    /// Records the primary key of each collection that doesn't have one
+    /// recorded yet, and if `deny_drift` is true, returns an error if the
+    /// stored data doesn't match the schema. This only happens once each time
+    /// the database's files are opened.
+    pub(crate) fn check_schema_if_needed(&self, deny_drift: bool) -> Result<(), Error> {
+        let mut checked = self.data.context.schema_checked.lock();
+        if *checked {
+            return Ok(());
+        }
+
+        let collection_keys = self.roots().tree(Unversioned::tree(COLLECTION_KEYS_TREE))?;
+        for collection in self.schematic().collections() {
+            let name = collection.encoded();
+            if collection_keys.get(name.as_bytes())?.is_none() {
+                let description = self
+                    .schematic()
+                    .collection_primary_key_description(collection)
+                    .expect("collection from schematic");
+                collection_keys.set(name.into_bytes(), pot::to_vec(description)?)?;
+            }
+        }
+
+        if deny_drift {
+            let drift = self.schema_drift()?;
+            if !drift.is_empty() {
+                return Err(Error::SchemaDrift(Box::new(drift)));
+            }
+        }
+
+        *checked = true;
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::collections::{btree_map, BTreeMap, VecDeque};
 use std::sync::{Arc, Weak};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use bonsaidb_core::connection::{Connection, HasSession};
 use bonsaidb_core::keyvalue::{
@@ -21,6 +21,7 @@ use watchable::{Watchable, Watcher};
 
 use crate::config::KeyValuePersistence;
 use crate::database::compat;
+use crate::metrics::MetricsCollector;
 use crate::storage::StorageLock;
 use crate::tasks::{Job, Keyed, Task};
 use crate::{Database, DatabaseNonBlocking, Error};
@@ -214,8 +215,13 @@ pub struct KeyValueState {
     expiration_order: VecDeque<String>,
     dirty_keys: BTreeMap<String, Option<Entry>>,
     keys_being_persisted: Option<Arc<BTreeMap<String, Option<Entry>>>>,
+    /// When `dirty_keys` last went from empty to non-empty.
+    dirty_since: Option<Timestamp>,
+    /// The `dirty_since` of the keys currently being persisted.
+    persisting_since: Option<Timestamp>,
     last_persistence: Watchable<Timestamp>,
     shutdown: Option<flume::Sender<()>>,
+    metrics: Arc<MetricsCollector>,
 }
 
 impl KeyValueState {
@@ -223,6 +229,7 @@ impl KeyValueState {
         persistence: KeyValuePersistence,
         roots: Roots<AnyFile>,
         background_worker_target: Watchable<BackgroundWorkerProcessTarget>,
+        metrics: Arc<MetricsCollector>,
     ) -> Self {
         Self {
             roots,
@@ -233,8 +240,11 @@ impl KeyValueState {
             expiration_order: VecDeque::new(),
             dirty_keys: BTreeMap::new(),
             keys_being_persisted: None,
+            dirty_since: None,
+            persisting_since: None,
             last_persistence: Watchable::new(Timestamp::MIN),
             shutdown: None,
+            metrics,
         }
     }
 
@@ -280,6 +290,9 @@ impl KeyValueState {
             ),
         };
         if result.is_ok() {
+            if self.dirty_since.is_none() && !self.dirty_keys.is_empty() {
+                self.dirty_since = Some(now);
+            }
             if self.needs_commit(now) {
                 self.commit_dirty_keys(state);
             }
@@ -637,6 +650,7 @@ impl KeyValueState {
             let key = self.expiration_order.pop_front().unwrap();
             self.expiring_keys.remove(&key);
             self.dirty_keys.insert(key, None);
+            self.dirty_since.get_or_insert(now);
         }
     }
 
@@ -654,6 +668,7 @@ impl KeyValueState {
         if !self.dirty_keys.is_empty() && self.keys_being_persisted.is_none() {
             let keys = Arc::new(std::mem::take(&mut self.dirty_keys));
             self.keys_being_persisted = Some(keys.clone());
+            self.persisting_since = self.dirty_since.take();
             Some(keys)
         } else {
             None
@@ -686,6 +701,7 @@ impl KeyValueState {
         roots: &Roots<AnyFile>,
         keys: &BTreeMap<String, Option<Entry>>,
     ) -> Result<(), bonsaidb_core::Error> {
+        let started_at = Instant::now();
         let mut transaction = roots
             .transaction(&[Unversioned::tree(KEY_TREE)])
             .map_err(Error::from)?;
@@ -738,7 +754,16 @@ impl KeyValueState {
         // If we are shutting down, check if we still have dirty keys.
         let final_keys = {
             let mut state = key_value_state.lock();
-            state.last_persistence.replace(Timestamp::now());
+            let now = Timestamp::now();
+            let lag = state
+                .persisting_since
+                .take()
+                .and_then(|since| now - since)
+                .unwrap_or_default();
+            state
+                .metrics
+                .record_key_value_persistence(keys.len(), started_at.elapsed(), lag);
+            state.last_persistence.replace(now);
             state.keys_being_persisted = None;
             state.update_background_worker_target();
             // This block is a little ugly to avoid having to acquire the lock
@@ -893,7 +918,7 @@ mod tests {
             .file_manager(AnyFileManager::std())
             .open()?;
 
-        let context = Context::new(sled.clone(), persistence, None);
+        let context = Context::new(sled.clone(), persistence, Arc::default(), None);
 
         test_contents(context, sled)?;
 
@@ -1177,6 +1202,7 @@ mod tests {
         let context = Context::new(
             sled,
             KeyValuePersistence::lazy([PersistenceThreshold::after_changes(2)]),
+            Arc::default(),
             None,
         );
         context
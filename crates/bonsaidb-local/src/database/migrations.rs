@@ -0,0 +1,205 @@
+use std::cmp::Ordering;
+
+use bonsaidb_core::connection::HasSession;
+use bonsaidb_core::permissions::bonsai::{database_resource_name, BonsaiAction, DatabaseAction};
+use bonsaidb_core::schema::CollectionName;
+use bonsaidb_core::transaction::{Operation, Transaction};
+use nebari::tree::{Root, Unversioned, Versioned};
+
+use crate::database::{deserialize_document, document_tree_name, Database};
+use crate::{DatabaseNonBlocking, Error};
+
+/// The tree storing the version of the last migration applied to each
+/// collection, keyed by the collection's name.
+pub(crate) const MIGRATIONS_TREE: &str = "migrations";
+
+/// The migrations applied, or that would be applied, to a collection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionMigration {
+    /// The migrated collection.
+    pub collection: CollectionName,
+    /// The version of the collection before migrating.
+    pub from_version: u64,
+    /// The version of the collection after migrating.
+    pub to_version: u64,
+    /// The number of documents whose contents were changed.
+    pub updated: u64,
+    /// The number of documents that were deleted.
+    pub deleted: u64,
+}
+
+impl Database {
+    /// Applies all pending migrations defined in this database's
+    /// [`Schematic`](bonsaidb_core::schema::Schematic). The documents of each
+    /// collection are migrated in a single transaction, which also records the
+    /// collection's new migration version.
+    ///
+    /// Returns the migrations applied for each collection that had pending
+    /// migrations. Requires the [`DatabaseAction::Migrate`] permission.
+    pub fn migrate(&self) -> Result<Vec<CollectionMigration>, Error> {
+        self.check_permission(
+            database_resource_name(self.name()),
+            &BonsaiAction::Database(DatabaseAction::Migrate),
+        )?;
+        let mut migrated = self.data.context.migrated.lock();
+        let applied = self.run_migrations(false)?;
+        *migrated = true;
+        Ok(applied)
+    }
+
+    /// Returns the changes [`Self::migrate()`] would make, without modifying
+    /// the database. Requires the [`DatabaseAction::Migrate`] permission.
+    pub fn migrate_dry_run(&self) -> Result<Vec<CollectionMigration>, Error> {
+        self.check_permission(
+            database_resource_name(self.name()),
+            &BonsaiAction::Database(DatabaseAction::Migrate),
+        )?;
+        let _migrated = self.data.context.migrated.lock();
+        self.run_migrations(true)
+    }
+
+    /// Applies pending migrations if they haven't been checked since the
+    /// database's files were opened.
+    pub(crate) fn migrate_if_needed(&self) -> Result<(), Error> {
+        let mut migrated = self.data.context.migrated.lock();
+        if !*migrated {
+            self.run_migrations(false)?;
+            *migrated = true;
+        }
+        Ok(())
+    }
+
+    /// Records the latest migration version for each migrated collection that
+    /// has no recorded version and no documents, if this hasn't been done
+    /// since the database's files were opened.
+    ///
+    /// Documents stored in a new collection are written in the format of the
+    /// latest migration, so migrating them again would corrupt them. Only
+    /// collections with existing documents and no recorded version start at
+    /// version 0.
+    pub(crate) fn record_new_collection_versions_if_needed(&self) -> Result<(), Error> {
+        let mut recorded = self.data.context.new_collection_versions_recorded.lock();
+        if *recorded {
+            return Ok(());
+        }
+
+        let versions = self.roots().tree(Unversioned::tree(MIGRATIONS_TREE))?;
+        for collection in self.data.schema.migrated_collections() {
+            if versions.get(collection.to_string().as_bytes())?.is_some() {
+                continue;
+            }
+
+            let documents = self.roots().tree(
+                self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+            )?;
+            if documents.last_key()?.is_none() {
+                let latest_version = self.data.schema.migrations_in_collection(collection).len();
+                self.set_migration_version(collection, latest_version as u64)?;
+            }
+        }
+
+        *recorded = true;
+        Ok(())
+    }
+
+    /// Returns the version of the last migration applied to `collection`.
+    pub fn migration_version(&self, collection: &CollectionName) -> Result<u64, Error> {
+        let versions = self.roots().tree(Unversioned::tree(MIGRATIONS_TREE))?;
+        match versions.get(collection.to_string().as_bytes())? {
+            Some(version) => {
+                let version = <[u8; 8]>::try_from(version.as_slice()).map_err(|_| {
+                    Error::other("bonsaidb-local", "invalid migration version stored")
+                })?;
+                Ok(u64::from_be_bytes(version))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Records `version` as the last migration applied to `collection`.
+    pub(crate) fn set_migration_version(
+        &self,
+        collection: &CollectionName,
+        version: u64,
+    ) -> Result<(), Error> {
+        let versions = self.roots().tree(Unversioned::tree(MIGRATIONS_TREE))?;
+        versions.set(
+            collection.to_string().into_bytes(),
+            version.to_be_bytes().to_vec(),
+        )?;
+        Ok(())
+    }
+
+    fn run_migrations(&self, dry_run: bool) -> Result<Vec<CollectionMigration>, Error> {
+        let schema = &self.data.schema;
+        let mut collections = schema.migrated_collections().collect::<Vec<_>>();
+        collections.sort();
+
+        let mut results = Vec::new();
+        for collection in collections {
+            let migrations = schema.migrations_in_collection(collection);
+            let from_version = self.migration_version(collection)?;
+            let to_version = migrations.len() as u64;
+            match from_version.cmp(&to_version) {
+                Ordering::Less => {}
+                Ordering::Equal => continue,
+                Ordering::Greater => {
+                    return Err(Error::UnknownMigrationVersion {
+                        collection: collection.clone(),
+                        version: from_version,
+                    })
+                }
+            }
+
+            let pending = &migrations[usize::try_from(from_version).unwrap_or(usize::MAX)..];
+            let documents = self.roots().tree(
+                self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+            )?;
+
+            let mut transaction = Transaction::new();
+            let mut result = CollectionMigration {
+                collection: collection.clone(),
+                from_version,
+                to_version,
+                updated: 0,
+                deleted: 0,
+            };
+            for (_, stored) in documents.get_range(&(..))? {
+                let original = deserialize_document(&stored)?.into_owned();
+                let mut document = Some(original.clone());
+                for migration in pending {
+                    let Some(current) = document else {
+                        break;
+                    };
+                    document = migration.migrate(current)?;
+                }
+
+                match document {
+                    Some(document) if document.contents != original.contents => {
+                        result.updated += 1;
+                        transaction.push(Operation::update(
+                            collection.clone(),
+                            original.header,
+                            document.contents,
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        result.deleted += 1;
+                        transaction.push(Operation::delete(collection.clone(), original.header));
+                    }
+                }
+            }
+
+            if !dry_run {
+                self.apply_transaction_after_eager_views(
+                    &transaction,
+                    Some((collection, to_version)),
+                )?;
+            }
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use bonsaidb_core::arc_bytes::ArcBytes;
+use bonsaidb_core::connection::{HasSession, Identity, IdentityId, Session};
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::permissions::bonsai::{
+    collection_resource_name, document_resource_name, BonsaiAction, DatabaseAction, DocumentAction,
+};
+use bonsaidb_core::schema::CollectionName;
+use nebari::io::any::AnyFile;
+use nebari::tree::Unversioned;
+use nebari::{ExecutingTransaction, Tree};
+
+use crate::database::{Database, DatabaseNonBlocking};
+use crate::Error;
+
+/// Returns the name of the tree storing the owner of each document in
+/// `collection`.
+pub fn document_owners_tree_name(collection: &CollectionName) -> String {
+    format!("owners.{collection:#}")
+}
+
+const GET_DOCUMENT: BonsaiAction =
+    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get));
+
+impl Database {
+    /// Returns the identity recorded as the owner of the document `id` in
+    /// `collection`.
+    ///
+    /// Owners are only recorded for collections that have document ownership
+    /// enabled using
+    /// [`Schematic::define_document_ownership()`](bonsaidb_core::schema::Schematic::define_document_ownership),
+    /// and only when the document was inserted by an authenticated session.
+    pub fn document_owner(
+        &self,
+        collection: &CollectionName,
+        id: &DocumentId,
+    ) -> Result<Option<IdentityId>, Error> {
+        if !self
+            .data
+            .schema
+            .collection_has_document_ownership(collection)
+        {
+            return Ok(None);
+        }
+
+        self.owners_tree(collection)?
+            .get(id.as_ref())?
+            .map(|owner| pot::from_slice(&owner).map_err(Error::from))
+            .transpose()
+    }
+
+    fn owners_tree(
+        &self,
+        collection: &CollectionName,
+    ) -> Result<Tree<Unversioned, AnyFile>, Error> {
+        self.roots()
+            .tree(self.collection_tree::<Unversioned, _>(
+                collection,
+                document_owners_tree_name(collection),
+            )?)
+            .map_err(Error::from)
+    }
+
+    /// Returns a filter limiting the documents of `collection` that the
+    /// current session can read, or `None` if every document is readable.
+    pub(crate) fn document_filter<'a>(
+        &'a self,
+        collection: &'a CollectionName,
+    ) -> Result<Option<DocumentFilter<'a>>, Error> {
+        let Some(session) = self.session() else {
+            return Ok(None);
+        };
+        if !self
+            .data
+            .schema
+            .collection_has_document_ownership(collection)
+            || session.allowed_to(
+                collection_resource_name(self.name(), collection),
+                &GET_DOCUMENT,
+            )
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(DocumentFilter {
+            database: self,
+            collection,
+            owners: self.owners_tree(collection)?,
+            identity: session.identity().map(Identity::id),
+        }))
+    }
+
+    /// Checks that `action` is permitted on the document `id`. Owners of
+    /// documents in collections with document ownership enabled are
+    /// permitted unless the action is explicitly denied.
+    pub(crate) fn check_document_permission(
+        &self,
+        collection: &CollectionName,
+        id: &DocumentId,
+        action: DocumentAction,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let resource_name = document_resource_name(self.name(), collection, id);
+        let action = BonsaiAction::Database(DatabaseAction::Document(action));
+        let result = self.check_permission(&resource_name, &action);
+        if result.is_err() {
+            if let Some(session) = self.session() {
+                if let Some(identity) = session.identity() {
+                    if !session.denies(&resource_name, &action)
+                        && self.document_owner(collection, id)? == Some(identity.id())
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Records the current session's identity as the owner of the newly
+    /// created document `document_id`.
+    pub(crate) fn record_document_owner(
+        &self,
+        collection: &CollectionName,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+        document_id: &ArcBytes<'static>,
+    ) -> Result<(), Error> {
+        if !self
+            .data
+            .schema
+            .collection_has_document_ownership(collection)
+        {
+            return Ok(());
+        }
+
+        if let Some(identity) = self.session().and_then(Session::identity) {
+            let mut owners = transaction
+                .tree::<Unversioned>(tree_index_map[&document_owners_tree_name(collection)])
+                .unwrap();
+            owners.set(document_id.clone(), pot::to_vec(&identity.id())?)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the recorded owner of the deleted document `document_id`.
+    pub(crate) fn remove_document_owner(
+        &self,
+        collection: &CollectionName,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+        document_id: &ArcBytes<'static>,
+    ) -> Result<(), Error> {
+        if self
+            .data
+            .schema
+            .collection_has_document_ownership(collection)
+        {
+            let mut owners = transaction
+                .tree::<Unversioned>(tree_index_map[&document_owners_tree_name(collection)])
+                .unwrap();
+            owners.remove(document_id.as_slice())?;
+        }
+        Ok(())
+    }
+}
+
+/// Limits the documents of a collection with document ownership enabled to
+/// the ones the current session owns or is allowed to
+/// [`DocumentAction::Get`].
+pub(crate) struct DocumentFilter<'a> {
+    database: &'a Database,
+    collection: &'a CollectionName,
+    owners: Tree<Unversioned, AnyFile>,
+    identity: Option<IdentityId>,
+}
+
+impl DocumentFilter<'_> {
+    /// Returns `true` if the document with the serialized `id` can be read.
+    /// Owners can read their documents unless reading is explicitly denied.
+    pub fn allows(&self, id: &[u8]) -> Result<bool, Error> {
+        let resource_name = document_resource_name(
+            self.database.name(),
+            self.collection,
+            &DocumentId::try_from(id)?,
+        );
+        if let Some(identity) = self.identity {
+            if let Some(owner) = self.owners.get(id)? {
+                if pot::from_slice::<IdentityId>(&owner)? == identity
+                    && !self.database.session().map_or(false, |session| {
+                        session.denies(&resource_name, &GET_DOCUMENT)
+                    })
+                {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(self.database.allowed_to(resource_name, &GET_DOCUMENT))
+    }
+}
@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use bonsaidb_core::permissions::PermissionDenied;
 use bonsaidb_core::pubsub::{Disconnected, TryReceiveError};
-use bonsaidb_core::schema::{view, InsertError, InvalidNameError};
+use bonsaidb_core::schema::{view, CollectionName, InsertError, InvalidNameError};
 use bonsaidb_core::AnyError;
 use nebari::AbortError;
 
@@ -71,6 +71,20 @@ pub enum Error {
     #[error("a backup error: {0}")]
     Backup(Box<dyn AnyError>),
 
+    /// A collection's stored migration version is newer than the migrations
+    /// defined in the schema.
+    #[error("collection {collection} has migration version {version}, which is not defined in the schema")]
+    UnknownMigrationVersion {
+        /// The collection with the unknown version.
+        collection: CollectionName,
+        /// The stored migration version.
+        version: u64,
+    },
+
+    /// The data stored in a database doesn't match its registered schema.
+    #[error("stored data doesn't match the schema:\n{0}")]
+    SchemaDrift(Box<crate::SchemaDrift>),
+
     /// An error occurred with a password hash.
     #[cfg(all(feature = "password-hashing", feature = "cli"))]
     #[error("error reading password: {0}")]
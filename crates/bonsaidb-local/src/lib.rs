@@ -24,6 +24,8 @@ mod database;
 mod error;
 #[cfg(feature = "encryption")]
 mod hpke_util;
+/// Metrics collected while BonsaiDb is running.
+pub mod metrics;
 mod open_trees;
 mod storage;
 mod tasks;
@@ -36,6 +38,8 @@ pub use argon2;
 #[cfg(not(feature = "included-from-omnibus"))]
 pub use bonsaidb_core as core;
 
+pub use self::database::drift::{ChangedPrimaryKey, SchemaDrift};
+pub use self::database::migrations::CollectionMigration;
 pub use self::database::pubsub::Subscriber;
 pub use self::database::{Database, DatabaseNonBlocking};
 pub use self::error::Error;
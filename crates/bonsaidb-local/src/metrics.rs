@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the metrics collected by a [`Storage`](crate::Storage)
+/// instance since it was opened.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct StorageMetrics {
+    /// The number of times views have been updated, and the total time spent
+    /// updating them.
+    pub view_updates: DurationSummary,
+    /// The number of compactions performed, and the total time spent
+    /// compacting.
+    pub compactions: DurationSummary,
+    /// The number of times dirty key-value entries have been persisted to
+    /// disk, and the total time spent persisting them.
+    pub key_value_persistence: DurationSummary,
+    /// The total number of key-value entries that have been persisted to disk.
+    pub key_value_keys_persisted: u64,
+    /// The amount of time the oldest change had been waiting to be written
+    /// when key-value entries were most recently persisted.
+    pub key_value_persistence_lag: Duration,
+}
+
+/// The number of times an operation has occurred and the total time spent
+/// performing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DurationSummary {
+    /// The number of times the operation has been performed.
+    pub count: u64,
+    /// The total duration of all operations.
+    pub total: Duration,
+}
+
+impl DurationSummary {
+    /// Returns the average duration of the operation, or `None` if it hasn't
+    /// been performed.
+    #[must_use]
+    pub fn average(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count).unwrap_or(u32::MAX);
+        (count > 0).then(|| self.total / count)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCollector {
+    pub view_updates: DurationCounter,
+    pub compactions: DurationCounter,
+    pub key_value_persistence: DurationCounter,
+    key_value_keys_persisted: AtomicU64,
+    key_value_persistence_lag: AtomicU64,
+}
+
+impl MetricsCollector {
+    pub fn record_key_value_persistence(&self, keys: usize, duration: Duration, lag: Duration) {
+        self.key_value_persistence.record(duration);
+        self.key_value_keys_persisted
+            .fetch_add(u64::try_from(keys).unwrap_or(u64::MAX), Ordering::Relaxed);
+        self.key_value_persistence_lag
+            .store(duration_nanos(lag), Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StorageMetrics {
+        StorageMetrics {
+            view_updates: self.view_updates.snapshot(),
+            compactions: self.compactions.snapshot(),
+            key_value_persistence: self.key_value_persistence.snapshot(),
+            key_value_keys_persisted: self.key_value_keys_persisted.load(Ordering::Relaxed),
+            key_value_persistence_lag: Duration::from_nanos(
+                self.key_value_persistence_lag.load(Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct DurationCounter {
+    count: AtomicU64,
+    total_nanos: AtomicU64,
+}
+
+impl DurationCounter {
+    pub fn record(&self, duration: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos
+            .fetch_add(duration_nanos(duration), Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> DurationSummary {
+        DurationSummary {
+            count: self.count.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+fn duration_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
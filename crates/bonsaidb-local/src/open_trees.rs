@@ -5,6 +5,7 @@ use nebari::io::any::AnyFile;
 use nebari::tree::{AnyTreeRoot, Root, Unversioned, Versioned};
 
 use crate::database::document_tree_name;
+use crate::database::ownership::document_owners_tree_name;
 #[cfg(any(feature = "encryption", feature = "compression"))]
 use crate::storage::TreeVault;
 use crate::views::{
@@ -52,6 +53,14 @@ impl OpenTrees {
             vault.clone(),
         );
 
+        if schema.collection_has_document_ownership(collection) {
+            self.open_tree::<Unversioned>(
+                &document_owners_tree_name(collection),
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                vault.clone(),
+            );
+        }
+
         for view in schema.views_in_collection(collection) {
             let view_name = view.view_name();
             if view.update_policy().is_eager() {
@@ -4,28 +4,42 @@ use std::fmt::{Debug, Display};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::marker::PhantomData;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, Weak};
 
+use bonsaidb_core::admin::authentication_token::ByIdentity;
 use bonsaidb_core::admin::database::{self, ByName, Database as DatabaseRecord};
 use bonsaidb_core::admin::user::User;
-use bonsaidb_core::admin::{self, Admin, PermissionGroup, Role, ADMIN_DATABASE_NAME};
+use bonsaidb_core::admin::{
+    self, Admin, AuthenticationToken, AuthenticationTokenSummary, PermissionGroup, Role,
+    ADMIN_DATABASE_NAME,
+};
+#[cfg(feature = "token-authentication")]
+use bonsaidb_core::admin::{AuthenticationTokenOptions, CreatedAuthenticationToken};
 use bonsaidb_core::circulate;
 pub use bonsaidb_core::circulate::Relay;
 use bonsaidb_core::connection::{
-    self, Connection, HasSession, Identity, IdentityReference, LowLevelConnection, Session,
-    SessionAuthentication, SessionId, StorageConnection,
+    self, AuthenticationTokenValidity, Connection, HasSession, Identity, IdentityId,
+    IdentityReference, LowLevelConnection, Session, SessionAuthentication, SessionId,
+    StorageConnection,
 };
-use bonsaidb_core::document::CollectionDocument;
 #[cfg(any(feature = "encryption", feature = "compression"))]
 use bonsaidb_core::document::KeyId;
+use bonsaidb_core::document::{CollectionDocument, DocumentId};
 use bonsaidb_core::permissions::bonsai::{
-    bonsaidb_resource_name, database_resource_name, role_resource_name, user_resource_name,
-    BonsaiAction, ServerAction,
+    authentication_token_resource_name, bonsaidb_resource_name, collection_resource_name,
+    database_resource_name, permission_group_resource_name, role_resource_name, user_resource_name,
+    BonsaiAction, DatabaseAction, DocumentAction, ServerAction,
+};
+use bonsaidb_core::permissions::{
+    Action, ActionName, ClientContext, Effect, ExplainedStatement, Identifier,
+    PermissionExplanation, Permissions, ResourceName, StatementSource,
 };
-use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::{
-    Nameable, NamedCollection, Schema, SchemaName, SchemaSummary, Schematic,
+    CollectionName, Nameable, NamedCollection, Schema, SchemaName, SchemaSummary, Schematic,
+    SerializedCollection,
 };
 use fs2::FileExt;
 use itertools::Itertools;
@@ -37,8 +51,11 @@ use rand::{thread_rng, Rng};
 
 #[cfg(feature = "compression")]
 use crate::config::Compression;
-use crate::config::{KeyValuePersistence, StorageConfiguration};
+#[cfg(feature = "password-hashing")]
+use crate::config::PasswordPolicy;
+use crate::config::{AuditLog, KeyValuePersistence, StorageConfiguration};
 use crate::database::Context;
+use crate::metrics::{MetricsCollector, StorageMetrics};
 use crate::tasks::manager::Manager;
 use crate::tasks::TaskManager;
 #[cfg(feature = "encryption")]
@@ -50,6 +67,7 @@ mod argon;
 #[cfg(feature = "token-authentication")]
 mod token_authentication;
 
+mod audit;
 mod backup;
 mod pubsub;
 pub use backup::{AnyBackupLocation, BackupLocation};
@@ -152,6 +170,7 @@ pub struct Storage {
     pub(crate) instance: StorageInstance,
     pub(crate) authentication: Option<Arc<AuthenticatedSession>>,
     effective_session: Option<Arc<Session>>,
+    client_address: Option<SocketAddr>,
 }
 
 #[derive(Debug)]
@@ -229,6 +248,7 @@ impl From<StorageInstance> for Storage {
             instance,
             authentication: None,
             effective_session: None,
+            client_address: None,
         }
     }
 }
@@ -249,6 +269,12 @@ struct Data {
     pub(crate) subscribers: Arc<RwLock<SessionSubscribers>>,
     #[cfg(feature = "password-hashing")]
     argon: argon::Hasher,
+    #[cfg(feature = "password-hashing")]
+    password_policy: PasswordPolicy,
+    #[cfg(feature = "password-hashing")]
+    login_attempts: argon::LoginAttempts,
+    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+    authentication_provider: Option<Arc<dyn crate::config::AuthenticationProvider>>,
     #[cfg(feature = "encryption")]
     pub(crate) vault: Arc<Vault>,
     #[cfg(feature = "encryption")]
@@ -256,8 +282,13 @@ struct Data {
     #[cfg(any(feature = "compression", feature = "encryption"))]
     tree_vault: Option<TreeVault>,
     pub(crate) key_value_persistence: KeyValuePersistence,
+    metrics: Arc<MetricsCollector>,
     chunk_cache: ChunkCache,
     pub(crate) check_view_integrity_on_database_open: bool,
+    run_migrations_on_database_open: bool,
+    deny_schema_drift_on_database_open: bool,
+    audit_log: AuditLog,
+    audit_log_writer: Option<flume::Sender<audit::AuditLogCommand>>,
     relay: Relay,
 }
 
@@ -303,9 +334,22 @@ impl Storage {
 
         let parallelization = configuration.workers.parallelization;
         let check_view_integrity_on_database_open = configuration.views.check_integrity_on_open;
+        let run_migrations_on_database_open = configuration.migrations.run_on_open;
+        let deny_schema_drift_on_database_open = configuration.schema_drift.deny_on_open;
+        let audit_log = configuration.audit_log;
+        let (audit_log_writer, audit_log_commands) = if audit_log.enabled {
+            let (sender, receiver) = audit::audit_log_channel();
+            (Some(sender), Some(receiver))
+        } else {
+            (None, None)
+        };
         let key_value_persistence = configuration.key_value_persistence;
         #[cfg(feature = "password-hashing")]
         let argon = argon::Hasher::new(configuration.argon);
+        #[cfg(feature = "password-hashing")]
+        let password_policy = configuration.password_policy;
+        #[cfg(feature = "password-hashing")]
+        let login_attempts = argon::LoginAttempts::new(configuration.login_lockout);
         #[cfg(feature = "encryption")]
         let default_encryption_key = configuration.default_encryption_key;
         #[cfg(all(feature = "compression", feature = "encryption"))]
@@ -320,6 +364,8 @@ impl Storage {
         let tree_vault = TreeVault::new_if_needed(configuration.default_compression);
 
         let authenticated_permissions = configuration.authenticated_permissions;
+        #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+        let authentication_provider = configuration.authentication_provider;
 
         let storage = Self {
             instance: StorageInstance {
@@ -332,6 +378,12 @@ impl Storage {
                     sessions: RwLock::default(),
                     #[cfg(feature = "password-hashing")]
                     argon,
+                    #[cfg(feature = "password-hashing")]
+                    password_policy,
+                    #[cfg(feature = "password-hashing")]
+                    login_attempts,
+                    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+                    authentication_provider,
                     #[cfg(feature = "encryption")]
                     vault,
                     #[cfg(feature = "encryption")]
@@ -346,14 +398,28 @@ impl Storage {
                     available_databases: RwLock::default(),
                     open_roots: Mutex::default(),
                     key_value_persistence,
+                    metrics: Arc::default(),
                     check_view_integrity_on_database_open,
+                    run_migrations_on_database_open,
+                    deny_schema_drift_on_database_open,
+                    audit_log,
+                    audit_log_writer,
                     relay: Relay::default(),
                 }),
             },
             authentication: None,
             effective_session: None,
+            client_address: None,
         };
 
+        if let Some(audit_log_commands) = audit_log_commands {
+            audit::spawn_audit_log_writer(
+                Arc::downgrade(&storage.instance.data),
+                audit_log_commands,
+                storage.instance.data.audit_log.retention,
+            );
+        }
+
         storage.cache_available_databases()?;
 
         storage.create_admin_database_if_needed()?;
@@ -464,6 +530,13 @@ impl Storage {
         self.instance.data.lock.id()
     }
 
+    /// Returns a snapshot of the metrics collected since this storage was
+    /// opened.
+    #[must_use]
+    pub fn metrics(&self) -> StorageMetrics {
+        self.instance.metrics().snapshot()
+    }
+
     #[must_use]
     pub(crate) fn parallelization(&self) -> usize {
         self.instance.data.parallelization
@@ -540,7 +613,9 @@ impl Storage {
                     id: None,
                     authentication: SessionAuthentication::None,
                     permissions: effective_permissions,
+                    ..Session::default()
                 })),
+                client_address: self.client_address,
             })
         }
     }
@@ -587,6 +662,25 @@ impl Storage {
     pub fn to_async_with_runtime(&self, runtime: tokio::runtime::Handle) -> crate::AsyncStorage {
         self.clone().into_async_with_runtime(runtime)
     }
+
+    /// Returns the resource name of the user or role `identity` refers to.
+    fn identity_resource_name(
+        &self,
+        identity: &IdentityReference<'_>,
+    ) -> Result<ResourceName<'static>, bonsaidb_core::Error> {
+        let admin = self.admin();
+        match identity {
+            IdentityReference::User(user) => Ok(user_resource_name(
+                user.id::<User, _>(&admin)?
+                    .ok_or(bonsaidb_core::Error::UserNotFound)?,
+            )),
+            IdentityReference::Role(role) => Ok(role_resource_name(
+                role.id::<Role, _>(&admin)?
+                    .ok_or(bonsaidb_core::Error::RoleNotFound)?,
+            )),
+            _ => Err(bonsaidb_core::Error::InvalidCredentials),
+        }
+    }
 }
 
 impl Debug for Data {
@@ -604,11 +698,21 @@ impl Debug for Data {
             .field("sessions", &self.sessions)
             .field("subscribers", &self.subscribers)
             .field("key_value_persistence", &self.key_value_persistence)
+            .field("metrics", &self.metrics)
             .field("chunk_cache", &self.chunk_cache)
             .field(
                 "check_view_integrity_on_database_open",
                 &self.check_view_integrity_on_database_open,
             )
+            .field(
+                "run_migrations_on_database_open",
+                &self.run_migrations_on_database_open,
+            )
+            .field(
+                "deny_schema_drift_on_database_open",
+                &self.deny_schema_drift_on_database_open,
+            )
+            .field("audit_log", &self.audit_log)
             .field("relay", &self.relay);
 
         if let Some(schemas) = self.schemas.try_read() {
@@ -619,8 +723,12 @@ impl Debug for Data {
             f.field("schemas", &"RwLock locked");
         }
 
+        #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+        f.field("authentication_provider", &self.authentication_provider);
         #[cfg(feature = "password-hashing")]
-        f.field("argon", &self.argon);
+        f.field("argon", &self.argon)
+            .field("password_policy", &self.password_policy)
+            .field("login_attempts", &self.login_attempts);
         #[cfg(feature = "encryption")]
         {
             f.field("vault", &self.vault)
@@ -659,6 +767,7 @@ impl StorageInstance {
             let context = Context::new(
                 roots,
                 self.data.key_value_persistence.clone(),
+                self.data.metrics.clone(),
                 Some(self.data.lock.clone()),
             );
 
@@ -676,10 +785,22 @@ impl StorageInstance {
         self.data.check_view_integrity_on_database_open
     }
 
+    pub(crate) fn run_migrations_on_database_open(&self) -> bool {
+        self.data.run_migrations_on_database_open
+    }
+
+    pub(crate) fn deny_schema_drift_on_database_open(&self) -> bool {
+        self.data.deny_schema_drift_on_database_open
+    }
+
     pub(crate) fn relay(&self) -> &'_ Relay {
         &self.data.relay
     }
 
+    pub(crate) fn metrics(&self) -> &'_ MetricsCollector {
+        &self.data.metrics
+    }
+
     /// Opens a database through a generic-free trait.
     pub(crate) fn database_without_schema(
         &self,
@@ -790,16 +911,69 @@ impl StorageInstance {
                         User::load(user, admin)?.ok_or(bonsaidb_core::Error::InvalidCredentials)?
                     }
                 };
-                let saved_hash = user
+                // The attempt is reserved before the slow password
+                // verification so that parallel guesses can't exceed the
+                // lockout's limit.
+                let attempt = self.data.login_attempts.reserve(user.header.id)?;
+                let verified = user
                     .contents
                     .argon_hash
                     .clone()
-                    .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+                    .ok_or(bonsaidb_core::Error::InvalidCredentials)
+                    .and_then(|saved_hash| {
+                        self.data
+                            .argon
+                            .verify(user.header.id, password, saved_hash)
+                            .map_err(bonsaidb_core::Error::from)
+                    });
+                attempt.finish(&verified);
+                verified?;
+                self.assume_user(user, None, admin)
+            }
+            Authentication::External(credential) => {
+                self.authenticate_externally(&credential, admin)
+            }
+        }
+    }
 
-                self.data
-                    .argon
-                    .verify(user.header.id, password, saved_hash)?;
-                self.assume_user(user, admin)
+    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+    fn authenticate_externally(
+        &self,
+        credential: &connection::SensitiveBytes,
+        admin: &Database,
+    ) -> Result<Storage, bonsaidb_core::Error> {
+        let provider = self
+            .data
+            .authentication_provider
+            .as_ref()
+            .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+        match provider.authenticate(credential)? {
+            crate::config::ExternalIdentity::User(username) => {
+                let user = match User::load(&username, admin)? {
+                    Some(user) => user,
+                    // Users are provisioned the first time they authenticate.
+                    None => match User::default_with_username(username.clone()).push_into(admin) {
+                        Ok(user) => user,
+                        // Another session provisioned the same user
+                        // concurrently.
+                        Err(err)
+                            if matches!(
+                                err.error,
+                                bonsaidb_core::Error::UniqueKeyViolation { .. }
+                            ) =>
+                        {
+                            User::load(&username, admin)?
+                                .ok_or(bonsaidb_core::Error::InvalidCredentials)?
+                        }
+                        Err(err) => return Err(err.error),
+                    },
+                };
+                self.assume_user(user, None, admin)
+            }
+            crate::config::ExternalIdentity::Role(name) => {
+                let role =
+                    Role::load(&name, admin)?.ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+                self.assume_role(role, None, admin)
             }
         }
     }
@@ -807,12 +981,14 @@ impl StorageInstance {
     fn assume_user(
         &self,
         user: CollectionDocument<User>,
+        token: Option<&CollectionDocument<AuthenticationToken>>,
         admin: &Database,
     ) -> Result<Storage, bonsaidb_core::Error> {
         let permissions = user.contents.effective_permissions(
             admin,
             &admin.storage().instance.data.authenticated_permissions,
         )?;
+        let conditional_permissions = user.contents.conditional_permissions(admin)?;
 
         let mut sessions = self.data.sessions.write();
         sessions.last_session_id += 1;
@@ -824,6 +1000,12 @@ impl StorageInstance {
                 username: user.contents.username,
             })),
             permissions,
+            scope: token.and_then(|token| token.contents.scope_permissions()),
+            conditional_permissions,
+            client: ClientContext::default(),
+            authentication_token: token.map(|token| token.header.id),
+            authentication_token_validity: token
+                .map(|token| AuthenticationTokenValidity::new(token.contents.expires_at)),
         };
         let authentication = Arc::new(AuthenticatedSession {
             storage: Arc::downgrade(&self.data),
@@ -835,18 +1017,21 @@ impl StorageInstance {
             instance: self.clone(),
             authentication: Some(authentication),
             effective_session: Some(Arc::new(session)),
+            client_address: None,
         })
     }
 
     fn assume_role(
         &self,
         role: CollectionDocument<Role>,
+        token: Option<&CollectionDocument<AuthenticationToken>>,
         admin: &Database,
     ) -> Result<Storage, bonsaidb_core::Error> {
         let permissions = role.contents.effective_permissions(
             admin,
             &admin.storage().instance.data.authenticated_permissions,
         )?;
+        let conditional_permissions = role.contents.conditional_permissions(admin)?;
 
         let mut sessions = self.data.sessions.write();
         sessions.last_session_id += 1;
@@ -858,6 +1043,12 @@ impl StorageInstance {
                 name: role.contents.name,
             })),
             permissions,
+            scope: token.and_then(|token| token.contents.scope_permissions()),
+            conditional_permissions,
+            client: ClientContext::default(),
+            authentication_token: token.map(|token| token.header.id),
+            authentication_token_validity: token
+                .map(|token| AuthenticationTokenValidity::new(token.contents.expires_at)),
         };
         let authentication = Arc::new(AuthenticatedSession {
             storage: Arc::downgrade(&self.data),
@@ -869,6 +1060,7 @@ impl StorageInstance {
             instance: self.clone(),
             authentication: Some(authentication),
             effective_session: Some(Arc::new(session)),
+            client_address: None,
         })
     }
 
@@ -1100,6 +1292,7 @@ impl StorageConnection for StorageInstance {
         user: U,
         password: bonsaidb_core::connection::SensitiveString,
     ) -> Result<(), bonsaidb_core::Error> {
+        self.data.password_policy.validate(&password)?;
         let admin = self.admin();
         let mut user = User::load(user, &admin)?.ok_or(bonsaidb_core::Error::UserNotFound)?;
         user.contents.argon_hash = Some(self.data.argon.hash(user.header.id, password)?);
@@ -1127,12 +1320,12 @@ impl StorageConnection for StorageInstance {
             IdentityReference::User(user) => {
                 let user =
                     User::load(user, &admin)?.ok_or(bonsaidb_core::Error::InvalidCredentials)?;
-                self.assume_user(user, &admin).map(Storage::from)
+                self.assume_user(user, None, &admin).map(Storage::from)
             }
             IdentityReference::Role(role) => {
                 let role =
                     Role::load(role, &admin)?.ok_or(bonsaidb_core::Error::InvalidCredentials)?;
-                self.assume_role(role, &admin).map(Storage::from)
+                self.assume_role(role, None, &admin).map(Storage::from)
             }
             _ => Err(bonsaidb_core::Error::InvalidCredentials),
         }
@@ -1195,7 +1388,7 @@ impl StorageConnection for StorageInstance {
         user: U,
         role: G,
     ) -> Result<(), bonsaidb_core::Error> {
-        self.update_user_with_named_id::<PermissionGroup, _, _, _>(user, role, |user, role_id| {
+        self.update_user_with_named_id::<Role, _, _, _>(user, role, |user, role_id| {
             Ok(Self::add_role_to_user_inner(user, role_id))
         })
     }
@@ -1215,12 +1408,299 @@ impl StorageConnection for StorageInstance {
             Ok(Self::remove_role_from_user_inner(user, role_id))
         })
     }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn create_permission_group(&self, group: PermissionGroup) -> Result<u64, bonsaidb_core::Error> {
+        let result = self.admin().collection::<PermissionGroup>().push(&group)?;
+        Ok(result.id)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let mut group = PermissionGroup::load(permission_group, &admin)?
+            .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?;
+        group.contents = updated;
+        group.update(&admin)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let group = PermissionGroup::load(permission_group, &admin)?
+            .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?;
+        for mut user in User::all(&admin).query()? {
+            if Self::remove_permission_group_from_user_inner(&mut user, group.header.id) {
+                user.update(&admin)?;
+            }
+        }
+        for mut role in Role::all(&admin).query()? {
+            let old_len = role.contents.groups.len();
+            role.contents.groups.retain(|id| id != &group.header.id);
+            if old_len != role.contents.groups.len() {
+                role.update(&admin)?;
+            }
+        }
+        group.delete(&admin)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn create_role(&self, role: Role) -> Result<u64, bonsaidb_core::Error> {
+        let result = self.admin().collection::<Role>().push(&role)?;
+        Ok(result.id)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let mut role = Role::load(role, &admin)?.ok_or(bonsaidb_core::Error::RoleNotFound)?;
+        role.contents = updated;
+        role.update(&admin)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let role = Role::load(role, &admin)?.ok_or(bonsaidb_core::Error::RoleNotFound)?;
+        for mut user in User::all(&admin).query()? {
+            if Self::remove_role_from_user_inner(&mut user, role.header.id) {
+                user.update(&admin)?;
+            }
+        }
+        role.delete(&admin)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let user = User::load(user, &admin)?.ok_or(bonsaidb_core::Error::UserNotFound)?;
+        let key = ByIdentity::key(IdentityId::User(user.header.id));
+        Ok(admin
+            .view::<ByIdentity>()
+            .with_key(&key)
+            .query_with_collection_docs()?
+            .documents
+            .into_values()
+            .map(AuthenticationTokenSummary::from)
+            .collect())
+    }
+
+    #[cfg(feature = "token-authentication")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: AuthenticationTokenOptions,
+    ) -> Result<CreatedAuthenticationToken, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let token = AuthenticationToken::create_with_options(&identity, &options, &admin)?;
+        Ok(CreatedAuthenticationToken {
+            id: token.header.id,
+            token: token.contents.token,
+        })
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        AuthenticationToken::get(&token_id, &admin)?
+            .ok_or(bonsaidb_core::Error::AuthenticationTokenNotFound)?
+            .delete(&admin)?;
+
+        // End all sessions that were authenticated using this token. Revoking
+        // the sessions' shared validity denies further actions by any
+        // existing handle to them. The sessions are dropped after the lock is
+        // released, as dropping an AuthenticatedSession acquires the lock
+        // itself.
+        let ended_sessions = {
+            let mut sessions = self.data.sessions.write();
+            let ended_ids = sessions
+                .sessions
+                .iter()
+                .filter_map(|(id, session)| {
+                    let session = session.session.lock();
+                    if session.authentication_token == Some(token_id) {
+                        if let Some(validity) = &session.authentication_token_validity {
+                            validity.revoke();
+                        }
+                        Some(*id)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            ended_ids
+                .into_iter()
+                .filter_map(|id| sessions.sessions.remove(&id))
+                .collect::<Vec<_>>()
+        };
+        drop(ended_sessions);
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let action = NamedAction(action);
+        let (identity, explanation) = match identity {
+            IdentityReference::User(user) => {
+                let user = User::load(user, &admin)?.ok_or(bonsaidb_core::Error::UserNotFound)?;
+                let explanation = user.contents.explain_permission(
+                    &admin,
+                    &self.data.authenticated_permissions,
+                    &resource_name,
+                    &action,
+                    &client,
+                )?;
+                (IdentityId::User(user.header.id), explanation)
+            }
+            IdentityReference::Role(role) => {
+                let role = Role::load(role, &admin)?.ok_or(bonsaidb_core::Error::RoleNotFound)?;
+                let explanation = role.contents.explain_permission(
+                    &admin,
+                    &self.data.authenticated_permissions,
+                    &resource_name,
+                    &action,
+                    &client,
+                )?;
+                (IdentityId::Role(role.header.id), explanation)
+            }
+            _ => return Err(bonsaidb_core::Error::InvalidCredentials),
+        };
+
+        self.explain_document_ownership(identity, resource_name.as_ref(), &action, explanation)
+    }
+}
+
+impl StorageInstance {
+    /// Adds [`StatementSource::Owner`] to `explanation` when `resource_name`
+    /// names a document owned by `identity` and `action` is one that owners
+    /// are permitted to perform.
+    fn explain_document_ownership(
+        &self,
+        identity: IdentityId,
+        resource_name: &[Identifier<'_>],
+        action: &NamedAction,
+        mut explanation: PermissionExplanation,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        let owner_action = [
+            DocumentAction::Get,
+            DocumentAction::Update,
+            DocumentAction::Overwrite,
+            DocumentAction::Delete,
+        ]
+        .into_iter()
+        .any(|owner_action| {
+            BonsaiAction::Database(DatabaseAction::Document(owner_action)).name() == action.0
+        });
+        if !owner_action {
+            return Ok(explanation);
+        }
+        let [_, Identifier::String(database), Identifier::String(collection), document, id] =
+            resource_name
+        else {
+            return Ok(explanation);
+        };
+        let Ok(collection) = CollectionName::from_str(collection) else {
+            return Ok(explanation);
+        };
+        if document != &Identifier::from("document")
+            || collection_resource_name(&**database, &collection).as_ref() != &resource_name[..3]
+        {
+            return Ok(explanation);
+        }
+        let id = match id {
+            Identifier::Integer(id) => DocumentId::from_u64(*id),
+            Identifier::String(id) => DocumentId::try_from(id.as_bytes())?,
+            Identifier::Bytes(id) => DocumentId::try_from(&id[..])?,
+            Identifier::Any => return Ok(explanation),
+        };
+
+        let database = match self.database_without_schema(database, None, None) {
+            Ok(database) => database,
+            Err(Error::Core(bonsaidb_core::Error::DatabaseNotFound(_))) => return Ok(explanation),
+            Err(err) => return Err(err.into()),
+        };
+        if database.document_owner(&collection, &id)? == Some(identity) {
+            // Owners are permitted unless the action is denied.
+            let denied = explanation
+                .statements
+                .iter()
+                .any(|statement| statement.effect == Effect::Deny && statement.applies);
+            explanation.allowed |= !denied;
+            explanation.statements.push(ExplainedStatement {
+                source: StatementSource::Owner,
+                effect: Effect::Allow,
+                statement: None,
+                conditions: None,
+                applies: true,
+            });
+        }
+
+        Ok(explanation)
+    }
+}
+
+/// An [`Action`] identified only by its name, used for actions received by
+/// name, such as when explaining permissions or scoping tokens from the
+/// command line.
+#[derive(Debug)]
+pub(crate) struct NamedAction(pub ActionName);
+
+impl Action for NamedAction {
+    fn name(&self) -> ActionName {
+        self.0.clone()
+    }
 }
 
 impl HasSession for Storage {
     fn session(&self) -> Option<&Session> {
         self.effective_session.as_deref()
     }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let Some(session) = self.session() else {
+            return Ok(());
+        };
+        let resource_name = resource_name.as_ref();
+        let result = session.check_permission(resource_name, action);
+        self.instance.record_audit_entry(
+            session,
+            self.client_address,
+            resource_name,
+            action,
+            result.is_ok(),
+        );
+        result
+    }
 }
 
 impl StorageConnection for Storage {
@@ -1348,6 +1828,14 @@ impl StorageConnection for Storage {
             }
             #[cfg(feature = "token-authentication")]
             bonsaidb_core::connection::Authentication::TokenChallengeResponse(_) => {}
+            bonsaidb_core::connection::Authentication::External(_) => {
+                self.check_permission(
+                    bonsaidb_resource_name(),
+                    &BonsaiAction::Server(ServerAction::Authenticate(
+                        bonsaidb_core::connection::AuthenticationMethod::External,
+                    )),
+                )?;
+            }
         }
         self.instance.authenticate_inner(
             authentication,
@@ -1372,7 +1860,7 @@ impl StorageConnection for Storage {
                     user_resource_name(user.header.id),
                     &BonsaiAction::Server(ServerAction::AssumeIdentity),
                 )?;
-                self.instance.assume_user(user, &admin)
+                self.instance.assume_user(user, None, &admin)
             }
             IdentityReference::Role(role) => {
                 let admin = self.admin();
@@ -1382,7 +1870,7 @@ impl StorageConnection for Storage {
                     role_resource_name(role.header.id),
                     &BonsaiAction::Server(ServerAction::AssumeIdentity),
                 )?;
-                self.instance.assume_role(role, &admin)
+                self.instance.assume_role(role, None, &admin)
             }
 
             _ => Err(bonsaidb_core::Error::InvalidCredentials),
@@ -1454,7 +1942,7 @@ impl StorageConnection for Storage {
         role: G,
     ) -> Result<(), bonsaidb_core::Error> {
         self.instance
-            .update_user_with_named_id::<PermissionGroup, _, _, _>(user, role, |user, role_id| {
+            .update_user_with_named_id::<Role, _, _, _>(user, role, |user, role_id| {
                 self.check_permission(
                     user_resource_name(user.header.id),
                     &BonsaiAction::Server(ServerAction::ModifyUserRoles),
@@ -1482,6 +1970,142 @@ impl StorageConnection for Storage {
                 Ok(StorageInstance::remove_role_from_user_inner(user, role_id))
             })
     }
+
+    fn create_permission_group(&self, group: PermissionGroup) -> Result<u64, bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::CreatePermissionGroup),
+        )?;
+        self.instance.create_permission_group(group)
+    }
+
+    fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let group_id = permission_group
+            .name()?
+            .id::<PermissionGroup, _>(&admin)?
+            .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?;
+        self.check_permission(
+            permission_group_resource_name(group_id),
+            &BonsaiAction::Server(ServerAction::ModifyPermissionGroup),
+        )?;
+        self.instance.update_permission_group(group_id, updated)
+    }
+
+    fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let group_id = permission_group
+            .name()?
+            .id::<PermissionGroup, _>(&admin)?
+            .ok_or(bonsaidb_core::Error::PermissionGroupNotFound)?;
+        self.check_permission(
+            permission_group_resource_name(group_id),
+            &BonsaiAction::Server(ServerAction::DeletePermissionGroup),
+        )?;
+        self.instance.delete_permission_group(group_id)
+    }
+
+    fn create_role(&self, role: Role) -> Result<u64, bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::CreateRole),
+        )?;
+        self.instance.create_role(role)
+    }
+
+    fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let role_id = role
+            .name()?
+            .id::<Role, _>(&admin)?
+            .ok_or(bonsaidb_core::Error::RoleNotFound)?;
+        self.check_permission(
+            role_resource_name(role_id),
+            &BonsaiAction::Server(ServerAction::ModifyRole),
+        )?;
+        self.instance.update_role(role_id, updated)
+    }
+
+    fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let admin = self.admin();
+        let role_id = role
+            .name()?
+            .id::<Role, _>(&admin)?
+            .ok_or(bonsaidb_core::Error::RoleNotFound)?;
+        self.check_permission(
+            role_resource_name(role_id),
+            &BonsaiAction::Server(ServerAction::DeleteRole),
+        )?;
+        self.instance.delete_role(role_id)
+    }
+
+    fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let user_id = user
+            .name()?
+            .id::<User, _>(&admin)?
+            .ok_or(bonsaidb_core::Error::UserNotFound)?;
+        self.check_permission(
+            user_resource_name(user_id),
+            &BonsaiAction::Server(ServerAction::ListAuthenticationTokens),
+        )?;
+        self.instance.list_authentication_tokens(user_id)
+    }
+
+    #[cfg(feature = "token-authentication")]
+    fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: AuthenticationTokenOptions,
+    ) -> Result<CreatedAuthenticationToken, bonsaidb_core::Error> {
+        let identity_resource_name = self.identity_resource_name(&identity)?;
+        self.check_permission(
+            identity_resource_name,
+            &BonsaiAction::Server(ServerAction::CreateAuthenticationToken),
+        )?;
+        self.instance.create_authentication_token(identity, options)
+    }
+
+    fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        self.check_permission(
+            authentication_token_resource_name(token_id),
+            &BonsaiAction::Server(ServerAction::RevokeAuthenticationToken),
+        )?;
+        self.instance.revoke_authentication_token(token_id)
+    }
+
+    fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        let identity_resource_name = self.identity_resource_name(&identity)?;
+        self.check_permission(
+            identity_resource_name,
+            &BonsaiAction::Server(ServerAction::ExplainPermission),
+        )?;
+        self.instance
+            .explain_permission(identity, resource_name, action, client)
+    }
 }
 
 #[test]
@@ -1651,6 +2275,7 @@ impl StorageNonBlocking for Storage {
                 instance: self.instance.clone(),
                 authentication: None,
                 effective_session: Some(Arc::new(session)),
+                client_address: self.client_address,
             });
         };
 
@@ -1668,12 +2293,20 @@ impl StorageNonBlocking for Storage {
             id: authentication_session.id,
             authentication: authentication_session.authentication.clone(),
             permissions: effective_permissions,
+            scope: authentication_session.scope.clone(),
+            conditional_permissions: authentication_session.conditional_permissions.clone(),
+            client: session.client,
+            authentication_token: authentication_session.authentication_token,
+            authentication_token_validity: authentication_session
+                .authentication_token_validity
+                .clone(),
         };
 
         Ok(Self {
             instance: self.instance.clone(),
             authentication: Some(authentication.clone()),
             effective_session: Some(Arc::new(effective_session)),
+            client_address: self.client_address,
         })
     }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
@@ -6,9 +7,10 @@ use argon2::password_hash::{ParamsString, SaltString};
 use argon2::{Algorithm, Argon2, AssociatedData, Block, ParamsBuilder, PasswordHash, Version};
 use bonsaidb_core::connection::SensitiveString;
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 use rand::{thread_rng, CryptoRng, Rng};
 
-use crate::config::{ArgonConfiguration, ArgonParams};
+use crate::config::{ArgonConfiguration, ArgonParams, LoginLockout};
 use crate::Error;
 
 #[derive(Debug)]
@@ -256,6 +258,117 @@ impl HashingThread {
     }
 }
 
+/// Tracks consecutive failed password authentication attempts for each user.
+#[derive(Debug)]
+pub struct LoginAttempts {
+    lockout: LoginLockout,
+    users: Mutex<HashMap<u64, FailedLogins>>,
+}
+
+#[derive(Debug, Default)]
+struct FailedLogins {
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+impl LoginAttempts {
+    pub fn new(lockout: LoginLockout) -> Self {
+        Self {
+            lockout,
+            users: Mutex::default(),
+        }
+    }
+
+    /// Reserves an attempt to authenticate as user `id`, returning an error if
+    /// the user is currently locked out.
+    ///
+    /// The attempt is counted as a failure until it is finished, which ensures
+    /// that attempts verified in parallel can't exceed the allowed number of
+    /// failures.
+    pub fn reserve(&self, id: u64) -> Result<ReservedLoginAttempt<'_>, bonsaidb_core::Error> {
+        let Some(max_failed_attempts) = self.lockout.max_failed_attempts else {
+            return Ok(ReservedLoginAttempt {
+                attempts: self,
+                id,
+                counted: false,
+            });
+        };
+
+        let mut users = self.users.lock();
+        let failed = users.entry(id).or_default();
+        let now = Instant::now();
+        if let Some(locked_until) = failed.locked_until {
+            if locked_until > now {
+                return Err(bonsaidb_core::Error::UserLockedOut {
+                    retry_after: locked_until - now,
+                });
+            }
+
+            *failed = FailedLogins::default();
+        }
+
+        failed.count += 1;
+        if failed.count >= max_failed_attempts {
+            failed.locked_until = Some(now + self.lockout.duration);
+        }
+
+        Ok(ReservedLoginAttempt {
+            attempts: self,
+            id,
+            counted: true,
+        })
+    }
+
+    fn reset(&self, id: u64) {
+        self.users.lock().remove(&id);
+    }
+
+    fn roll_back(&self, id: u64) {
+        let Some(max_failed_attempts) = self.lockout.max_failed_attempts else {
+            return;
+        };
+        let mut users = self.users.lock();
+        if let Some(failed) = users.get_mut(&id) {
+            failed.count = failed.count.saturating_sub(1);
+            if failed.count < max_failed_attempts {
+                failed.locked_until = None;
+            }
+        }
+    }
+}
+
+/// An authentication attempt reserved by [`LoginAttempts::reserve`]. If the
+/// attempt is dropped without being finished, the reservation is rolled back.
+#[must_use]
+pub struct ReservedLoginAttempt<'a> {
+    attempts: &'a LoginAttempts,
+    id: u64,
+    counted: bool,
+}
+
+impl ReservedLoginAttempt<'_> {
+    /// Finishes this attempt. A successful attempt resets the user's failed
+    /// attempts, and only [`InvalidCredentials`](bonsaidb_core::Error::InvalidCredentials)
+    /// errors count as a failed attempt. Any other error rolls back the
+    /// reservation.
+    pub fn finish<T>(mut self, result: &Result<T, bonsaidb_core::Error>) {
+        match result {
+            Ok(_) => self.attempts.reset(self.id),
+            Err(bonsaidb_core::Error::InvalidCredentials) => {}
+            Err(_) => return,
+        }
+        self.counted = false;
+    }
+}
+
+impl Drop for ReservedLoginAttempt<'_> {
+    fn drop(&mut self) {
+        if self.counted {
+            self.attempts.roll_back(self.id);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct HashRequest {
     id: u64,
@@ -304,3 +417,47 @@ fn basic_test() {
         thread.join().unwrap();
     }
 }
+
+#[test]
+fn lockout_test() {
+    let attempts = LoginAttempts::new(LoginLockout {
+        max_failed_attempts: Some(2),
+        duration: Duration::from_secs(60),
+    });
+
+    attempts
+        .reserve(1)
+        .unwrap()
+        .finish::<()>(&Err(bonsaidb_core::Error::InvalidCredentials));
+    // A successful login resets the count.
+    attempts.reserve(1).unwrap().finish(&Ok(()));
+    attempts
+        .reserve(1)
+        .unwrap()
+        .finish::<()>(&Err(bonsaidb_core::Error::InvalidCredentials));
+    // Errors other than invalid credentials aren't counted.
+    attempts
+        .reserve(1)
+        .unwrap()
+        .finish::<()>(&Err(bonsaidb_core::Error::UserNotFound));
+    // Attempts that are still being verified count towards the limit.
+    let pending = attempts.reserve(1).unwrap();
+    assert!(matches!(
+        attempts.reserve(1),
+        Err(bonsaidb_core::Error::UserLockedOut { retry_after }) if retry_after <= Duration::from_secs(60)
+    ));
+    // Other users aren't affected.
+    attempts.reserve(2).unwrap().finish(&Ok(()));
+    // Abandoning the pending attempt releases its reservation.
+    drop(pending);
+    attempts.reserve(1).unwrap().finish(&Ok(()));
+
+    let unlimited = LoginAttempts::new(LoginLockout::default());
+    for _ in 0..10 {
+        unlimited
+            .reserve(1)
+            .unwrap()
+            .finish::<()>(&Err(bonsaidb_core::Error::InvalidCredentials));
+    }
+    unlimited.reserve(1).unwrap().finish(&Ok(()));
+}
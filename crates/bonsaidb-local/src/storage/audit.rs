@@ -0,0 +1,221 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant, SystemTime};
+
+use bonsaidb_core::admin::audit::ByTimestamp;
+use bonsaidb_core::admin::AuditEntry;
+use bonsaidb_core::connection::{
+    Connection, Identity, LowLevelConnection, Session, StorageConnection,
+};
+use bonsaidb_core::key::time::TimestampAsNanoseconds;
+use bonsaidb_core::permissions::{canonical_address, Action, ClientTransport, Identifier};
+use bonsaidb_core::transaction::{Operation, Transaction};
+use flume::RecvTimeoutError;
+
+use crate::Storage;
+
+/// The minimum amount of time between removing expired audit entries.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The maximum number of audit entries waiting to be written. Once full,
+/// recording an entry waits for the writer to catch up.
+const PENDING_ENTRY_LIMIT: usize = 16_384;
+
+/// A request sent to the audit log writer thread.
+#[derive(Debug)]
+pub(crate) enum AuditLogCommand {
+    /// Write an entry to the admin database.
+    Record(AuditEntry),
+    /// Notify the sender once all previously recorded entries are written.
+    Flush(flume::Sender<()>),
+}
+
+/// Creates the channel used to send [`AuditLogCommand`]s to the audit log
+/// writer thread.
+pub(crate) fn audit_log_channel() -> (
+    flume::Sender<AuditLogCommand>,
+    flume::Receiver<AuditLogCommand>,
+) {
+    flume::bounded(PENDING_ENTRY_LIMIT)
+}
+
+/// Spawns the thread that writes recorded audit entries to the admin
+/// database, batching entries that are recorded while a write is in progress.
+/// Expired entries are removed periodically when `retention` is set.
+///
+/// The thread exits once the storage has been dropped.
+pub(crate) fn spawn_audit_log_writer(
+    data: Weak<super::Data>,
+    commands: flume::Receiver<AuditLogCommand>,
+    retention: Option<Duration>,
+) {
+    std::thread::Builder::new()
+        .name(String::from("audit-log-writer"))
+        .spawn(move || audit_log_writer(&data, &commands, retention))
+        .unwrap();
+}
+
+fn audit_log_writer(
+    data: &Weak<super::Data>,
+    commands: &flume::Receiver<AuditLogCommand>,
+    retention: Option<Duration>,
+) {
+    let mut pruned_at = None::<Instant>;
+    loop {
+        let first = match commands.recv_timeout(PRUNE_INTERVAL) {
+            Ok(command) => Some(command),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut transaction = Transaction::new();
+        let mut flushed = Vec::new();
+        for command in first.into_iter().chain(commands.try_iter()) {
+            match command {
+                AuditLogCommand::Record(entry) => {
+                    match Operation::push_serialized::<AuditEntry>(&entry) {
+                        Ok(operation) => transaction.push(operation),
+                        Err(err) => log::error!("[audit] error serializing entry: {err}"),
+                    }
+                }
+                AuditLogCommand::Flush(flushed_sender) => flushed.push(flushed_sender),
+            }
+        }
+
+        let Some(data) = data.upgrade() else {
+            break;
+        };
+        let instance = super::StorageInstance { data };
+        if !transaction.operations.is_empty() {
+            if let Err(err) = instance.admin().apply_transaction(transaction) {
+                log::error!("[audit] error writing entries: {err}");
+            }
+        }
+
+        if let Some(retention) = retention {
+            if pruned_at.map_or(true, |pruned_at| pruned_at.elapsed() >= PRUNE_INTERVAL) {
+                pruned_at = Some(Instant::now());
+                if let Err(err) = instance.prune_audit_log(retention) {
+                    log::error!("[audit] error removing expired entries: {err}");
+                }
+            }
+        }
+
+        for flushed in flushed {
+            drop(flushed.send(()));
+        }
+    }
+}
+
+impl super::StorageInstance {
+    /// Records the result of `session` checking whether it is allowed to
+    /// perform `action` on `resource_name`, if the audit log is enabled.
+    ///
+    /// Entries are written to the admin database by a background thread.
+    pub(crate) fn record_audit_entry<P: Action>(
+        &self,
+        session: &Session,
+        address: Option<SocketAddr>,
+        resource_name: &[Identifier<'_>],
+        action: &P,
+        allowed: bool,
+    ) {
+        let Some(audit_log_writer) = &self.data.audit_log_writer else {
+            return;
+        };
+
+        let entry = AuditEntry {
+            timestamp: TimestampAsNanoseconds::now(),
+            session: session.id,
+            identity: session.identity().map(Identity::id),
+            action: action.name().to_string(),
+            resource: resource_name
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("."),
+            allowed,
+            address,
+        };
+        // The writer only stops after the storage has been dropped.
+        drop(audit_log_writer.send(AuditLogCommand::Record(entry)));
+    }
+
+    fn prune_audit_log(&self, retention: Duration) -> Result<u64, bonsaidb_core::Error> {
+        let Some(cutoff) = SystemTime::now()
+            .checked_sub(retention)
+            .and_then(|cutoff| TimestampAsNanoseconds::try_from(cutoff).ok())
+        else {
+            return Ok(0);
+        };
+
+        self.admin()
+            .view::<ByTimestamp>()
+            .with_key_range(..cutoff)
+            .delete_docs()
+    }
+}
+
+impl Storage {
+    /// Removes all [`AuditEntry`s](AuditEntry) older than the configured
+    /// [`AuditLog::retention`](crate::config::AuditLog::retention). Expired
+    /// entries are also removed periodically in the background.
+    ///
+    /// Returns the number of entries removed.
+    pub fn prune_audit_log(&self) -> Result<u64, bonsaidb_core::Error> {
+        match self.instance.data.audit_log.retention {
+            Some(retention) => self.instance.prune_audit_log(retention),
+            None => Ok(0),
+        }
+    }
+
+    /// Waits until every [`AuditEntry`] recorded before this call has been
+    /// written to the admin database. Entries are written in the background,
+    /// so they may not be visible immediately after the permission check that
+    /// recorded them.
+    pub fn flush_audit_log(&self) {
+        let Some(audit_log_writer) = &self.instance.data.audit_log_writer else {
+            return;
+        };
+        let (flushed_sender, flushed) = flume::bounded(1);
+        if audit_log_writer
+            .send(AuditLogCommand::Flush(flushed_sender))
+            .is_ok()
+        {
+            drop(flushed.recv());
+        }
+    }
+
+    /// Returns a copy of this storage that records `address` as the client
+    /// address of its [`AuditEntry`s](AuditEntry). The address is also used
+    /// when evaluating the address conditions of
+    /// [`ConditionalStatement`s](bonsaidb_core::permissions::ConditionalStatement).
+    pub fn with_client_address(&self, address: SocketAddr) -> Self {
+        let address = SocketAddr::new(canonical_address(address.ip()), address.port());
+        let mut storage = self.clone();
+        storage.client_address = Some(address);
+        if let Some(session) = &mut storage.effective_session {
+            Arc::make_mut(session).client.address = Some(address.ip());
+        }
+        storage
+    }
+
+    /// Returns a copy of this storage whose session reports `transport` as
+    /// the transport the client is connected with. The transport is used when
+    /// evaluating the transport conditions of
+    /// [`ConditionalStatement`s](bonsaidb_core::permissions::ConditionalStatement).
+    pub fn with_client_transport(&self, transport: ClientTransport) -> Self {
+        let mut storage = self.clone();
+        if let Some(session) = &mut storage.effective_session {
+            Arc::make_mut(session).client.transport = Some(transport);
+        }
+        storage
+    }
+
+    /// Returns the client address set by
+    /// [`with_client_address()`](Self::with_client_address), if any.
+    #[must_use]
+    pub const fn client_address(&self) -> Option<SocketAddr> {
+        self.client_address
+    }
+}
@@ -120,6 +120,16 @@ impl Storage {
                     &document.contents,
                 )?;
             }
+            let version = database.migration_version(collection)?;
+            if version > 0 {
+                location.store(
+                    &schema,
+                    database.name(),
+                    "_migrations",
+                    &collection_name,
+                    &version.to_be_bytes(),
+                )?;
+            }
             for ((namespace, key), entry) in database.all_key_value_entries()? {
                 let full_name = format!("{}._key._{key}", namespace.as_deref().unwrap_or(""));
                 location.store(
@@ -167,6 +177,27 @@ impl Storage {
         }
         database.apply_transaction(transaction)?;
 
+        let stored_versions = location.list_stored(&schema, database.name(), "_migrations")?;
+        for collection in database.schematic().migrated_collections() {
+            // Collections missing from the backup were backed up before any
+            // migrations were applied to them.
+            let collection_name = collection.encoded();
+            let version = if stored_versions.contains(&collection_name) {
+                let version =
+                    location.load(&schema, database.name(), "_migrations", &collection_name)?;
+                let version = <[u8; 8]>::try_from(version.as_slice()).map_err(|_| {
+                    Error::other("bonsaidb-local", "invalid migration version in backup")
+                })?;
+                u64::from_be_bytes(version)
+            } else {
+                0
+            };
+            database.set_migration_version(collection, version)?;
+        }
+        if database.storage.instance.run_migrations_on_database_open() {
+            database.migrate()?;
+        }
+
         for full_key in location.list_stored(&schema, database.name(), "_kv")? {
             if let Some((namespace, key)) = full_key.split_once("._key._") {
                 let entry = location.load(&schema, database.name(), "_kv", &full_key)?;
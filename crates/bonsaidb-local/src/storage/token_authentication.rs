@@ -4,6 +4,7 @@ use bonsaidb_core::admin::{AuthenticationToken, Role, User};
 use bonsaidb_core::connection::{
     IdentityId, Session, SessionAuthentication, SessionId, TokenChallengeAlgorithm,
 };
+use bonsaidb_core::document::CollectionDocument;
 use bonsaidb_core::key::time::TimestampAsNanoseconds;
 use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::SerializedCollection;
@@ -32,6 +33,9 @@ impl super::StorageInstance {
         }
         let token = AuthenticationToken::get(&id, admin)?
             .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+        if token.contents.is_expired() {
+            return Err(bonsaidb_core::Error::InvalidCredentials);
+        }
         AuthenticationToken::check_request_time(
             request_time,
             request_time_check,
@@ -56,6 +60,7 @@ impl super::StorageInstance {
                 server_timestamp: TimestampAsNanoseconds::now(),
             },
             permissions: Permissions::default(), /* This session will have no permissions until it finishes token authentication */
+            ..Session::default()
         };
         let authentication = Arc::new(AuthenticatedSession {
             storage: Arc::downgrade(&self.data),
@@ -67,6 +72,7 @@ impl super::StorageInstance {
             instance: self.clone(),
             authentication: Some(authentication),
             effective_session: Some(Arc::new(session)),
+            client_address: None,
         })
     }
 
@@ -94,19 +100,24 @@ impl super::StorageInstance {
             } => {
                 let token = AuthenticationToken::get(id, admin)?
                     .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+                if token.contents.is_expired() {
+                    return Err(bonsaidb_core::Error::InvalidCredentials);
+                }
                 token
                     .contents
                     .validate_challenge(*algorithm, *server_timestamp, nonce, hash)?;
+                Self::record_token_use(&token, admin);
+
                 match token.contents.identity {
                     IdentityId::User(id) => {
                         let user = User::get(&id, admin)?
                             .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
-                        self.assume_user(user, admin)
+                        self.assume_user(user, Some(&token), admin)
                     }
                     IdentityId::Role(id) => {
                         let role = Role::get(&id, admin)?
                             .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
-                        self.assume_role(role, admin)
+                        self.assume_role(role, Some(&token), admin)
                     }
                     _ => Err(bonsaidb_core::Error::InvalidCredentials),
                 }
@@ -116,4 +127,14 @@ impl super::StorageInstance {
             }
         }
     }
+
+    /// Updates the time `token` was last used. This is informational only, so
+    /// failing to record it -- for example, because another session
+    /// authenticated with the same token concurrently -- must not prevent
+    /// authentication.
+    fn record_token_use(token: &CollectionDocument<AuthenticationToken>, admin: &Database) {
+        let mut token = token.clone();
+        token.contents.last_used_at = Some(TimestampAsNanoseconds::now());
+        drop(token.update(admin));
+    }
 }
@@ -1,10 +1,14 @@
 use std::borrow::Cow;
+use std::time::Instant;
 
 use bonsaidb_core::connection::Connection;
 use bonsaidb_core::schema::CollectionName;
 use nebari::tree::{Root, Unversioned, Versioned};
 
+use crate::database::drift::COLLECTION_KEYS_TREE;
 use crate::database::keyvalue::KEY_TREE;
+use crate::database::migrations::MIGRATIONS_TREE;
+use crate::database::ownership::document_owners_tree_name;
 use crate::database::{document_tree_name, DatabaseNonBlocking};
 use crate::tasks::{Job, Keyed, Task};
 use crate::views::{
@@ -74,6 +78,10 @@ impl Target {
                 for collection in database.schematic().collections() {
                     gather_collection_trees(database, collection, &mut trees);
                 }
+                trees.push(Target::UnversionedTree(COLLECTION_KEYS_TREE.to_string()));
+                if database.schematic().migrated_collections().next().is_some() {
+                    trees.push(Target::UnversionedTree(MIGRATIONS_TREE.to_string()));
+                }
                 trees.push(Target::KeyValue);
                 compact_trees(database, trees)
             }
@@ -87,7 +95,15 @@ impl Job for Compactor {
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn execute(&mut self) -> Result<Self::Output, Error> {
-        self.compaction.target.clone().compact(&self.database)
+        let started_at = Instant::now();
+        self.compaction.target.clone().compact(&self.database)?;
+        self.database
+            .storage
+            .instance
+            .metrics()
+            .compactions
+            .record(started_at.elapsed());
+        Ok(())
     }
 }
 
@@ -104,6 +120,15 @@ fn gather_collection_trees(
 ) {
     trees.push(Target::VersionedTree(document_tree_name(collection)));
     trees.push(Target::UnversionedTree(view_versions_tree_name(collection)));
+    if database
+        .data
+        .schema
+        .collection_has_document_ownership(collection)
+    {
+        trees.push(Target::UnversionedTree(document_owners_tree_name(
+            collection,
+        )));
+    }
 
     for view in database.data.schema.views_in_collection(collection) {
         let name = view.view_name();
@@ -2,17 +2,30 @@ mod compatibility;
 
 use std::time::Duration;
 
-use bonsaidb_core::connection::{AccessPolicy, Connection};
-use bonsaidb_core::permissions::{Permissions, Statement};
+use bonsaidb_core::admin::AuditEntry;
+use bonsaidb_core::connection::{
+    AccessPolicy, Connection, IdentityId, IdentityReference, LowLevelConnection, Sort,
+    StorageConnection,
+};
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::permissions::bonsai::{
+    BonsaiAction, DatabaseAction, DocumentAction, ViewAction,
+};
+use bonsaidb_core::permissions::{Action, Permissions, Statement};
+use bonsaidb_core::schema::{
+    Collection, Schema, SchemaName, SchemaSummary, Schematic, SerializationFormat,
+    SerializedCollection, View,
+};
 #[cfg(feature = "encryption")]
 use bonsaidb_core::test_util::EncryptedBasic;
 use bonsaidb_core::test_util::{
     Basic, BasicByBrokenParentId, BasicByParentId, BasicCollectionWithNoViews,
     BasicCollectionWithOnlyBrokenParentId, BasicSchema, HarnessTest, TestDirectory,
 };
+use pot::Value;
 
 use crate::config::{Builder, StorageConfiguration};
-use crate::{Database, Storage};
+use crate::{CollectionMigration, Database, Storage};
 
 macro_rules! define_local_suite {
     ($name:ident) => {
@@ -318,3 +331,814 @@ fn expiration_after_close() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[derive(Debug)]
+struct MigratedBasic;
+
+impl Schema for MigratedBasic {
+    fn schema_name() -> SchemaName {
+        // Migrations are added to an existing schema, so the name must match.
+        SchemaName::from(Basic::collection_name())
+    }
+
+    fn define_collections(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+        schema.define_collection::<Basic>()?;
+        schema.define_migration::<Basic, _>("uppercase values", |mut doc| {
+            let mut basic = Basic::document_contents(&doc)?;
+            basic.value = basic.value.to_uppercase();
+            Basic::set_document_contents(&mut doc, basic)?;
+            Ok(Some(doc))
+        });
+        schema.define_migration::<Basic, _>("remove deleted", |doc| {
+            if Basic::document_contents(&doc)?.value == "DELETE" {
+                Ok(None)
+            } else {
+                Ok(Some(doc))
+            }
+        });
+        Ok(())
+    }
+}
+
+fn migrated_values(db: &Database) -> anyhow::Result<Vec<String>> {
+    Ok(Basic::all(db)
+        .query()?
+        .into_iter()
+        .map(|doc| doc.contents.value)
+        .collect())
+}
+
+#[test]
+fn migrations() -> anyhow::Result<()> {
+    let path = TestDirectory::new("migrations");
+    {
+        let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+        Basic::new("a").push_into(&db)?;
+        Basic::new("b").push_into(&db)?;
+        Basic::new("delete").push_into(&db)?;
+    }
+
+    let db = Database::open::<MigratedBasic>(StorageConfiguration::new(&path))?;
+    let expected = vec![CollectionMigration {
+        collection: Basic::collection_name(),
+        from_version: 0,
+        to_version: 2,
+        updated: 2,
+        deleted: 1,
+    }];
+
+    // A dry run reports the changes without making them.
+    assert_eq!(db.migrate_dry_run()?, expected);
+    assert_eq!(db.migration_version(&Basic::collection_name())?, 0);
+    assert_eq!(migrated_values(&db)?, vec!["a", "b", "delete"]);
+
+    assert_eq!(db.migrate()?, expected);
+    assert_eq!(db.migration_version(&Basic::collection_name())?, 2);
+    assert_eq!(migrated_values(&db)?, vec!["A", "B"]);
+
+    // Applied migrations aren't run again.
+    assert!(db.migrate()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn migrations_skip_new_collections() -> anyhow::Result<()> {
+    let path = TestDirectory::new("migrations-skip-new-collections");
+    let db = Database::open::<MigratedBasic>(StorageConfiguration::new(&path))?;
+    // Documents stored after the collection was created are already in the
+    // latest format.
+    assert_eq!(db.migration_version(&Basic::collection_name())?, 2);
+    Basic::new("delete").push_into(&db)?;
+    Basic::new("lower").push_into(&db)?;
+
+    assert!(db.migrate()?.is_empty());
+    assert_eq!(migrated_values(&db)?, vec!["delete", "lower"]);
+
+    Ok(())
+}
+
+#[test]
+fn migrations_on_open() -> anyhow::Result<()> {
+    let path = TestDirectory::new("migrations-on-open");
+    {
+        let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+        Basic::new("c").push_into(&db)?;
+    }
+
+    let db = Database::open::<MigratedBasic>(
+        StorageConfiguration::new(&path).run_migrations_on_open(true),
+    )?;
+    assert_eq!(db.migration_version(&Basic::collection_name())?, 2);
+    assert_eq!(migrated_values(&db)?, vec!["C"]);
+
+    Ok(())
+}
+
+#[test]
+fn schema_drift() -> anyhow::Result<()> {
+    let path = TestDirectory::new("schema-drift");
+    // Store a document and populate a view that the next schema doesn't define.
+    {
+        let db = Database::open::<BasicCollectionWithOnlyBrokenParentId>(
+            StorageConfiguration::new(&path),
+        )?;
+        db.collection::<BasicCollectionWithOnlyBrokenParentId>()
+            .push(&Basic::default().with_parent_id(1))?;
+        db.view::<BasicByBrokenParentId>().query()?;
+        assert!(db.schema_drift()?.is_empty());
+    }
+
+    let deny_drift = StorageConfiguration::new(&path).deny_schema_drift_on_open(true);
+    let err = Database::open::<BasicCollectionWithNoViews>(deny_drift.clone())
+        .expect_err("drift not detected");
+    assert!(err.to_string().contains("orphaned view"));
+
+    let db = Database::open::<BasicCollectionWithNoViews>(StorageConfiguration::new(&path))?;
+    let drift = db.schema_drift()?;
+    assert_eq!(
+        drift.orphaned_views,
+        vec![View::view_name(&BasicByBrokenParentId)]
+    );
+    assert!(drift.orphaned_collections.is_empty());
+    assert!(drift.changed_primary_keys.is_empty());
+    assert!(!drift.orphaned_trees.is_empty());
+
+    assert_eq!(db.clean_schema_drift()?, drift);
+    assert!(db.schema_drift()?.is_empty());
+    assert_eq!(
+        db.collection::<BasicCollectionWithNoViews>()
+            .all()
+            .query()?
+            .len(),
+        1
+    );
+    drop(db);
+
+    Database::open::<BasicCollectionWithNoViews>(deny_drift)?;
+
+    Ok(())
+}
+
+#[test]
+fn dynamic_documents() -> anyhow::Result<()> {
+    let path = TestDirectory::new("dynamic-documents");
+    let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+    let doc = Basic::new("hello").with_parent_id(7).push_into(&db)?;
+
+    // Decode the document and view entry using only the schema summary.
+    let summary = SchemaSummary::from(db.schematic());
+    let collection = summary.collection(&Basic::collection_name()).unwrap();
+    assert_eq!(collection.format, SerializationFormat::Pot);
+    let stored = db
+        .get_from_collection(DocumentId::from_u64(doc.header.id), &collection.name)?
+        .unwrap();
+    let decoded = collection.decode_document(&stored)?;
+    assert_eq!(decoded.id, Value::from(doc.header.id));
+    let Value::Mappings(fields) = &decoded.contents else {
+        unreachable!("unexpected contents: {:?}", decoded.contents)
+    };
+    assert!(fields
+        .iter()
+        .any(|(name, value)| name == &Value::from("value") && value == &Value::from("hello")));
+
+    let view = collection.view(&View::view_name(&BasicByParentId)).unwrap();
+    assert_eq!(view.value_format, SerializationFormat::Pot);
+    let mappings = db.query_by_name(
+        &view.name,
+        None,
+        Sort::Ascending,
+        None,
+        AccessPolicy::UpdateBefore,
+    )?;
+    let mapping = view.decode_mapping(&mappings[0])?;
+    assert_eq!(mapping.source, stored.header);
+    assert_eq!(mapping.key, Value::from(7_u64));
+    assert!(matches!(mapping.value, Value::Integer(_)));
+
+    Ok(())
+}
+
+#[test]
+fn add_role_to_user_by_name() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{Role, User};
+    use bonsaidb_core::connection::StorageConnection;
+
+    let path = TestDirectory::new("add-role-to-user-by-name");
+    let storage = Storage::open(StorageConfiguration::new(&path))?;
+    let admin = storage.admin();
+
+    let user_id = storage.create_user("alice")?;
+    let role = Role::named("operators").push_into(&admin)?;
+    storage.add_role_to_user("alice", "operators")?;
+
+    let user = User::get(&user_id, &admin)?.unwrap();
+    assert_eq!(user.contents.roles, vec![role.header.id]);
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct OwnedBasic;
+
+impl Schema for OwnedBasic {
+    fn schema_name() -> SchemaName {
+        SchemaName::new("khonsulabs", "owned-basic")
+    }
+
+    fn define_collections(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+        schema.define_collection::<Basic>()?;
+        schema.define_document_ownership::<Basic>();
+        Ok(())
+    }
+}
+
+/// Opens the database `name` using a session authenticated as `username`.
+fn database_as(storage: &Storage, name: &str, username: &str) -> anyhow::Result<Database> {
+    let storage = storage.assume_identity(IdentityReference::user(username)?)?;
+    Ok(storage
+        .instance
+        .database_without_schema(name, Some(&storage), None)?)
+}
+
+#[test]
+fn document_ownership() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::PermissionGroup;
+    use bonsaidb_core::permissions::bonsai::collection_resource_name;
+    use bonsaidb_core::permissions::{
+        ClientContext, ConditionalStatement, Conditions, Effect, StatementSource,
+    };
+
+    let path = TestDirectory::new("document-ownership");
+    // Authenticated users can use the collection, but aren't allowed to read
+    // or delete any specific documents.
+    let statements = [
+        DatabaseAction::Document(DocumentAction::Insert),
+        DatabaseAction::Document(DocumentAction::List),
+        DatabaseAction::Document(DocumentAction::Count),
+        DatabaseAction::View(ViewAction::Query),
+        DatabaseAction::View(ViewAction::Reduce),
+    ]
+    .into_iter()
+    .map(|action| Statement::for_any().allowing(&BonsaiAction::Database(action)))
+    .collect::<Vec<_>>();
+    let storage = Storage::open(
+        StorageConfiguration::new(&path)
+            .with_schema::<OwnedBasic>()?
+            .authenticated_permissions(Permissions::from(statements)),
+    )?;
+    storage.create_database::<OwnedBasic>("tests", false)?;
+    storage.create_user("alice")?;
+    let bob_id = storage.create_user("bob")?;
+    let alice = database_as(&storage, "tests", "alice")?;
+    let bob = database_as(&storage, "tests", "bob")?;
+
+    let alices = Basic::new("alice").with_parent_id(1).push_into(&alice)?;
+    Basic::new("alice").with_parent_id(2).push_into(&alice)?;
+    let bobs = Basic::new("bob").with_parent_id(1).push_into(&bob)?;
+
+    // Each session only sees the documents it owns.
+    assert_eq!(
+        Basic::all(&bob)
+            .query()?
+            .into_iter()
+            .map(|doc| doc.header.id)
+            .collect::<Vec<_>>(),
+        vec![bobs.header.id]
+    );
+    assert_eq!(Basic::all(&alice).count()?, 2);
+    assert_eq!(Basic::all(&bob).count()?, 1);
+    assert!(Basic::get(&alices.header.id, &bob)?.is_none());
+    assert!(Basic::get(&alices.header.id, &alice)?.is_some());
+    assert_eq!(
+        Basic::get_multiple([&alices.header.id, &bobs.header.id], &bob)?.len(),
+        1
+    );
+
+    let mappings = bob.view::<BasicByParentId>().with_key(&Some(1)).query()?;
+    assert_eq!(mappings.len(), 1);
+    assert_eq!(mappings[0].source.id, bobs.header.id);
+    assert_eq!(alice.view::<BasicByParentId>().reduce()?, 2);
+    assert_eq!(bob.view::<BasicByParentId>().reduce()?, 1);
+
+    // Explanations list the allowance granted to owners.
+    let delete = BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete));
+    let bobs_resource_name = collection_resource_name("tests", &Basic::collection_name())
+        .and("document")
+        .and(DocumentId::from_u64(bobs.header.id));
+    let explanation = storage.explain_permission(
+        IdentityReference::user("bob")?,
+        bobs_resource_name.clone(),
+        delete.name(),
+        ClientContext::default(),
+    )?;
+    assert!(explanation.allowed);
+    assert!(matches!(
+        explanation.statements.as_slice(),
+        [statement] if matches!(statement.source, StatementSource::Owner)
+    ));
+    let explanation = storage.explain_permission(
+        IdentityReference::user("alice")?,
+        bobs_resource_name,
+        delete.name(),
+        ClientContext::default(),
+    )?;
+    assert!(!explanation.allowed);
+    assert!(explanation.statements.is_empty());
+
+    // Only owners can delete their documents.
+    assert!(matches!(
+        alices.delete(&bob),
+        Err(bonsaidb_core::Error::PermissionDenied(_))
+    ));
+    alices.delete(&alice)?;
+
+    // Deny statements apply to owners too.
+    let denied = Basic::new("denied").push_into(&alice)?;
+    let group_id = storage.create_permission_group(
+        PermissionGroup::named("deny-documents").with_conditional_statements([
+            ConditionalStatement::deny(
+                Statement::for_any()
+                    .allowing(&BonsaiAction::Database(DatabaseAction::Document(
+                        DocumentAction::Get,
+                    )))
+                    .allowing(&BonsaiAction::Database(DatabaseAction::Document(
+                        DocumentAction::Delete,
+                    ))),
+                Conditions::default(),
+            ),
+        ]),
+    )?;
+    storage.add_permission_group_to_user("alice", group_id)?;
+    let denied_alice = database_as(&storage, "tests", "alice")?;
+    assert!(Basic::get(&denied.header.id, &denied_alice)?.is_none());
+    assert_eq!(Basic::all(&denied_alice).count()?, 0);
+    assert!(matches!(
+        denied.delete(&denied_alice),
+        Err(bonsaidb_core::Error::PermissionDenied(_))
+    ));
+    let explanation = storage.explain_permission(
+        IdentityReference::user("alice")?,
+        collection_resource_name("tests", &Basic::collection_name())
+            .and("document")
+            .and(DocumentId::from_u64(denied.header.id)),
+        delete.name(),
+        ClientContext::default(),
+    )?;
+    assert!(!explanation.allowed);
+    assert!(explanation
+        .statements
+        .iter()
+        .any(|statement| statement.effect == Effect::Deny && statement.applies));
+    assert!(explanation
+        .statements
+        .iter()
+        .any(|statement| matches!(statement.source, StatementSource::Owner)));
+    storage.remove_permission_group_from_user("alice", group_id)?;
+    denied.delete(&alice)?;
+
+    // Sessions without restrictions see every document.
+    let db = storage.database::<OwnedBasic>("tests")?;
+    assert_eq!(Basic::all(&db).count()?, 2);
+    assert_eq!(
+        db.document_owner(
+            &Basic::collection_name(),
+            &DocumentId::from_u64(bobs.header.id)
+        )?,
+        Some(IdentityId::User(bob_id))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn audit_log() -> anyhow::Result<()> {
+    let path = TestDirectory::new("audit-log");
+    let insert = BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert));
+    let get = BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get));
+    let storage = Storage::open(
+        StorageConfiguration::new(&path)
+            .with_schema::<BasicSchema>()?
+            .authenticated_permissions(Permissions::from(vec![
+                Statement::for_any().allowing(&insert)
+            ]))
+            .audit_log(true),
+    )?;
+    storage.create_database::<BasicSchema>("tests", false)?;
+    let alice_id = storage.create_user("alice")?;
+    // Actions performed without a session aren't recorded.
+    storage.flush_audit_log();
+    assert_eq!(AuditEntry::all(&storage.admin()).count()?, 0);
+
+    let alice = database_as(&storage, "tests", "alice")?;
+    let doc = Basic::new("alice").push_into(&alice)?;
+    assert!(matches!(
+        Basic::get(&doc.header.id, &alice),
+        Err(bonsaidb_core::Error::PermissionDenied(_))
+    ));
+
+    // Entries are written in the background.
+    storage.flush_audit_log();
+    let entries = AuditEntry::all(&storage.admin()).query()?;
+    let entry = |action: &BonsaiAction| {
+        entries
+            .iter()
+            .find(|entry| entry.contents.action == action.name().to_string())
+            .map(|entry| &entry.contents)
+            .unwrap()
+    };
+    let inserted = entry(&insert);
+    assert!(inserted.allowed);
+    assert_eq!(inserted.identity, Some(IdentityId::User(alice_id)));
+    assert!(inserted.session.is_some());
+    assert!(inserted.address.is_none());
+    assert!(!entry(&get).allowed);
+    drop((alice, storage));
+
+    // Reopening with a retention of zero expires every existing entry.
+    let storage = Storage::open(
+        StorageConfiguration::new(&path)
+            .with_schema::<BasicSchema>()?
+            .audit_log(true)
+            .audit_log_retention(Duration::ZERO),
+    )?;
+    assert_eq!(storage.prune_audit_log()?, u64::try_from(entries.len())?);
+    assert_eq!(AuditEntry::all(&storage.admin()).count()?, 0);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "password-hashing")]
+fn password_policy_and_lockout() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::SensitiveString;
+
+    let path = TestDirectory::new("password-policy");
+    let storage = Storage::open(
+        StorageConfiguration::new(&path)
+            .password_minimum_length(8)
+            .reject_common_passwords(true)
+            .login_lockout(2, Duration::from_secs(60)),
+    )?;
+    storage.create_user("alice")?;
+    storage.create_user("bob")?;
+
+    assert!(matches!(
+        storage.set_user_password("alice", SensitiveString::from("short")),
+        Err(bonsaidb_core::Error::PasswordTooShort { minimum_length: 8 })
+    ));
+    assert!(matches!(
+        storage.set_user_password("alice", SensitiveString::from("PassWord123")),
+        Err(bonsaidb_core::Error::CommonPassword)
+    ));
+    storage.set_user_password("alice", SensitiveString::from("correct horse"))?;
+    storage.set_user_password("bob", SensitiveString::from("battery staple"))?;
+
+    // A successful login resets the failed attempt count.
+    assert!(matches!(
+        storage.authenticate_with_password("alice", SensitiveString::from("wrong")),
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+    storage.authenticate_with_password("alice", SensitiveString::from("correct horse"))?;
+    assert!(matches!(
+        storage.authenticate_with_password("alice", SensitiveString::from("wrong")),
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+    assert!(matches!(
+        storage.authenticate_with_password("alice", SensitiveString::from("wrong")),
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+
+    // Alice is now locked out, even with the correct password.
+    assert!(matches!(
+        storage.authenticate_with_password("alice", SensitiveString::from("correct horse")),
+        Err(bonsaidb_core::Error::UserLockedOut { .. })
+    ));
+    // Other users are unaffected.
+    storage.authenticate_with_password("bob", SensitiveString::from("battery staple"))?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+fn external_authentication() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{Role, User};
+    use bonsaidb_core::connection::{Authentication, HasSession, Identity};
+
+    use crate::config::{ExternalIdentity, StaticAuthenticationProvider};
+
+    let path = TestDirectory::new("external-authentication");
+    let storage = Storage::open(
+        StorageConfiguration::new(&path).authentication_provider(
+            StaticAuthenticationProvider::default()
+                .with_credential("alice-token", ExternalIdentity::User(String::from("alice")))
+                .with_credential("carol-token", ExternalIdentity::User(String::from("carol")))
+                .with_credential("ops-token", ExternalIdentity::Role(String::from("ops"))),
+        ),
+    )?;
+
+    // The first login provisions the user.
+    let alice = storage.authenticate(Authentication::external("alice-token"))?;
+    let user = User::load("alice", &storage.admin())?.expect("user not provisioned");
+    assert!(matches!(
+        alice.session().and_then(|session| session.identity()),
+        Some(Identity::User { id, .. }) if *id == user.header.id
+    ));
+
+    // Later logins assume the existing user.
+    let alice = storage.authenticate(Authentication::external("alice-token"))?;
+    assert!(matches!(
+        alice.session().and_then(|session| session.identity()),
+        Some(Identity::User { id, .. }) if *id == user.header.id
+    ));
+
+    // Concurrent first logins all succeed, provisioning the user once.
+    let sessions = std::thread::scope(|scope| {
+        let logins = (0..4)
+            .map(|_| scope.spawn(|| storage.authenticate(Authentication::external("carol-token"))))
+            .collect::<Vec<_>>();
+        logins
+            .into_iter()
+            .map(|login| login.join().unwrap())
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    let carol = User::load("carol", &storage.admin())?.expect("user not provisioned");
+    for session in &sessions {
+        assert!(matches!(
+            session.session().and_then(|session| session.identity()),
+            Some(Identity::User { id, .. }) if *id == carol.header.id
+        ));
+    }
+
+    // Roles aren't provisioned automatically.
+    assert!(matches!(
+        storage.authenticate(Authentication::external("ops-token")),
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+    storage.create_role(Role::named("ops"))?;
+    let ops = storage.authenticate(Authentication::external("ops-token"))?;
+    assert!(matches!(
+        ops.session().and_then(|session| session.identity()),
+        Some(Identity::Role { name, .. }) if name == "ops"
+    ));
+
+    assert!(matches!(
+        storage.authenticate(Authentication::external("unknown")),
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+fn conditional_permissions() -> anyhow::Result<()> {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::SystemTime;
+
+    use bonsaidb_core::admin::{PermissionGroup, Role};
+    use bonsaidb_core::connection::{Authentication, HasSession};
+    use bonsaidb_core::key::time::TimestampAsNanoseconds;
+    use bonsaidb_core::permissions::bonsai::{database_resource_name, ServerAction};
+    use bonsaidb_core::permissions::{
+        AddressRange, ClientTransport, ConditionalStatement, Conditions,
+    };
+
+    use crate::config::{ExternalIdentity, StaticAuthenticationProvider};
+
+    let path = TestDirectory::new("conditional-permissions");
+    let storage = Storage::open(
+        StorageConfiguration::new(&path).authentication_provider(
+            StaticAuthenticationProvider::default()
+                .with_credential("ops-token", ExternalIdentity::Role(String::from("ops"))),
+        ),
+    )?;
+
+    let hour = Duration::from_secs(60 * 60);
+    let an_hour_ago = TimestampAsNanoseconds::try_from(SystemTime::now() - hour)?;
+    let in_an_hour = TimestampAsNanoseconds::try_from(SystemTime::now() + hour)?;
+    let create_database = BonsaiAction::Server(ServerAction::CreateDatabase);
+    let list_databases = BonsaiAction::Server(ServerAction::ListDatabases);
+    let group = PermissionGroup::named("conditional")
+        .with_group_ids([Statement::for_any().allowing(&list_databases)])
+        .with_conditional_statements([
+            ConditionalStatement::allow(
+                Statement::for_resource(database_resource_name("current"))
+                    .allowing(&create_database),
+                Conditions::default()
+                    .not_before(an_hour_ago)
+                    .not_after(in_an_hour),
+            ),
+            ConditionalStatement::allow(
+                Statement::for_resource(database_resource_name("expired"))
+                    .allowing(&create_database),
+                Conditions::default().not_after(an_hour_ago),
+            ),
+            ConditionalStatement::allow(
+                Statement::for_resource(database_resource_name("websocket-only"))
+                    .allowing(&create_database),
+                Conditions::default().with_transport(ClientTransport::WebSocket),
+            ),
+            ConditionalStatement::deny(
+                Statement::for_any().allowing(&list_databases),
+                Conditions::default().with_address_range(AddressRange::new(
+                    IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)),
+                    8,
+                )),
+            ),
+        ])
+        .push_into(&storage.admin())?;
+    storage.create_role(Role::named("ops").with_group_ids([group.header.id]))?;
+
+    let ops = storage.authenticate(Authentication::external("ops-token"))?;
+    let session = ops.session().expect("no session");
+    assert!(session.allowed_to(database_resource_name("current"), &create_database));
+    assert!(!session.allowed_to(database_resource_name("expired"), &create_database));
+    assert!(!session.allowed_to(database_resource_name("websocket-only"), &create_database));
+    // Deny statements whose conditions depend on information the session
+    // doesn't have, such as the client's address, apply.
+    assert!(!session.allowed_to(database_resource_name("any"), &list_databases));
+
+    let websocket = ops.with_client_transport(ClientTransport::WebSocket);
+    let session = websocket.session().expect("no session");
+    assert!(session.allowed_to(database_resource_name("websocket-only"), &create_database));
+
+    // Deny statements take precedence over the static statement allowing
+    // listing databases.
+    let internal = ops.with_client_address(SocketAddr::from(([10, 1, 2, 3], 5645)));
+    assert!(matches!(
+        internal.list_databases(),
+        Err(bonsaidb_core::Error::PermissionDenied(_))
+    ));
+    let external = ops.with_client_address(SocketAddr::from(([192, 168, 1, 1], 5645)));
+    external.list_databases()?;
+
+    // Listeners bound to an unspecified IPv6 address report IPv4 clients using
+    // IPv4-mapped addresses, which must still match IPv4 ranges.
+    let mapped = ops.with_client_address(SocketAddr::from((
+        Ipv4Addr::new(10, 1, 2, 3).to_ipv6_mapped(),
+        5645,
+    )));
+    assert_eq!(
+        mapped.client_address().map(|address| address.ip()),
+        Some(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)))
+    );
+    assert!(matches!(
+        mapped.list_databases(),
+        Err(bonsaidb_core::Error::PermissionDenied(_))
+    ));
+    assert!(AddressRange::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8)
+        .contains(IpAddr::V6(Ipv4Addr::new(10, 1, 2, 3).to_ipv6_mapped())));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "token-authentication")]
+fn revoked_token_sessions() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{AuthenticationToken, PermissionGroup};
+    use bonsaidb_core::connection::HasSession;
+    use bonsaidb_core::permissions::bonsai::{database_resource_name, ServerAction};
+
+    let path = TestDirectory::new("revoked-token-sessions");
+    let storage = Storage::open(StorageConfiguration::new(&path))?;
+
+    let list_databases = BonsaiAction::Server(ServerAction::ListDatabases);
+    let group = PermissionGroup::named("list-databases")
+        .with_group_ids([Statement::for_any().allowing(&list_databases)])
+        .push_into(&storage.admin())?;
+    let user_id = storage.create_user("token-user")?;
+    storage.add_permission_group_to_user(user_id, group.header.id)?;
+    let token =
+        AuthenticationToken::create(&IdentityReference::user("token-user")?, &storage.admin())?;
+
+    let as_user = storage.authenticate_with_token(token.header.id, &token.contents.token)?;
+    let database = database_resource_name("any");
+    assert!(as_user.allowed_to(&database, &list_databases));
+
+    // Every check made through the revoked session is denied, including
+    // those that don't return an error.
+    storage.revoke_authentication_token(token.header.id)?;
+    assert!(!as_user.allowed_to(&database, &list_databases));
+    assert!(as_user
+        .session()
+        .expect("no session")
+        .denies(&database, &list_databases));
+    assert!(matches!(
+        as_user.list_databases(),
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn role_permissions() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{PermissionGroup, Role};
+    use bonsaidb_core::permissions::bonsai::ServerAction;
+
+    let path = TestDirectory::new("role-permissions");
+    let storage = Storage::open(StorageConfiguration::new(&path))?;
+
+    let listers =
+        storage.create_permission_group(PermissionGroup::named("listers").with_group_ids([
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::ListDatabases)),
+        ]))?;
+    storage.create_role(Role::named("operators").with_group_ids([listers]))?;
+    storage.create_user("alice")?;
+    let alice = storage.assume_identity(IdentityReference::user("alice")?)?;
+    assert!(matches!(
+        alice.list_databases(),
+        Err(bonsaidb_core::Error::PermissionDenied(_))
+    ));
+
+    // A user is granted the permission groups of its roles, even when the
+    // user has no permission groups of its own.
+    storage.add_role_to_user("alice", "operators")?;
+    let alice = storage.assume_identity(IdentityReference::user("alice")?)?;
+    alice.list_databases()?;
+
+    Ok(())
+}
+
+#[test]
+fn explain_permission() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{PermissionGroup, Role};
+    use bonsaidb_core::connection::IdentityReference;
+    use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, ServerAction};
+    use bonsaidb_core::permissions::{
+        ClientContext, ConditionalStatement, Conditions, Effect, StatementSource,
+    };
+
+    let path = TestDirectory::new("explain-permission");
+    let storage = Storage::open(StorageConfiguration::new(&path))?;
+
+    let list_databases = BonsaiAction::Server(ServerAction::ListDatabases);
+    storage.create_permission_group(
+        PermissionGroup::named("readers")
+            .with_group_ids([Statement::for_any().allowing(&list_databases)]),
+    )?;
+    let deniers = storage.create_permission_group(
+        PermissionGroup::named("deniers").with_conditional_statements([
+            ConditionalStatement::deny(
+                Statement::for_any().allowing(&list_databases),
+                Conditions::default(),
+            ),
+        ]),
+    )?;
+    storage.create_role(Role::named("ops").with_group_ids([deniers]))?;
+    storage.create_user("alice")?;
+    storage.add_permission_group_to_user("alice", "readers")?;
+
+    let explanation = storage.explain_permission(
+        IdentityReference::user("alice")?,
+        bonsaidb_resource_name(),
+        list_databases.name(),
+        ClientContext::default(),
+    )?;
+    assert!(explanation.allowed);
+    assert_eq!(explanation.statements.len(), 1);
+    assert!(matches!(
+        &explanation.statements[0].source,
+        StatementSource::Group { name, role: None, .. } if name == "readers"
+    ));
+
+    // Deny statements assigned through a role take precedence.
+    storage.add_role_to_user("alice", "ops")?;
+    let explanation = storage.explain_permission(
+        IdentityReference::user("alice")?,
+        bonsaidb_resource_name(),
+        list_databases.name(),
+        ClientContext::default(),
+    )?;
+    assert!(!explanation.allowed);
+    assert_eq!(explanation.statements.len(), 2);
+    assert!(explanation.statements.iter().any(|statement| matches!(
+        &statement.source,
+        StatementSource::Group { name, role: Some(role), .. }
+            if name == "deniers" && role == "ops"
+    ) && statement.effect == Effect::Deny
+        && statement.applies));
+
+    let explanation = storage.explain_permission(
+        IdentityReference::role("ops")?,
+        bonsaidb_resource_name(),
+        list_databases.name(),
+        ClientContext::default(),
+    )?;
+    assert!(!explanation.allowed);
+    assert_eq!(explanation.statements.len(), 1);
+
+    assert!(matches!(
+        storage.explain_permission(
+            IdentityReference::user("bob")?,
+            bonsaidb_resource_name(),
+            list_databases.name(),
+            ClientContext::default(),
+        ),
+        Err(bonsaidb_core::Error::UserNotFound)
+    ));
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::collections::hash_map::RandomState;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::arc_bytes::{ArcBytes, OwnedBytes};
@@ -81,6 +82,7 @@ impl Job for Mapper {
         let storage = self.database.clone();
         let map_request = self.map.clone();
 
+        let started_at = Instant::now();
         map_view(
             &invalidated_entries,
             &document_map,
@@ -89,6 +91,12 @@ impl Job for Mapper {
             &storage,
             &map_request,
         )?;
+        self.database
+            .storage
+            .instance
+            .metrics()
+            .view_updates
+            .record(started_at.elapsed());
 
         self.database.storage.instance.tasks().mark_view_updated(
             self.map.database.clone(),
@@ -158,6 +158,7 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
 
     let primary_key = primary_key.unwrap_or_else(|| parse_quote!(u64));
 
+    let mut serialization_format = None;
     let serialization = if matches!(&serialization, Some(serialization) if serialization.is_ident("None"))
     {
         if let Some(natural_id) = natural_id {
@@ -180,6 +181,9 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
 
         if let Some(serialization) = serialization {
             let serialization = if serialization.is_ident("Key") {
+                serialization_format = Some(quote!(#core::schema::SerializationFormat::Key(
+                    #core::key::KeyDescription::for_key::<Self>()
+                )));
                 quote!(#core::key::KeyFormat)
             } else {
                 quote!(#serialization)
@@ -197,6 +201,7 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
                 }
             }
         } else {
+            serialization_format = Some(quote!(#core::schema::SerializationFormat::Pot));
             quote! {
                 impl #impl_generics #core::schema::DefaultSerialization for #ident #ty_generics #where_clause {
                     #natural_id
@@ -210,6 +215,14 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
         |authority| quote!(#core::schema::Qualified::new(#authority, #name)),
     );
 
+    let serialization_format = serialization_format.map(|format| {
+        quote! {
+            fn serialization_format() -> #core::schema::SerializationFormat {
+                #format
+            }
+        }
+    });
+
     let encryption = encryption_key.map(|encryption_key| {
         let encryption = if encryption_required || !encryption_optional {
             encryption_key.into_token_stream()
@@ -241,6 +254,7 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
                 Ok(())
             }
             #encryption
+            #serialization_format
         }
         #serialization
     })
@@ -946,6 +960,8 @@ struct FileConfigAttribute {
     files_name: Option<String>,
     #[attribute(example = "\"blocks\"")]
     blocks_name: Option<String>,
+    deduplicate_blocks: bool,
+    versioned: bool,
     #[attribute(example = "bosaidb::core")]
     core: Option<Path>,
     #[attribute(example = "bosaidb::files")]
@@ -954,7 +970,7 @@ struct FileConfigAttribute {
 
 /// Derives the `bonsaidb::files::FileConfig` trait.
 ///
-/// `#[api(metadata = MetadataType, block_size = 65_536, authority = "authority", files_name = "files", blocks_name = "blocks", core = bonsaidb::core, files = bosaidb::files)]`
+/// `#[api(metadata = MetadataType, block_size = 65_536, authority = "authority", files_name = "files", blocks_name = "blocks", deduplicate_blocks, versioned, core = bonsaidb::core, files = bosaidb::files)]`
 /// all arguments are optional
 #[manyhow]
 #[proc_macro_derive(FileConfig, attributes(file_config))]
@@ -972,6 +988,8 @@ pub fn file_config_derive(input: proc_macro::TokenStream) -> Result {
         authority,
         files_name,
         blocks_name,
+        deduplicate_blocks,
+        versioned,
         core,
         files,
     } = FileConfigAttribute::from_attributes(&attrs)?;
@@ -1018,6 +1036,8 @@ pub fn file_config_derive(input: proc_macro::TokenStream) -> Result {
         impl #impl_generics FileConfig for #ident #ty_generics #where_clause {
             type Metadata = #metadata;
             const BLOCK_SIZE: usize = #block_size;
+            const DEDUPLICATE_BLOCKS: bool = #deduplicate_blocks;
+            const VERSIONED: bool = #versioned;
 
             fn files_name() -> CollectionName {
                 #files_name
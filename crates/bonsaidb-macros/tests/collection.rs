@@ -17,6 +17,10 @@ fn name_only() {
         Test::<String>::collection_name(),
         bonsaidb::core::schema::CollectionName::private("Name")
     );
+    assert_eq!(
+        Test::<String>::serialization_format(),
+        bonsaidb::core::schema::SerializationFormat::Pot
+    );
 }
 
 #[test]
@@ -92,6 +96,10 @@ fn serialization() {
     );
 
     let _: transmog_bincode::Bincode = Test::format();
+    assert_eq!(
+        Test::serialization_format(),
+        bonsaidb::core::schema::SerializationFormat::Unknown
+    );
 }
 
 #[test]
@@ -56,3 +56,31 @@ fn response() {
 
     assert_eq!(<Test as FileConfig>::Metadata::default(), String::default());
 }
+
+#[test]
+fn deduplicate_blocks() {
+    #[derive(FileConfig)]
+    struct NotDeduplicated;
+
+    assert!(!NotDeduplicated::DEDUPLICATE_BLOCKS);
+
+    #[derive(FileConfig)]
+    #[file_config(deduplicate_blocks)]
+    struct Test;
+
+    assert!(Test::DEDUPLICATE_BLOCKS);
+}
+
+#[test]
+fn versioned() {
+    #[derive(FileConfig)]
+    struct NotVersioned;
+
+    assert!(!NotVersioned::VERSIONED);
+
+    #[derive(FileConfig)]
+    #[file_config(versioned)]
+    struct Test;
+
+    assert!(Test::VERSIONED);
+}
@@ -6,6 +6,7 @@ use bonsaidb_core::api::{self, Api, ApiError, Infallible};
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::permissions::PermissionDenied;
 use bonsaidb_core::schema::{InsertError, InvalidNameError};
+use bonsaidb_local::AsyncDatabase;
 
 use crate::{Backend, ConnectedClient, CustomServer, Error, NoBackend};
 
@@ -30,6 +31,19 @@ pub struct HandlerSession<'a, B: Backend = NoBackend> {
     pub as_client: CustomServer<B>,
     /// The connected client making the API request.
     pub client: &'a ConnectedClient<B>,
+    pub(crate) request_bytes: usize,
+}
+
+impl<'a, B: Backend> HandlerSession<'a, B> {
+    /// Opens the database named `name` using the permissions of the connected
+    /// client. Returns [`bonsaidb_core::Error::RateLimited`] if the server's
+    /// per-database rate limit for `name` has been exceeded.
+    pub async fn database(&self, name: &str) -> Result<AsyncDatabase, Error> {
+        let database = self.as_client.database_without_schema(name).await?;
+        self.server
+            .check_database_rate_limit(&database, self.request_bytes)?;
+        Ok(database)
+    }
 }
 
 #[async_trait]
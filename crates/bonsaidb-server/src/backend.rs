@@ -6,7 +6,8 @@ use bonsaidb_core::connection::Session;
 use bonsaidb_core::permissions::PermissionDenied;
 use bonsaidb_core::schema::{InsertError, InvalidNameError};
 
-use crate::server::ConnectedClient;
+use crate::config::RateLimit;
+use crate::server::{ConnectedClient, RateLimitScope};
 use crate::{CustomServer, Error, ServerConfiguration};
 
 /// Tailors the behavior of a server to your needs.
@@ -105,6 +106,18 @@ pub trait Backend: Debug + Send + Sync + Sized + 'static {
         );
         Ok(())
     }
+
+    /// Returns the rate limit to apply to `scope`. `configured` is the limit
+    /// from [`ServerConfiguration::rate_limits`] for the scope's kind.
+    ///
+    /// This is invoked the first time a limit is needed for each connection,
+    /// identity, and database. The returned limit is used for the remainder
+    /// of the connection's lifetime or, for identities and databases, until
+    /// the server is shut down.
+    #[allow(unused_variables)]
+    fn rate_limit(&self, scope: RateLimitScope<'_, Self>, configured: RateLimit) -> RateLimit {
+        configured
+    }
 }
 
 /// A [`Backend`] with no custom functionality.
@@ -3,6 +3,7 @@ use std::marker::PhantomData;
 use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
@@ -12,7 +13,9 @@ use bonsaidb_core::permissions::{Permissions, Statement};
 use bonsaidb_core::schema::Schema;
 #[cfg(feature = "compression")]
 use bonsaidb_local::config::Compression;
-use bonsaidb_local::config::{Builder, KeyValuePersistence, StorageConfiguration};
+use bonsaidb_local::config::{
+    AuthenticationProvider, Builder, KeyValuePersistence, StorageConfiguration,
+};
 #[cfg(feature = "encryption")]
 use bonsaidb_local::vault::AnyVaultKeyStorage;
 
@@ -42,6 +45,20 @@ pub struct ServerConfiguration<B: Backend = NoBackend> {
     /// The ACME settings for automatic TLS certificate management.
     #[cfg(feature = "acme")]
     pub acme: AcmeConfiguration,
+    /// The rate limits applied to requests from connected clients. By
+    /// default, requests are not limited.
+    pub rate_limits: RateLimits,
+    /// Limits on failed authentication attempts made from a single address.
+    /// By default, authentication attempts are not limited.
+    pub authentication_throttle: AuthenticationThrottle,
+    /// Whether clients connecting over QUIC or TLS are asked to present a
+    /// certificate that authenticates them. Default value is
+    /// [`ClientCertificates::Ignored`].
+    pub client_certificates: ClientCertificates,
+    /// The certificate authorities trusted to issue client certificates.
+    /// [`CertificateMatcher::Subject`](bonsaidb_core::admin::CertificateMatcher::Subject)
+    /// mappings only match certificates issued by one of these authorities.
+    pub client_certificate_authorities: Vec<fabruic::Certificate>,
 
     pub(crate) custom_apis: HashMap<ApiName, Arc<dyn AnyHandler<B>>>,
 }
@@ -61,6 +78,10 @@ impl<B: Backend> ServerConfiguration<B> {
             custom_apis: HashMap::default(),
             #[cfg(feature = "acme")]
             acme: AcmeConfiguration::default(),
+            rate_limits: RateLimits::default(),
+            authentication_throttle: AuthenticationThrottle::default(),
+            client_certificates: ClientCertificates::default(),
+            client_certificate_authorities: Vec::new(),
         }
     }
 
@@ -96,6 +117,30 @@ impl<B: Backend> ServerConfiguration<B> {
         self
     }
 
+    /// Sets [`Self::rate_limits`](Self#structfield.rate_limits) to `rate_limits` and returns self.
+    pub const fn rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Sets [`Self::authentication_throttle`](Self#structfield.authentication_throttle) to `throttle` and returns self.
+    pub const fn authentication_throttle(mut self, throttle: AuthenticationThrottle) -> Self {
+        self.authentication_throttle = throttle;
+        self
+    }
+
+    /// Sets [`Self::client_certificates`](Self#structfield.client_certificates) to `mode` and returns self.
+    pub const fn client_certificates(mut self, mode: ClientCertificates) -> Self {
+        self.client_certificates = mode;
+        self
+    }
+
+    /// Adds `authority` to [`Self::client_certificate_authorities`](Self#structfield.client_certificate_authorities) and returns self.
+    pub fn trust_client_certificate_authority(mut self, authority: fabruic::Certificate) -> Self {
+        self.client_certificate_authorities.push(authority);
+        self
+    }
+
     /// Sets [`AcmeConfiguration::contact_email`] to `contact_email` and returns self.
     #[cfg(feature = "acme")]
     pub fn acme_contact_email(mut self, contact_email: impl Into<String>) -> Self {
@@ -168,6 +213,152 @@ mod acme {
 #[cfg(feature = "acme")]
 pub use acme::*;
 
+/// Limits on how quickly requests can be made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[must_use]
+pub struct RateLimit {
+    /// The maximum number of requests allowed per second. `None` allows an
+    /// unlimited number of requests, and `Some(0)` rejects every request.
+    pub requests_per_second: Option<u32>,
+    /// The maximum number of request payload bytes allowed per second. `None`
+    /// allows an unlimited number of bytes, and `Some(0)` rejects every
+    /// request.
+    pub bytes_per_second: Option<u64>,
+}
+
+impl RateLimit {
+    /// A rate limit that allows all requests.
+    pub const UNLIMITED: Self = Self {
+        requests_per_second: None,
+        bytes_per_second: None,
+    };
+
+    /// Sets [`Self::requests_per_second`](Self#structfield.requests_per_second)
+    /// to `requests` and returns self.
+    pub const fn requests_per_second(mut self, requests: u32) -> Self {
+        self.requests_per_second = Some(requests);
+        self
+    }
+
+    /// Sets [`Self::bytes_per_second`](Self#structfield.bytes_per_second) to
+    /// `bytes` and returns self.
+    pub const fn bytes_per_second(mut self, bytes: u64) -> Self {
+        self.bytes_per_second = Some(bytes);
+        self
+    }
+
+    /// Returns true if this limit allows all requests.
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.requests_per_second.is_none() && self.bytes_per_second.is_none()
+    }
+}
+
+/// The rate limits applied to requests made by connected clients. Each
+/// request must be allowed by every limit that applies to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[must_use]
+#[non_exhaustive]
+pub struct RateLimits {
+    /// The limit applied to each connected client.
+    pub per_connection: RateLimit,
+    /// The limit applied to each authenticated identity, shared across all of
+    /// its connections.
+    pub per_user: RateLimit,
+    /// The limit applied to each database, shared across all clients.
+    pub per_database: RateLimit,
+}
+
+impl RateLimits {
+    /// Sets [`Self::per_connection`](Self#structfield.per_connection) to
+    /// `limit` and returns self.
+    pub const fn per_connection(mut self, limit: RateLimit) -> Self {
+        self.per_connection = limit;
+        self
+    }
+
+    /// Sets [`Self::per_user`](Self#structfield.per_user) to `limit` and
+    /// returns self.
+    pub const fn per_user(mut self, limit: RateLimit) -> Self {
+        self.per_user = limit;
+        self
+    }
+
+    /// Sets [`Self::per_database`](Self#structfield.per_database) to `limit`
+    /// and returns self.
+    pub const fn per_database(mut self, limit: RateLimit) -> Self {
+        self.per_database = limit;
+        self
+    }
+}
+
+/// Limits on failed authentication attempts made from a single IP address.
+///
+/// Once `max_failed_attempts` authentication attempts have failed within
+/// `window`, further attempts from the same address are rejected with
+/// [`Error::AuthenticationThrottled`](bonsaidb_core::Error::AuthenticationThrottled)
+/// until the window ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+#[non_exhaustive]
+pub struct AuthenticationThrottle {
+    /// The number of failed authentication attempts allowed from a single
+    /// address within [`Self::window`](Self#structfield.window). `None`
+    /// disables throttling. Default value is `None`.
+    pub max_failed_attempts: Option<u32>,
+    /// The period of time failed attempts are counted over, starting with the
+    /// first failed attempt. Default value is 1 minute.
+    pub window: Duration,
+}
+
+impl Default for AuthenticationThrottle {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: None,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+impl AuthenticationThrottle {
+    /// Sets [`Self::max_failed_attempts`](Self#structfield.max_failed_attempts)
+    /// to `attempts` and returns self.
+    pub const fn max_failed_attempts(mut self, attempts: u32) -> Self {
+        self.max_failed_attempts = Some(attempts);
+        self
+    }
+
+    /// Sets [`Self::window`](Self#structfield.window) to `window` and returns
+    /// self.
+    pub const fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+/// Controls how the server handles certificates presented by clients
+/// connecting over QUIC or TLS.
+///
+/// A presented certificate is matched against the
+/// [`ClientCertificate`](bonsaidb_core::admin::ClientCertificate) mappings
+/// stored in the admin database, first by its fingerprint and then by its
+/// subject. If a mapping is found, the connection begins authenticated as the
+/// mapping's identity. Subjects are only matched for certificates issued by
+/// one of the
+/// [`client_certificate_authorities`](ServerConfiguration#structfield.client_certificate_authorities).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientCertificates {
+    /// Clients are not asked for a certificate.
+    #[default]
+    Ignored,
+    /// Clients may present a certificate. Connections without a certificate,
+    /// or whose certificate has no mapping, begin unauthenticated.
+    Optional,
+    /// Clients must present a certificate that has a mapping. Other
+    /// connections are refused.
+    Required,
+}
+
 /// The default permissions to use for all connections to the server.
 #[derive(Debug, Clone)]
 pub enum DefaultPermissions {
@@ -255,6 +446,26 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         self
     }
 
+    fn run_migrations_on_open(mut self, run: bool) -> Self {
+        self.storage.migrations.run_on_open = run;
+        self
+    }
+
+    fn deny_schema_drift_on_open(mut self, deny: bool) -> Self {
+        self.storage.schema_drift.deny_on_open = deny;
+        self
+    }
+
+    fn audit_log(mut self, enabled: bool) -> Self {
+        self.storage.audit_log.enabled = enabled;
+        self
+    }
+
+    fn audit_log_retention(mut self, retention: Duration) -> Self {
+        self.storage.audit_log.retention = Some(retention);
+        self
+    }
+
     #[cfg(feature = "compression")]
     fn default_compression(mut self, compression: Compression) -> Self {
         self.storage.default_compression = Some(compression);
@@ -279,6 +490,33 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         self.storage.argon = argon;
         self
     }
+
+    #[cfg(feature = "password-hashing")]
+    fn password_minimum_length(mut self, minimum_length: usize) -> Self {
+        self.storage.password_policy.minimum_length = minimum_length;
+        self
+    }
+
+    #[cfg(feature = "password-hashing")]
+    fn reject_common_passwords(mut self, reject: bool) -> Self {
+        self.storage.password_policy.reject_common_passwords = reject;
+        self
+    }
+
+    #[cfg(feature = "password-hashing")]
+    fn login_lockout(mut self, max_failed_attempts: u32, duration: Duration) -> Self {
+        self.storage.login_lockout.max_failed_attempts = Some(max_failed_attempts);
+        self.storage.login_lockout.duration = duration;
+        self
+    }
+
+    fn authentication_provider<Provider: AuthenticationProvider>(
+        mut self,
+        provider: Provider,
+    ) -> Self {
+        self.storage.authentication_provider = Some(Arc::new(provider));
+        self
+    }
 }
 
 /// Configuration for the BonsaiDb network protocol.
@@ -5,13 +5,18 @@ use bonsaidb_core::connection::{
     AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection, HasSession,
 };
 use bonsaidb_core::keyvalue::AsyncKeyValue;
+#[cfg(feature = "token-authentication")]
+use bonsaidb_core::networking::CreateAuthenticationToken;
 use bonsaidb_core::networking::{
     AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction, AssumeIdentity,
-    Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreateSubscriber,
-    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get, GetMultiple,
-    LastTransactionId, List, ListAvailableSchemas, ListDatabases, ListExecutedTransactions,
-    ListHeaders, LogOutSession, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped,
-    SubscribeTo, UnregisterSubscriber, UnsubscribeFrom,
+    Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreatePermissionGroup,
+    CreateRole, CreateSubscriber, CreateUser, DeleteDatabase, DeleteDocs, DeletePermissionGroup,
+    DeleteRole, DeleteUser, DisconnectClient, ExecuteKeyOperation, ExplainPermission, Get,
+    GetMultiple, LastTransactionId, List, ListAuthenticationTokens, ListAvailableSchemas,
+    ListDatabases, ListExecutedTransactions, ListHeaders, ListSessions, LogOutSession, Publish,
+    PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped, RevokeAuthenticationToken,
+    SubscribeTo, TerminateSession, UnregisterSubscriber, UnsubscribeFrom, UpdatePermissionGroup,
+    UpdateRole,
 };
 #[cfg(feature = "password-hashing")]
 use bonsaidb_core::networking::{Authenticate, SetUserPassword};
@@ -34,30 +39,42 @@ pub fn register_api_handlers<B: Backend>(
         .with_api::<ServerDispatcher, CompactKeyValueStore>()?
         .with_api::<ServerDispatcher, Count>()?
         .with_api::<ServerDispatcher, CreateDatabase>()?
+        .with_api::<ServerDispatcher, CreatePermissionGroup>()?
+        .with_api::<ServerDispatcher, CreateRole>()?
         .with_api::<ServerDispatcher, CreateSubscriber>()?
         .with_api::<ServerDispatcher, CreateUser>()?
         .with_api::<ServerDispatcher, DeleteDatabase>()?
         .with_api::<ServerDispatcher, DeleteDocs>()?
+        .with_api::<ServerDispatcher, DeletePermissionGroup>()?
+        .with_api::<ServerDispatcher, DeleteRole>()?
         .with_api::<ServerDispatcher, DeleteUser>()?
         .with_api::<ServerDispatcher, ExecuteKeyOperation>()?
+        .with_api::<ServerDispatcher, ExplainPermission>()?
         .with_api::<ServerDispatcher, Get>()?
         .with_api::<ServerDispatcher, GetMultiple>()?
         .with_api::<ServerDispatcher, LastTransactionId>()?
         .with_api::<ServerDispatcher, List>()?
         .with_api::<ServerDispatcher, ListHeaders>()?
+        .with_api::<ServerDispatcher, ListSessions>()?
+        .with_api::<ServerDispatcher, ListAuthenticationTokens>()?
         .with_api::<ServerDispatcher, ListAvailableSchemas>()?
         .with_api::<ServerDispatcher, ListDatabases>()?
         .with_api::<ServerDispatcher, ListExecutedTransactions>()?
         .with_api::<ServerDispatcher, LogOutSession>()?
+        .with_api::<ServerDispatcher, TerminateSession>()?
+        .with_api::<ServerDispatcher, DisconnectClient>()?
         .with_api::<ServerDispatcher, Publish>()?
         .with_api::<ServerDispatcher, PublishToAll>()?
         .with_api::<ServerDispatcher, Query>()?
         .with_api::<ServerDispatcher, QueryWithDocs>()?
         .with_api::<ServerDispatcher, Reduce>()?
         .with_api::<ServerDispatcher, ReduceGrouped>()?
+        .with_api::<ServerDispatcher, RevokeAuthenticationToken>()?
         .with_api::<ServerDispatcher, SubscribeTo>()?
         .with_api::<ServerDispatcher, UnregisterSubscriber>()?
-        .with_api::<ServerDispatcher, UnsubscribeFrom>()?;
+        .with_api::<ServerDispatcher, UnsubscribeFrom>()?
+        .with_api::<ServerDispatcher, UpdatePermissionGroup>()?
+        .with_api::<ServerDispatcher, UpdateRole>()?;
 
     #[cfg(feature = "password-hashing")]
     {
@@ -66,6 +83,11 @@ pub fn register_api_handlers<B: Backend>(
             .with_api::<ServerDispatcher, SetUserPassword>()?;
     }
 
+    #[cfg(feature = "token-authentication")]
+    {
+        config = config.with_api::<ServerDispatcher, CreateAuthenticationToken>()?;
+    }
+
     Ok(config)
 }
 
@@ -258,6 +280,46 @@ impl<B: Backend> Handler<LogOutSession, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<ListSessions, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        _command: ListSessions,
+    ) -> HandlerResult<ListSessions> {
+        session
+            .as_client
+            .active_sessions()
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<TerminateSession, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: TerminateSession,
+    ) -> HandlerResult<TerminateSession> {
+        session
+            .as_client
+            .terminate_session(command.0)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<DisconnectClient, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: DisconnectClient,
+    ) -> HandlerResult<DisconnectClient> {
+        session
+            .as_client
+            .disconnect_client(command.0)
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<AlterUserPermissionGroupMembership, B> for ServerDispatcher {
     async fn handle(
@@ -302,13 +364,156 @@ impl<B: Backend> Handler<AlterUserRoleMembership, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<CreatePermissionGroup, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: CreatePermissionGroup,
+    ) -> HandlerResult<CreatePermissionGroup> {
+        session
+            .as_client
+            .create_permission_group(command.group)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<UpdatePermissionGroup, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: UpdatePermissionGroup,
+    ) -> HandlerResult<UpdatePermissionGroup> {
+        session
+            .as_client
+            .update_permission_group(command.group, command.updated)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<DeletePermissionGroup, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: DeletePermissionGroup,
+    ) -> HandlerResult<DeletePermissionGroup> {
+        session
+            .as_client
+            .delete_permission_group(command.group)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<CreateRole, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: CreateRole,
+    ) -> HandlerResult<CreateRole> {
+        session
+            .as_client
+            .create_role(command.role)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<UpdateRole, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: UpdateRole,
+    ) -> HandlerResult<UpdateRole> {
+        session
+            .as_client
+            .update_role(command.role, command.updated)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<DeleteRole, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: DeleteRole,
+    ) -> HandlerResult<DeleteRole> {
+        session
+            .as_client
+            .delete_role(command.role)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<ListAuthenticationTokens, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ListAuthenticationTokens,
+    ) -> HandlerResult<ListAuthenticationTokens> {
+        session
+            .as_client
+            .list_authentication_tokens(command.user)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<ExplainPermission, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ExplainPermission,
+    ) -> HandlerResult<ExplainPermission> {
+        session
+            .as_client
+            .explain_permission(
+                command.identity,
+                command.resource_name,
+                command.action,
+                command.client,
+            )
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[cfg(feature = "token-authentication")]
+#[async_trait]
+impl<B: Backend> Handler<CreateAuthenticationToken, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: CreateAuthenticationToken,
+    ) -> HandlerResult<CreateAuthenticationToken> {
+        session
+            .as_client
+            .create_authentication_token(command.identity, command.options)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<RevokeAuthenticationToken, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: RevokeAuthenticationToken,
+    ) -> HandlerResult<RevokeAuthenticationToken> {
+        session
+            .as_client
+            .revoke_authentication_token(command.token_id)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<Get, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: Get) -> HandlerResult<Get> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .get_from_collection(command.id, &command.collection)
             .await
@@ -322,10 +527,7 @@ impl<B: Backend> Handler<GetMultiple, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: GetMultiple,
     ) -> HandlerResult<GetMultiple> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .get_multiple_from_collection(&command.ids, &command.collection)
             .await
@@ -336,10 +538,7 @@ impl<B: Backend> Handler<GetMultiple, B> for ServerDispatcher {
 #[async_trait]
 impl<B: Backend> Handler<List, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: List) -> HandlerResult<List> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .list_from_collection(
                 command.ids,
@@ -358,10 +557,7 @@ impl<B: Backend> Handler<ListHeaders, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: ListHeaders,
     ) -> HandlerResult<ListHeaders> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.0.database)
-            .await?;
+        let database = session.database(&command.0.database).await?;
         database
             .list_headers_from_collection(
                 command.0.ids,
@@ -377,10 +573,7 @@ impl<B: Backend> Handler<ListHeaders, B> for ServerDispatcher {
 #[async_trait]
 impl<B: Backend> Handler<Count, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: Count) -> HandlerResult<Count> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .count_from_collection(command.ids, &command.collection)
             .await
@@ -391,10 +584,7 @@ impl<B: Backend> Handler<Count, B> for ServerDispatcher {
 #[async_trait]
 impl<B: Backend> Handler<Query, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: Query) -> HandlerResult<Query> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .query_by_name(
                 &command.view,
@@ -414,10 +604,7 @@ impl<B: Backend> Handler<QueryWithDocs, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: QueryWithDocs,
     ) -> HandlerResult<QueryWithDocs> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.0.database)
-            .await?;
+        let database = session.database(&command.0.database).await?;
         database
             .query_by_name_with_docs(
                 &command.0.view,
@@ -434,10 +621,7 @@ impl<B: Backend> Handler<QueryWithDocs, B> for ServerDispatcher {
 #[async_trait]
 impl<B: Backend> Handler<Reduce, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: Reduce) -> HandlerResult<Reduce> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .reduce_by_name(&command.view, command.key, command.access_policy)
             .await
@@ -452,10 +636,7 @@ impl<B: Backend> Handler<ReduceGrouped, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: ReduceGrouped,
     ) -> HandlerResult<ReduceGrouped> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.0.database)
-            .await?;
+        let database = session.database(&command.0.database).await?;
         database
             .reduce_grouped_by_name(&command.0.view, command.0.key, command.0.access_policy)
             .await
@@ -469,10 +650,7 @@ impl<B: Backend> Handler<ApplyTransaction, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: ApplyTransaction,
     ) -> HandlerResult<ApplyTransaction> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .apply_transaction(command.transaction)
             .await
@@ -486,10 +664,7 @@ impl<B: Backend> Handler<DeleteDocs, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: DeleteDocs,
     ) -> HandlerResult<DeleteDocs> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .delete_docs_by_name(&command.view, command.key, command.access_policy)
             .await
@@ -503,10 +678,7 @@ impl<B: Backend> Handler<ListExecutedTransactions, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: ListExecutedTransactions,
     ) -> HandlerResult<ListExecutedTransactions> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .list_executed_transactions(command.starting_id, command.result_limit)
             .await
@@ -520,10 +692,7 @@ impl<B: Backend> Handler<LastTransactionId, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: LastTransactionId,
     ) -> HandlerResult<LastTransactionId> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .last_transaction_id()
             .await
@@ -537,10 +706,7 @@ impl<B: Backend> Handler<CreateSubscriber, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: CreateSubscriber,
     ) -> HandlerResult<CreateSubscriber> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         let subscriber = database.create_subscriber().await?;
         let subscriber_id = subscriber.id();
 
@@ -556,10 +722,7 @@ impl<B: Backend> Handler<CreateSubscriber, B> for ServerDispatcher {
 #[async_trait]
 impl<B: Backend> Handler<Publish, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: Publish) -> HandlerResult<Publish> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .publish_bytes(command.topic.into_vec(), command.payload.into_vec())
             .await
@@ -573,10 +736,7 @@ impl<B: Backend> Handler<PublishToAll, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: PublishToAll,
     ) -> HandlerResult<PublishToAll> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .publish_bytes_to_all(
                 command.topics.into_iter().map(Bytes::into_vec),
@@ -643,10 +803,7 @@ impl<B: Backend> Handler<ExecuteKeyOperation, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: ExecuteKeyOperation,
     ) -> HandlerResult<ExecuteKeyOperation> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .execute_key_operation(command.op)
             .await
@@ -660,10 +817,7 @@ impl<B: Backend> Handler<CompactCollection, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: CompactCollection,
     ) -> HandlerResult<CompactCollection> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .compact_collection_by_name(command.name)
             .await
@@ -677,10 +831,7 @@ impl<B: Backend> Handler<CompactKeyValueStore, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: CompactKeyValueStore,
     ) -> HandlerResult<CompactKeyValueStore> {
-        let database = session
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = session.database(&command.database).await?;
         database
             .compact_key_value_store()
             .await
@@ -691,10 +842,7 @@ impl<B: Backend> Handler<CompactKeyValueStore, B> for ServerDispatcher {
 #[async_trait]
 impl<B: Backend> Handler<Compact, B> for ServerDispatcher {
     async fn handle(client: HandlerSession<'_, B>, command: Compact) -> HandlerResult<Compact> {
-        let database = client
-            .as_client
-            .database_without_schema(&command.database)
-            .await?;
+        let database = client.database(&command.database).await?;
         database.compact().await.map_err(HandlerError::from)
     }
 }
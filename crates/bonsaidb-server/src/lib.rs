@@ -34,11 +34,15 @@ pub use config::{
 };
 
 pub use self::backend::{Backend, BackendError, ConnectionHandling, NoBackend};
-pub use self::config::{BonsaiListenConfig, DefaultPermissions, ServerConfiguration};
+pub use self::config::{
+    AuthenticationThrottle, BonsaiListenConfig, ClientCertificates, DefaultPermissions, RateLimit,
+    RateLimits, ServerConfiguration,
+};
 pub use self::error::Error;
 pub use self::server::{
     ApplicationProtocols, ConnectedClient, CustomServer, HttpService, LockedClientDataGuard, Peer,
-    Server, ServerDatabase, StandardTcpProtocols, TcpService, Transport,
+    RateLimitScope, RequestMetrics, Server, ServerDatabase, ServerMetrics, StandardTcpProtocols,
+    TcpService, Transport,
 };
 
 #[cfg(test)]
@@ -5,10 +5,12 @@ use std::ops::Deref;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use bonsaidb_core::admin::{Admin, ADMIN_DATABASE_NAME};
+use bonsaidb_core::admin::{
+    Admin, AuthenticationTokenSummary, PermissionGroup, Role, ADMIN_DATABASE_NAME,
+};
 use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
 use bonsaidb_core::arc_bytes::serde::Bytes;
@@ -18,7 +20,9 @@ use bonsaidb_core::connection::{
 };
 use bonsaidb_core::networking::{self, Payload, CURRENT_PROTOCOL_VERSION};
 use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, BonsaiAction, ServerAction};
-use bonsaidb_core::permissions::Permissions;
+use bonsaidb_core::permissions::{
+    ActionName, ClientContext, ClientTransport, PermissionExplanation, Permissions, ResourceName,
+};
 use bonsaidb_core::schema::{self, Nameable, NamedCollection, Schema, SchemaSummary};
 use bonsaidb_local::config::Builder;
 use bonsaidb_local::{AsyncStorage, Storage, StorageNonBlocking};
@@ -43,13 +47,20 @@ use crate::dispatch::{register_api_handlers, ServerDispatcher};
 use crate::error::Error;
 use crate::hosted::{Hosted, SerializablePrivateKey, TlsCertificate, TlsCertificatesByDomain};
 use crate::server::shutdown::{Shutdown, ShutdownState, ShutdownStateWatcher};
-use crate::{Backend, BackendError, BonsaiListenConfig, NoBackend, ServerConfiguration};
+use crate::{
+    Backend, BackendError, BonsaiListenConfig, ClientCertificates, NoBackend, ServerConfiguration,
+};
 
 #[cfg(feature = "acme")]
 pub mod acme;
+mod client_certificates;
 mod connected_client;
 mod database;
-
+#[cfg(feature = "rest-api")]
+mod http;
+mod metrics;
+mod rate_limit;
+mod sessions;
 mod shutdown;
 mod tcp;
 #[cfg(feature = "websockets")]
@@ -58,6 +69,10 @@ mod websockets;
 use self::connected_client::OwnedClient;
 pub use self::connected_client::{ConnectedClient, LockedClientDataGuard, Transport};
 pub use self::database::ServerDatabase;
+use self::metrics::RequestMetricsCollector;
+pub use self::metrics::{RequestMetrics, ServerMetrics};
+pub use self::rate_limit::RateLimitScope;
+use self::rate_limit::RateLimiter;
 pub use self::tcp::{ApplicationProtocols, HttpService, Peer, StandardTcpProtocols, TcpService};
 
 static CONNECTED_CLIENT_ID_COUNTER: AtomicU32 = AtomicU32::new(0);
@@ -95,10 +110,16 @@ struct Data<B: Backend = NoBackend> {
     primary_tls_key: CachedCertifiedKey,
     primary_domain: String,
     custom_apis: RwLock<HashMap<ApiName, Arc<dyn AnyHandler<B>>>>,
+    request_metrics: RequestMetricsCollector,
+    rate_limiter: RateLimiter,
+    client_certificates: ClientCertificates,
+    client_certificate_authorities: Vec<fabruic::Certificate>,
     #[cfg(feature = "acme")]
     acme: AcmeConfiguration,
     #[cfg(feature = "acme")]
     alpn_keys: AlpnKeys,
+    #[cfg(feature = "rest-api")]
+    event_streams: http::EventStreams,
     shutdown: Shutdown,
 }
 
@@ -132,10 +153,16 @@ impl<B: Backend> CustomServer<B> {
                 while let Ok(mut client_request) = request_receiver.recv_async().await {
                     let request = client_request.request.take().unwrap();
                     let session = client_request.session.clone();
+                    let started_at = Instant::now();
                     // TODO we should be able to upgrade a session-less Storage to one with a Session.
                     // The Session needs to be looked up from the client based on the request's session id.
                     let result = match client_request.server.storage.assume_session(session) {
                         Ok(storage) => {
+                            let storage = storage
+                                .with_client_address(*client_request.client.address())
+                                .with_client_transport(ClientTransport::from(
+                                    client_request.client.transport(),
+                                ));
                             let client = HandlerSession {
                                 server: &client_request.server,
                                 client: &client_request.client,
@@ -143,6 +170,10 @@ impl<B: Backend> CustomServer<B> {
                                     data: client_request.server.data.clone(),
                                     storage,
                                 },
+                                request_bytes: request
+                                    .value
+                                    .as_ref()
+                                    .map_or(0, |value| value.len()),
                             };
                             ServerDispatcher::dispatch_api_request(
                                 client,
@@ -154,6 +185,11 @@ impl<B: Backend> CustomServer<B> {
                         }
                         Err(err) => Err(err),
                     };
+                    client_request.server.data.request_metrics.record(
+                        &request.name,
+                        started_at.elapsed(),
+                        result.is_ok(),
+                    );
                     drop(client_request.result_sender.send((request.name, result)));
                 }
             });
@@ -179,10 +215,19 @@ impl<B: Backend> CustomServer<B> {
                 primary_tls_key: CachedCertifiedKey::default(),
                 primary_domain: configuration.server_name,
                 custom_apis: parking_lot::RwLock::new(configuration.custom_apis),
+                request_metrics: RequestMetricsCollector::default(),
+                rate_limiter: RateLimiter::new(
+                    configuration.rate_limits,
+                    configuration.authentication_throttle,
+                ),
+                client_certificates: configuration.client_certificates,
+                client_certificate_authorities: configuration.client_certificate_authorities,
                 #[cfg(feature = "acme")]
                 acme: configuration.acme,
                 #[cfg(feature = "acme")]
                 alpn_keys: AlpnKeys::default(),
+                #[cfg(feature = "rest-api")]
+                event_streams: http::EventStreams::default(),
                 shutdown: Shutdown::new(),
             }),
         };
@@ -220,6 +265,20 @@ impl<B: Backend> CustomServer<B> {
         }
     }
 
+    /// Returns the database named `name` using the schema it was created
+    /// with, without requiring the schema's Rust type. This is useful for
+    /// tools that access data using [`SchemaSummary`].
+    pub async fn database_without_schema(
+        &self,
+        name: &str,
+    ) -> Result<ServerDatabase<B>, bonsaidb_core::Error> {
+        let db = self.storage.database_without_schema(name).await?;
+        Ok(ServerDatabase {
+            server: self.clone(),
+            db,
+        })
+    }
+
     pub(crate) async fn hosted(&self) -> ServerDatabase<B> {
         let db = self.storage.database::<Hosted>("_hosted").await.unwrap();
         ServerDatabase {
@@ -233,6 +292,16 @@ impl<B: Backend> CustomServer<B> {
         dispatchers.get(name).cloned()
     }
 
+    /// Returns a clone of this server that acts on behalf of a client
+    /// connected from `address`.
+    #[cfg(test)]
+    pub(crate) fn with_client_address(&self, address: SocketAddr) -> Self {
+        Self {
+            data: self.data.clone(),
+            storage: self.storage.with_client_address(address),
+        }
+    }
+
     /// Installs an X.509 certificate used for general purpose connections.
     pub async fn install_self_signed_certificate(&self, overwrite: bool) -> Result<(), Error> {
         let keypair = KeyPair::new_self_signed(&self.data.primary_domain);
@@ -386,6 +455,10 @@ impl<B: Backend> CustomServer<B> {
         builder.set_protocols([CURRENT_PROTOCOL_VERSION.as_bytes().to_vec()]);
         builder.set_address(config.address);
         builder.set_max_idle_timeout(None)?;
+        // fabruic always requests a certificate from connecting clients and
+        // accepts any certificate presented, but it never requires one. The
+        // configured `ClientCertificates` mode is enforced once the handshake
+        // completes in `handle_bonsai_connection`.
         builder.set_server_key_pair(Some(keypair));
         builder.set_reuse_address(config.reuse_address);
         let mut server = builder.build()?;
@@ -433,6 +506,18 @@ impl<B: Backend> CustomServer<B> {
         clients.values().cloned().collect()
     }
 
+    /// Returns a snapshot of the metrics collected since this server was
+    /// started.
+    #[must_use]
+    pub fn metrics(&self) -> ServerMetrics {
+        ServerMetrics {
+            connected_clients: self.data.clients.read().len(),
+            request_queue_depth: self.data.request_processor.len(),
+            requests: self.data.request_metrics.snapshot(),
+            storage: self.storage.metrics(),
+        }
+    }
+
     /// Sends a custom API response to all connected clients.
     pub fn broadcast<Api: api::Api>(&self, response: &Api::Response) {
         let clients = self.data.clients.read();
@@ -446,6 +531,7 @@ impl<B: Backend> CustomServer<B> {
         &self,
         transport: Transport,
         address: SocketAddr,
+        client_certificate: Option<&[u8]>,
         sender: Sender<(Option<SessionId>, ApiName, Bytes)>,
     ) -> Option<OwnedClient<B>> {
         if !self.data.default_session.allowed_to(
@@ -455,6 +541,14 @@ impl<B: Backend> CustomServer<B> {
             return None;
         }
 
+        let session = match self.client_certificate_session(client_certificate).await {
+            Ok(session) => session.unwrap_or_else(|| self.data.default_session.clone()),
+            Err(err) => {
+                log::warn!("[server] Rejecting connection from {address}: {err}");
+                return None;
+            }
+        };
+
         let client = loop {
             let next_id = CONNECTED_CLIENT_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
             let mut clients = self.data.clients.write();
@@ -465,7 +559,7 @@ impl<B: Backend> CustomServer<B> {
                     transport,
                     sender,
                     self.clone(),
-                    self.data.default_session.clone(),
+                    session.clone(),
                 );
                 e.insert(client.clone());
                 break client;
@@ -484,7 +578,7 @@ impl<B: Backend> CustomServer<B> {
         }
     }
 
-    async fn disconnect_client(&self, id: u32) {
+    async fn remove_client(&self, id: u32) {
         let removed_client = {
             let mut clients = self.data.clients.write();
             clients.remove(&id)
@@ -513,6 +607,24 @@ impl<B: Backend> CustomServer<B> {
         &self,
         mut connection: fabruic::Connection<()>,
     ) -> Result<(), Error> {
+        let client_certificate = match self.data.client_certificates {
+            ClientCertificates::Ignored => None,
+            mode => {
+                let certificate = connection
+                    .peer_identity()
+                    .map(CertificateChain::into_end_entity_certificate);
+                if certificate.is_none() && mode == ClientCertificates::Required {
+                    log::warn!(
+                        "[server] Rejecting connection from {}: no client certificate presented",
+                        connection.remote_address()
+                    );
+                    drop(connection.close().await);
+                    return Ok(());
+                }
+                certificate
+            }
+        };
+
         if let Some(incoming) = connection.next().await {
             let incoming = match incoming {
                 Ok(incoming) => incoming,
@@ -532,6 +644,7 @@ impl<B: Backend> CustomServer<B> {
                         .initialize_client(
                             Transport::Bonsai,
                             connection.remote_address(),
+                            client_certificate.as_ref().map(AsRef::as_ref),
                             api_response_sender,
                         )
                         .await
@@ -659,9 +772,17 @@ impl<B: Backend> CustomServer<B> {
         client: ConnectedClient<B>,
     ) -> Result<(), Error> {
         let (result_sender, result_receiver) = oneshot::channel();
+        client.record_activity();
         let session = client
             .session(request.session_id)
             .unwrap_or_else(|| self.data.default_session.clone());
+        let request_bytes = request.value.as_ref().map_or(0, |value| value.len());
+        if let Err(err) = self.check_client_rate_limits(&client, &session, request_bytes) {
+            // Rate limited requests are rejected without occupying a worker.
+            let name = request.name;
+            tokio::spawn(async move { callback(name, Err(err)).await });
+            return Ok(());
+        }
         self.data
             .request_processor
             .send(ClientRequest::<B>::new(
@@ -722,6 +843,7 @@ impl<B: Backend> CustomServer<B> {
 
         let (request_sender, request_receiver) =
             flume::bounded::<Payload>(self.data.client_simultaneous_request_limit);
+        let connected_client = client.clone();
         let task_self = self.clone();
         tokio::spawn({
             let shutdown = shutdown.clone();
@@ -754,6 +876,9 @@ impl<B: Backend> CustomServer<B> {
                             return Ok(());
                         }
                     }
+                    () = connected_client.disconnect_requested() => {
+                        return Ok(());
+                    }
                 }
             };
             drop(request_sender.send_async(payload?).await);
@@ -958,10 +1083,27 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         &self,
         authentication: bonsaidb_core::connection::Authentication,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
-        let storage = self.storage.authenticate(authentication).await?;
+        let reservation = match self.storage.client_address() {
+            Some(address) => self.reserve_authentication_attempt(address.ip())?,
+            None => None,
+        };
+
+        let result = self.storage.authenticate(authentication).await;
+        if let Some(reservation) = reservation {
+            // Only failures caused by the credentials count towards the
+            // throttle.
+            if !matches!(
+                result,
+                Err(bonsaidb_core::Error::InvalidCredentials
+                    | bonsaidb_core::Error::UserLockedOut { .. })
+            ) {
+                self.release_authentication_attempt(reservation);
+            }
+        }
+
         Ok(Self {
             data: self.data.clone(),
-            storage,
+            storage: result?,
         })
     }
 
@@ -1031,6 +1173,83 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
     ) -> Result<(), bonsaidb_core::Error> {
         self.storage.remove_role_from_user(user, role).await
     }
+
+    async fn create_permission_group(
+        &self,
+        group: PermissionGroup,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        self.storage.create_permission_group(group).await
+    }
+
+    async fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage
+            .update_permission_group(permission_group, updated)
+            .await
+    }
+
+    async fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.delete_permission_group(permission_group).await
+    }
+
+    async fn create_role(&self, role: Role) -> Result<u64, bonsaidb_core::Error> {
+        self.storage.create_role(role).await
+    }
+
+    async fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.update_role(role, updated).await
+    }
+
+    async fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.delete_role(role).await
+    }
+
+    async fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        self.storage.list_authentication_tokens(user).await
+    }
+
+    #[cfg(feature = "token-authentication")]
+    async fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: bonsaidb_core::admin::AuthenticationTokenOptions,
+    ) -> Result<bonsaidb_core::admin::CreatedAuthenticationToken, bonsaidb_core::Error> {
+        self.storage
+            .create_authentication_token(identity, options)
+            .await
+    }
+
+    async fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        self.storage.revoke_authentication_token(token_id).await
+    }
+
+    async fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        self.storage
+            .explain_permission(identity, resource_name, action, client)
+            .await
+    }
 }
 
 #[derive(Default)]
@@ -0,0 +1,90 @@
+use bonsaidb_core::admin::client_certificate::ByMatcher;
+use bonsaidb_core::admin::CertificateMatcher;
+use bonsaidb_core::connection::{
+    AsyncConnection, AsyncStorageConnection, HasSession, IdentityId, IdentityReference, Session,
+};
+use x509_parser::certificate::X509Certificate;
+
+use crate::{Backend, ClientCertificates, CustomServer};
+
+impl<B: Backend> CustomServer<B> {
+    /// Returns the session a client presenting the DER-encoded `certificate`
+    /// should begin with, if the certificate is mapped to an identity.
+    ///
+    /// Returns an error if [`ClientCertificates::Required`] is configured and
+    /// the certificate is missing or not mapped to an identity.
+    pub(crate) async fn client_certificate_session(
+        &self,
+        certificate: Option<&[u8]>,
+    ) -> Result<Option<Session>, bonsaidb_core::Error> {
+        let mode = self.data.client_certificates;
+        let identity = match (mode, certificate) {
+            (ClientCertificates::Ignored, _) => None,
+            (_, Some(certificate)) => self.certificate_identity(certificate).await?,
+            (_, None) => None,
+        };
+
+        let reference = match identity {
+            Some(IdentityId::User(id)) => IdentityReference::user(id)?,
+            Some(IdentityId::Role(id)) => IdentityReference::role(id)?,
+            _ if mode == ClientCertificates::Required => {
+                return Err(bonsaidb_core::Error::InvalidCredentials)
+            }
+            _ => return Ok(None),
+        };
+
+        let authenticated = self.storage.assume_identity(reference).await?;
+        Ok(authenticated.session().cloned())
+    }
+
+    async fn certificate_identity(
+        &self,
+        certificate: &[u8],
+    ) -> Result<Option<IdentityId>, bonsaidb_core::Error> {
+        let parsed = match x509_parser::parse_x509_certificate(certificate) {
+            Ok((_, parsed)) => parsed,
+            Err(err) => {
+                log::warn!("[server] Unable to parse client certificate: {err:?}");
+                return Ok(None);
+            }
+        };
+        if !parsed.validity().is_valid() {
+            return Ok(None);
+        }
+
+        let mut matchers = vec![CertificateMatcher::fingerprint_of(certificate)];
+        if self.issued_by_trusted_authority(&parsed) {
+            matchers.push(CertificateMatcher::subject(parsed.subject().to_string()));
+        }
+
+        let admin = self.storage.admin().await;
+        for matcher in matchers {
+            if let Some(mapping) = admin
+                .view::<ByMatcher>()
+                .with_key(&matcher.to_string())
+                .query_with_collection_docs()
+                .await?
+                .documents
+                .into_values()
+                .next()
+            {
+                return Ok(Some(mapping.contents.identity));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn issued_by_trusted_authority(&self, certificate: &X509Certificate<'_>) -> bool {
+        self.data
+            .client_certificate_authorities
+            .iter()
+            .filter_map(|authority| x509_parser::parse_x509_certificate(authority.as_ref()).ok())
+            .any(|(_, authority)| {
+                certificate.issuer() == authority.subject()
+                    && certificate
+                        .verify_signature(Some(authority.public_key()))
+                        .is_ok()
+            })
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -9,14 +10,18 @@ use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{Session, SessionId};
+use bonsaidb_core::key::time::TimestampAsNanoseconds;
 use bonsaidb_core::networking::MessageReceived;
+use bonsaidb_core::permissions::ClientTransport;
 use bonsaidb_core::pubsub::{Receiver, Subscriber as _};
 use bonsaidb_local::Subscriber;
 use bonsaidb_utils::fast_async_lock;
 use derive_where::derive_where;
 use flume::Sender;
 use parking_lot::RwLock;
+use tokio::sync::Notify;
 
+use crate::server::rate_limit::TokenBucket;
 use crate::{Backend, CustomServer, Error, NoBackend};
 
 /// The ways a client can be connected to the server.
@@ -29,6 +34,26 @@ pub enum Transport {
     WebSocket,
 }
 
+impl Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transport::Bonsai => f.write_str("bonsai"),
+            #[cfg(feature = "websockets")]
+            Transport::WebSocket => f.write_str("websocket"),
+        }
+    }
+}
+
+impl From<&Transport> for ClientTransport {
+    fn from(transport: &Transport) -> Self {
+        match transport {
+            Transport::Bonsai => Self::Bonsai,
+            #[cfg(feature = "websockets")]
+            Transport::WebSocket => Self::WebSocket,
+        }
+    }
+}
+
 /// A connected database client.
 #[derive(Debug)]
 #[derive_where(Clone)]
@@ -45,6 +70,10 @@ struct Data<B: Backend = NoBackend> {
     response_sender: Sender<(Option<SessionId>, ApiName, Bytes)>,
     client_data: Mutex<Option<B::ClientData>>,
     connected: AtomicBool,
+    connected_at: TimestampAsNanoseconds,
+    last_activity_at: parking_lot::Mutex<TimestampAsNanoseconds>,
+    disconnect: Notify,
+    rate_limit: parking_lot::Mutex<Option<TokenBucket>>,
 }
 
 #[derive(Debug)]
@@ -54,6 +83,12 @@ struct ClientSession {
 }
 
 impl<B: Backend> ConnectedClient<B> {
+    /// Returns the unique id of this client.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.data.id
+    }
+
     /// Returns the address of the connected client.
     #[must_use]
     pub fn address(&self) -> &SocketAddr {
@@ -76,6 +111,37 @@ impl<B: Backend> ConnectedClient<B> {
         self.data.connected.store(false, Ordering::Relaxed);
     }
 
+    /// Returns the time the client connected.
+    #[must_use]
+    pub fn connected_at(&self) -> TimestampAsNanoseconds {
+        self.data.connected_at
+    }
+
+    /// Returns the time the client last sent a request.
+    #[must_use]
+    pub fn last_activity_at(&self) -> TimestampAsNanoseconds {
+        *self.data.last_activity_at.lock()
+    }
+
+    pub(crate) fn record_activity(&self) {
+        *self.data.last_activity_at.lock() = TimestampAsNanoseconds::now();
+    }
+
+    /// Closes the client's connection. The client's sessions end once the
+    /// connection has been closed.
+    pub fn disconnect(&self) {
+        self.data.disconnect.notify_one();
+    }
+
+    /// Waits until [`disconnect()`](Self::disconnect) is called.
+    pub(crate) async fn disconnect_requested(&self) {
+        self.data.disconnect.notified().await;
+    }
+
+    pub(crate) fn rate_limit_bucket(&self) -> &parking_lot::Mutex<Option<TokenBucket>> {
+        &self.data.rate_limit
+    }
+
     pub(crate) fn logged_in_as(&self, session: Session) {
         let mut sessions = self.data.sessions.write();
         sessions.insert(
@@ -300,6 +366,10 @@ impl<B: Backend> OwnedClient<B> {
                     sessions: RwLock::new(session),
                     client_data: Mutex::default(),
                     connected: AtomicBool::new(true),
+                    connected_at: TimestampAsNanoseconds::now(),
+                    last_activity_at: parking_lot::Mutex::new(TimestampAsNanoseconds::now()),
+                    disconnect: Notify::new(),
+                    rate_limit: parking_lot::Mutex::default(),
                 }),
             },
             runtime: Arc::new(tokio::runtime::Handle::current()),
@@ -317,7 +387,7 @@ impl<B: Backend> Drop for OwnedClient<B> {
         let id = self.client.data.id;
         let server = self.server.take().unwrap();
         self.runtime
-            .spawn(async move { server.disconnect_client(id).await });
+            .spawn(async move { server.remove_client(id).await });
     }
 }
 
@@ -0,0 +1,165 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use fabruic::Certificate;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Request, Response, StatusCode};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{Backend, CustomServer, Error};
+
+#[cfg(any(feature = "s3-api", feature = "webdav"))]
+mod files;
+mod rest;
+#[cfg(feature = "s3-api")]
+mod s3;
+#[cfg(feature = "webdav")]
+mod webdav;
+
+pub(crate) use self::rest::EventStreams;
+
+impl<B: Backend> CustomServer<B> {
+    /// Serves HTTP/1.1 requests on `connection` using
+    /// [`Self::handle_http_request()`]. If the peer presented
+    /// `client_certificate` during the TLS handshake, it is inserted into each
+    /// request's extensions.
+    pub(crate) async fn handle_http_connection<
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    >(
+        &self,
+        connection: S,
+        peer_address: SocketAddr,
+        client_certificate: Option<Certificate>,
+    ) -> Result<(), Error> {
+        let task_self = self.clone();
+        Http::new()
+            .http1_only(true)
+            .serve_connection(
+                connection,
+                service_fn(move |mut request: Request<Body>| {
+                    let task_self = task_self.clone();
+                    if let Some(client_certificate) = &client_certificate {
+                        request.extensions_mut().insert(client_certificate.clone());
+                    }
+                    async move {
+                        Ok::<_, Infallible>(
+                            task_self.handle_http_request(peer_address, request).await,
+                        )
+                    }
+                }),
+            )
+            .with_upgrades()
+            .await
+            .map_err(|err| Error::other("bonsaidb-server http", err))
+    }
+
+    /// Handles an HTTP `request` from `peer_address` using BonsaiDb's built-in
+    /// HTTP services. Requires feature `rest-api` to be enabled.
+    ///
+    /// - Requests to upgrade to the `WebSocket` protocol are handled using
+    ///   [`Self::upgrade_websocket()`], if feature `websockets` is enabled.
+    /// - Requests whose path begins with `/v1/` are handled by the REST API.
+    /// - Requests whose path begins with `/dav/` are handled by the WebDAV
+    ///   server using [`Self::handle_webdav_request()`] with
+    ///   [`BonsaiFiles`](bonsaidb_files::BonsaiFiles), if feature `webdav` is
+    ///   enabled.
+    /// - All other requests are handled by the S3-compatible object API using
+    ///   [`Self::handle_s3_request()`] with
+    ///   [`BonsaiFiles`](bonsaidb_files::BonsaiFiles), if feature `s3-api` is
+    ///   enabled.
+    ///
+    /// This function can be used to embed BonsaiDb's HTTP services in a
+    /// custom [`HttpService`](crate::HttpService).
+    pub async fn handle_http_request(
+        &self,
+        peer_address: SocketAddr,
+        mut request: Request<Body>,
+    ) -> Response<Body> {
+        #[cfg(feature = "websockets")]
+        if request.headers().contains_key(hyper::header::UPGRADE) {
+            return self.upgrade_versioned_websocket(peer_address, request);
+        }
+
+        // Authentication attempts and audit entries are attributed to the
+        // peer's address.
+        request.extensions_mut().insert(peer_address);
+
+        if request.uri().path().starts_with(rest::PATH_PREFIX) {
+            return self.handle_rest_request(request).await;
+        }
+
+        #[cfg(feature = "webdav")]
+        if request.uri().path().starts_with(webdav::PATH_PREFIX)
+            || request.uri().path() == webdav::PATH_PREFIX.trim_end_matches('/')
+        {
+            return self
+                .handle_webdav_request::<bonsaidb_files::BonsaiFiles>(request)
+                .await;
+        }
+
+        #[cfg(feature = "s3-api")]
+        {
+            self.handle_s3_request::<bonsaidb_files::BonsaiFiles>(request)
+                .await
+        }
+        #[cfg(not(feature = "s3-api"))]
+        {
+            error_response(StatusCode::NOT_FOUND, "not found")
+        }
+    }
+
+    #[cfg(feature = "websockets")]
+    fn upgrade_versioned_websocket(
+        &self,
+        peer_address: SocketAddr,
+        request: Request<Body>,
+    ) -> Response<Body> {
+        use bonsaidb_core::networking::CURRENT_PROTOCOL_VERSION;
+        use hyper::header::SEC_WEBSOCKET_PROTOCOL;
+
+        // Clients that don't request a protocol are assumed to speak the
+        // current version. Only clients requesting other versions are
+        // rejected.
+        let requests_current_version =
+            request
+                .headers()
+                .get(SEC_WEBSOCKET_PROTOCOL)
+                .map(|protocols| {
+                    protocols.to_str().map_or(false, |protocols| {
+                        protocols
+                            .split(',')
+                            .map(str::trim)
+                            .any(|protocol| protocol == CURRENT_PROTOCOL_VERSION)
+                    })
+                });
+        if requests_current_version == Some(false) {
+            return error_response(StatusCode::NOT_ACCEPTABLE, "unsupported protocol version");
+        }
+
+        let mut response = self.upgrade_websocket(peer_address, request);
+        if requests_current_version.is_some()
+            && response.status() == StatusCode::SWITCHING_PROTOCOLS
+        {
+            response.headers_mut().insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(CURRENT_PROTOCOL_VERSION),
+            );
+        }
+        response
+    }
+}
+
+pub(crate) fn json_response(status: StatusCode, value: &serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(value.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+pub(crate) fn error_response(status: StatusCode, message: impl ToString) -> Response<Body> {
+    json_response(status, &serde_json::json!({ "error": message.to_string() }))
+}
@@ -0,0 +1,250 @@
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bonsaidb_files::direct::{Async, File};
+use bonsaidb_files::FileConfig;
+use bonsaidb_local::AsyncDatabase;
+use hyper::header::{
+    HeaderMap, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+    LAST_MODIFIED, RANGE,
+};
+use hyper::{Body, Response, StatusCode};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// A file accessed by an HTTP file service.
+pub(super) type FileHandle<Config> = File<Async<AsyncDatabase>, Config>;
+
+/// An error that occurs while serving a file's contents.
+pub(super) enum ContentsError {
+    Database(bonsaidb_core::Error),
+    Io(std::io::Error),
+    /// The requested range does not overlap a file of the contained length.
+    UnsatisfiableRange(u64),
+}
+
+impl From<bonsaidb_core::Error> for ContentsError {
+    fn from(err: bonsaidb_core::Error) -> Self {
+        Self::Database(err)
+    }
+}
+
+impl From<std::io::Error> for ContentsError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Returns a response containing the contents of `file`. If `headers`
+/// contains a satisfiable single byte range, only that range is returned.
+pub(super) async fn contents_response<Config: FileConfig>(
+    file: &FileHandle<Config>,
+    headers: &HeaderMap,
+    include_body: bool,
+) -> Result<Response<Body>, ContentsError> {
+    let mut contents = file.contents().await?;
+    let length = contents.len();
+    let range = byte_range(headers, length)?;
+
+    let mut response = Response::new(Body::empty());
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    response_headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Some(last_modified) =
+        header_value(httpdate::fmt_http_date(last_modified(file, &contents)))
+    {
+        response_headers.insert(LAST_MODIFIED, last_modified);
+    }
+    if let Some(etag) = etag(file).await?.and_then(header_value) {
+        response_headers.insert(ETAG, etag);
+    }
+
+    if let Some(range) = range {
+        if let Some(content_range) =
+            header_value(format!("bytes {}-{}/{length}", range.start, range.end - 1))
+        {
+            response_headers.insert(CONTENT_RANGE, content_range);
+        }
+        response_headers.insert(CONTENT_LENGTH, HeaderValue::from(range.end - range.start));
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+        if include_body {
+            let range_length = usize::try_from(range.end - range.start)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::OutOfMemory, err))?;
+            let mut buffer = vec![0; range_length];
+            contents.seek(SeekFrom::Start(range.start)).await?;
+            contents.read_exact(&mut buffer).await?;
+            *response.body_mut() = Body::from(buffer);
+        }
+    } else {
+        response_headers.insert(CONTENT_LENGTH, HeaderValue::from(length));
+        if include_body {
+            *response.body_mut() = Body::wrap_stream(contents);
+        }
+    }
+    Ok(response)
+}
+
+/// Parses the `Range` header into an exclusive range of bytes within a file
+/// of `length` bytes. Only single byte ranges are supported, and all other
+/// ranges are ignored.
+fn byte_range(headers: &HeaderMap, length: u64) -> Result<Option<Range<u64>>, ContentsError> {
+    let Some(ranges) = headers
+        .get(RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.trim().strip_prefix("bytes="))
+    else {
+        return Ok(None);
+    };
+    let Some((start, end)) = ranges.split_once('-').filter(|_| !ranges.contains(',')) else {
+        return Ok(None);
+    };
+
+    let range = match (start.trim(), end.trim()) {
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(suffix) if suffix > 0 => length.saturating_sub(suffix)..length,
+            _ => return Err(ContentsError::UnsatisfiableRange(length)),
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => start..length,
+            Err(_) => return Ok(None),
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => start..length.min(end + 1),
+            _ => return Ok(None),
+        },
+    };
+    if range.start < length {
+        Ok(Some(range))
+    } else {
+        Err(ContentsError::UnsatisfiableRange(length))
+    }
+}
+
+/// Returns the quoted entity tag of `file`, derived from its checksum.
+pub(super) async fn etag<Config: FileConfig>(
+    file: &FileHandle<Config>,
+) -> Result<Option<String>, bonsaidb_core::Error> {
+    Ok(file
+        .checksum()
+        .await?
+        .map(|checksum| format!("\"{checksum}\"")))
+}
+
+/// Returns the time `file` was last written to, falling back to its creation
+/// time if it is empty.
+pub(super) fn last_modified<Database, Config>(
+    file: &File<Database, Config>,
+    contents: &bonsaidb_files::direct::Contents<Database, Config>,
+) -> SystemTime
+where
+    Database: Clone,
+    Config: FileConfig,
+{
+    SystemTime::try_from(
+        contents
+            .last_appended_at()
+            .unwrap_or_else(|| file.created_at()),
+    )
+    .unwrap_or(UNIX_EPOCH)
+}
+
+/// Replaces the file at `path` with a new file containing `contents`.
+pub(super) async fn replace_file<Config>(
+    database: &AsyncDatabase,
+    path: &str,
+    contents: &[u8],
+) -> Result<FileHandle<Config>, bonsaidb_files::Error>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    Config::delete_async(path, database).await?;
+    Config::build(path)
+        .contents(contents)
+        .create_async(database)
+        .await
+}
+
+/// Decodes a percent-encoded path, returning `None` if the decoded path is not
+/// valid UTF-8.
+pub(super) fn decode_path(path: &str) -> Option<String> {
+    percent_encoding::percent_decode_str(path)
+        .decode_utf8()
+        .ok()
+        .map(|path| path.into_owned())
+}
+
+pub(super) fn header_value(value: String) -> Option<HeaderValue> {
+    HeaderValue::try_from(value).ok()
+}
+
+pub(super) fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// Appends `<name>value</name>` to `xml`, escaping `value`.
+pub(super) fn push_element(xml: &mut String, name: &str, value: &str) {
+    xml.push('<');
+    xml.push_str(name);
+    xml.push('>');
+    push_escaped(xml, value);
+    xml.push_str("</");
+    xml.push_str(name);
+    xml.push('>');
+}
+
+/// Appends `value` to `xml`, escaping characters that have special meaning in
+/// XML.
+pub(super) fn push_escaped(xml: &mut String, value: &str) {
+    for ch in value.chars() {
+        match ch {
+            '&' => xml.push_str("&amp;"),
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            '"' => xml.push_str("&quot;"),
+            '\'' => xml.push_str("&apos;"),
+            ch => xml.push(ch),
+        }
+    }
+}
+
+/// Formats `time` as an ISO 8601 timestamp in UTC with millisecond precision.
+pub(super) fn iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days(seconds / 86_400);
+    let time_of_day = seconds % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Converts a number of days since the Unix epoch into a (year, month, day)
+/// in the proleptic Gregorian calendar.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // Based on Howard Hinnant's `civil_from_days` algorithm, with eras
+    // starting on March 1st.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    (year_of_era + era * 400 + u64::from(month <= 2), month, day)
+}
@@ -0,0 +1,965 @@
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::connection::{
+    AccessPolicy, AsyncLowLevelConnection, AsyncStorageConnection, Bound, HasSchema, HasSession,
+    Range, SerializedQueryKey, Sort,
+};
+use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
+use bonsaidb_core::key::{ByteSource, Key, KeyDescription, KeyEncoding, KeyKind};
+use bonsaidb_core::keyvalue::{
+    AsyncKeyValue, Command, KeyOperation, KeyStatus, Numeric, Output, SetCommand, Value,
+};
+use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, BonsaiAction, ServerAction};
+use bonsaidb_core::permissions::ClientTransport;
+use bonsaidb_core::pubsub::AsyncPubSub;
+use bonsaidb_core::schema::{CollectionName, InvalidNameError, SerializationFormat, ViewName};
+use bonsaidb_core::transaction::{OperationResult, Transaction};
+use bonsaidb_local::{AsyncDatabase, StorageNonBlocking};
+use hyper::header::{
+    HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, IF_MATCH, RETRY_AFTER,
+};
+use hyper::http::request::Parts;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::json;
+
+use super::{error_response, json_response};
+use crate::{Backend, CustomServer};
+
+mod sse;
+
+pub(crate) use self::sse::EventStreams;
+
+/// The path prefix that all REST API requests are served beneath.
+pub(super) const PATH_PREFIX: &str = "/v1/";
+
+impl<B: Backend> CustomServer<B> {
+    /// Handles a REST API request. All operations are performed with the
+    /// permissions of the session authenticated by the request's
+    /// `Authorization` header, or the default session if none is provided.
+    pub(super) async fn handle_rest_request(&self, request: Request<Body>) -> Response<Body> {
+        match self.route_rest_request(request).await {
+            Ok(response) => response,
+            Err(err) => err.into_response(),
+        }
+    }
+
+    async fn route_rest_request(
+        &self,
+        request: Request<Body>,
+    ) -> Result<Response<Body>, RestError> {
+        let (parts, body) = request.into_parts();
+        let client = self.rest_client(&parts).await?;
+        let request_bytes = parts
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        if let Some(session) = client.session() {
+            self.check_user_rate_limit(session, request_bytes)?;
+        }
+
+        let segments = parts.uri.path()[PATH_PREFIX.len()..]
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                percent_encoding::percent_decode_str(segment)
+                    .decode_utf8()
+                    .map(|segment| segment.into_owned())
+                    .map_err(RestError::bad_request)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+        let request = RestRequest {
+            method: parts.method,
+            query: parts
+                .uri
+                .query()
+                .map(|query| {
+                    form_urlencoded::parse(query.as_bytes())
+                        .into_owned()
+                        .collect()
+                })
+                .unwrap_or_default(),
+            headers: parts.headers,
+            body,
+        };
+
+        match (&request.method, segments.as_slice()) {
+            (&Method::GET, ["databases"]) => {
+                let databases = client.list_databases().await?;
+                Ok(json_response(
+                    StatusCode::OK,
+                    &serde_json::to_value(databases).map_err(RestError::internal)?,
+                ))
+            }
+            (&Method::GET, ["metrics"]) => {
+                client.check_permission(
+                    bonsaidb_resource_name(),
+                    &BonsaiAction::Server(ServerAction::ViewMetrics),
+                )?;
+                let mut response = Response::new(Body::from(self.metrics().to_prometheus_text()));
+                response.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("text/plain; version=0.0.4"),
+                );
+                Ok(response)
+            }
+            (&Method::GET, ["databases", database, "events"]) => {
+                let database = client.storage.database_without_schema(database).await?;
+                self.check_database_rate_limit(&database, request_bytes)?;
+                self.stream_events(database, &request).await
+            }
+            (_, ["databases", database, rest @ ..]) => {
+                let database = client.storage.database_without_schema(database).await?;
+                self.check_database_rate_limit(&database, request_bytes)?;
+                route_database_request(&database, rest, request).await
+            }
+            _ => Err(RestError::not_found()),
+        }
+    }
+
+    /// Returns a client for the session authenticated by `parts`'
+    /// `Authorization` header, or the default session if none is provided.
+    pub(super) async fn rest_client(&self, parts: &Parts) -> Result<Self, RestError> {
+        let client = Self {
+            data: self.data.clone(),
+            storage: self
+                .storage
+                .assume_session(self.data.default_session.clone())?,
+        }
+        .with_rest_client_context(parts);
+        match parts.headers.get(AUTHORIZATION) {
+            Some(authorization) => {
+                let authorization = authorization
+                    .to_str()
+                    .map_err(|_| RestError::unauthorized("invalid authorization header"))?;
+                // Authenticating replaces the session, so the client context
+                // must be applied again.
+                Ok(client
+                    .authenticate_rest(authorization)
+                    .await?
+                    .with_rest_client_context(parts))
+            }
+            None => Ok(client),
+        }
+    }
+
+    fn with_rest_client_context(self, parts: &Parts) -> Self {
+        let mut storage = self.storage.with_client_transport(ClientTransport::Http);
+        if let Some(address) = parts.extensions.get::<SocketAddr>() {
+            storage = storage.with_client_address(*address);
+        }
+        Self {
+            data: self.data,
+            storage,
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "password-hashing", feature = "token-authentication")),
+        allow(clippy::unused_async, unused_variables)
+    )]
+    async fn authenticate_rest(self, authorization: &str) -> Result<Self, RestError> {
+        let (scheme, credentials) = authorization
+            .split_once(' ')
+            .ok_or_else(|| RestError::unauthorized("invalid authorization header"))?;
+        let credentials = credentials.trim();
+
+        #[cfg(feature = "password-hashing")]
+        if scheme.eq_ignore_ascii_case("basic") {
+            use base64::Engine;
+            let credentials = base64::engine::general_purpose::STANDARD
+                .decode(credentials)
+                .ok()
+                .and_then(|credentials| String::from_utf8(credentials).ok())
+                .ok_or_else(|| RestError::unauthorized("invalid basic credentials"))?;
+            let (username, password) = credentials
+                .split_once(':')
+                .ok_or_else(|| RestError::unauthorized("invalid basic credentials"))?;
+            return Ok(self
+                .authenticate_with_password(
+                    username,
+                    bonsaidb_core::connection::SensitiveString(password.to_string()),
+                )
+                .await?);
+        }
+
+        #[cfg(feature = "token-authentication")]
+        if scheme.eq_ignore_ascii_case("bearer") {
+            let (id, token) = credentials
+                .split_once(':')
+                .and_then(|(id, token)| id.parse::<u64>().ok().map(|id| (id, token)))
+                .ok_or_else(|| RestError::unauthorized("invalid bearer token"))?;
+            return Ok(self
+                .authenticate_with_token(
+                    id,
+                    &bonsaidb_core::connection::SensitiveString(token.to_string()),
+                )
+                .await?);
+        }
+
+        Err(RestError::unauthorized("unsupported authorization scheme"))
+    }
+}
+
+struct RestRequest {
+    method: Method,
+    headers: HeaderMap,
+    query: Vec<(String, String)>,
+    body: Body,
+}
+
+impl RestRequest {
+    async fn json_body(self) -> Result<serde_json::Value, RestError> {
+        let body = hyper::body::to_bytes(self.body)
+            .await
+            .map_err(RestError::bad_request)?;
+        serde_json::from_slice(&body).map_err(RestError::bad_request)
+    }
+
+    /// Returns the first value of the query parameter `name`.
+    fn query_value(&self, name: &str) -> Option<&String> {
+        self.query
+            .iter()
+            .find_map(|(key, value)| (key == name).then_some(value))
+    }
+
+    /// Returns all values of the query parameter `name`.
+    fn query_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.query
+            .iter()
+            .filter_map(move |(key, value)| (key == name).then_some(value))
+    }
+
+    fn order(&self) -> Result<Sort, RestError> {
+        match self.query_value("order").map(String::as_str) {
+            None | Some("asc" | "ascending") => Ok(Sort::Ascending),
+            Some("desc" | "descending") => Ok(Sort::Descending),
+            Some(other) => Err(RestError::bad_request(format!("invalid order: {other}"))),
+        }
+    }
+
+    fn limit(&self) -> Result<Option<u32>, RestError> {
+        self.query_value("limit")
+            .map(|limit| limit.parse().map_err(RestError::bad_request))
+            .transpose()
+    }
+
+    fn access_policy(&self) -> Result<AccessPolicy, RestError> {
+        match self.query_value("access_policy").map(String::as_str) {
+            None | Some("update-before") => Ok(AccessPolicy::UpdateBefore),
+            Some("update-after") => Ok(AccessPolicy::UpdateAfter),
+            Some("no-update") => Ok(AccessPolicy::NoUpdate),
+            Some(other) => Err(RestError::bad_request(format!(
+                "invalid access_policy: {other}"
+            ))),
+        }
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        matches!(
+            self.query_value(name).map(String::as_str),
+            Some("" | "true" | "1")
+        )
+    }
+
+    fn if_match(&self) -> Result<Option<&str>, RestError> {
+        self.headers
+            .get(IF_MATCH)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map(|value| value.trim().trim_matches('"'))
+                    .map_err(RestError::bad_request)
+            })
+            .transpose()
+    }
+
+    /// Parses the `start` (inclusive) and `end` (exclusive) query parameters
+    /// into a range, encoding each bound with `encode`.
+    fn range<T>(
+        &self,
+        mut encode: impl FnMut(&str) -> Result<T, RestError>,
+    ) -> Result<Option<Range<T>>, RestError> {
+        let start = self
+            .query_value("start")
+            .map(|start| encode(start))
+            .transpose()?;
+        let end = self.query_value("end").map(|end| encode(end)).transpose()?;
+        if start.is_none() && end.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Range {
+            start: start.map_or(Bound::Unbounded, Bound::Included),
+            end: end.map_or(Bound::Unbounded, Bound::Excluded),
+        }))
+    }
+}
+
+async fn route_database_request(
+    database: &AsyncDatabase,
+    segments: &[&str],
+    request: RestRequest,
+) -> Result<Response<Body>, RestError> {
+    match (&request.method, segments) {
+        (&Method::GET, ["collections", collection, "documents"]) => {
+            list_documents(database, collection, &request).await
+        }
+        (&Method::POST, ["collections", collection, "documents"]) => {
+            insert_document(database, collection, request).await
+        }
+        (&Method::GET, ["collections", collection, "documents", id]) => {
+            get_document(database, collection, id).await
+        }
+        (&Method::PUT, ["collections", collection, "documents", id]) => {
+            put_document(database, collection, id, request).await
+        }
+        (&Method::DELETE, ["collections", collection, "documents", id]) => {
+            delete_document(database, collection, id, &request).await
+        }
+        (&Method::GET, ["collections", collection, "count"]) => {
+            count_documents(database, collection, &request).await
+        }
+        (&Method::GET, ["views", view]) => query_view(database, view, &request).await,
+        (&Method::GET, ["views", view, "reduce"]) => reduce_view(database, view, &request).await,
+        (&Method::GET, ["kv", key]) => get_key(database, key, &request).await,
+        (&Method::PUT, ["kv", key]) => set_key(database, key, request).await,
+        (&Method::DELETE, ["kv", key]) => delete_key(database, key, &request).await,
+        (&Method::POST, ["pubsub", topic]) => publish(database, topic, request).await,
+        _ => Err(RestError::not_found()),
+    }
+}
+
+fn collection_key_description<'a>(
+    database: &'a AsyncDatabase,
+    collection: &str,
+) -> Result<(CollectionName, &'a KeyDescription), RestError> {
+    let collection = CollectionName::from_str(collection)?;
+    let description = database
+        .schematic()
+        .collection_primary_key_description(&collection)
+        .ok_or(bonsaidb_core::Error::CollectionNotFound)?;
+    Ok((collection, description))
+}
+
+/// Returns the format the contents of documents in `collection` are
+/// serialized with.
+fn collection_format<'a>(
+    database: &'a AsyncDatabase,
+    collection: &CollectionName,
+) -> Result<&'a SerializationFormat, RestError> {
+    database
+        .schematic()
+        .collection_serialization_format(collection)
+        .ok_or_else(|| RestError::from(bonsaidb_core::Error::CollectionNotFound))
+}
+
+async fn list_documents(
+    database: &AsyncDatabase,
+    collection: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let (collection, key) = collection_key_description(database, collection)?;
+    let ids = request
+        .range(|id| document_id(key, id))?
+        .unwrap_or_else(|| Range::from(..));
+    let format = collection_format(database, &collection)?;
+    let documents = database
+        .list_from_collection(ids, request.order()?, request.limit()?, &collection)
+        .await?;
+    let documents = documents
+        .iter()
+        .map(|document| document_json(document, key, format))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(json_response(StatusCode::OK, &json!(documents)))
+}
+
+async fn insert_document(
+    database: &AsyncDatabase,
+    collection: &str,
+    request: RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let (collection, key) = collection_key_description(database, collection)?;
+    let id = request
+        .query_value("id")
+        .map(|id| document_id(key, id))
+        .transpose()?;
+    let contents = json_to_contents(
+        collection_format(database, &collection)?,
+        &request.json_body().await?,
+    )?;
+    let results = database
+        .apply_transaction(Transaction::insert(collection, id, contents))
+        .await?;
+    Ok(json_response(
+        StatusCode::CREATED,
+        &updated_header_json(results, key)?,
+    ))
+}
+
+async fn get_document(
+    database: &AsyncDatabase,
+    collection: &str,
+    id: &str,
+) -> Result<Response<Body>, RestError> {
+    let (collection, key) = collection_key_description(database, collection)?;
+    let document = database
+        .get_from_collection(document_id(key, id)?, &collection)
+        .await?
+        .ok_or_else(RestError::not_found)?;
+    Ok(json_response(
+        StatusCode::OK,
+        &document_json(&document, key, collection_format(database, &collection)?)?,
+    ))
+}
+
+async fn put_document(
+    database: &AsyncDatabase,
+    collection: &str,
+    id: &str,
+    request: RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let (collection, key) = collection_key_description(database, collection)?;
+    let id = document_id(key, id)?;
+    let format = collection_format(database, &collection)?;
+    let transaction = if let Some(expected_revision) = request.if_match()? {
+        let header = current_header(database, &collection, id, expected_revision).await?;
+        let contents = json_to_contents(format, &request.json_body().await?)?;
+        Transaction::update(collection, header, contents)
+    } else {
+        let contents = json_to_contents(format, &request.json_body().await?)?;
+        Transaction::overwrite(collection, id, contents)
+    };
+    let results = database.apply_transaction(transaction).await?;
+    Ok(json_response(
+        StatusCode::OK,
+        &updated_header_json(results, key)?,
+    ))
+}
+
+async fn delete_document(
+    database: &AsyncDatabase,
+    collection: &str,
+    id: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let (collection, key) = collection_key_description(database, collection)?;
+    let id = document_id(key, id)?;
+    let header = if let Some(expected_revision) = request.if_match()? {
+        current_header(database, &collection, id, expected_revision).await?
+    } else {
+        database
+            .get_from_collection(id, &collection)
+            .await?
+            .ok_or_else(RestError::not_found)?
+            .header
+    };
+    database
+        .apply_transaction(Transaction::delete(collection, header))
+        .await?;
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+/// Returns the current header of the document `id`, ensuring its revision
+/// matches `expected_revision`.
+async fn current_header(
+    database: &AsyncDatabase,
+    collection: &CollectionName,
+    id: DocumentId,
+    expected_revision: &str,
+) -> Result<Header, RestError> {
+    let document = database
+        .get_from_collection(id, collection)
+        .await?
+        .ok_or_else(RestError::not_found)?;
+    if document.header.revision.to_string() == expected_revision {
+        Ok(document.header)
+    } else {
+        Err(RestError::new(
+            StatusCode::PRECONDITION_FAILED,
+            "revision does not match",
+        ))
+    }
+}
+
+async fn count_documents(
+    database: &AsyncDatabase,
+    collection: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let (collection, key) = collection_key_description(database, collection)?;
+    let ids = request
+        .range(|id| document_id(key, id))?
+        .unwrap_or_else(|| Range::from(..));
+    let count = database.count_from_collection(ids, &collection).await?;
+    Ok(json_response(StatusCode::OK, &json!({ "count": count })))
+}
+
+fn view_query_key(
+    key: &KeyDescription,
+    request: &RestRequest,
+) -> Result<Option<SerializedQueryKey>, RestError> {
+    if let Some(matches) = request.query_value("key") {
+        Ok(Some(SerializedQueryKey::Matches(Bytes::from(encode_key(
+            key, matches,
+        )?))))
+    } else {
+        Ok(request
+            .range(|bound| encode_key(key, bound).map(Bytes::from))?
+            .map(SerializedQueryKey::Range))
+    }
+}
+
+async fn query_view(
+    database: &AsyncDatabase,
+    view: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let view = ViewName::from_str(view)?;
+    let schematic = database.schematic();
+    let key = schematic.view_by_name(&view)?.key_description();
+    let primary_key = schematic
+        .collection_primary_key_description(&view.collection)
+        .ok_or(bonsaidb_core::Error::CollectionNotFound)?;
+    let query_key = view_query_key(&key, request)?;
+
+    let mappings = if request.flag("docs") {
+        let format = collection_format(database, &view.collection)?;
+        let results = database
+            .query_by_name_with_docs(
+                &view,
+                query_key,
+                request.order()?,
+                request.limit()?,
+                request.access_policy()?,
+            )
+            .await?;
+        results
+            .mappings
+            .iter()
+            .map(|mapping| {
+                let document = results
+                    .documents
+                    .get(&mapping.source.id)
+                    .map(|document| document_json(document, primary_key, format))
+                    .transpose()?;
+                Ok(json!({
+                    "source": header_json(&mapping.source, primary_key),
+                    "key": decode_key(&key, &mapping.key),
+                    "value": pot_to_json(&mapping.value)?,
+                    "document": document,
+                }))
+            })
+            .collect::<Result<Vec<_>, RestError>>()?
+    } else {
+        database
+            .query_by_name(
+                &view,
+                query_key,
+                request.order()?,
+                request.limit()?,
+                request.access_policy()?,
+            )
+            .await?
+            .iter()
+            .map(|mapping| {
+                Ok(json!({
+                    "source": header_json(&mapping.source, primary_key),
+                    "key": decode_key(&key, &mapping.key),
+                    "value": pot_to_json(&mapping.value)?,
+                }))
+            })
+            .collect::<Result<Vec<_>, RestError>>()?
+    };
+    Ok(json_response(StatusCode::OK, &json!(mappings)))
+}
+
+async fn reduce_view(
+    database: &AsyncDatabase,
+    view: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let view = ViewName::from_str(view)?;
+    let key = database.schematic().view_by_name(&view)?.key_description();
+    let query_key = view_query_key(&key, request)?;
+
+    let result = if request.flag("grouped") {
+        let reductions = database
+            .reduce_grouped_by_name(&view, query_key, request.access_policy()?)
+            .await?;
+        json!(reductions
+            .iter()
+            .map(|reduction| Ok(json!({
+                "key": decode_key(&key, &reduction.key),
+                "value": pot_to_json(&reduction.value)?,
+            })))
+            .collect::<Result<Vec<_>, RestError>>()?)
+    } else {
+        let value = database
+            .reduce_by_name(&view, query_key, request.access_policy()?)
+            .await?;
+        pot_to_json(&value)?
+    };
+    Ok(json_response(StatusCode::OK, &result))
+}
+
+async fn get_key(
+    database: &AsyncDatabase,
+    key: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let output = database
+        .execute_key_operation(KeyOperation {
+            namespace: request.query_value("namespace").cloned(),
+            key: key.to_string(),
+            command: Command::Get { delete: false },
+        })
+        .await?;
+    match output {
+        Output::Value(Some(value)) => Ok(json_response(StatusCode::OK, &kv_value_json(&value))),
+        Output::Value(None) => Err(RestError::not_found()),
+        Output::Status(_) => Err(RestError::internal("unexpected key-value output")),
+    }
+}
+
+async fn set_key(
+    database: &AsyncDatabase,
+    key: &str,
+    request: RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let namespace = request.query_value("namespace").cloned();
+    let value = json_to_kv_value(&request.json_body().await?)?;
+    let output = database
+        .execute_key_operation(KeyOperation {
+            namespace,
+            key: key.to_string(),
+            command: Command::Set(SetCommand {
+                value,
+                expiration: None,
+                keep_existing_expiration: false,
+                check: None,
+                return_previous_value: false,
+            }),
+        })
+        .await?;
+    match output {
+        Output::Status(KeyStatus::Inserted) => Ok(empty_response(StatusCode::CREATED)),
+        _ => Ok(empty_response(StatusCode::NO_CONTENT)),
+    }
+}
+
+async fn delete_key(
+    database: &AsyncDatabase,
+    key: &str,
+    request: &RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let output = database
+        .execute_key_operation(KeyOperation {
+            namespace: request.query_value("namespace").cloned(),
+            key: key.to_string(),
+            command: Command::Delete,
+        })
+        .await?;
+    match output {
+        Output::Status(KeyStatus::Deleted) => Ok(empty_response(StatusCode::NO_CONTENT)),
+        _ => Err(RestError::not_found()),
+    }
+}
+
+async fn publish(
+    database: &AsyncDatabase,
+    topic: &str,
+    request: RestRequest,
+) -> Result<Response<Body>, RestError> {
+    let payload = json_to_pot(&request.json_body().await?)?;
+    let topic = pot::to_vec(topic).map_err(RestError::internal)?;
+    database.publish_bytes(topic, payload).await?;
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn updated_header_json(
+    results: Vec<OperationResult>,
+    key: &KeyDescription,
+) -> Result<serde_json::Value, RestError> {
+    results
+        .into_iter()
+        .find_map(|result| match result {
+            OperationResult::DocumentUpdated { header, .. } => Some(header_json(&header, key)),
+            _ => None,
+        })
+        .ok_or_else(|| RestError::internal("transaction did not update a document"))
+}
+
+fn header_json(header: &Header, key: &KeyDescription) -> serde_json::Value {
+    json!({
+        "id": decode_key(key, &header.id),
+        "revision": header.revision.to_string(),
+    })
+}
+
+fn document_json(
+    document: &OwnedDocument,
+    key: &KeyDescription,
+    format: &SerializationFormat,
+) -> Result<serde_json::Value, RestError> {
+    let contents = format.decode(&document.contents)?;
+    Ok(json!({
+        "id": decode_key(key, &document.header.id),
+        "revision": document.header.revision.to_string(),
+        "contents": serde_json::to_value(&contents).map_err(RestError::internal)?,
+    }))
+}
+
+fn pot_to_json(bytes: &[u8]) -> Result<serde_json::Value, RestError> {
+    let value = pot::from_slice::<pot::Value<'_>>(bytes).map_err(RestError::internal)?;
+    serde_json::to_value(&value).map_err(RestError::internal)
+}
+
+fn json_to_pot(value: &serde_json::Value) -> Result<Vec<u8>, RestError> {
+    pot::to_vec(value).map_err(RestError::internal)
+}
+
+/// Encodes `value` as the contents of a document serialized with `format`.
+/// Documents of collections using a custom format can't be written, as there
+/// is no way to encode JSON in that format.
+fn json_to_contents(
+    format: &SerializationFormat,
+    value: &serde_json::Value,
+) -> Result<Vec<u8>, RestError> {
+    match format {
+        SerializationFormat::Pot => json_to_pot(value),
+        SerializationFormat::Key(key) => {
+            let value = pot::from_slice::<pot::Value<'_>>(&json_to_pot(value)?)
+                .map_err(RestError::internal)?;
+            key.encode_value(&value).map_err(RestError::bad_request)
+        }
+        SerializationFormat::Unknown => Err(RestError::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "the collection's serialization format is not supported",
+        )),
+    }
+}
+
+fn kv_value_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Numeric(Numeric::Integer(value)) => json!(value),
+        Value::Numeric(Numeric::UnsignedInteger(value)) => json!(value),
+        Value::Numeric(Numeric::Float(value)) => json!(value),
+        Value::Bytes(bytes) => pot_to_json(bytes).unwrap_or_else(|_| json!(&bytes[..])),
+    }
+}
+
+fn json_to_kv_value(value: &serde_json::Value) -> Result<Value, RestError> {
+    if let serde_json::Value::Number(number) = value {
+        let numeric = if let Some(value) = number.as_i64() {
+            Numeric::Integer(value)
+        } else if let Some(value) = number.as_u64() {
+            Numeric::UnsignedInteger(value)
+        } else {
+            Numeric::Float(number.as_f64().unwrap_or_default())
+        };
+        Ok(Value::Numeric(numeric))
+    } else {
+        Ok(Value::Bytes(Bytes::from(json_to_pot(value)?)))
+    }
+}
+
+/// Parses `id` into a [`DocumentId`]. Basic key types are parsed from their
+/// natural textual representation, while all other key types must be
+/// provided using [`DocumentId`]'s `Display` format.
+fn document_id(key: &KeyDescription, id: &str) -> Result<DocumentId, RestError> {
+    if matches!(key, KeyDescription::Basic(_)) {
+        DocumentId::try_from(encode_key(key, id)?.as_slice()).map_err(RestError::from)
+    } else {
+        DocumentId::from_str(id).map_err(RestError::bad_request)
+    }
+}
+
+/// Encodes `value` using the [`Key`] implementation described by `key`.
+fn encode_key(key: &KeyDescription, value: &str) -> Result<Vec<u8>, RestError> {
+    fn encode<T>(value: &str) -> Result<Vec<u8>, RestError>
+    where
+        T: for<'k> Key<'k> + FromStr,
+        T::Err: Display,
+    {
+        let value = value.parse::<T>().map_err(RestError::bad_request)?;
+        value
+            .as_ord_bytes()
+            .map(|bytes| bytes.to_vec())
+            .map_err(RestError::bad_request)
+    }
+
+    let KeyDescription::Basic(kind) = key else {
+        return Err(RestError::bad_request(
+            "this key type cannot be encoded by the REST API",
+        ));
+    };
+    match kind {
+        KeyKind::Unit => Ok(Vec::new()),
+        KeyKind::U8 => encode::<u8>(value),
+        KeyKind::U16 => encode::<u16>(value),
+        KeyKind::U32 => encode::<u32>(value),
+        KeyKind::U64 => encode::<u64>(value),
+        KeyKind::U128 => encode::<u128>(value),
+        KeyKind::Usize => encode::<usize>(value),
+        KeyKind::I8 => encode::<i8>(value),
+        KeyKind::I16 => encode::<i16>(value),
+        KeyKind::I32 => encode::<i32>(value),
+        KeyKind::I64 => encode::<i64>(value),
+        KeyKind::I128 => encode::<i128>(value),
+        KeyKind::Isize => encode::<isize>(value),
+        KeyKind::Bool => encode::<bool>(value),
+        KeyKind::String | KeyKind::Bytes => Ok(value.as_bytes().to_vec()),
+        KeyKind::Signed | KeyKind::Unsigned => Err(RestError::bad_request(
+            "variable-length integer keys cannot be encoded by the REST API",
+        )),
+    }
+}
+
+/// Decodes `bytes` using the [`Key`] implementation described by `key`. If the
+/// key cannot be decoded, the raw bytes are returned as an array.
+fn decode_key(key: &KeyDescription, bytes: &[u8]) -> serde_json::Value {
+    fn decode<'k, T>(bytes: &'k [u8]) -> Option<T>
+    where
+        T: Key<'k>,
+    {
+        T::from_ord_bytes(ByteSource::Borrowed(bytes)).ok()
+    }
+
+    let decoded = match key {
+        KeyDescription::Basic(kind) => match kind {
+            KeyKind::Unit => Some(serde_json::Value::Null),
+            KeyKind::U8 => decode::<u8>(bytes).map(|value| json!(value)),
+            KeyKind::U16 => decode::<u16>(bytes).map(|value| json!(value)),
+            KeyKind::U32 => decode::<u32>(bytes).map(|value| json!(value)),
+            KeyKind::U64 => decode::<u64>(bytes).map(|value| json!(value)),
+            KeyKind::U128 => decode::<u128>(bytes).map(|value| json!(value.to_string())),
+            KeyKind::Usize => decode::<usize>(bytes).map(|value| json!(value)),
+            KeyKind::I8 => decode::<i8>(bytes).map(|value| json!(value)),
+            KeyKind::I16 => decode::<i16>(bytes).map(|value| json!(value)),
+            KeyKind::I32 => decode::<i32>(bytes).map(|value| json!(value)),
+            KeyKind::I64 => decode::<i64>(bytes).map(|value| json!(value)),
+            KeyKind::I128 => decode::<i128>(bytes).map(|value| json!(value.to_string())),
+            KeyKind::Isize => decode::<isize>(bytes).map(|value| json!(value)),
+            KeyKind::Bool => decode::<bool>(bytes).map(|value| json!(value)),
+            KeyKind::String => decode::<String>(bytes).map(|value| json!(value)),
+            KeyKind::Bytes | KeyKind::Signed | KeyKind::Unsigned => None,
+        },
+        KeyDescription::Composite(_) | KeyDescription::Other(_) => None,
+    };
+    decoded.unwrap_or_else(|| json!(bytes))
+}
+
+/// An error that is returned to the client of a REST API request.
+pub(super) struct RestError {
+    pub(super) status: StatusCode,
+    pub(super) message: String,
+    pub(super) retry_after: Option<Duration>,
+    /// True if the session was denied permission to perform the request.
+    pub(super) permission_denied: bool,
+}
+
+impl RestError {
+    pub(super) fn new(status: StatusCode, message: impl ToString) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+            retry_after: None,
+            permission_denied: false,
+        }
+    }
+
+    pub(super) fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not found")
+    }
+
+    pub(super) fn bad_request(message: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    fn unauthorized(message: impl ToString) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, message)
+    }
+
+    pub(super) fn internal(message: impl ToString) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    pub(super) fn into_response(self) -> Response<Body> {
+        let mut response = error_response(self.status, self.message);
+        if let Some(retry_after) = self.retry_after {
+            // Retry-After only supports whole seconds.
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+impl From<bonsaidb_core::Error> for RestError {
+    fn from(err: bonsaidb_core::Error) -> Self {
+        use bonsaidb_core::Error;
+        let status = match &err {
+            Error::PermissionDenied(_) => {
+                let mut error = Self::new(StatusCode::FORBIDDEN, &err);
+                error.permission_denied = true;
+                return error;
+            }
+            Error::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            Error::DatabaseNotFound(_)
+            | Error::CollectionNotFound
+            | Error::ViewNotFound
+            | Error::DocumentNotFound(..)
+            | Error::UserNotFound
+            | Error::PermissionGroupNotFound
+            | Error::RoleNotFound
+            | Error::AuthenticationTokenNotFound
+            | Error::SessionNotFound
+            | Error::ClientNotFound
+            | Error::SchemaNotRegistered(_) => StatusCode::NOT_FOUND,
+            Error::DocumentConflict(..) | Error::UniqueKeyViolation { .. } => StatusCode::CONFLICT,
+            Error::InvalidName(_)
+            | Error::InvalidDatabaseName(_)
+            | Error::DocumentIdTooLong
+            | Error::ReduceUnimplemented
+            | Error::NotANumber
+            | Error::PasswordTooShort { .. }
+            | Error::CommonPassword => StatusCode::BAD_REQUEST,
+            Error::RateLimited { retry_after }
+            | Error::UserLockedOut { retry_after }
+            | Error::AuthenticationThrottled { retry_after } => {
+                let mut error = Self::new(StatusCode::TOO_MANY_REQUESTS, &err);
+                error.retry_after = Some(*retry_after);
+                return error;
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, err)
+    }
+}
+
+impl From<bonsaidb_local::Error> for RestError {
+    fn from(err: bonsaidb_local::Error) -> Self {
+        Self::from(bonsaidb_core::Error::from(err))
+    }
+}
+
+impl From<InvalidNameError> for RestError {
+    fn from(err: InvalidNameError) -> Self {
+        Self::bad_request(err)
+    }
+}
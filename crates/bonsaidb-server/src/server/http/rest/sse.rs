@@ -0,0 +1,271 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bonsaidb_core::connection::{HasSession, SessionAuthentication};
+use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber};
+use bonsaidb_local::{AsyncDatabase, DatabaseNonBlocking, Subscriber};
+use hyper::body::Sender;
+use hyper::header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE};
+use hyper::{Body, Response, StatusCode};
+use parking_lot::Mutex;
+use serde_json::json;
+use tokio::sync::Notify;
+
+use super::{pot_to_json, RestError, RestRequest};
+use crate::server::shutdown::ShutdownStateWatcher;
+use crate::{Backend, CustomServer};
+
+/// The number of events retained by each stream for replaying to clients that
+/// reconnect using `Last-Event-ID`.
+const MAX_RETAINED_EVENTS: usize = 1024;
+/// How long a stream keeps receiving messages after its client disconnects,
+/// allowing the client to reconnect without missing any messages.
+const RESUME_WINDOW: Duration = Duration::from_secs(30);
+/// How often a comment is sent to keep idle connections open.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Server-Sent Event streams of `PubSub` messages.
+#[derive(Debug, Default)]
+pub(crate) struct EventStreams {
+    last_id: AtomicU64,
+    streams: Mutex<HashMap<u64, Arc<EventStream>>>,
+}
+
+#[derive(Debug)]
+struct EventStream {
+    id: u64,
+    database: String,
+    topics: Vec<String>,
+    authentication: SessionAuthentication,
+    state: Mutex<EventStreamState>,
+    changed: Notify,
+}
+
+#[derive(Debug)]
+struct EventStreamState {
+    next_sequence: u64,
+    events: VecDeque<(u64, String)>,
+    /// Incremented each time a client attaches to this stream. Only the most
+    /// recently attached client receives events.
+    attachment: u64,
+    detached_at: Option<Instant>,
+    closed: bool,
+}
+
+impl<B: Backend> CustomServer<B> {
+    /// Streams messages published to the `topic` query parameters of
+    /// `database` as Server-Sent Events. Each topic is subscribed to using
+    /// the permissions of `database`'s session.
+    ///
+    /// If the request contains a `Last-Event-ID` header identifying a stream
+    /// that is still being retained, the stream is resumed and any events
+    /// after the identified event are replayed.
+    pub(super) async fn stream_events(
+        &self,
+        database: AsyncDatabase,
+        request: &RestRequest,
+    ) -> Result<Response<Body>, RestError> {
+        let mut topics = request.query_values("topic").cloned().collect::<Vec<_>>();
+        if topics.is_empty() {
+            return Err(RestError::bad_request("at least one topic is required"));
+        }
+        topics.sort();
+        topics.dedup();
+        let authentication = database
+            .session()
+            .map_or(SessionAuthentication::None, |session| {
+                session.authentication.clone()
+            });
+
+        let resumed = request
+            .headers
+            .get("last-event-id")
+            .and_then(|id| id.to_str().ok())
+            .and_then(parse_event_id)
+            .and_then(|(stream_id, sequence)| {
+                let streams = self.data.event_streams.streams.lock();
+                let stream = streams.get(&stream_id)?;
+                (stream.database == database.name()
+                    && stream.topics == topics
+                    && stream.authentication == authentication)
+                    .then(|| (stream.clone(), Some(sequence)))
+            });
+
+        let (stream, last_sequence) = if let Some(resumed) = resumed {
+            resumed
+        } else {
+            let shutdown = self.data.shutdown.watcher().await.ok_or_else(|| {
+                RestError::new(StatusCode::SERVICE_UNAVAILABLE, "server is shutting down")
+            })?;
+            let subscriber = database.create_subscriber().await?;
+            for topic in &topics {
+                subscriber
+                    .subscribe_to_bytes(pot::to_vec(topic).map_err(RestError::internal)?)
+                    .await?;
+            }
+
+            let stream = Arc::new(EventStream {
+                id: self
+                    .data
+                    .event_streams
+                    .last_id
+                    .fetch_add(1, Ordering::SeqCst)
+                    + 1,
+                database: database.name().to_string(),
+                topics,
+                authentication,
+                state: Mutex::new(EventStreamState {
+                    next_sequence: 0,
+                    events: VecDeque::new(),
+                    attachment: 0,
+                    detached_at: None,
+                    closed: false,
+                }),
+                changed: Notify::new(),
+            });
+            self.data
+                .event_streams
+                .streams
+                .lock()
+                .insert(stream.id, stream.clone());
+            tokio::spawn(receive_messages(
+                self.clone(),
+                stream.clone(),
+                subscriber,
+                shutdown,
+            ));
+            (stream, None)
+        };
+
+        let (sender, body) = Body::channel();
+        let attachment = {
+            let mut state = stream.state.lock();
+            state.attachment += 1;
+            state.detached_at = None;
+            state.attachment
+        };
+        stream.changed.notify_waiters();
+        tokio::spawn(send_events(stream, attachment, last_sequence, sender));
+
+        let mut response = Response::new(body);
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+        response
+            .headers_mut()
+            .insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        Ok(response)
+    }
+}
+
+/// Parses an event id in the format `{stream_id}-{sequence}`.
+fn parse_event_id(id: &str) -> Option<(u64, u64)> {
+    let (stream_id, sequence) = id.trim().split_once('-')?;
+    Some((stream_id.parse().ok()?, sequence.parse().ok()?))
+}
+
+async fn receive_messages<B: Backend>(
+    server: CustomServer<B>,
+    stream: Arc<EventStream>,
+    subscriber: Subscriber,
+    mut shutdown: ShutdownStateWatcher,
+) {
+    let mut expiration_check = tokio::time::interval(RESUME_WINDOW / 2);
+    loop {
+        tokio::select! {
+            message = subscriber.receiver().receive_async() => {
+                let Ok(message) = message else { break };
+                let topic = pot::from_slice::<String>(&message.topic)
+                    .map_or_else(|_| json!(&message.topic[..]), |topic| json!(topic));
+                let payload = pot_to_json(&message.payload)
+                    .unwrap_or_else(|_| json!(&message.payload[..]));
+                let data = json!({ "topic": topic, "payload": payload });
+
+                let mut state = stream.state.lock();
+                let sequence = state.next_sequence;
+                state.next_sequence += 1;
+                state.events.push_back((
+                    sequence,
+                    format!("id: {}-{sequence}\nevent: message\ndata: {data}\n\n", stream.id),
+                ));
+                if state.events.len() > MAX_RETAINED_EVENTS {
+                    state.events.pop_front();
+                }
+                drop(state);
+                stream.changed.notify_waiters();
+            }
+            _ = expiration_check.tick() => {
+                let state = stream.state.lock();
+                if state
+                    .detached_at
+                    .map_or(false, |detached_at| detached_at.elapsed() >= RESUME_WINDOW)
+                {
+                    break;
+                }
+            }
+            _ = shutdown.wait_for_shutdown() => break,
+        }
+    }
+
+    server.data.event_streams.streams.lock().remove(&stream.id);
+    stream.state.lock().closed = true;
+    stream.changed.notify_waiters();
+}
+
+async fn send_events(
+    stream: Arc<EventStream>,
+    attachment: u64,
+    mut last_sequence: Option<u64>,
+    mut sender: Sender,
+) {
+    loop {
+        let changed = stream.changed.notified();
+        tokio::pin!(changed);
+
+        let (pending, closed) = {
+            let state = stream.state.lock();
+            if state.attachment != attachment {
+                // Another client has resumed this stream.
+                return;
+            }
+            let pending = state
+                .events
+                .iter()
+                .filter(|(sequence, _)| last_sequence.map_or(true, |last| *sequence > last))
+                .cloned()
+                .collect::<Vec<_>>();
+            (pending, state.closed)
+        };
+
+        for (sequence, event) in pending {
+            if sender.send_data(event.into()).await.is_err() {
+                detach(&stream, attachment);
+                return;
+            }
+            last_sequence = Some(sequence);
+        }
+
+        if closed {
+            return;
+        }
+
+        tokio::select! {
+            _ = &mut changed => {}
+            _ = tokio::time::sleep(KEEP_ALIVE_INTERVAL) => {
+                if sender.send_data(": keep-alive\n\n".into()).await.is_err() {
+                    detach(&stream, attachment);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn detach(stream: &EventStream, attachment: u64) {
+    let mut state = stream.state.lock();
+    if state.attachment == attachment {
+        state.detached_at = Some(Instant::now());
+    }
+}
@@ -0,0 +1,753 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bonsaidb_core::connection::{AsyncStorageConnection, HasSession};
+use bonsaidb_files::FileConfig;
+use bonsaidb_local::AsyncDatabase;
+use hyper::header::{
+    HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, ETAG, RETRY_AFTER,
+};
+use hyper::http::request::Parts;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use sha2::{Digest, Sha256};
+
+use super::files::{
+    contents_response, decode_path, empty_response, etag, header_value, iso8601, last_modified,
+    push_element, replace_file, ContentsError, FileHandle,
+};
+use super::rest::RestError;
+use crate::{Backend, CustomServer};
+
+#[cfg(feature = "token-authentication")]
+mod signature;
+
+/// The path that in-progress multipart uploads are stored beneath. Keys within
+/// this path cannot be accessed through the S3 API.
+const UPLOADS_PATH: &str = "/.s3-uploads/";
+/// The maximum number of keys returned by a single `ListObjectsV2` request.
+const MAX_KEYS: usize = 1000;
+/// The maximum part number allowed in a multipart upload.
+const MAX_PART_NUMBER: u32 = 10_000;
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const XML_NAMESPACE: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+impl<B: Backend> CustomServer<B> {
+    /// Handles a request to BonsaiDb's S3-compatible object API. Requires
+    /// feature `s3-api` to be enabled.
+    ///
+    /// Requests use path-style addressing: each bucket is a database, and each
+    /// object key is the path of a file stored using `Config` within that
+    /// database. The database must include `Config`'s collections in its
+    /// schema.
+    ///
+    /// The supported operations are `ListBuckets`, `HeadBucket`,
+    /// `ListObjectsV2`, `PutObject`, `GetObject` (including `Range` requests),
+    /// `HeadObject`, `DeleteObject`, and multipart uploads.
+    ///
+    /// Requests are authenticated the same way as REST API requests. If
+    /// feature `token-authentication` is enabled, requests signed using AWS
+    /// Signature Version 4 are also accepted, using an
+    /// [`AuthenticationToken`](bonsaidb_core::admin::AuthenticationToken)'s id
+    /// as the access key and its token as the secret key. Expired and scoped
+    /// tokens are rejected.
+    pub async fn handle_s3_request<Config>(&self, request: Request<Body>) -> Response<Body>
+    where
+        Config: FileConfig,
+        Config::Metadata: Default,
+    {
+        match self.route_s3_request::<Config>(request).await {
+            Ok(response) => response,
+            Err(err) => err.into_response(),
+        }
+    }
+
+    async fn route_s3_request<Config>(
+        &self,
+        request: Request<Body>,
+    ) -> Result<Response<Body>, S3Error>
+    where
+        Config: FileConfig,
+        Config::Metadata: Default,
+    {
+        let (parts, body) = request.into_parts();
+        let (client, payload_sha256) = self.s3_client(&parts).await?;
+        let request_bytes = parts
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        if let Some(session) = client.session() {
+            self.check_user_rate_limit(session, request_bytes)?;
+        }
+
+        let path = parts.uri.path().trim_start_matches('/');
+        let (bucket, key) = match path.split_once('/') {
+            Some((bucket, key)) => (decode_segment(bucket)?, decode_segment(key)?),
+            None => (decode_segment(path)?, String::new()),
+        };
+        let request = S3Request {
+            method: parts.method,
+            query: parts
+                .uri
+                .query()
+                .map(|query| {
+                    form_urlencoded::parse(query.as_bytes())
+                        .into_owned()
+                        .collect()
+                })
+                .unwrap_or_default(),
+            headers: parts.headers,
+            body,
+            payload_sha256,
+        };
+
+        if bucket.is_empty() {
+            return if request.method == Method::GET {
+                list_buckets(&client).await
+            } else {
+                Err(S3Error::not_implemented())
+            };
+        }
+
+        let database = client.storage.database_without_schema(&bucket).await?;
+        self.check_database_rate_limit(&database, request_bytes)?;
+        if key.is_empty() {
+            return match request.method {
+                Method::GET => list_objects::<Config>(&database, &bucket, &request).await,
+                Method::HEAD => {
+                    // Ensure the database stores files using `Config`.
+                    Config::list_async("/", &database).await?;
+                    Ok(empty_response(StatusCode::OK))
+                }
+                _ => Err(S3Error::not_implemented()),
+            };
+        }
+
+        let path = object_path(&key)?;
+        match request.method {
+            Method::POST if request.query_value("uploads").is_some() => {
+                create_multipart_upload::<Config>(&database, &bucket, &key, &path).await
+            }
+            Method::POST => {
+                complete_multipart_upload::<Config>(&database, &bucket, &key, &path, request).await
+            }
+            Method::PUT if request.query_value("uploadId").is_some() => {
+                upload_part::<Config>(&database, &path, request).await
+            }
+            Method::DELETE if request.query_value("uploadId").is_some() => {
+                abort_multipart_upload::<Config>(&database, &path, &request).await
+            }
+            Method::PUT if request.headers.contains_key("x-amz-copy-source") => {
+                Err(S3Error::not_implemented())
+            }
+            Method::PUT => put_object::<Config>(&database, &path, request).await,
+            Method::GET => get_object::<Config>(&database, &path, &request, true).await,
+            Method::HEAD => get_object::<Config>(&database, &path, &request, false).await,
+            Method::DELETE => {
+                Config::delete_async(&path, &database).await?;
+                Ok(empty_response(StatusCode::NO_CONTENT))
+            }
+            _ => Err(S3Error::not_implemented()),
+        }
+    }
+
+    /// Returns a client for the session that `parts` is authenticated as, and
+    /// the signed SHA-256 digest of the request's body, if any.
+    async fn s3_client(&self, parts: &Parts) -> Result<(Self, Option<[u8; 32]>), S3Error> {
+        match parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+        {
+            #[cfg(feature = "token-authentication")]
+            Some(authorization) if authorization.starts_with(signature::ALGORITHM) => {
+                self.authenticate_s3_signature(parts, authorization).await
+            }
+            #[cfg(not(feature = "token-authentication"))]
+            Some(authorization) if authorization.starts_with("AWS4-HMAC-SHA256") => Err(
+                S3Error::access_denied("signed requests require feature token-authentication"),
+            ),
+            _ => Ok((self.rest_client(parts).await?, None)),
+        }
+    }
+}
+
+struct S3Request {
+    method: Method,
+    headers: HeaderMap,
+    query: Vec<(String, String)>,
+    body: Body,
+    /// The SHA-256 digest of the body included in the request's signature.
+    payload_sha256: Option<[u8; 32]>,
+}
+
+impl S3Request {
+    /// Reads the entire body, verifying it against the signed digest if one
+    /// was provided.
+    async fn body(self) -> Result<hyper::body::Bytes, S3Error> {
+        let body = hyper::body::to_bytes(self.body)
+            .await
+            .map_err(|err| S3Error::new(StatusCode::BAD_REQUEST, "IncompleteBody", err))?;
+        if let Some(expected) = self.payload_sha256 {
+            if Sha256::digest(&body).as_slice() != expected {
+                return Err(S3Error::new(
+                    StatusCode::BAD_REQUEST,
+                    "XAmzContentSHA256Mismatch",
+                    "the body does not match the signed digest",
+                ));
+            }
+        }
+        Ok(body)
+    }
+
+    /// Returns the first value of the query parameter `name`.
+    fn query_value(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find_map(|(key, value)| (key == name).then_some(value.as_str()))
+    }
+
+    fn upload_id(&self) -> Result<&str, S3Error> {
+        self.query_value("uploadId")
+            .filter(|id| !id.is_empty() && id.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(S3Error::no_such_upload)
+    }
+}
+
+async fn list_buckets<B: Backend>(client: &CustomServer<B>) -> Result<Response<Body>, S3Error> {
+    let databases = client.list_databases().await?;
+    let mut xml =
+        format!(r#"{XML_HEADER}<ListAllMyBucketsResult xmlns="{XML_NAMESPACE}"><Buckets>"#);
+    for database in databases {
+        xml.push_str("<Bucket>");
+        push_element(&mut xml, "Name", &database.name);
+        push_element(&mut xml, "CreationDate", &iso8601(UNIX_EPOCH));
+        xml.push_str("</Bucket>");
+    }
+    xml.push_str("</Buckets></ListAllMyBucketsResult>");
+    Ok(xml_response(StatusCode::OK, xml))
+}
+
+async fn list_objects<Config: FileConfig>(
+    database: &AsyncDatabase,
+    bucket: &str,
+    request: &S3Request,
+) -> Result<Response<Body>, S3Error> {
+    let prefix = request.query_value("prefix").unwrap_or_default();
+    let delimiter = request
+        .query_value("delimiter")
+        .filter(|delimiter| !delimiter.is_empty());
+    let max_keys = request
+        .query_value("max-keys")
+        .map(|max_keys| {
+            max_keys
+                .parse::<usize>()
+                .map_err(|_| S3Error::invalid_argument("invalid max-keys"))
+        })
+        .transpose()?
+        .unwrap_or(MAX_KEYS)
+        .min(MAX_KEYS);
+    let continuation_token = request.query_value("continuation-token");
+    let start_after = request.query_value("start-after");
+    let after = continuation_token.or(start_after).unwrap_or_default();
+
+    // Only files beneath the deepest directory named in the prefix can match.
+    let directory = match prefix.rfind('/') {
+        Some(index) => format!("/{}", &prefix[..=index]),
+        None => String::from("/"),
+    };
+    let mut files = Config::list_recursive_async(&directory, database)
+        .await?
+        .into_iter()
+        .filter_map(|file| {
+            let path = file.path();
+            (!path.starts_with(UPLOADS_PATH) && path[1..].starts_with(prefix))
+                .then(|| (path[1..].to_string(), file))
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut objects = Vec::new();
+    let mut common_prefixes = Vec::<String>::new();
+    let mut next_token = None;
+    for (key, file) in files {
+        let after_common_prefix = delimiter.map_or(false, |delimiter| after.ends_with(delimiter))
+            && key.starts_with(after);
+        if key.as_str() <= after || after_common_prefix {
+            continue;
+        }
+
+        let common_prefix = delimiter.and_then(|delimiter| {
+            key[prefix.len()..]
+                .find(delimiter)
+                .map(|index| key[..prefix.len() + index + delimiter.len()].to_string())
+        });
+        if let Some(common_prefix) = &common_prefix {
+            if common_prefixes.last() == Some(common_prefix) {
+                continue;
+            }
+        }
+
+        if objects.len() + common_prefixes.len() == max_keys {
+            next_token = Some(match (common_prefixes.last(), objects.last()) {
+                (Some(common_prefix), Some((key, _))) => common_prefix.max(key).clone(),
+                (Some(common_prefix), None) => common_prefix.clone(),
+                (None, Some((key, _))) => key.clone(),
+                (None, None) => String::new(),
+            });
+            break;
+        }
+
+        match common_prefix {
+            Some(common_prefix) => common_prefixes.push(common_prefix),
+            None => objects.push((key, file)),
+        }
+    }
+
+    let mut xml = format!(r#"{XML_HEADER}<ListBucketResult xmlns="{XML_NAMESPACE}">"#);
+    push_element(&mut xml, "Name", bucket);
+    push_element(&mut xml, "Prefix", prefix);
+    if let Some(delimiter) = delimiter {
+        push_element(&mut xml, "Delimiter", delimiter);
+    }
+    push_element(&mut xml, "MaxKeys", &max_keys.to_string());
+    push_element(
+        &mut xml,
+        "KeyCount",
+        &(objects.len() + common_prefixes.len()).to_string(),
+    );
+    push_element(&mut xml, "IsTruncated", &next_token.is_some().to_string());
+    if let Some(continuation_token) = continuation_token {
+        push_element(&mut xml, "ContinuationToken", continuation_token);
+    }
+    if let Some(next_token) = &next_token {
+        push_element(&mut xml, "NextContinuationToken", next_token);
+    }
+    if let Some(start_after) = start_after {
+        push_element(&mut xml, "StartAfter", start_after);
+    }
+    for (key, file) in objects {
+        let contents = file.contents().await?;
+        xml.push_str("<Contents>");
+        push_element(&mut xml, "Key", &key);
+        push_element(
+            &mut xml,
+            "LastModified",
+            &iso8601(last_modified(&file, &contents)),
+        );
+        if let Some(etag) = etag(&file).await? {
+            push_element(&mut xml, "ETag", &etag);
+        }
+        push_element(&mut xml, "Size", &contents.len().to_string());
+        push_element(&mut xml, "StorageClass", "STANDARD");
+        xml.push_str("</Contents>");
+    }
+    for common_prefix in common_prefixes {
+        xml.push_str("<CommonPrefixes>");
+        push_element(&mut xml, "Prefix", &common_prefix);
+        xml.push_str("</CommonPrefixes>");
+    }
+    xml.push_str("</ListBucketResult>");
+    Ok(xml_response(StatusCode::OK, xml))
+}
+
+async fn put_object<Config>(
+    database: &AsyncDatabase,
+    path: &str,
+    request: S3Request,
+) -> Result<Response<Body>, S3Error>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let contents = request.body().await?;
+    let file = replace_file::<Config>(database, path, &contents).await?;
+    written_response(&file).await
+}
+
+async fn get_object<Config: FileConfig>(
+    database: &AsyncDatabase,
+    path: &str,
+    request: &S3Request,
+    include_body: bool,
+) -> Result<Response<Body>, S3Error> {
+    let file = Config::load_async(path, database)
+        .await?
+        .ok_or_else(S3Error::no_such_key)?;
+    Ok(contents_response(&file, &request.headers, include_body).await?)
+}
+
+async fn create_multipart_upload<Config>(
+    database: &AsyncDatabase,
+    bucket: &str,
+    key: &str,
+    path: &str,
+) -> Result<Response<Body>, S3Error>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    static UPLOAD_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let upload_id = format!(
+        "{:x}{:04x}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        UPLOAD_COUNTER.fetch_add(1, Ordering::Relaxed) & 0xFFFF
+    );
+    // The upload's directory contains a file recording the destination path,
+    // which is verified by each subsequent request for this upload.
+    Config::build(format!("{UPLOADS_PATH}{upload_id}/path"))
+        .contents(path.as_bytes())
+        .create_async(database)
+        .await?;
+
+    let mut xml = format!(r#"{XML_HEADER}<InitiateMultipartUploadResult xmlns="{XML_NAMESPACE}">"#);
+    push_element(&mut xml, "Bucket", bucket);
+    push_element(&mut xml, "Key", key);
+    push_element(&mut xml, "UploadId", &upload_id);
+    xml.push_str("</InitiateMultipartUploadResult>");
+    Ok(xml_response(StatusCode::OK, xml))
+}
+
+async fn upload_part<Config>(
+    database: &AsyncDatabase,
+    path: &str,
+    request: S3Request,
+) -> Result<Response<Body>, S3Error>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let directory = upload_directory::<Config>(database, path, request.upload_id()?).await?;
+    let part_number = request
+        .query_value("partNumber")
+        .and_then(|part| part.parse::<u32>().ok())
+        .filter(|part| (1..=MAX_PART_NUMBER).contains(part))
+        .ok_or_else(|| S3Error::invalid_argument("invalid partNumber"))?;
+    let contents = request.body().await?;
+    let part =
+        replace_file::<Config>(database, &part_path(&directory, part_number), &contents).await?;
+    written_response(&part).await
+}
+
+async fn complete_multipart_upload<Config>(
+    database: &AsyncDatabase,
+    bucket: &str,
+    key: &str,
+    path: &str,
+    request: S3Request,
+) -> Result<Response<Body>, S3Error>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let directory = upload_directory::<Config>(database, path, request.upload_id()?).await?;
+    let body = request.body().await?;
+    let body = std::str::from_utf8(&body).map_err(|_| S3Error::malformed_xml())?;
+    let part_numbers = body
+        .split("<PartNumber>")
+        .skip(1)
+        .map(|part| {
+            part.split_once("</PartNumber>")
+                .and_then(|(number, _)| number.trim().parse::<u32>().ok())
+                .ok_or_else(S3Error::malformed_xml)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if part_numbers.is_empty() {
+        return Err(S3Error::malformed_xml());
+    } else if part_numbers.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidPartOrder",
+            "parts must be listed in ascending order",
+        ));
+    }
+
+    let mut parts = Vec::with_capacity(part_numbers.len());
+    for part_number in part_numbers {
+        parts.push(
+            Config::load_async(&part_path(&directory, part_number), database)
+                .await?
+                .ok_or_else(|| {
+                    S3Error::new(
+                        StatusCode::BAD_REQUEST,
+                        "InvalidPart",
+                        format!("part {part_number} has not been uploaded"),
+                    )
+                })?,
+        );
+    }
+
+    let file = replace_file::<Config>(database, path, b"").await?;
+    for part in &parts {
+        let mut contents = part.contents().await?;
+        while let Some(block) = futures::StreamExt::next(&mut contents).await {
+            file.append(&block.map_err(S3Error::internal)?).await?;
+        }
+    }
+    delete_upload::<Config>(database, &directory).await?;
+
+    let mut xml = format!(r#"{XML_HEADER}<CompleteMultipartUploadResult xmlns="{XML_NAMESPACE}">"#);
+    push_element(&mut xml, "Location", &format!("/{bucket}/{key}"));
+    push_element(&mut xml, "Bucket", bucket);
+    push_element(&mut xml, "Key", key);
+    if let Some(etag) = etag(&file).await? {
+        push_element(&mut xml, "ETag", &etag);
+    }
+    xml.push_str("</CompleteMultipartUploadResult>");
+    Ok(xml_response(StatusCode::OK, xml))
+}
+
+async fn abort_multipart_upload<Config: FileConfig>(
+    database: &AsyncDatabase,
+    path: &str,
+    request: &S3Request,
+) -> Result<Response<Body>, S3Error> {
+    let directory = upload_directory::<Config>(database, path, request.upload_id()?).await?;
+    delete_upload::<Config>(database, &directory).await?;
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+/// Returns the directory containing the parts of `upload_id`, ensuring the
+/// upload was created for `path`.
+async fn upload_directory<Config: FileConfig>(
+    database: &AsyncDatabase,
+    path: &str,
+    upload_id: &str,
+) -> Result<String, S3Error> {
+    let directory = format!("{UPLOADS_PATH}{upload_id}/");
+    let upload = Config::load_async(&format!("{directory}path"), database)
+        .await?
+        .ok_or_else(S3Error::no_such_upload)?;
+    let upload_path = upload
+        .contents()
+        .await?
+        .into_vec()
+        .await
+        .map_err(S3Error::internal)?;
+    if upload_path == path.as_bytes() {
+        Ok(directory)
+    } else {
+        Err(S3Error::no_such_upload())
+    }
+}
+
+async fn delete_upload<Config: FileConfig>(
+    database: &AsyncDatabase,
+    directory: &str,
+) -> Result<(), S3Error> {
+    for file in Config::list_async(directory, database).await? {
+        file.delete().await?;
+    }
+    Ok(())
+}
+
+fn part_path(directory: &str, part_number: u32) -> String {
+    format!("{directory}{part_number:05}")
+}
+
+async fn written_response<Config: FileConfig>(
+    file: &FileHandle<Config>,
+) -> Result<Response<Body>, S3Error> {
+    let mut response = empty_response(StatusCode::OK);
+    if let Some(etag) = etag(file).await?.and_then(header_value) {
+        response.headers_mut().insert(ETAG, etag);
+    }
+    Ok(response)
+}
+
+fn decode_segment(segment: &str) -> Result<String, S3Error> {
+    decode_path(segment).ok_or_else(|| S3Error::invalid_argument("paths must be valid UTF-8"))
+}
+
+/// Converts an object key into the absolute path of the file storing it.
+fn object_path(key: &str) -> Result<String, S3Error> {
+    let path = format!("/{key}");
+    if path.ends_with('/') || path.contains("//") {
+        Err(S3Error::invalid_argument(
+            "keys must not be empty or contain empty path segments",
+        ))
+    } else if path.starts_with(UPLOADS_PATH) {
+        Err(S3Error::invalid_argument(format!(
+            "keys must not begin with {}",
+            &UPLOADS_PATH[1..]
+        )))
+    } else {
+        Ok(path)
+    }
+}
+
+fn xml_response(status: StatusCode, xml: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(xml));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+    response
+}
+
+/// An error that is returned to the client of an S3 API request.
+pub(super) struct S3Error {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    retry_after: Option<Duration>,
+}
+
+impl S3Error {
+    fn new(status: StatusCode, code: &'static str, message: impl ToString) -> Self {
+        Self {
+            status,
+            code,
+            message: message.to_string(),
+            retry_after: None,
+        }
+    }
+
+    fn no_such_key() -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchKey",
+            "the specified key does not exist",
+        )
+    }
+
+    fn no_such_upload() -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchUpload",
+            "the specified upload does not exist",
+        )
+    }
+
+    fn invalid_argument(message: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "InvalidArgument", message)
+    }
+
+    fn malformed_xml() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "MalformedXML",
+            "the request body is not a valid part list",
+        )
+    }
+
+    fn access_denied(message: impl ToString) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "AccessDenied", message)
+    }
+
+    fn not_implemented() -> Self {
+        Self::new(
+            StatusCode::NOT_IMPLEMENTED,
+            "NotImplemented",
+            "this operation is not supported",
+        )
+    }
+
+    fn internal(message: impl ToString) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalError", message)
+    }
+
+    fn into_response(self) -> Response<Body> {
+        let mut xml = format!("{XML_HEADER}<Error>");
+        push_element(&mut xml, "Code", self.code);
+        push_element(&mut xml, "Message", &self.message);
+        xml.push_str("</Error>");
+        let mut response = xml_response(self.status, xml);
+        if let Some(retry_after) = self.retry_after {
+            // Retry-After only supports whole seconds.
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+impl From<bonsaidb_core::Error> for S3Error {
+    fn from(err: bonsaidb_core::Error) -> Self {
+        use bonsaidb_core::Error;
+        let (status, code) = match &err {
+            Error::PermissionDenied(_) | Error::InvalidCredentials => {
+                (StatusCode::FORBIDDEN, "AccessDenied")
+            }
+            Error::DatabaseNotFound(_)
+            | Error::CollectionNotFound
+            | Error::SchemaNotRegistered(_) => (StatusCode::NOT_FOUND, "NoSuchBucket"),
+            Error::DocumentNotFound(..) => (StatusCode::NOT_FOUND, "NoSuchKey"),
+            Error::DocumentConflict(..) | Error::UniqueKeyViolation { .. } => {
+                (StatusCode::CONFLICT, "OperationAborted")
+            }
+            Error::InvalidDatabaseName(_) => (StatusCode::BAD_REQUEST, "InvalidBucketName"),
+            Error::RateLimited { retry_after }
+            | Error::UserLockedOut { retry_after }
+            | Error::AuthenticationThrottled { retry_after } => {
+                let mut error = Self::new(StatusCode::SERVICE_UNAVAILABLE, "SlowDown", &err);
+                error.retry_after = Some(*retry_after);
+                return error;
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "InternalError"),
+        };
+        Self::new(status, code, err)
+    }
+}
+
+impl From<bonsaidb_local::Error> for S3Error {
+    fn from(err: bonsaidb_local::Error) -> Self {
+        Self::from(bonsaidb_core::Error::from(err))
+    }
+}
+
+impl From<bonsaidb_files::Error> for S3Error {
+    fn from(err: bonsaidb_files::Error) -> Self {
+        match err {
+            bonsaidb_files::Error::Database(err) => Self::from(err),
+            bonsaidb_files::Error::InvalidName | bonsaidb_files::Error::InvalidPath => {
+                Self::invalid_argument(err)
+            }
+            bonsaidb_files::Error::AlreadyExists | bonsaidb_files::Error::Deleted => {
+                Self::new(StatusCode::CONFLICT, "OperationAborted", err)
+            }
+            other => Self::internal(other),
+        }
+    }
+}
+
+impl From<ContentsError> for S3Error {
+    fn from(err: ContentsError) -> Self {
+        match err {
+            ContentsError::Database(err) => Self::from(err),
+            ContentsError::Io(err) => Self::internal(err),
+            ContentsError::UnsatisfiableRange(length) => Self::new(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                "InvalidRange",
+                format!("the requested range is not satisfiable for {length} bytes"),
+            ),
+        }
+    }
+}
+
+impl From<RestError> for S3Error {
+    fn from(err: RestError) -> Self {
+        let code = match err.status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => "AccessDenied",
+            StatusCode::TOO_MANY_REQUESTS => "SlowDown",
+            StatusCode::BAD_REQUEST => "InvalidArgument",
+            _ => "InternalError",
+        };
+        let status = match err.status {
+            StatusCode::UNAUTHORIZED => StatusCode::FORBIDDEN,
+            other => other,
+        };
+        Self {
+            status,
+            code,
+            message: err.message,
+            retry_after: err.retry_after,
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bonsaidb_core::admin::AuthenticationToken;
+use bonsaidb_core::connection::{AsyncStorageConnection, IdentityId, IdentityReference};
+use bonsaidb_core::schema::SerializedCollection;
+use hmac::{Hmac, Mac};
+use hyper::http::request::Parts;
+use hyper::StatusCode;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+
+use super::S3Error;
+use crate::{Backend, CustomServer};
+
+/// The only signing algorithm supported: AWS Signature Version 4.
+pub(super) const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+/// The maximum difference allowed between the time a request was signed and
+/// the server's clock.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(15 * 60);
+/// The characters that are not percent-encoded in canonical requests.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+type HmacSha256 = Hmac<Sha256>;
+
+impl<B: Backend> CustomServer<B> {
+    /// Authenticates a request signed using AWS Signature Version 4. The
+    /// access key is the id of an [`AuthenticationToken`], and the secret key
+    /// is the token itself.
+    ///
+    /// Returns a client for the token's identity, and the signed SHA-256
+    /// digest of the request's body, if the body was signed.
+    pub(super) async fn authenticate_s3_signature(
+        &self,
+        parts: &Parts,
+        authorization: &str,
+    ) -> Result<(Self, Option<[u8; 32]>), S3Error> {
+        let authorization = Authorization::parse(authorization).ok_or_else(|| {
+            S3Error::new(
+                StatusCode::BAD_REQUEST,
+                "AuthorizationHeaderMalformed",
+                "invalid authorization header",
+            )
+        })?;
+        let token_id = authorization
+            .access_key
+            .parse::<u64>()
+            .map_err(|_| invalid_access_key())?;
+        let admin = self.storage.admin().await;
+        let token = AuthenticationToken::get_async(&token_id, &admin)
+            .await?
+            .ok_or_else(invalid_access_key)?;
+        // Scoped tokens can't be used here, because assuming the token's
+        // identity would grant the identity's full permissions.
+        if token.contents.is_expired() || token.contents.scope.is_some() {
+            return Err(invalid_access_key());
+        }
+        let payload_sha256 = authorization.verify(parts, &token.contents.token.0)?;
+
+        let identity = match token.contents.identity {
+            IdentityId::User(id) => IdentityReference::user(id)?,
+            IdentityId::Role(id) => IdentityReference::role(id)?,
+            _ => return Err(invalid_access_key()),
+        };
+        let client = Self {
+            data: self.data.clone(),
+            storage: self.storage.assume_identity(identity).await?,
+        };
+        Ok((client, payload_sha256))
+    }
+}
+
+/// The parameters of a Signature Version 4 `Authorization` header.
+struct Authorization<'a> {
+    access_key: &'a str,
+    date: &'a str,
+    region: &'a str,
+    service: &'a str,
+    signed_headers: Vec<&'a str>,
+    signature: Vec<u8>,
+}
+
+impl<'a> Authorization<'a> {
+    fn parse(header: &'a str) -> Option<Self> {
+        let mut credential = None;
+        let mut signed_headers = None;
+        let mut signature = None;
+        for parameter in header.strip_prefix(ALGORITHM)?.split(',') {
+            let (name, value) = parameter.trim().split_once('=')?;
+            match name {
+                "Credential" => credential = Some(value),
+                "SignedHeaders" => signed_headers = Some(value),
+                "Signature" => signature = Some(value),
+                _ => {}
+            }
+        }
+
+        // The credential is formatted as `key/date/region/service/aws4_request`.
+        let mut credential = credential?.split('/');
+        let access_key = credential.next()?;
+        let date = credential.next()?;
+        let region = credential.next()?;
+        let service = credential.next()?;
+        if credential.next()? != "aws4_request" || credential.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            access_key,
+            date,
+            region,
+            service,
+            signed_headers: signed_headers?.split(';').collect(),
+            signature: hex::decode(signature?).ok()?,
+        })
+    }
+
+    /// Verifies that this signature was produced by signing `parts` with
+    /// `secret`. Returns the signed SHA-256 digest of the request's body, if
+    /// the body was signed.
+    fn verify(&self, parts: &Parts, secret: &str) -> Result<Option<[u8; 32]>, S3Error> {
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| S3Error::access_denied(format!("missing {name} header")))
+        };
+
+        let timestamp = header("x-amz-date")?;
+        let signed_at = parse_timestamp(timestamp)
+            .filter(|_| timestamp.starts_with(self.date))
+            .ok_or_else(|| S3Error::access_denied("invalid x-amz-date header"))?;
+        let now = SystemTime::now();
+        let skew = now
+            .duration_since(signed_at)
+            .unwrap_or_else(|err| err.duration());
+        if skew > MAX_CLOCK_SKEW {
+            return Err(S3Error::new(
+                StatusCode::FORBIDDEN,
+                "RequestTimeTooSkewed",
+                "the request was signed too long ago",
+            ));
+        }
+
+        let payload_hash = header("x-amz-content-sha256")?;
+        let payload_sha256 = if payload_hash == "UNSIGNED-PAYLOAD" {
+            None
+        } else {
+            let mut digest = [0; 32];
+            // Streaming (chunked) payload signatures are not supported.
+            hex::decode_to_slice(payload_hash, &mut digest)
+                .map_err(|_| S3Error::not_implemented())?;
+            Some(digest)
+        };
+
+        let string_to_sign = format!(
+            "{ALGORITHM}\n{timestamp}\n{}/{}/{}/aws4_request\n{}",
+            self.date,
+            self.region,
+            self.service,
+            hex::encode(Sha256::digest(
+                self.canonical_request(parts, payload_hash).as_bytes()
+            )),
+        );
+        let mut key = hmac_sha256(format!("AWS4{secret}").as_bytes(), self.date.as_bytes());
+        for scope in [self.region, self.service, "aws4_request"] {
+            key = hmac_sha256(&key, scope.as_bytes());
+        }
+        let mut mac = HmacSha256::new_from_slice(&key).expect("hmac accepts any key length");
+        mac.update(string_to_sign.as_bytes());
+        mac.verify_slice(&self.signature).map_err(|_| {
+            S3Error::new(
+                StatusCode::FORBIDDEN,
+                "SignatureDoesNotMatch",
+                "the request signature does not match",
+            )
+        })?;
+
+        Ok(payload_sha256)
+    }
+
+    fn canonical_request(&self, parts: &Parts, payload_hash: &str) -> String {
+        let mut query = parts
+            .uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (uri_encode(name), uri_encode(value))
+            })
+            .collect::<Vec<_>>();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut canonical = format!("{}\n{}\n{query}\n", parts.method, parts.uri.path());
+        for name in &self.signed_headers {
+            let values = parts
+                .headers
+                .get_all(*name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+                .join(",");
+            canonical.push_str(name);
+            canonical.push(':');
+            canonical.push_str(&values);
+            canonical.push('\n');
+        }
+        canonical.push('\n');
+        canonical.push_str(&self.signed_headers.join(";"));
+        canonical.push('\n');
+        canonical.push_str(payload_hash);
+        canonical
+    }
+}
+
+fn invalid_access_key() -> S3Error {
+    S3Error::new(
+        StatusCode::FORBIDDEN,
+        "InvalidAccessKeyId",
+        "the access key does not exist",
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Normalizes the percent-encoding of a query string component.
+fn uri_encode(component: &str) -> String {
+    utf8_percent_encode(
+        &percent_decode_str(component).decode_utf8_lossy(),
+        UNRESERVED,
+    )
+    .to_string()
+}
+
+/// Parses an ISO 8601 basic format timestamp, such as `20130524T000000Z`.
+fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    let field = |range: std::ops::Range<usize>| timestamp.get(range)?.parse::<u64>().ok();
+    if timestamp.len() != 16 || timestamp.get(8..9)? != "T" || timestamp.get(15..)? != "Z" {
+        return None;
+    }
+    let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+    let (hour, minute, second) = (field(9..11)?, field(11..13)?, field(13..15)?);
+    if year < 1970
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Converts a date in the proleptic Gregorian calendar into the number of days
+/// since the Unix epoch. This is the inverse of `civil_from_days`.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = year - u64::from(month <= 2);
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,830 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use bonsaidb_core::connection::{AsyncStorageConnection, HasSession};
+use bonsaidb_files::FileConfig;
+use bonsaidb_local::AsyncDatabase;
+use futures::StreamExt;
+use hyper::body::HttpBody;
+use hyper::header::{
+    HeaderMap, HeaderValue, ALLOW, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, ETAG,
+    WWW_AUTHENTICATE,
+};
+use hyper::{Body, Method, Request, Response, StatusCode, Uri};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use tokio::io::AsyncWriteExt;
+
+use super::files::{
+    contents_response, decode_path, empty_response, etag, header_value, iso8601, last_modified,
+    push_element, replace_file, ContentsError, FileHandle,
+};
+use super::rest::RestError;
+use crate::{Backend, CustomServer};
+
+/// The path prefix that all WebDAV requests are served beneath.
+pub(super) const PATH_PREFIX: &str = "/dav/";
+/// The name of the empty file that marks a directory as a collection created
+/// using `MKCOL`. Files with this name cannot be accessed through WebDAV.
+const COLLECTION_MARKER: &str = ".dav-collection";
+/// The prefix of the names that uploads, copies, and moves are written to
+/// before replacing their destination. Resources with names starting with this
+/// prefix cannot be accessed through WebDAV.
+const TRANSFER_PREFIX: &str = ".dav-transfer-";
+const ALLOWED_METHODS: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, MKCOL, COPY, MOVE";
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="utf-8"?>"#;
+/// The characters that are not percent-encoded in the segments of an `href`.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+impl<B: Backend> CustomServer<B> {
+    /// Handles a WebDAV request beneath `/dav/`. Requires feature `webdav` to
+    /// be enabled.
+    ///
+    /// The root collection contains a collection for each database, and each
+    /// database's collection contains the files stored using `Config` within
+    /// that database. The database must include `Config`'s collections in its
+    /// schema. Directories are collections if they contain at least one file
+    /// or were created using `MKCOL`. If a file exists at the same path as a
+    /// directory, the file is served and the directory's contents are only
+    /// reachable using their full paths.
+    ///
+    /// The supported methods are `OPTIONS`, `PROPFIND` with a `Depth` of `0`
+    /// or `1`, `GET` and `HEAD` (including `Range` requests), `PUT`, `MKCOL`,
+    /// `DELETE`, `COPY`, and `MOVE`. `PROPFIND` always returns every supported
+    /// property, and locking is not supported.
+    ///
+    /// Requests are authenticated the same way as REST API requests. If the
+    /// default session is denied permission to perform a request that has no
+    /// credentials, the client is challenged to authenticate using HTTP Basic
+    /// authentication.
+    pub async fn handle_webdav_request<Config>(&self, request: Request<Body>) -> Response<Body>
+    where
+        Config: FileConfig,
+        Config::Metadata: Default,
+    {
+        let has_credentials = request.headers().contains_key(AUTHORIZATION);
+        match self.route_webdav_request::<Config>(request).await {
+            Ok(response) => response,
+            Err(mut err) => {
+                let challenge = err.status == StatusCode::UNAUTHORIZED
+                    || (err.permission_denied && !has_credentials);
+                if challenge {
+                    err.status = StatusCode::UNAUTHORIZED;
+                }
+                let mut response = err.into_response();
+                if challenge {
+                    response.headers_mut().insert(
+                        WWW_AUTHENTICATE,
+                        HeaderValue::from_static(r#"Basic realm="BonsaiDb""#),
+                    );
+                }
+                response
+            }
+        }
+    }
+
+    async fn route_webdav_request<Config>(
+        &self,
+        request: Request<Body>,
+    ) -> Result<Response<Body>, RestError>
+    where
+        Config: FileConfig,
+        Config::Metadata: Default,
+    {
+        let (parts, body) = request.into_parts();
+        let client = self.rest_client(&parts).await?;
+        let request_bytes = parts
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        if let Some(session) = client.session() {
+            self.check_user_rate_limit(session, request_bytes)?;
+        }
+
+        if parts.method == Method::OPTIONS {
+            return Ok(options_response());
+        }
+
+        let (database_name, path) = parse_path(parts.uri.path())?;
+        let Some(database_name) = database_name else {
+            return match parts.method.as_str() {
+                "PROPFIND" => list_databases(&client, &parts.headers).await,
+                _ => Err(method_not_allowed()),
+            };
+        };
+
+        let database = client
+            .storage
+            .database_without_schema(&database_name)
+            .await?;
+        self.check_database_rate_limit(&database, request_bytes)?;
+        let request = DavRequest {
+            database_name,
+            path,
+            headers: parts.headers,
+            body,
+        };
+        match parts.method.as_str() {
+            "PROPFIND" => propfind::<Config>(&database, &request).await,
+            "GET" => get::<Config>(&database, &request, true).await,
+            "HEAD" => get::<Config>(&database, &request, false).await,
+            "PUT" => put::<Config>(&database, request).await,
+            "MKCOL" => mkcol::<Config>(&database, request).await,
+            "DELETE" => delete::<Config>(&database, &request).await,
+            "COPY" => copy_or_move::<Config>(&database, &request, false).await,
+            "MOVE" => copy_or_move::<Config>(&database, &request, true).await,
+            _ => Err(method_not_allowed()),
+        }
+    }
+}
+
+struct DavRequest {
+    database_name: String,
+    /// The absolute path of the requested resource within the database.
+    path: String,
+    headers: HeaderMap,
+    body: Body,
+}
+
+impl DavRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    }
+
+    /// Returns the `Depth` header, where `None` represents `infinity`.
+    fn depth(&self) -> Result<Option<u8>, RestError> {
+        match self.header("depth") {
+            Some("0") => Ok(Some(0)),
+            Some("1") => Ok(Some(1)),
+            Some(depth) if !depth.eq_ignore_ascii_case("infinity") => {
+                Err(RestError::bad_request("invalid depth"))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn href(&self, path: &str, collection: bool) -> String {
+        href(&self.database_name, path, collection)
+    }
+}
+
+/// A resource within a database.
+enum Resource<Config: FileConfig> {
+    File(FileHandle<Config>),
+    Collection,
+    Missing,
+}
+
+impl<Config: FileConfig> Resource<Config> {
+    async fn load(database: &AsyncDatabase, path: &str) -> Result<Self, RestError> {
+        if path == "/" {
+            // Ensure the database stores files using `Config`.
+            Config::list_async("/", database).await?;
+            return Ok(Self::Collection);
+        }
+
+        if let Some(file) = Config::load_async(path, database).await? {
+            Ok(Self::File(file))
+        } else if Config::list_recursive_async(&directory(path), database)
+            .await?
+            .is_empty()
+        {
+            Ok(Self::Missing)
+        } else {
+            Ok(Self::Collection)
+        }
+    }
+
+    fn exists(&self) -> bool {
+        !matches!(self, Self::Missing)
+    }
+}
+
+async fn list_databases<B: Backend>(
+    client: &CustomServer<B>,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, RestError> {
+    let mut xml = multistatus_start();
+    push_response(&mut xml, PATH_PREFIX, &Properties::collection(""));
+    if headers.get("depth").map_or(true, |depth| depth != "0") {
+        for database in client.list_databases().await? {
+            push_response(
+                &mut xml,
+                &href(&database.name, "/", true),
+                &Properties::collection(&database.name),
+            );
+        }
+    }
+    Ok(multistatus_response(xml))
+}
+
+async fn propfind<Config: FileConfig>(
+    database: &AsyncDatabase,
+    request: &DavRequest,
+) -> Result<Response<Body>, RestError> {
+    let depth = request.depth()?.ok_or_else(|| {
+        RestError::new(
+            StatusCode::FORBIDDEN,
+            "PROPFIND requests must have a depth of 0 or 1",
+        )
+    })?;
+
+    let mut xml = multistatus_start();
+    match Resource::<Config>::load(database, &request.path).await? {
+        Resource::File(file) => {
+            push_response(
+                &mut xml,
+                &request.href(&request.path, false),
+                &Properties::file(&file).await?,
+            );
+        }
+        Resource::Collection => {
+            let name = match request.path.rsplit_once('/') {
+                Some((_, "")) | None => request.database_name.as_str(),
+                Some((_, name)) => name,
+            };
+            push_response(
+                &mut xml,
+                &request.href(&request.path, true),
+                &Properties::collection(name),
+            );
+            if depth > 0 {
+                let directory = directory(&request.path);
+                for (name, file) in children::<Config>(database, &directory).await? {
+                    let path = format!("{directory}{name}");
+                    if let Some(file) = file {
+                        push_response(
+                            &mut xml,
+                            &request.href(&path, false),
+                            &Properties::file(&file).await?,
+                        );
+                    } else {
+                        push_response(
+                            &mut xml,
+                            &request.href(&path, true),
+                            &Properties::collection(&name),
+                        );
+                    }
+                }
+            }
+        }
+        Resource::Missing => return Err(RestError::not_found()),
+    }
+    Ok(multistatus_response(xml))
+}
+
+/// Returns the names of the resources directly within `directory`, along with
+/// the file stored at each name. Names without a file are collections.
+async fn children<Config: FileConfig>(
+    database: &AsyncDatabase,
+    directory: &str,
+) -> Result<BTreeMap<String, Option<FileHandle<Config>>>, RestError> {
+    let mut children = BTreeMap::new();
+    for file in Config::list_recursive_async(directory, database).await? {
+        let path = file.path();
+        match path[directory.len()..].split_once('/') {
+            Some((collection, _)) if is_hidden(collection) => {}
+            None if is_hidden(file.name()) => {}
+            Some((collection, _)) => {
+                children.entry(collection.to_string()).or_insert(None);
+            }
+            None => {
+                children.insert(file.name().to_string(), Some(file));
+            }
+        }
+    }
+    Ok(children)
+}
+
+async fn get<Config: FileConfig>(
+    database: &AsyncDatabase,
+    request: &DavRequest,
+    include_body: bool,
+) -> Result<Response<Body>, RestError> {
+    match Resource::<Config>::load(database, &request.path).await? {
+        Resource::File(file) => Ok(contents_response(&file, &request.headers, include_body).await?),
+        Resource::Collection => Err(method_not_allowed()),
+        Resource::Missing => Err(RestError::not_found()),
+    }
+}
+
+async fn put<Config>(
+    database: &AsyncDatabase,
+    request: DavRequest,
+) -> Result<Response<Body>, RestError>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let existed = match Resource::<Config>::load(database, &request.path).await? {
+        Resource::File(_) => true,
+        Resource::Collection => return Err(method_not_allowed()),
+        Resource::Missing => false,
+    };
+    ensure_parent_exists::<Config>(database, &request.path).await?;
+
+    // The body is streamed into a temporary file, which only replaces the
+    // existing file once the entire body has been received.
+    let mut file = Config::build(temporary_path(&request.path))
+        .create_async(database)
+        .await?;
+    if let Err(err) = receive_body(&mut file, request.body).await {
+        // Failing to remove the incomplete upload doesn't change the outcome
+        // of the request.
+        drop(file.delete().await);
+        return Err(err);
+    }
+    if let Some(existing) = Config::load_async(&request.path, database).await? {
+        existing.delete().await?;
+    }
+    file.move_to(&request.path).await?;
+    let mut response = empty_response(if existed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    });
+    if let Some(etag) = etag(&file).await?.and_then(header_value) {
+        response.headers_mut().insert(ETAG, etag);
+    }
+    Ok(response)
+}
+
+async fn mkcol<Config>(
+    database: &AsyncDatabase,
+    request: DavRequest,
+) -> Result<Response<Body>, RestError>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let mut body = request.body;
+    while let Some(chunk) = body.data().await {
+        if !chunk.map_err(RestError::bad_request)?.is_empty() {
+            return Err(RestError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "MKCOL request bodies are not supported",
+            ));
+        }
+    }
+    if Resource::<Config>::load(database, &request.path)
+        .await?
+        .exists()
+    {
+        return Err(method_not_allowed());
+    }
+    ensure_parent_exists::<Config>(database, &request.path).await?;
+
+    create_collection::<Config>(database, &request.path).await?;
+    Ok(empty_response(StatusCode::CREATED))
+}
+
+async fn delete<Config: FileConfig>(
+    database: &AsyncDatabase,
+    request: &DavRequest,
+) -> Result<Response<Body>, RestError> {
+    if request.path == "/" {
+        return Err(RestError::new(
+            StatusCode::FORBIDDEN,
+            "databases cannot be deleted using WebDAV",
+        ));
+    }
+    match Resource::<Config>::load(database, &request.path).await? {
+        Resource::File(file) => file.delete().await?,
+        Resource::Collection => delete_collection::<Config>(database, &request.path).await?,
+        Resource::Missing => return Err(RestError::not_found()),
+    }
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+async fn copy_or_move<Config>(
+    database: &AsyncDatabase,
+    request: &DavRequest,
+    is_move: bool,
+) -> Result<Response<Body>, RestError>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let destination = request
+        .header("destination")
+        .ok_or_else(|| RestError::bad_request("missing destination header"))?;
+    let destination = destination
+        .parse::<Uri>()
+        .map_err(|_| RestError::bad_request("invalid destination header"))?;
+    let (destination_database, destination) = parse_path(destination.path())?;
+    if destination_database.as_deref() != Some(request.database_name.as_str()) {
+        return Err(RestError::new(
+            StatusCode::BAD_GATEWAY,
+            "the destination must be within the same database",
+        ));
+    }
+    if request.path == "/" || destination == "/" {
+        return Err(RestError::new(
+            StatusCode::FORBIDDEN,
+            "databases cannot be copied or moved using WebDAV",
+        ));
+    } else if destination == request.path || destination.starts_with(&directory(&request.path)) {
+        return Err(RestError::new(
+            StatusCode::FORBIDDEN,
+            "the destination must not be the source or within it",
+        ));
+    }
+    let overwrite = !request
+        .header("overwrite")
+        .map_or(false, |overwrite| overwrite.eq_ignore_ascii_case("F"));
+
+    let source = Resource::<Config>::load(database, &request.path).await?;
+    if !source.exists() {
+        return Err(RestError::not_found());
+    }
+    ensure_parent_exists::<Config>(database, &destination).await?;
+    let existed = match Resource::<Config>::load(database, &destination).await? {
+        Resource::Missing => false,
+        _ if !overwrite => {
+            return Err(RestError::new(
+                StatusCode::PRECONDITION_FAILED,
+                "the destination already exists",
+            ))
+        }
+        Resource::File(_) | Resource::Collection => true,
+    };
+
+    // The source is transferred to a temporary path first, ensuring the
+    // destination is only replaced once the transfer has completed.
+    let temporary = temporary_path(&destination);
+    let transferred = match source {
+        Resource::File(mut file) if is_move => file.move_to(&temporary).await.map_err(Into::into),
+        Resource::File(file) => copy_file(database, &file, &temporary).await,
+        Resource::Collection => {
+            transfer_collection::<Config>(
+                database,
+                &request.path,
+                &temporary,
+                is_move,
+                request.depth()?.is_none(),
+            )
+            .await
+        }
+        Resource::Missing => unreachable!("checked above"),
+    };
+    if let Err(err) = transferred {
+        if !is_move {
+            // Failing to remove the incomplete copy doesn't change the outcome
+            // of the request.
+            drop(delete_resource::<Config>(database, &temporary).await);
+        }
+        return Err(err);
+    }
+
+    delete_resource::<Config>(database, &destination).await?;
+    match Resource::<Config>::load(database, &temporary).await? {
+        Resource::File(mut file) => file.move_to(&destination).await?,
+        Resource::Collection | Resource::Missing => {
+            transfer_collection::<Config>(database, &temporary, &destination, true, true).await?;
+        }
+    }
+
+    Ok(empty_response(if existed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }))
+}
+
+/// Copies or moves the collection at `source` to `destination`. When copying,
+/// the collection's contents are only copied if `recursive` is true.
+async fn transfer_collection<Config>(
+    database: &AsyncDatabase,
+    source: &str,
+    destination: &str,
+    is_move: bool,
+    recursive: bool,
+) -> Result<(), RestError>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let source_directory = directory(source);
+    let destination_directory = directory(destination);
+    let files = Config::list_recursive_async(&source_directory, database).await?;
+    if is_move {
+        for mut file in files {
+            let path = file.path();
+            file.move_to(&format!(
+                "{destination_directory}{}",
+                &path[source_directory.len()..]
+            ))
+            .await?;
+        }
+    } else {
+        create_collection::<Config>(database, destination).await?;
+        if recursive {
+            for file in files {
+                let path = file.path();
+                if file.name() != COLLECTION_MARKER {
+                    copy_file(
+                        database,
+                        &file,
+                        &format!("{destination_directory}{}", &path[source_directory.len()..]),
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error if the collection containing `path` does not exist.
+async fn ensure_parent_exists<Config: FileConfig>(
+    database: &AsyncDatabase,
+    path: &str,
+) -> Result<(), RestError> {
+    let parent = match path.rsplit_once('/') {
+        Some(("", _)) | None => "/",
+        Some((parent, _)) => parent,
+    };
+    match Resource::<Config>::load(database, parent).await? {
+        Resource::Collection => Ok(()),
+        Resource::File(_) | Resource::Missing => Err(RestError::new(
+            StatusCode::CONFLICT,
+            "the parent collection does not exist",
+        )),
+    }
+}
+
+async fn create_collection<Config>(database: &AsyncDatabase, path: &str) -> Result<(), RestError>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    Config::build(format!("{}{COLLECTION_MARKER}", directory(path)))
+        .create_async(database)
+        .await?;
+    Ok(())
+}
+
+async fn delete_collection<Config: FileConfig>(
+    database: &AsyncDatabase,
+    path: &str,
+) -> Result<(), RestError> {
+    for file in Config::list_recursive_async(&directory(path), database).await? {
+        file.delete().await?;
+    }
+    Ok(())
+}
+
+/// Deletes the file or collection at `path`, if either exists.
+async fn delete_resource<Config: FileConfig>(
+    database: &AsyncDatabase,
+    path: &str,
+) -> Result<(), RestError> {
+    match Resource::<Config>::load(database, path).await? {
+        Resource::File(file) => file.delete().await?,
+        Resource::Collection => delete_collection::<Config>(database, path).await?,
+        Resource::Missing => {}
+    }
+    Ok(())
+}
+
+/// Appends the contents of `body` to `file` as they are received.
+async fn receive_body<Config: FileConfig>(
+    file: &mut FileHandle<Config>,
+    mut body: Body,
+) -> Result<(), RestError> {
+    let mut writer = file.append_buffered();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(RestError::bad_request)?;
+        writer
+            .write_all(&chunk)
+            .await
+            .map_err(RestError::internal)?;
+    }
+    writer.flush().await.map_err(RestError::internal)
+}
+
+/// Returns a hidden path within the collection containing `path`, which a
+/// resource is written to before it replaces the resource at `path`.
+fn temporary_path(path: &str) -> String {
+    static TRANSFERS: AtomicU64 = AtomicU64::new(0);
+    let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "{parent}/{TRANSFER_PREFIX}{now:x}-{:x}",
+        TRANSFERS.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Returns true if resources named `name` cannot be accessed through WebDAV.
+fn is_hidden(name: &str) -> bool {
+    name == COLLECTION_MARKER || name.starts_with(TRANSFER_PREFIX)
+}
+
+/// Creates a file at `destination` with the same contents as `file`.
+async fn copy_file<Config>(
+    database: &AsyncDatabase,
+    file: &FileHandle<Config>,
+    destination: &str,
+) -> Result<(), RestError>
+where
+    Config: FileConfig,
+    Config::Metadata: Default,
+{
+    let copy = replace_file::<Config>(database, destination, b"").await?;
+    let mut contents = file.contents().await?;
+    while let Some(block) = contents.next().await {
+        copy.append(&block.map_err(RestError::internal)?).await?;
+    }
+    Ok(())
+}
+
+/// Splits a request path into the name of the database it refers to and the
+/// absolute path of the resource within that database. The database name is
+/// `None` for the root collection.
+fn parse_path(path: &str) -> Result<(Option<String>, String), RestError> {
+    let path = path
+        .strip_prefix(PATH_PREFIX)
+        .or_else(|| (path == PATH_PREFIX.trim_end_matches('/')).then_some(""))
+        .ok_or_else(RestError::not_found)?;
+    let mut segments = path.trim_end_matches('/').split('/').map(|segment| {
+        decode_path(segment)
+            .filter(|segment| !segment.contains('/'))
+            .ok_or_else(|| RestError::bad_request("paths must be valid UTF-8"))
+    });
+    let database = segments.next().transpose()?.filter(|name| !name.is_empty());
+    if database.is_none() && path.trim_matches('/').contains('/') {
+        return Err(RestError::not_found());
+    }
+
+    let mut resource_path = String::new();
+    for segment in segments {
+        let segment = segment?;
+        if segment.is_empty() || segment == "." || segment == ".." {
+            return Err(RestError::bad_request(
+                "paths must not contain empty segments",
+            ));
+        } else if is_hidden(&segment) {
+            return Err(RestError::not_found());
+        }
+        resource_path.push('/');
+        resource_path.push_str(&segment);
+    }
+    if resource_path.is_empty() {
+        resource_path.push('/');
+    }
+    Ok((database, resource_path))
+}
+
+/// Returns `path` with a trailing `/`, for listing the files beneath it.
+fn directory(path: &str) -> String {
+    if path.ends_with('/') {
+        path.to_string()
+    } else {
+        format!("{path}/")
+    }
+}
+
+fn href(database: &str, path: &str, collection: bool) -> String {
+    let mut href = String::from(PATH_PREFIX);
+    href.extend(utf8_percent_encode(database, UNRESERVED));
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        href.push('/');
+        href.extend(utf8_percent_encode(segment, UNRESERVED));
+    }
+    if collection {
+        href.push('/');
+    }
+    href
+}
+
+/// The properties reported for a resource by `PROPFIND`.
+struct Properties {
+    display_name: String,
+    file: Option<FileProperties>,
+}
+
+struct FileProperties {
+    length: u64,
+    created_at: SystemTime,
+    last_modified: SystemTime,
+    etag: Option<String>,
+}
+
+impl Properties {
+    fn collection(name: &str) -> Self {
+        Self {
+            display_name: name.to_string(),
+            file: None,
+        }
+    }
+
+    async fn file<Config: FileConfig>(file: &FileHandle<Config>) -> Result<Self, RestError> {
+        let contents = file.contents().await?;
+        Ok(Self {
+            display_name: file.name().to_string(),
+            file: Some(FileProperties {
+                length: contents.len(),
+                created_at: SystemTime::try_from(file.created_at())
+                    .unwrap_or(SystemTime::UNIX_EPOCH),
+                last_modified: last_modified(file, &contents),
+                etag: etag(file).await?,
+            }),
+        })
+    }
+}
+
+fn multistatus_start() -> String {
+    format!(r#"{XML_HEADER}<D:multistatus xmlns:D="DAV:">"#)
+}
+
+/// Appends a `response` element describing the resource at `href`.
+fn push_response(xml: &mut String, href: &str, properties: &Properties) {
+    xml.push_str("<D:response>");
+    push_element(xml, "D:href", href);
+    xml.push_str("<D:propstat><D:prop>");
+    push_element(xml, "D:displayname", &properties.display_name);
+    if let Some(file) = &properties.file {
+        xml.push_str("<D:resourcetype/>");
+        push_element(xml, "D:getcontentlength", &file.length.to_string());
+        push_element(xml, "D:getcontenttype", "application/octet-stream");
+        push_element(xml, "D:creationdate", &iso8601(file.created_at));
+        push_element(
+            xml,
+            "D:getlastmodified",
+            &httpdate::fmt_http_date(file.last_modified),
+        );
+        if let Some(etag) = &file.etag {
+            push_element(xml, "D:getetag", etag);
+        }
+    } else {
+        xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    }
+    xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>");
+}
+
+fn multistatus_response(mut xml: String) -> Response<Body> {
+    xml.push_str("</D:multistatus>");
+    let mut response = Response::new(Body::from(xml));
+    *response.status_mut() = StatusCode::MULTI_STATUS;
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/xml; charset=utf-8"),
+    );
+    response
+}
+
+fn options_response() -> Response<Body> {
+    let mut response = empty_response(StatusCode::OK);
+    let headers = response.headers_mut();
+    headers.insert("dav", HeaderValue::from_static("1"));
+    headers.insert(ALLOW, HeaderValue::from_static(ALLOWED_METHODS));
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(0));
+    response
+}
+
+fn method_not_allowed() -> RestError {
+    RestError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "the method is not supported for this resource",
+    )
+}
+
+impl From<bonsaidb_files::Error> for RestError {
+    fn from(err: bonsaidb_files::Error) -> Self {
+        match err {
+            bonsaidb_files::Error::Database(err) => Self::from(err),
+            bonsaidb_files::Error::InvalidName | bonsaidb_files::Error::InvalidPath => {
+                Self::bad_request(err)
+            }
+            bonsaidb_files::Error::AlreadyExists | bonsaidb_files::Error::Deleted => {
+                Self::new(StatusCode::CONFLICT, err)
+            }
+            other => Self::internal(other),
+        }
+    }
+}
+
+impl From<ContentsError> for RestError {
+    fn from(err: ContentsError) -> Self {
+        match err {
+            ContentsError::Database(err) => Self::from(err),
+            ContentsError::Io(err) => Self::internal(err),
+            ContentsError::UnsatisfiableRange(length) => Self::new(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                format!("the requested range is not satisfiable for {length} bytes"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::time::Duration;
+
+use bonsaidb_core::api::ApiName;
+use bonsaidb_local::metrics::{DurationSummary, StorageMetrics};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the metrics collected by a
+/// [`CustomServer`](crate::CustomServer) since it was started.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ServerMetrics {
+    /// The number of currently connected clients.
+    pub connected_clients: usize,
+    /// The number of requests waiting for a request worker.
+    pub request_queue_depth: usize,
+    /// Metrics for each API that has handled at least one request.
+    pub requests: BTreeMap<ApiName, RequestMetrics>,
+    /// Metrics collected by the underlying storage.
+    pub storage: StorageMetrics,
+}
+
+/// Metrics for requests to a single API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestMetrics {
+    /// The number of requests handled, and the total time spent handling them.
+    pub handled: DurationSummary,
+    /// The number of requests that resulted in an error.
+    pub errors: u64,
+}
+
+impl ServerMetrics {
+    /// Returns these metrics formatted using the [Prometheus text exposition
+    /// format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+    #[must_use]
+    pub fn to_prometheus_text(&self) -> String {
+        let mut text = String::new();
+        write_metric(
+            &mut text,
+            "bonsaidb_connected_clients",
+            "gauge",
+            "The number of currently connected clients.",
+            [(None, self.connected_clients.to_string())],
+        );
+        write_metric(
+            &mut text,
+            "bonsaidb_request_queue_depth",
+            "gauge",
+            "The number of requests waiting for a request worker.",
+            [(None, self.request_queue_depth.to_string())],
+        );
+        write_metric(
+            &mut text,
+            "bonsaidb_requests_total",
+            "counter",
+            "The number of requests handled.",
+            self.requests
+                .iter()
+                .map(|(api, metrics)| (Some(api_label(api)), metrics.handled.count.to_string())),
+        );
+        write_metric(
+            &mut text,
+            "bonsaidb_request_errors_total",
+            "counter",
+            "The number of requests that resulted in an error.",
+            self.requests
+                .iter()
+                .map(|(api, metrics)| (Some(api_label(api)), metrics.errors.to_string())),
+        );
+        write_summary(
+            &mut text,
+            "bonsaidb_request_duration_seconds",
+            "The time spent handling requests.",
+            self.requests
+                .iter()
+                .map(|(api, metrics)| (Some(api_label(api)), metrics.handled)),
+        );
+        write_summary(
+            &mut text,
+            "bonsaidb_view_update_duration_seconds",
+            "The time spent updating views.",
+            [(None, self.storage.view_updates)],
+        );
+        write_summary(
+            &mut text,
+            "bonsaidb_compaction_duration_seconds",
+            "The time spent compacting.",
+            [(None, self.storage.compactions)],
+        );
+        write_summary(
+            &mut text,
+            "bonsaidb_key_value_persistence_duration_seconds",
+            "The time spent persisting key-value entries.",
+            [(None, self.storage.key_value_persistence)],
+        );
+        write_metric(
+            &mut text,
+            "bonsaidb_key_value_keys_persisted_total",
+            "counter",
+            "The number of key-value entries persisted.",
+            [(None, self.storage.key_value_keys_persisted.to_string())],
+        );
+        write_metric(
+            &mut text,
+            "bonsaidb_key_value_persistence_lag_seconds",
+            "gauge",
+            "How long the oldest change had been waiting when key-value entries were last persisted.",
+            [(None, seconds(self.storage.key_value_persistence_lag))],
+        );
+        text
+    }
+}
+
+fn write_metric(
+    text: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    values: impl IntoIterator<Item = (Option<String>, String)>,
+) {
+    let _ = writeln!(text, "# HELP {name} {help}");
+    let _ = writeln!(text, "# TYPE {name} {kind}");
+    for (labels, value) in values {
+        let _ = writeln!(text, "{name}{} {value}", labels.unwrap_or_default());
+    }
+}
+
+fn write_summary(
+    text: &mut String,
+    name: &str,
+    help: &str,
+    values: impl IntoIterator<Item = (Option<String>, DurationSummary)>,
+) {
+    let _ = writeln!(text, "# HELP {name} {help}");
+    let _ = writeln!(text, "# TYPE {name} summary");
+    for (labels, summary) in values {
+        let labels = labels.unwrap_or_default();
+        let _ = writeln!(text, "{name}_sum{labels} {}", seconds(summary.total));
+        let _ = writeln!(text, "{name}_count{labels} {}", summary.count);
+    }
+}
+
+fn api_label(api: &ApiName) -> String {
+    let mut escaped = String::new();
+    for ch in api.to_string().chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            ch => escaped.push(ch),
+        }
+    }
+    format!("{{api=\"{escaped}\"}}")
+}
+
+fn seconds(duration: Duration) -> String {
+    duration.as_secs_f64().to_string()
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RequestMetricsCollector {
+    requests: RwLock<HashMap<ApiName, RequestMetrics>>,
+}
+
+impl RequestMetricsCollector {
+    pub fn record(&self, api: &ApiName, duration: Duration, succeeded: bool) {
+        let mut requests = self.requests.write();
+        let metrics = requests.entry(api.clone()).or_default();
+        metrics.handled.count += 1;
+        metrics.handled.total += duration;
+        if !succeeded {
+            metrics.errors += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<ApiName, RequestMetrics> {
+        let requests = self.requests.read();
+        requests
+            .iter()
+            .map(|(api, metrics)| (api.clone(), *metrics))
+            .collect()
+    }
+}
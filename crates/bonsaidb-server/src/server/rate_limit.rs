@@ -0,0 +1,384 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bonsaidb_core::connection::{Identity, Session, SessionAuthentication};
+use bonsaidb_local::{AsyncDatabase, DatabaseNonBlocking};
+use parking_lot::Mutex;
+
+use crate::config::{AuthenticationThrottle, RateLimit, RateLimits};
+use crate::{Backend, ConnectedClient, CustomServer};
+
+/// The scope a [`RateLimit`] is being applied to. Passed to
+/// [`Backend::rate_limit()`](crate::Backend::rate_limit).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RateLimitScope<'a, B: Backend> {
+    /// The limit for a single connected client.
+    Connection(&'a ConnectedClient<B>),
+    /// The limit for an authenticated identity.
+    User(&'a Identity),
+    /// The limit for a database.
+    Database(&'a str),
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limits: RateLimits,
+    users: Mutex<TokenBuckets<Arc<Identity>>>,
+    databases: Mutex<TokenBuckets<String>>,
+    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+    authentication: AuthenticationThrottle,
+    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+    failed_authentications: Mutex<HashMap<IpAddr, FailedAuthentications>>,
+}
+
+impl RateLimiter {
+    #[cfg_attr(
+        not(any(feature = "token-authentication", feature = "password-hashing")),
+        allow(unused_variables)
+    )]
+    pub fn new(limits: RateLimits, authentication: AuthenticationThrottle) -> Self {
+        Self {
+            limits,
+            users: Mutex::default(),
+            databases: Mutex::default(),
+            #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+            authentication,
+            #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+            failed_authentications: Mutex::default(),
+        }
+    }
+}
+
+/// The minimum amount of time between evicting buckets that have refilled
+/// completely. Buckets allow bursts of one second's worth of their limit, so
+/// any bucket that has been idle for this long is full.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A set of [`TokenBucket`]s keyed by `K`.
+///
+/// A bucket that has refilled completely is indistinguishable from a newly
+/// created bucket, so full buckets are periodically evicted to prevent
+/// identities and databases that are no longer being used from being tracked
+/// forever.
+#[derive(Debug)]
+struct TokenBuckets<K> {
+    buckets: HashMap<K, TokenBucket>,
+    evicted_at: Instant,
+}
+
+impl<K> Default for TokenBuckets<K> {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::default(),
+            evicted_at: Instant::now(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> TokenBuckets<K> {
+    /// Acquires one request and `request_bytes` bytes from the bucket for
+    /// `key`. If no bucket exists, one is created using the key and limit
+    /// returned from `new_bucket`.
+    fn acquire<Q>(
+        &mut self,
+        key: &Q,
+        request_bytes: usize,
+        new_bucket: impl FnOnce() -> (K, RateLimit),
+    ) -> Result<(), Duration>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let now = Instant::now();
+        if now.duration_since(self.evicted_at) >= EVICTION_INTERVAL {
+            self.buckets.retain(|_, bucket| !bucket.is_full(now));
+            self.evicted_at = now;
+        }
+
+        if !self.buckets.contains_key(key) {
+            let (key, limit) = new_bucket();
+            self.buckets.insert(key, TokenBucket::new(limit));
+        }
+        self.buckets
+            .get_mut(key)
+            .expect("just inserted")
+            .acquire(request_bytes)
+    }
+}
+
+/// The failed authentication attempts made from an address during the
+/// current throttling window.
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+#[derive(Debug)]
+struct FailedAuthentications {
+    count: u32,
+    window_started_at: Instant,
+}
+
+impl<B: Backend> CustomServer<B> {
+    /// Checks the per-connection and per-user rate limits for a request of
+    /// `request_bytes` from `client` made using `session`.
+    pub(crate) fn check_client_rate_limits(
+        &self,
+        client: &ConnectedClient<B>,
+        session: &Session,
+        request_bytes: usize,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let limiter = &self.data.rate_limiter;
+        client
+            .rate_limit_bucket()
+            .lock()
+            .get_or_insert_with(|| {
+                TokenBucket::new(self.data.backend.rate_limit(
+                    RateLimitScope::Connection(client),
+                    limiter.limits.per_connection,
+                ))
+            })
+            .acquire(request_bytes)
+            .map_err(rate_limited)?;
+
+        self.check_user_rate_limit(session, request_bytes)
+    }
+
+    /// Checks the per-user rate limit for a request of `request_bytes` made
+    /// using `session`. Unauthenticated sessions are not limited by this
+    /// check.
+    pub(crate) fn check_user_rate_limit(
+        &self,
+        session: &Session,
+        request_bytes: usize,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let limiter = &self.data.rate_limiter;
+        if let SessionAuthentication::Identity(identity) = &session.authentication {
+            limiter
+                .users
+                .lock()
+                .acquire(identity, request_bytes, || {
+                    let limit = self
+                        .data
+                        .backend
+                        .rate_limit(RateLimitScope::User(identity), limiter.limits.per_user);
+                    (identity.clone(), limit)
+                })
+                .map_err(rate_limited)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the per-database rate limit for a request of `request_bytes` to
+    /// `database`. Requiring an opened database ensures that buckets are only
+    /// created for databases that exist.
+    pub(crate) fn check_database_rate_limit(
+        &self,
+        database: &AsyncDatabase,
+        request_bytes: usize,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let limiter = &self.data.rate_limiter;
+        let name = database.name();
+        limiter
+            .databases
+            .lock()
+            .acquire(name, request_bytes, || {
+                let limit = self
+                    .data
+                    .backend
+                    .rate_limit(RateLimitScope::Database(name), limiter.limits.per_database);
+                (name.to_string(), limit)
+            })
+            .map_err(rate_limited)
+    }
+
+    /// Returns the number of identities and databases that are currently
+    /// being rate limited.
+    #[cfg(test)]
+    pub(crate) fn rate_limit_bucket_count(&self) -> usize {
+        let limiter = &self.data.rate_limiter;
+        limiter.users.lock().buckets.len() + limiter.databases.lock().buckets.len()
+    }
+}
+
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+impl<B: Backend> CustomServer<B> {
+    /// Reserves an authentication attempt from `address`, returning an error
+    /// if too many attempts from `address` have failed during the current
+    /// throttling window.
+    ///
+    /// The attempt is counted as a failure until it is released, which
+    /// ensures that attempts made in parallel can't exceed the allowed number
+    /// of failures. Returns `None` if authentication isn't throttled.
+    pub(crate) fn reserve_authentication_attempt(
+        &self,
+        address: IpAddr,
+    ) -> Result<Option<ReservedAuthentication>, bonsaidb_core::Error> {
+        let limiter = &self.data.rate_limiter;
+        let Some(max_failed_attempts) = limiter.authentication.max_failed_attempts else {
+            return Ok(None);
+        };
+
+        let mut failed_authentications = limiter.failed_authentications.lock();
+        // Forget addresses whose window has ended. Their next attempt starts a
+        // new window.
+        failed_authentications
+            .retain(|_, failed| failed.window_started_at.elapsed() < limiter.authentication.window);
+        let failed =
+            failed_authentications
+                .entry(address)
+                .or_insert_with(|| FailedAuthentications {
+                    count: 0,
+                    window_started_at: Instant::now(),
+                });
+        let elapsed = failed.window_started_at.elapsed();
+        if failed.count >= max_failed_attempts {
+            return Err(bonsaidb_core::Error::AuthenticationThrottled {
+                retry_after: limiter.authentication.window - elapsed,
+            });
+        }
+        failed.count += 1;
+
+        Ok(Some(ReservedAuthentication {
+            address,
+            window_started_at: failed.window_started_at,
+        }))
+    }
+
+    /// Releases an attempt reserved with
+    /// [`Self::reserve_authentication_attempt`] that should not be counted as
+    /// a failure.
+    pub(crate) fn release_authentication_attempt(&self, reservation: ReservedAuthentication) {
+        let mut failed_authentications = self.data.rate_limiter.failed_authentications.lock();
+        if let Some(failed) = failed_authentications.get_mut(&reservation.address) {
+            // If a new window has started, the reservation was already
+            // discarded.
+            if failed.window_started_at == reservation.window_started_at {
+                failed.count = failed.count.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Returns the number of addresses whose failed authentication attempts
+    /// are being tracked.
+    #[cfg(test)]
+    pub(crate) fn tracked_authentication_addresses(&self) -> usize {
+        self.data.rate_limiter.failed_authentications.lock().len()
+    }
+}
+
+/// An authentication attempt reserved by
+/// [`CustomServer::reserve_authentication_attempt`].
+#[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
+#[derive(Debug)]
+pub(crate) struct ReservedAuthentication {
+    address: IpAddr,
+    window_started_at: Instant,
+}
+
+const fn rate_limited(retry_after: Duration) -> bonsaidb_core::Error {
+    bonsaidb_core::Error::RateLimited { retry_after }
+}
+
+/// The amount of time clients are asked to wait before retrying a request
+/// rejected by a limit of zero.
+const ZERO_LIMIT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// A token bucket that refills continuously and allows bursts of up to one
+/// second's worth of its limit. A limit of zero rejects every request.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    requests: f64,
+    bytes: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            requests: limit.requests_per_second.map_or(0., f64::from),
+            #[allow(clippy::cast_precision_loss)]
+            bytes: limit.bytes_per_second.map_or(0., |bytes| bytes as f64),
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Acquires one request and `request_bytes` bytes from the bucket. If the
+    /// limit has been exceeded, the duration to wait before retrying is
+    /// returned.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn acquire(&mut self, request_bytes: usize) -> Result<(), Duration> {
+        if self.limit.is_unlimited() {
+            return Ok(());
+        } else if self.limit.requests_per_second == Some(0)
+            || self.limit.bytes_per_second == Some(0)
+        {
+            return Err(ZERO_LIMIT_RETRY_AFTER);
+        }
+
+        let now = Instant::now();
+        let elapsed = now
+            .checked_duration_since(self.refilled_at)
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.refilled_at = now;
+
+        let requests_per_second = self.limit.requests_per_second.map(f64::from);
+        let bytes_per_second = self.limit.bytes_per_second.map(|bytes| bytes as f64);
+        if let Some(rate) = requests_per_second {
+            self.requests = (self.requests + elapsed * rate).min(rate);
+        }
+        if let Some(rate) = bytes_per_second {
+            self.bytes = (self.bytes + elapsed * rate).min(rate);
+        }
+
+        let request_bytes = request_bytes as f64;
+        let mut wait = 0_f64;
+        if let Some(rate) = requests_per_second {
+            if self.requests < 1. {
+                wait = wait.max((1. - self.requests) / rate);
+            }
+        }
+        if let Some(rate) = bytes_per_second {
+            // Requests larger than the burst size are allowed once the bucket
+            // is full, leaving the bucket in debt.
+            let required = request_bytes.min(rate);
+            if self.bytes < required {
+                wait = wait.max((required - self.bytes) / rate);
+            }
+        }
+
+        if wait > 0. {
+            return Err(Duration::from_secs_f64(wait));
+        }
+
+        if requests_per_second.is_some() {
+            self.requests -= 1.;
+        }
+        if bytes_per_second.is_some() {
+            self.bytes -= request_bytes;
+        }
+        Ok(())
+    }
+
+    /// Returns true if this bucket will have refilled completely at `now`.
+    #[allow(clippy::cast_precision_loss)]
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now
+            .checked_duration_since(self.refilled_at)
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.limit.requests_per_second.map_or(true, |rate| {
+            let rate = f64::from(rate);
+            self.requests + elapsed * rate >= rate
+        }) && self.limit.bytes_per_second.map_or(true, |rate| {
+            let rate = rate as f64;
+            self.bytes + elapsed * rate >= rate
+        })
+    }
+}
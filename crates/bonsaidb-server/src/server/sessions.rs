@@ -0,0 +1,84 @@
+use bonsaidb_core::connection::{HasSession, SessionId};
+use bonsaidb_core::networking::ActiveSession;
+use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, BonsaiAction, ServerAction};
+use bonsaidb_core::permissions::ClientTransport;
+
+use crate::{Backend, CustomServer};
+
+impl<B: Backend> CustomServer<B> {
+    /// Returns the sessions of every connected client, including each client's
+    /// unauthenticated session.
+    ///
+    /// Requires permission to [`ServerAction::ListSessions`].
+    pub fn active_sessions(&self) -> Result<Vec<ActiveSession>, bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::ListSessions),
+        )?;
+
+        let mut active = Vec::new();
+        for client in self.connected_clients() {
+            let transport = ClientTransport::from(client.transport());
+            for session in client.all_sessions::<Vec<_>>() {
+                active.push(ActiveSession {
+                    client_id: client.id(),
+                    session_id: session.id,
+                    identity: session.identity().cloned(),
+                    transport,
+                    address: *client.address(),
+                    connected_at: client.connected_at(),
+                    last_activity_at: client.last_activity_at(),
+                });
+            }
+        }
+        active.sort_by_key(|session| (session.client_id, session.session_id.map(|id| id.0)));
+        Ok(active)
+    }
+
+    /// Ends the authenticated session `session_id`. Future requests made by
+    /// the client using this session will use the client's unauthenticated
+    /// session instead.
+    ///
+    /// Requires permission to [`ServerAction::TerminateSession`].
+    pub async fn terminate_session(
+        &self,
+        session_id: SessionId,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::TerminateSession),
+        )?;
+
+        for client in self.connected_clients() {
+            if let Some(session) = client.log_out(session_id) {
+                if let Err(err) = self
+                    .backend()
+                    .client_session_ended(session, &client, false, self)
+                    .await
+                {
+                    log::error!("[server] Error in `client_session_ended`: {err:?}");
+                }
+                return Ok(());
+            }
+        }
+
+        Err(bonsaidb_core::Error::SessionNotFound)
+    }
+
+    /// Closes the connection of the client with id `client_id`.
+    ///
+    /// Requires permission to [`ServerAction::DisconnectClient`].
+    pub fn disconnect_client(&self, client_id: u32) -> Result<(), bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::DisconnectClient),
+        )?;
+
+        let clients = self.data.clients.read();
+        clients
+            .get(&client_id)
+            .ok_or(bonsaidb_core::Error::ClientNotFound)?
+            .disconnect();
+        Ok(())
+    }
+}
@@ -1,15 +1,19 @@
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
-use rustls::server::ResolvesServerCert;
+use fabruic::Certificate;
+use rustls::server::{ClientCertVerified, ClientCertVerifier, ResolvesServerCert};
+use rustls::DistinguishedName;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
 
-use crate::{Backend, CustomServer, Error};
+use crate::{Backend, ClientCertificates, CustomServer, Error};
 
 impl<B: Backend> CustomServer<B> {
     /// Listens for HTTP traffic on `port`. This port will also receive
-    /// `WebSocket` connections if feature `websockets` is enabled.
+    /// `WebSocket` connections if feature `websockets` is enabled, and REST API
+    /// requests if feature `rest-api` is enabled.
     pub async fn listen_for_tcp_on<S: TcpService, T: tokio::net::ToSocketAddrs + Send + Sync>(
         &self,
         addr: T,
@@ -38,6 +42,7 @@ impl<B: Backend> CustomServer<B> {
                         address: remote_addr,
                         protocol: service.available_protocols()[0].clone(),
                         secure: false,
+                        client_certificate: None,
                     };
 
                     let task_self = self.clone();
@@ -82,10 +87,16 @@ impl<B: Backend> CustomServer<B> {
             });
         }
 
-        let mut config = rustls::ServerConfig::builder()
-            .with_safe_defaults()
-            .with_no_client_auth()
-            .with_cert_resolver(Arc::new(self.clone()));
+        let config = rustls::ServerConfig::builder().with_safe_defaults();
+        let mode = self.data.client_certificates;
+        let config = if mode == ClientCertificates::Ignored {
+            config.with_no_client_auth()
+        } else {
+            config.with_client_cert_verifier(Arc::new(AnyClientCertificate {
+                required: mode == ClientCertificates::Required,
+            }))
+        };
+        let mut config = config.with_cert_resolver(Arc::new(self.clone()));
         config.alpn_protocols = service
             .available_protocols()
             .iter()
@@ -121,10 +132,17 @@ impl<B: Backend> CustomServer<B> {
                             .cloned()
                     })
                     .unwrap_or_else(|| available_protocols[0].clone());
+                let client_certificate = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certificates| certificates.first())
+                    .map(|certificate| Certificate::unchecked_from_der(certificate.0.clone()));
                 let peer = Peer {
                     address: peer_addr,
                     secure: true,
                     protocol,
+                    client_certificate,
                 };
                 if let Err(err) = task_self
                     .handle_tcp_connection(stream, peer, &task_service)
@@ -136,7 +154,10 @@ impl<B: Backend> CustomServer<B> {
         }
     }
 
-    #[cfg_attr(not(feature = "websockets"), allow(unused_variables))]
+    #[cfg_attr(
+        not(any(feature = "websockets", feature = "rest-api")),
+        allow(unused_variables)
+    )]
     async fn handle_tcp_connection<
         S: TcpService,
         C: AsyncRead + AsyncWrite + Unpin + Send + 'static,
@@ -154,9 +175,17 @@ impl<B: Backend> CustomServer<B> {
         }
 
         if let Err(connection) = service.handle_connection(connection, &peer).await {
-            #[cfg(feature = "websockets")]
+            #[cfg(feature = "rest-api")]
+            if let Err(err) = self
+                .handle_http_connection(connection, peer.address, peer.client_certificate)
+                .await
+            {
+                log::error!("[server] error on http for {}: {:?}", peer.address, err);
+            }
+
+            #[cfg(all(feature = "websockets", not(feature = "rest-api")))]
             if let Err(err) = self
-                .handle_raw_websocket_connection(connection, peer.address)
+                .handle_raw_websocket_connection(connection, peer.address, peer.client_certificate)
                 .await
             {
                 log::error!(
@@ -207,6 +236,33 @@ impl<B: Backend> ResolvesServerCert for CustomServer<B> {
     }
 }
 
+/// Requests a certificate from TLS clients, accepting any certificate
+/// presented. Certificates are authenticated by matching them against the
+/// [`ClientCertificate`](bonsaidb_core::admin::ClientCertificate) mappings
+/// once the connection is established.
+struct AnyClientCertificate {
+    required: bool,
+}
+
+impl ClientCertVerifier for AnyClientCertificate {
+    fn client_auth_mandatory(&self) -> bool {
+        self.required
+    }
+
+    fn client_auth_root_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _now: SystemTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+}
+
 /// A service that can handle incoming TCP connections.
 #[async_trait]
 pub trait TcpService: Clone + Send + Sync + 'static {
@@ -295,6 +351,9 @@ pub struct Peer<P: ApplicationProtocols = StandardTcpProtocols> {
     pub secure: bool,
     /// The application protocol to use for this connection.
     pub protocol: P,
+    /// The certificate presented by the peer during the TLS handshake, if
+    /// any.
+    pub client_certificate: Option<Certificate>,
 }
 
 /// TCP [`ApplicationProtocols`] that BonsaiDb has some knowledge of.
@@ -1,4 +1,5 @@
 use bonsaidb_core::networking::{Payload, CURRENT_PROTOCOL_VERSION};
+use fabruic::Certificate;
 use futures::{SinkExt, StreamExt};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_tungstenite::tungstenite::Message;
@@ -27,14 +28,19 @@ impl<B: Backend> CustomServer<B> {
         &self,
         connection: S,
         peer_address: std::net::SocketAddr,
+        client_certificate: Option<Certificate>,
     ) -> Result<(), Error> {
         let stream = tokio_tungstenite::accept_hdr_async(connection, VersionChecker).await?;
-        self.handle_websocket(stream, peer_address).await;
+        self.serve_websocket(stream, peer_address, client_certificate)
+            .await;
         Ok(())
     }
 
     /// Handles upgrading an HTTP connection to the `WebSocket` protocol based
     /// on the upgrade `request`. Requires feature `hyper` to be enabled.
+    ///
+    /// If `request`'s extensions contain the [`Certificate`] presented by the
+    /// peer, it is used to authenticate the connection.
     #[cfg(feature = "hyper")]
     pub fn upgrade_websocket(
         &self,
@@ -61,12 +67,15 @@ impl<B: Backend> CustomServer<B> {
             return response;
         };
 
+        let client_certificate = request.extensions_mut().remove::<Certificate>();
         let task_self = self.clone();
         tokio::spawn(async move {
             match hyper::upgrade::on(&mut request).await {
                 Ok(upgraded) => {
                     let ws = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
-                    task_self.handle_websocket(ws, peer_address).await;
+                    task_self
+                        .serve_websocket(ws, peer_address, client_certificate)
+                        .await;
                 }
                 Err(err) => {
                     log::error!("Error upgrading websocket: {:?}", err);
@@ -100,6 +109,21 @@ impl<B: Backend> CustomServer<B> {
         &self,
         connection: S,
         peer_address: std::net::SocketAddr,
+    ) {
+        self.serve_websocket(connection, peer_address, None).await;
+    }
+
+    async fn serve_websocket<
+        S: futures::Stream<Item = Result<tokio_tungstenite::tungstenite::Message, E>>
+            + futures::Sink<tokio_tungstenite::tungstenite::Message>
+            + Send
+            + 'static,
+        E: std::fmt::Debug + Send,
+    >(
+        &self,
+        connection: S,
+        peer_address: std::net::SocketAddr,
+        client_certificate: Option<Certificate>,
     ) {
         let mut shutdown = self
             .data
@@ -114,7 +138,12 @@ impl<B: Backend> CustomServer<B> {
 
         let (api_response_sender, api_response_receiver) = flume::unbounded();
         let Some(client) = self
-            .initialize_client(Transport::WebSocket, peer_address, api_response_sender)
+            .initialize_client(
+                Transport::WebSocket,
+                peer_address,
+                client_certificate.as_ref().map(AsRef::as_ref),
+                api_response_sender,
+            )
             .await
         else {
             return;
@@ -163,6 +192,7 @@ impl<B: Backend> CustomServer<B> {
         let (request_sender, request_receiver) =
             flume::bounded::<Payload>(self.data.client_simultaneous_request_limit);
 
+        let connected_client = client.clone();
         self.spawn_client_request_handler(client, request_receiver, response_sender, &shutdown);
 
         loop {
@@ -195,6 +225,9 @@ impl<B: Backend> CustomServer<B> {
                         return;
                     }
                 }
+                () = connected_client.disconnect_requested() => {
+                    return;
+                }
             }
         }
     }
@@ -14,6 +14,243 @@ async fn simple_test() -> anyhow::Result<()> {
     test_util::store_retrieve_update_delete_tests(&db).await
 }
 
+#[tokio::test]
+async fn metrics_tests() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::AsyncConnection;
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, BasicByParentId};
+
+    let test_dir = TestDirectory::new("metrics-test");
+    let server = initialize_basic_server(test_dir.as_ref()).await?;
+    let db = server.database::<BasicSchema>("tests").await?;
+    Basic::new("metrics").push_into_async(&db).await?;
+    db.view::<BasicByParentId>().query().await?;
+    db.compact().await?;
+
+    let metrics = server.metrics();
+    assert_eq!(metrics.connected_clients, 0);
+    assert!(metrics.storage.view_updates.count > 0);
+    assert!(metrics.storage.compactions.count > 0);
+
+    let text = metrics.to_prometheus_text();
+    assert!(
+        text.contains("# TYPE bonsaidb_connected_clients gauge\nbonsaidb_connected_clients 0\n")
+    );
+    assert!(text.contains("bonsaidb_compaction_duration_seconds_count "));
+    Ok(())
+}
+
+#[tokio::test]
+async fn rate_limit_tests() -> anyhow::Result<()> {
+    use std::time::Duration;
+
+    use bonsaidb_local::config::Builder;
+
+    use crate::{RateLimit, RateLimits, ServerConfiguration};
+
+    let test_dir = TestDirectory::new("rate-limit-test");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .rate_limits(
+                RateLimits::default().per_database(
+                    RateLimit::default()
+                        .requests_per_second(2)
+                        .bytes_per_second(100),
+                ),
+            )
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    let tests = server
+        .create_database::<BasicSchema>("tests", false)
+        .await?;
+    let other = server
+        .create_database::<BasicSchema>("other", false)
+        .await?;
+
+    // The bucket starts full, allowing a burst of one second's worth of
+    // requests.
+    server.check_database_rate_limit(&tests, 10)?;
+    server.check_database_rate_limit(&tests, 10)?;
+    match server.check_database_rate_limit(&tests, 10) {
+        Err(bonsaidb_core::Error::RateLimited { retry_after }) => {
+            assert!(retry_after > Duration::ZERO);
+            assert!(retry_after <= Duration::from_millis(500));
+        }
+        other => unreachable!("expected rate limited error, got {other:?}"),
+    }
+
+    // Each database has its own limit, and the byte limit is enforced
+    // independently of the request limit.
+    server.check_database_rate_limit(&other, 100)?;
+    assert!(matches!(
+        server.check_database_rate_limit(&other, 1),
+        Err(bonsaidb_core::Error::RateLimited { .. })
+    ));
+
+    // After waiting, the bucket refills.
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    server.check_database_rate_limit(&tests, 10)?;
+
+    // Once buckets have refilled completely, they are no longer tracked.
+    assert_eq!(server.rate_limit_bucket_count(), 2);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    server.check_database_rate_limit(&tests, 10)?;
+    assert_eq!(server.rate_limit_bucket_count(), 1);
+
+    // A limit of zero rejects every request.
+    let test_dir = TestDirectory::new("rate-limit-zero-test");
+    let closed = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .rate_limits(
+                RateLimits::default().per_database(RateLimit::default().requests_per_second(0)),
+            )
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    let tests = closed
+        .create_database::<BasicSchema>("tests", false)
+        .await?;
+    assert!(matches!(
+        closed.check_database_rate_limit(&tests, 0),
+        Err(bonsaidb_core::Error::RateLimited { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "password-hashing")]
+async fn authentication_throttle_tests() -> anyhow::Result<()> {
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use bonsaidb_core::connection::{Authentication, SensitiveString};
+
+    use crate::{AuthenticationThrottle, ServerConfiguration};
+
+    let test_dir = TestDirectory::new("authentication-throttle-test");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .authentication_throttle(
+                AuthenticationThrottle::default()
+                    .max_failed_attempts(2)
+                    .window(Duration::from_millis(500)),
+            )
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server.create_user("throttled").await?;
+    server
+        .set_user_password("throttled", SensitiveString(String::from("hunter2")))
+        .await?;
+    let password = |password: &str| {
+        Authentication::password("throttled", SensitiveString(password.to_string())).unwrap()
+    };
+
+    let attacker = server.with_client_address(SocketAddr::from(([10, 0, 0, 1], 1234)));
+    for _ in 0..2 {
+        assert!(matches!(
+            attacker.authenticate(password("wrong")).await,
+            Err(bonsaidb_core::Error::InvalidCredentials)
+        ));
+    }
+    // Once throttled, even the correct password is rejected.
+    match attacker.authenticate(password("hunter2")).await {
+        Err(bonsaidb_core::Error::AuthenticationThrottled { retry_after }) => {
+            assert!(retry_after <= Duration::from_millis(500));
+        }
+        other => unreachable!("expected throttled error, got {:?}", other.map(|_| ())),
+    }
+
+    // Other addresses aren't affected, and successful attempts aren't counted.
+    let user = server.with_client_address(SocketAddr::from(([10, 0, 0, 2], 1234)));
+    for _ in 0..3 {
+        user.authenticate(password("hunter2")).await?;
+    }
+
+    // Attempts made in parallel can't exceed the limit.
+    let parallel = server.with_client_address(SocketAddr::from(([10, 0, 0, 3], 1234)));
+    let results =
+        futures::future::join_all((0..8).map(|_| parallel.authenticate(password("wrong")))).await;
+    let failed = results
+        .iter()
+        .filter(|result| matches!(result, Err(bonsaidb_core::Error::InvalidCredentials)))
+        .count();
+    assert_eq!(failed, 2);
+
+    // Once the window ends, attempts are allowed again, and addresses whose
+    // window has ended are no longer tracked.
+    assert_eq!(server.tracked_authentication_addresses(), 3);
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    attacker.authenticate(password("hunter2")).await?;
+    assert_eq!(server.tracked_authentication_addresses(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_certificate_tests() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{CertificateMatcher, ClientCertificate};
+    use bonsaidb_core::connection::{Identity, IdentityId};
+    use bonsaidb_core::schema::SerializedCollection;
+    use fabruic::KeyPair;
+
+    use crate::{ClientCertificates, ServerConfiguration};
+
+    let test_dir = TestDirectory::new("client-certificate-test");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .client_certificates(ClientCertificates::Required)
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+
+    let user_id = server.create_user("certified").await?;
+    let admin = server.admin().await;
+    let mapped = KeyPair::new_self_signed("mapped");
+    let mapped = mapped.end_entity_certificate().as_ref();
+    ClientCertificate::new(
+        CertificateMatcher::fingerprint_of(mapped),
+        IdentityId::User(user_id),
+    )
+    .push_into_async(&admin)
+    .await?;
+
+    let session = server
+        .client_certificate_session(Some(mapped))
+        .await?
+        .expect("no session for mapped certificate");
+    assert!(matches!(
+        session.identity(),
+        Some(Identity::User { id, .. }) if *id == user_id
+    ));
+
+    // Certificates are required, so connections without a certificate or with
+    // an unmapped certificate are rejected.
+    assert!(server.client_certificate_session(None).await.is_err());
+    let unmapped = KeyPair::new_self_signed("unmapped");
+    let unmapped = unmapped.end_entity_certificate().as_ref();
+    assert!(server
+        .client_certificate_session(Some(unmapped))
+        .await
+        .is_err());
+
+    // Subjects are only matched for certificates issued by a trusted
+    // authority, which self-signed certificates aren't.
+    let (_, parsed) = x509_parser::parse_x509_certificate(unmapped).unwrap();
+    ClientCertificate::new(
+        CertificateMatcher::subject(parsed.subject().to_string()),
+        IdentityId::User(user_id),
+    )
+    .push_into_async(&admin)
+    .await?;
+    assert!(server
+        .client_certificate_session(Some(unmapped))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn install_self_signed_certificate_tests() -> anyhow::Result<()> {
     let test_dir = TestDirectory::new("cert-install-test");
@@ -35,6 +272,622 @@ async fn install_self_signed_certificate_tests() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn rest_api_tests() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{Collection, CollectionName, Qualified, Schematic};
+    use bonsaidb_local::config::Builder;
+    use hyper::{Body, Request, StatusCode};
+
+    use crate::{DefaultPermissions, ServerConfiguration};
+
+    async fn request(
+        server: &Server,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<(StatusCode, Option<serde_json::Value>)> {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))?;
+        let response = server
+            .handle_http_request(([127, 0, 0, 1], 0).into(), request)
+            .await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let body = if body.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_slice(&body)?)
+        };
+        Ok((status, body))
+    }
+
+    let test_dir = TestDirectory::new("rest-api");
+    let server = initialize_basic_server(test_dir.as_ref()).await?;
+    let documents = "/v1/databases/tests/collections/khonsulabs_._basic/documents";
+
+    let (status, header) = request(
+        &server,
+        "POST",
+        &format!("{documents}?id=42"),
+        Some(serde_json::json!({ "value": "hello", "category": null, "parent_id": null, "tags": [] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let header = header.unwrap();
+    assert_eq!(header["id"], 42);
+
+    let (status, document) = request(&server, "GET", &format!("{documents}/42"), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let document = document.unwrap();
+    assert_eq!(document["contents"]["value"], "hello");
+    assert_eq!(document["revision"], header["revision"]);
+
+    let (status, _) = request(&server, "GET", &format!("{documents}/43"), None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = request(
+        &server,
+        "PUT",
+        "/v1/databases/tests/kv/counter",
+        Some(serde_json::json!(1)),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, value) = request(&server, "GET", "/v1/databases/tests/kv/counter", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(value, Some(serde_json::json!(1)));
+
+    let (status, _) = request(&server, "GET", "/v1/databases/missing/kv/counter", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Documents of collections with a custom serialization format can't be
+    // written, as JSON can't be encoded in their format.
+    #[derive(Debug)]
+    struct Opaque;
+
+    impl Collection for Opaque {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "opaque")
+        }
+
+        fn define_views(_schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            Ok(())
+        }
+    }
+
+    let test_dir = TestDirectory::new("rest-api-opaque");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .with_schema::<Opaque>()?,
+    )
+    .await?;
+    server.create_database::<Opaque>("opaque", false).await?;
+    let (status, _) = request(
+        &server,
+        "POST",
+        "/v1/databases/opaque/collections/khonsulabs_._opaque/documents",
+        Some(serde_json::json!({ "value": "hello" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    Ok(())
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn rest_api_event_stream_tests() -> anyhow::Result<()> {
+    use hyper::body::HttpBody;
+    use hyper::{Body, Request, StatusCode};
+
+    let test_dir = TestDirectory::new("rest-api-events");
+    let server = initialize_basic_server(test_dir.as_ref()).await?;
+    let peer = std::net::SocketAddr::from(([127, 0, 0, 1], 0));
+
+    let response = server
+        .handle_http_request(
+            peer,
+            Request::get("/v1/databases/tests/events?topic=greetings").body(Body::empty())?,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut events = response.into_body();
+
+    let response = server
+        .handle_http_request(
+            peer,
+            Request::post("/v1/databases/tests/pubsub/greetings").body(Body::from(
+                serde_json::json!({ "hello": "world" }).to_string(),
+            ))?,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let event = events.data().await.expect("stream ended")?;
+    let event = std::str::from_utf8(&event)?;
+    assert!(event.starts_with("id: "));
+    let data = event
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .expect("no data in event");
+    let data = serde_json::from_str::<serde_json::Value>(data)?;
+    assert_eq!(data["topic"], "greetings");
+    assert_eq!(data["payload"]["hello"], "world");
+
+    let response = server
+        .handle_http_request(
+            peer,
+            Request::get("/v1/databases/tests/events").body(Body::empty())?,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn rest_api_event_stream_resume_tests() -> anyhow::Result<()> {
+    use hyper::body::HttpBody;
+    use hyper::{Body, Request, StatusCode};
+
+    /// Publishes `payload` to the `greetings` topic.
+    async fn publish(server: &Server, payload: &str) -> anyhow::Result<()> {
+        let response = server
+            .handle_http_request(
+                ([127, 0, 0, 1], 0).into(),
+                Request::post("/v1/databases/tests/pubsub/greetings")
+                    .body(Body::from(serde_json::json!(payload).to_string()))?,
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        Ok(())
+    }
+
+    /// Returns the id and payload of the next event received on `events`.
+    async fn next_event(events: &mut Body) -> anyhow::Result<(String, serde_json::Value)> {
+        let event = events.data().await.expect("stream ended")?;
+        let event = std::str::from_utf8(&event)?;
+        let id = event
+            .lines()
+            .find_map(|line| line.strip_prefix("id: "))
+            .expect("no id in event");
+        let data = event
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .expect("no data in event");
+        let data = serde_json::from_str::<serde_json::Value>(data)?;
+        Ok((id.to_string(), data["payload"].clone()))
+    }
+
+    let test_dir = TestDirectory::new("rest-api-events-resume");
+    let server = initialize_basic_server(test_dir.as_ref()).await?;
+    let peer = std::net::SocketAddr::from(([127, 0, 0, 1], 0));
+
+    let response = server
+        .handle_http_request(
+            peer,
+            Request::get("/v1/databases/tests/events?topic=greetings").body(Body::empty())?,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut events = response.into_body();
+
+    publish(&server, "first").await?;
+    let (first_id, payload) = next_event(&mut events).await?;
+    assert_eq!(payload, "first");
+    publish(&server, "second").await?;
+    let (second_id, payload) = next_event(&mut events).await?;
+    assert_eq!(payload, "second");
+    drop(events);
+
+    // Reconnecting with the id of the first event replays the events that
+    // followed it before delivering new events.
+    let response = server
+        .handle_http_request(
+            peer,
+            Request::get("/v1/databases/tests/events?topic=greetings")
+                .header("last-event-id", &first_id)
+                .body(Body::empty())?,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut events = response.into_body();
+    let (id, payload) = next_event(&mut events).await?;
+    assert_eq!(id, second_id);
+    assert_eq!(payload, "second");
+    publish(&server, "third").await?;
+    let (_, payload) = next_event(&mut events).await?;
+    assert_eq!(payload, "third");
+
+    Ok(())
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn rest_api_event_stream_permission_tests() -> anyhow::Result<()> {
+    use bonsaidb_core::permissions::bonsai::{
+        BonsaiAction, DatabaseAction, PubSubAction, ServerAction,
+    };
+    use bonsaidb_local::config::Builder;
+    use hyper::{Body, Request, StatusCode};
+
+    use crate::{DefaultPermissions, ServerConfiguration};
+
+    // The default session can create subscribers, but can't subscribe to any
+    // topics.
+    let test_dir = TestDirectory::new("rest-api-events-permissions");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .default_permissions(DefaultPermissions::Permissions(Permissions::from(vec![
+                Statement::for_any()
+                    .allowing(&BonsaiAction::Server(ServerAction::Connect))
+                    .allowing(&BonsaiAction::Database(DatabaseAction::PubSub(
+                        PubSubAction::CreateSuscriber,
+                    ))),
+            ])))
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server
+        .create_database::<BasicSchema>("tests", false)
+        .await?;
+
+    let response = server
+        .handle_http_request(
+            ([127, 0, 0, 1], 0).into(),
+            Request::get("/v1/databases/tests/events?topic=greetings").body(Body::empty())?,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[cfg(all(feature = "rest-api", feature = "websockets"))]
+#[tokio::test]
+async fn websocket_protocol_negotiation_tests() -> anyhow::Result<()> {
+    use bonsaidb_core::networking::CURRENT_PROTOCOL_VERSION;
+    use hyper::header::SEC_WEBSOCKET_PROTOCOL;
+    use hyper::{Body, Request, Response, StatusCode};
+
+    async fn upgrade(server: &Server, protocol: Option<&str>) -> anyhow::Result<Response<Body>> {
+        let mut request = Request::get("/")
+            .header("connection", "upgrade")
+            .header("upgrade", "websocket")
+            .header("sec-websocket-version", "13")
+            .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(protocol) = protocol {
+            request = request.header(SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+        Ok(server
+            .handle_http_request(([127, 0, 0, 1], 0).into(), request.body(Body::empty())?)
+            .await)
+    }
+
+    let test_dir = TestDirectory::new("websocket-protocols");
+    let server = initialize_basic_server(test_dir.as_ref()).await?;
+
+    // Clients that don't request a protocol are accepted.
+    let response = upgrade(&server, None).await?;
+    assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert!(!response.headers().contains_key(SEC_WEBSOCKET_PROTOCOL));
+
+    // Clients requesting the current version have it echoed back.
+    let response = upgrade(&server, Some(CURRENT_PROTOCOL_VERSION)).await?;
+    assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(
+        response.headers()[SEC_WEBSOCKET_PROTOCOL],
+        CURRENT_PROTOCOL_VERSION
+    );
+
+    // Clients requesting only other versions are rejected.
+    let response = upgrade(&server, Some("bonsai-pre-0")).await?;
+    assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+
+    Ok(())
+}
+
+#[cfg(feature = "s3-api")]
+#[tokio::test]
+async fn s3_api_tests() -> anyhow::Result<()> {
+    use bonsaidb_files::FilesSchema;
+    use bonsaidb_local::config::Builder;
+    use hyper::{Body, Request, StatusCode};
+
+    use crate::{DefaultPermissions, ServerConfiguration};
+
+    async fn request(
+        server: &Server,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> anyhow::Result<(StatusCode, hyper::HeaderMap, Vec<u8>)> {
+        let mut request = Request::builder().method(method).uri(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = server
+            .handle_http_request(
+                ([127, 0, 0, 1], 0).into(),
+                request.body(Body::from(body.to_vec()))?,
+            )
+            .await;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok((status, headers, body.to_vec()))
+    }
+
+    let test_dir = TestDirectory::new("s3-api");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .with_schema::<FilesSchema>()?,
+    )
+    .await?;
+    server
+        .create_database::<FilesSchema>("files", false)
+        .await?;
+
+    let (status, headers, _) =
+        request(&server, "PUT", "/files/docs/a.txt", &[], b"hello world").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers.contains_key("etag"));
+    request(&server, "PUT", "/files/docs/nested/b.txt", &[], b"b").await?;
+    request(&server, "PUT", "/files/c.txt", &[], b"c").await?;
+
+    let (status, _, body) = request(&server, "GET", "/files/docs/a.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, b"hello world");
+
+    let (status, headers, body) = request(
+        &server,
+        "GET",
+        "/files/docs/a.txt",
+        &[("range", "bytes=6-")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(headers["content-range"], "bytes 6-10/11");
+    assert_eq!(body, b"world");
+
+    let (status, headers, _) = request(&server, "HEAD", "/files/docs/a.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-length"], "11");
+
+    let (status, _, body) = request(
+        &server,
+        "GET",
+        "/files?list-type=2&prefix=docs/&delimiter=/",
+        &[],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let body = String::from_utf8(body)?;
+    assert!(body.contains("<Key>docs/a.txt</Key>"));
+    assert!(body.contains("<CommonPrefixes><Prefix>docs/nested/</Prefix></CommonPrefixes>"));
+    assert!(!body.contains("c.txt"));
+
+    // Multipart uploads are assembled from their parts in order.
+    let (status, _, body) = request(&server, "POST", "/files/big.bin?uploads", &[], b"").await?;
+    assert_eq!(status, StatusCode::OK);
+    let body = String::from_utf8(body)?;
+    let upload_id = body
+        .split_once("<UploadId>")
+        .and_then(|(_, rest)| rest.split_once("</UploadId>"))
+        .map(|(id, _)| id.to_string())
+        .expect("no upload id");
+    for (part, contents) in [(2, "second"), (1, "first-")] {
+        let (status, _, _) = request(
+            &server,
+            "PUT",
+            &format!("/files/big.bin?partNumber={part}&uploadId={upload_id}"),
+            &[],
+            contents.as_bytes(),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _, _) = request(
+        &server,
+        "POST",
+        &format!("/files/big.bin?uploadId={upload_id}"),
+        &[],
+        b"<CompleteMultipartUpload><Part><PartNumber>1</PartNumber></Part>\
+          <Part><PartNumber>2</PartNumber></Part></CompleteMultipartUpload>",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, _, body) = request(&server, "GET", "/files/big.bin", &[], b"").await?;
+    assert_eq!(body, b"first-second");
+    let (_, _, body) = request(&server, "GET", "/files?prefix=.s3", &[], b"").await?;
+    assert!(!String::from_utf8(body)?.contains("<Key>"));
+
+    let (status, _, _) = request(&server, "DELETE", "/files/docs/a.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _, body) = request(&server, "GET", "/files/docs/a.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(String::from_utf8(body)?.contains("<Code>NoSuchKey</Code>"));
+
+    let (status, _, _) = request(&server, "GET", "/missing/a.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[cfg(feature = "webdav")]
+#[tokio::test]
+async fn webdav_tests() -> anyhow::Result<()> {
+    use bonsaidb_files::FilesSchema;
+    use bonsaidb_local::config::Builder;
+    use hyper::{Body, Request, StatusCode};
+
+    use crate::{DefaultPermissions, ServerConfiguration};
+
+    async fn request(
+        server: &Server,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> anyhow::Result<(StatusCode, String)> {
+        let mut request = Request::builder().method(method).uri(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = server
+            .handle_http_request(
+                ([127, 0, 0, 1], 0).into(),
+                request.body(Body::from(body.to_vec()))?,
+            )
+            .await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok((status, String::from_utf8(body.to_vec())?))
+    }
+
+    let test_dir = TestDirectory::new("webdav");
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .with_schema::<FilesSchema>()?,
+    )
+    .await?;
+    server
+        .create_database::<FilesSchema>("files", false)
+        .await?;
+
+    let (status, body) = request(&server, "PROPFIND", "/dav/", &[("depth", "1")], b"").await?;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(body.contains("<D:href>/dav/files/</D:href>"));
+
+    // Files can only be created within existing collections.
+    let (status, _) = request(&server, "PUT", "/dav/files/docs/a.txt", &[], b"hello").await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = request(&server, "MKCOL", "/dav/files/docs", &[], b"").await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = request(&server, "MKCOL", "/dav/files/docs", &[], b"").await?;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    let (status, _) = request(&server, "PUT", "/dav/files/docs/a.txt", &[], b"hello").await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = request(&server, "PUT", "/dav/files/docs/a.txt", &[], b"hello world").await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = request(
+        &server,
+        "GET",
+        "/dav/files/docs/a.txt",
+        &[("range", "bytes=6-")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body, "world");
+
+    let (status, body) = request(
+        &server,
+        "PROPFIND",
+        "/dav/files/docs/",
+        &[("depth", "1")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(body.contains("<D:href>/dav/files/docs/a.txt</D:href>"));
+    assert!(body.contains("<D:getcontentlength>11</D:getcontentlength>"));
+    assert!(!body.contains(".dav-collection"));
+    let (status, _) = request(&server, "PROPFIND", "/dav/files/", &[], b"").await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Collections are copied and moved along with their contents.
+    let (status, _) = request(
+        &server,
+        "COPY",
+        "/dav/files/docs",
+        &[("destination", "http://localhost/dav/files/copy")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = request(
+        &server,
+        "MOVE",
+        "/dav/files/docs",
+        &[("destination", "/dav/files/copy"), ("overwrite", "F")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    let (status, _) = request(
+        &server,
+        "MOVE",
+        "/dav/files/docs",
+        &[("destination", "/dav/files/moved")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = request(&server, "GET", "/dav/files/docs/a.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    for path in ["/dav/files/copy/a.txt", "/dav/files/moved/a.txt"] {
+        let (status, body) = request(&server, "GET", path, &[], b"").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "hello world");
+    }
+
+    // Overwriting replaces the destination once the transfer completes, and
+    // the temporary resources used for transfers are never listed.
+    let (status, _) = request(&server, "PUT", "/dav/files/copy/b.txt", &[], b"replaced").await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = request(
+        &server,
+        "COPY",
+        "/dav/files/copy/a.txt",
+        &[("destination", "/dav/files/copy/b.txt")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, body) = request(&server, "GET", "/dav/files/copy/b.txt", &[], b"").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello world");
+    let (status, body) = request(
+        &server,
+        "PROPFIND",
+        "/dav/files/copy/",
+        &[("depth", "1")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(body.contains("<D:href>/dav/files/copy/b.txt</D:href>"));
+    assert!(!body.contains(".dav-transfer-"));
+
+    let (status, _) = request(&server, "DELETE", "/dav/files/moved", &[], b"").await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = request(
+        &server,
+        "PROPFIND",
+        "/dav/files/moved",
+        &[("depth", "0")],
+        b"",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
 struct TestHarness {
     _directory: TestDirectory,
     server: Server,
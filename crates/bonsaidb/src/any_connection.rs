@@ -1,10 +1,14 @@
-use bonsaidb_client::{AsyncClient, AsyncRemoteDatabase};
+use bonsaidb_client::{AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber};
+use bonsaidb_core::admin::{AuthenticationTokenSummary, PermissionGroup, Role};
 use bonsaidb_core::async_trait::async_trait;
 use bonsaidb_core::connection::{
     self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection,
     HasSchema, HasSession, IdentityReference, Range, SerializedQueryKey, Session, Sort,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
+use bonsaidb_core::keyvalue::{AsyncKeyValue, KeyOperation, Output};
+use bonsaidb_core::permissions::{ActionName, ClientContext, PermissionExplanation, ResourceName};
+use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber, Receiver};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
 use bonsaidb_core::schema::{
     self, Collection, CollectionName, Nameable, Schema, SchemaName, SchemaSummary, Schematic,
@@ -23,6 +27,28 @@ pub enum AnyServerConnection<B: Backend> {
     Networked(AsyncClient),
 }
 
+impl<B: Backend> AnyServerConnection<B> {
+    /// Returns the database named `name` using the schema it was created
+    /// with, without requiring the schema's Rust type. The returned database
+    /// can be accessed using [`AsyncLowLevelConnection`] and the
+    /// [`SchemaSummary`] of its schema.
+    pub async fn database_without_schema(
+        &self,
+        name: &str,
+    ) -> Result<AnyDatabase<B>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server
+                .database_without_schema(name)
+                .await
+                .map(AnyDatabase::Local),
+            Self::Networked(client) => client
+                .database::<()>(name)
+                .await
+                .map(AnyDatabase::Networked),
+        }
+    }
+}
+
 impl<B: Backend> HasSession for AnyServerConnection<B> {
     fn session(&self) -> Option<&Session> {
         match self {
@@ -230,6 +256,123 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
             Self::Networked(client) => client.remove_role_from_user(user, role).await,
         }
     }
+
+    async fn create_permission_group(
+        &self,
+        group: PermissionGroup,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.create_permission_group(group).await,
+            Self::Networked(client) => client.create_permission_group(group).await,
+        }
+    }
+
+    async fn update_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+        updated: PermissionGroup,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => {
+                server
+                    .update_permission_group(permission_group, updated)
+                    .await
+            }
+            Self::Networked(client) => {
+                client
+                    .update_permission_group(permission_group, updated)
+                    .await
+            }
+        }
+    }
+
+    async fn delete_permission_group<'group, G: Nameable<'group, u64> + Send + Sync>(
+        &self,
+        permission_group: G,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.delete_permission_group(permission_group).await,
+            Self::Networked(client) => client.delete_permission_group(permission_group).await,
+        }
+    }
+
+    async fn create_role(&self, role: Role) -> Result<u64, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.create_role(role).await,
+            Self::Networked(client) => client.create_role(role).await,
+        }
+    }
+
+    async fn update_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+        updated: Role,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.update_role(role, updated).await,
+            Self::Networked(client) => client.update_role(role, updated).await,
+        }
+    }
+
+    async fn delete_role<'role, R: Nameable<'role, u64> + Send + Sync>(
+        &self,
+        role: R,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.delete_role(role).await,
+            Self::Networked(client) => client.delete_role(role).await,
+        }
+    }
+
+    async fn list_authentication_tokens<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<AuthenticationTokenSummary>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.list_authentication_tokens(user).await,
+            Self::Networked(client) => client.list_authentication_tokens(user).await,
+        }
+    }
+
+    #[cfg(feature = "token-authentication")]
+    async fn create_authentication_token(
+        &self,
+        identity: IdentityReference<'_>,
+        options: bonsaidb_core::admin::AuthenticationTokenOptions,
+    ) -> Result<bonsaidb_core::admin::CreatedAuthenticationToken, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.create_authentication_token(identity, options).await,
+            Self::Networked(client) => client.create_authentication_token(identity, options).await,
+        }
+    }
+
+    async fn revoke_authentication_token(&self, token_id: u64) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.revoke_authentication_token(token_id).await,
+            Self::Networked(client) => client.revoke_authentication_token(token_id).await,
+        }
+    }
+
+    async fn explain_permission(
+        &self,
+        identity: IdentityReference<'_>,
+        resource_name: ResourceName<'static>,
+        action: ActionName,
+        client: ClientContext,
+    ) -> Result<PermissionExplanation, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => {
+                server
+                    .explain_permission(identity, resource_name, action, client)
+                    .await
+            }
+            Self::Networked(connection) => {
+                connection
+                    .explain_permission(identity, resource_name, action, client)
+                    .await
+            }
+        }
+    }
 }
 
 /// A database connection that can be either from a local server or a server
@@ -504,3 +647,86 @@ impl<B: Backend> HasSchema for AnyDatabase<B> {
         }
     }
 }
+
+#[async_trait]
+impl<B: Backend> AsyncKeyValue for AnyDatabase<B> {
+    async fn execute_key_operation(
+        &self,
+        op: KeyOperation,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.execute_key_operation(op).await,
+            Self::Networked(client) => client.execute_key_operation(op).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend> AsyncPubSub for AnyDatabase<B> {
+    type Subscriber = AnySubscriber;
+
+    async fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.create_subscriber().await.map(AnySubscriber::Local),
+            Self::Networked(client) => client
+                .create_subscriber()
+                .await
+                .map(AnySubscriber::Networked),
+        }
+    }
+
+    async fn publish_bytes(
+        &self,
+        topic: Vec<u8>,
+        payload: Vec<u8>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.publish_bytes(topic, payload).await,
+            Self::Networked(client) => client.publish_bytes(topic, payload).await,
+        }
+    }
+
+    async fn publish_bytes_to_all(
+        &self,
+        topics: impl IntoIterator<Item = Vec<u8>> + Send + 'async_trait,
+        payload: Vec<u8>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.publish_bytes_to_all(topics, payload).await,
+            Self::Networked(client) => client.publish_bytes_to_all(topics, payload).await,
+        }
+    }
+}
+
+/// A PubSub subscriber for an [`AnyDatabase`].
+#[derive(Debug)]
+pub enum AnySubscriber {
+    /// A subscriber to a local database.
+    Local(bonsaidb_local::Subscriber),
+    /// A subscriber to a networked database.
+    Networked(AsyncRemoteSubscriber),
+}
+
+#[async_trait]
+impl AsyncSubscriber for AnySubscriber {
+    async fn subscribe_to_bytes(&self, topic: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(subscriber) => subscriber.subscribe_to_bytes(topic).await,
+            Self::Networked(subscriber) => subscriber.subscribe_to_bytes(topic).await,
+        }
+    }
+
+    async fn unsubscribe_from_bytes(&self, topic: &[u8]) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(subscriber) => subscriber.unsubscribe_from_bytes(topic).await,
+            Self::Networked(subscriber) => subscriber.unsubscribe_from_bytes(topic).await,
+        }
+    }
+
+    fn receiver(&self) -> &Receiver {
+        match self {
+            Self::Local(subscriber) => AsyncSubscriber::receiver(subscriber),
+            Self::Networked(subscriber) => subscriber.receiver(),
+        }
+    }
+}
@@ -7,7 +7,11 @@ use std::path::PathBuf;
 use bonsaidb_client::fabruic::Certificate;
 use bonsaidb_client::AsyncClient;
 use bonsaidb_core::async_trait::async_trait;
-#[cfg(any(feature = "password-hashing", feature = "token-authentication"))]
+#[cfg(any(
+    feature = "password-hashing",
+    feature = "token-authentication",
+    feature = "files"
+))]
 use bonsaidb_core::connection::AsyncStorageConnection;
 use bonsaidb_server::{Backend, CustomServer, NoBackend, ServerConfiguration};
 use clap::{Parser, Subcommand};
@@ -15,12 +19,27 @@ use url::Url;
 
 use crate::AnyServerConnection;
 
+pub mod shell;
+
 /// All available command line commands.
 #[derive(Subcommand, Debug)]
 pub enum Command<Cli: CommandLine> {
     /// Execute a BonsaiDb server command.
     #[clap(flatten)]
     Server(bonsaidb_server::cli::Command<Cli::Backend>),
+    /// Starts an interactive shell for browsing and modifying data.
+    Shell(shell::Shell),
+    /// Execute a command on the files stored in a database using
+    /// [`BonsaiFiles`](bonsaidb_files::BonsaiFiles).
+    #[cfg(feature = "files")]
+    Files {
+        /// The name of the database containing the files.
+        #[clap(long)]
+        database: String,
+        /// The command to execute.
+        #[clap(subcommand)]
+        command: bonsaidb_files::cli::Command,
+    },
     /// An external command.
     #[clap(flatten)]
     External(Cli::Subcommand),
@@ -48,40 +67,49 @@ where
 
                 server.execute_on(cli.open_server().await?).await?;
             }
+            Command::Shell(shell) => {
+                let connection = connect(
+                    server_url,
+                    pinned_certificate,
+                    #[cfg(feature = "password-hashing")]
+                    username,
+                    #[cfg(feature = "token-authentication")]
+                    token_id,
+                    &mut cli,
+                )
+                .await?;
+                shell.execute(connection).await?;
+            }
+            #[cfg(feature = "files")]
+            Command::Files { database, command } => {
+                let connection = connect(
+                    server_url,
+                    pinned_certificate,
+                    #[cfg(feature = "password-hashing")]
+                    username,
+                    #[cfg(feature = "token-authentication")]
+                    token_id,
+                    &mut cli,
+                )
+                .await?;
+                let database = connection
+                    .database::<bonsaidb_files::FilesSchema>(&database)
+                    .await?;
+                command
+                    .execute_async::<bonsaidb_files::BonsaiFiles, _>(&database)
+                    .await?;
+            }
             Command::External(command) => {
-                let connection = if let Some(server_url) = server_url {
-                    // TODO how does custom API handling work here?
-                    let mut client = AsyncClient::build(server_url);
-
-                    if let Some(certificate) = pinned_certificate {
-                        client = client.with_certificate(certificate);
-                    }
-
-                    AnyServerConnection::Networked(client.build()?)
-                } else {
-                    AnyServerConnection::Local(cli.open_server().await?)
-                };
-
-                #[cfg(feature = "password-hashing")]
-                let connection = if let Some(username) = username {
-                    let password = bonsaidb_local::cli::read_password_from_stdin(false)?;
-                    connection
-                        .authenticate_with_password(&username, password)
-                        .await?
-                } else {
-                    connection
-                };
-
-                #[cfg(feature = "token-authentication")]
-                let connection = if let Some(token_id) = token_id {
-                    let token = bonsaidb_core::connection::SensitiveString(std::env::var(
-                        "BONSAIDB_TOKEN_SECRET",
-                    )?);
-                    connection.authenticate_with_token(token_id, &token).await?
-                } else {
-                    connection
-                };
-
+                let connection = connect(
+                    server_url,
+                    pinned_certificate,
+                    #[cfg(feature = "password-hashing")]
+                    username,
+                    #[cfg(feature = "token-authentication")]
+                    token_id,
+                    &mut cli,
+                )
+                .await?;
                 cli.execute(command, connection).await?;
             }
         }
@@ -89,6 +117,50 @@ where
     }
 }
 
+/// Opens a connection to `server_url`, or to a local server if no url is
+/// provided, and authenticates it using the provided credentials.
+async fn connect<Cli: CommandLine>(
+    server_url: Option<Url>,
+    pinned_certificate: Option<Certificate>,
+    #[cfg(feature = "password-hashing")] username: Option<String>,
+    #[cfg(feature = "token-authentication")] token_id: Option<u64>,
+    cli: &mut Cli,
+) -> anyhow::Result<AnyServerConnection<Cli::Backend>> {
+    let connection = if let Some(server_url) = server_url {
+        // TODO how does custom API handling work here?
+        let mut client = AsyncClient::build(server_url);
+
+        if let Some(certificate) = pinned_certificate {
+            client = client.with_certificate(certificate);
+        }
+
+        AnyServerConnection::Networked(client.build()?)
+    } else {
+        AnyServerConnection::Local(cli.open_server().await?)
+    };
+
+    #[cfg(feature = "password-hashing")]
+    let connection = if let Some(username) = username {
+        let password = bonsaidb_local::cli::read_password_from_stdin(false)?;
+        connection
+            .authenticate_with_password(&username, password)
+            .await?
+    } else {
+        connection
+    };
+
+    #[cfg(feature = "token-authentication")]
+    let connection = if let Some(token_id) = token_id {
+        let token =
+            bonsaidb_core::connection::SensitiveString(std::env::var("BONSAIDB_TOKEN_SECRET")?);
+        connection.authenticate_with_token(token_id, &token).await?
+    } else {
+        connection
+    };
+
+    Ok(connection)
+}
+
 /// The command line interface for `bonsaidb`.
 #[derive(Parser, Debug)]
 pub struct Args<Cli: CommandLine> {
@@ -0,0 +1,704 @@
+//! An interactive shell for browsing and modifying data.
+
+use std::io::Write;
+use std::ops::ControlFlow;
+
+use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::connection::{
+    AccessPolicy, AsyncLowLevelConnection, AsyncStorageConnection, Bound, Range,
+    SerializedQueryKey, Sort,
+};
+use bonsaidb_core::document::{DocumentId, OwnedDocument};
+use bonsaidb_core::keyvalue::{self, AsyncKeyValue, KeyOperation, Numeric, Output, SetCommand};
+use bonsaidb_core::pot;
+use bonsaidb_core::pubsub::AsyncPubSub;
+use bonsaidb_core::schema::{CollectionName, CollectionSummary, SchemaSummary, ViewName};
+use bonsaidb_server::Backend;
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::{AnyDatabase, AnyServerConnection};
+
+/// Starts an interactive shell for browsing and modifying data.
+///
+/// Each line read from stdin is executed as a shell command. Documents, view
+/// entries, and key-value entries are printed as JSON, decoded using the
+/// schema of the current database. Keys, ids, and values are provided as
+/// JSON. Arguments that aren't valid JSON are treated as strings.
+#[derive(Args, Debug)]
+pub struct Shell {
+    /// The database to use when the shell starts.
+    #[clap(long, short = 'd')]
+    pub database: Option<String>,
+}
+
+impl Shell {
+    /// Runs the shell on `connection` until stdin is closed or `exit` is
+    /// executed.
+    pub async fn execute<B: Backend>(
+        self,
+        connection: AnyServerConnection<B>,
+    ) -> anyhow::Result<()> {
+        let mut state = ShellState::new(connection);
+        if let Some(database) = self.database {
+            state.use_database(database).await?;
+        }
+
+        let mut stdout = std::io::stdout();
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            write!(stdout, "{}> ", state.prompt())?;
+            stdout.flush()?;
+            let Some(line) = lines.next_line().await? else {
+                break;
+            };
+
+            let arguments = match split_arguments(&line) {
+                Ok(arguments) if arguments.is_empty() => continue,
+                Ok(arguments) => arguments,
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    continue;
+                }
+            };
+            match ShellLine::try_parse_from(arguments) {
+                Ok(line) => match state.execute(line.command, &mut stdout).await {
+                    Ok(ControlFlow::Continue(())) => {}
+                    Ok(ControlFlow::Break(())) => break,
+                    Err(err) => eprintln!("error: {err}"),
+                },
+                Err(err) => err.print()?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+#[clap(multicall = true)]
+struct ShellLine {
+    #[clap(subcommand)]
+    command: ShellCommand,
+}
+
+/// A command executed by [`Shell`].
+#[derive(Subcommand, Debug)]
+pub enum ShellCommand {
+    /// Lists all databases.
+    Databases,
+    /// Selects the database used by subsequent commands.
+    Use {
+        /// The name of the database.
+        database: String,
+    },
+    /// Lists the collections in the current database.
+    Collections,
+    /// Lists the views of a collection in the current database.
+    Views {
+        /// The name of the collection.
+        collection: CollectionName,
+    },
+    /// Lists documents in a collection.
+    List {
+        /// The name of the collection.
+        collection: CollectionName,
+        /// The maximum number of documents to list.
+        #[clap(long, default_value_t = 25)]
+        limit: u32,
+        /// Lists documents in descending order of their ids.
+        #[clap(long)]
+        descending: bool,
+    },
+    /// Retrieves a document by its id.
+    Get {
+        /// The name of the collection.
+        collection: CollectionName,
+        /// The id of the document.
+        id: String,
+    },
+    /// Queries a view's entries.
+    Query {
+        /// The name of the view.
+        view: ViewName,
+        /// Only returns entries whose key matches this key.
+        #[clap(long, conflicts_with_all = ["start", "end"])]
+        key: Option<String>,
+        /// Only returns entries whose key is greater than or equal to this key.
+        #[clap(long)]
+        start: Option<String>,
+        /// Only returns entries whose key is less than this key.
+        #[clap(long)]
+        end: Option<String>,
+        /// The maximum number of entries to return.
+        #[clap(long)]
+        limit: Option<u32>,
+        /// Returns entries in descending order of their keys.
+        #[clap(long)]
+        descending: bool,
+    },
+    /// Inspects or modifies the key-value store of the current database.
+    #[clap(subcommand)]
+    Kv(KeyValueCommand),
+    /// Publishes a PubSub message in the current database.
+    Publish {
+        /// The topic to publish to.
+        topic: String,
+        /// The payload of the message.
+        payload: String,
+    },
+    /// Exits the shell.
+    #[clap(alias = "quit")]
+    Exit,
+}
+
+/// A key-value command executed by [`Shell`].
+#[derive(Subcommand, Debug)]
+pub enum KeyValueCommand {
+    /// Retrieves the value stored at a key.
+    Get {
+        /// The key to retrieve.
+        key: String,
+        /// The namespace containing the key.
+        #[clap(long)]
+        namespace: Option<String>,
+    },
+    /// Stores a value at a key.
+    Set {
+        /// The key to store the value at.
+        key: String,
+        /// The value to store.
+        value: String,
+        /// The namespace containing the key.
+        #[clap(long)]
+        namespace: Option<String>,
+    },
+    /// Deletes a key.
+    Delete {
+        /// The key to delete.
+        key: String,
+        /// The namespace containing the key.
+        #[clap(long)]
+        namespace: Option<String>,
+    },
+}
+
+struct ShellState<B: Backend> {
+    connection: AnyServerConnection<B>,
+    database: Option<CurrentDatabase<B>>,
+}
+
+struct CurrentDatabase<B: Backend> {
+    name: String,
+    database: AnyDatabase<B>,
+    schema: SchemaSummary,
+}
+
+impl<B: Backend> ShellState<B> {
+    fn new(connection: AnyServerConnection<B>) -> Self {
+        Self {
+            connection,
+            database: None,
+        }
+    }
+
+    fn prompt(&self) -> String {
+        match &self.database {
+            Some(current) => format!("bonsaidb:{}", current.name),
+            None => String::from("bonsaidb"),
+        }
+    }
+
+    fn current(&self) -> anyhow::Result<&CurrentDatabase<B>> {
+        self.database
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no database selected, run `use <database>` first"))
+    }
+
+    async fn use_database(&mut self, name: String) -> anyhow::Result<()> {
+        let databases = self.connection.list_databases().await?;
+        let Some(stored) = databases.into_iter().find(|db| db.name == name) else {
+            anyhow::bail!("database not found: {name}");
+        };
+        let schemas = self.connection.list_available_schemas().await?;
+        let Some(schema) = schemas.into_iter().find(|s| s.name == stored.schema) else {
+            anyhow::bail!("schema not available: {}", stored.schema);
+        };
+        let database = self.connection.database_without_schema(&name).await?;
+        self.database = Some(CurrentDatabase {
+            name,
+            database,
+            schema,
+        });
+        Ok(())
+    }
+
+    async fn execute<W: Write>(
+        &mut self,
+        command: ShellCommand,
+        output: &mut W,
+    ) -> anyhow::Result<ControlFlow<()>> {
+        match command {
+            ShellCommand::Databases => {
+                let mut databases = self.connection.list_databases().await?;
+                databases.sort_by(|a, b| a.name.cmp(&b.name));
+                for database in databases {
+                    writeln!(output, "{} ({})", database.name, database.schema)?;
+                }
+            }
+            ShellCommand::Use { database } => self.use_database(database).await?,
+            ShellCommand::Collections => {
+                let mut collections = self
+                    .current()?
+                    .schema
+                    .collections()
+                    .map(|collection| collection.name.to_string())
+                    .collect::<Vec<_>>();
+                collections.sort();
+                for collection in collections {
+                    writeln!(output, "{collection}")?;
+                }
+            }
+            ShellCommand::Views { collection } => {
+                let collection = self.current()?.collection(&collection)?;
+                let mut views = collection
+                    .views()
+                    .map(|view| view.name.to_string())
+                    .collect::<Vec<_>>();
+                views.sort();
+                for view in views {
+                    writeln!(output, "{view}")?;
+                }
+            }
+            ShellCommand::List {
+                collection,
+                limit,
+                descending,
+            } => {
+                let current = self.current()?;
+                let collection = current.collection(&collection)?;
+                let documents = current
+                    .database
+                    .list_from_collection(
+                        Range::from(..),
+                        sort(descending),
+                        Some(limit),
+                        &collection.name,
+                    )
+                    .await?;
+                for document in documents {
+                    write_document(output, collection, &document)?;
+                }
+            }
+            ShellCommand::Get { collection, id } => {
+                let current = self.current()?;
+                let collection = current.collection(&collection)?;
+                let encoded = collection.primary_key.encode_value(&parse_value(&id)?)?;
+                let id = DocumentId::try_from(encoded.as_slice())?;
+                match current
+                    .database
+                    .get_from_collection(id, &collection.name)
+                    .await?
+                {
+                    Some(document) => {
+                        write_document(output, collection, &document)?;
+                    }
+                    None => writeln!(output, "document not found")?,
+                }
+            }
+            ShellCommand::Query {
+                view,
+                key,
+                start,
+                end,
+                limit,
+                descending,
+            } => {
+                self.query(view, key, start, end, limit, descending, output)
+                    .await?
+            }
+            ShellCommand::Kv(command) => self.key_value(command, output).await?,
+            ShellCommand::Publish { topic, payload } => {
+                let topic = parse_json(&topic);
+                let payload = parse_json(&payload);
+                self.current()?.database.publish(&topic, &payload).await?;
+            }
+            ShellCommand::Exit => return Ok(ControlFlow::Break(())),
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn query<W: Write>(
+        &self,
+        view: ViewName,
+        key: Option<String>,
+        start: Option<String>,
+        end: Option<String>,
+        limit: Option<u32>,
+        descending: bool,
+        output: &mut W,
+    ) -> anyhow::Result<()> {
+        let current = self.current()?;
+        let collection = current.collection(&view.collection)?;
+        let view = collection
+            .view(&view)
+            .ok_or_else(|| anyhow::anyhow!("view not found: {view}"))?;
+        let encode = |key: &str| -> anyhow::Result<Bytes> {
+            Ok(Bytes::from(view.key.encode_value(&parse_value(key)?)?))
+        };
+        let key = if let Some(key) = key {
+            Some(SerializedQueryKey::Matches(encode(&key)?))
+        } else if start.is_some() || end.is_some() {
+            Some(SerializedQueryKey::Range(Range {
+                start: start
+                    .as_deref()
+                    .map(encode)
+                    .transpose()?
+                    .map_or(Bound::Unbounded, Bound::Included),
+                end: end
+                    .as_deref()
+                    .map(encode)
+                    .transpose()?
+                    .map_or(Bound::Unbounded, Bound::Excluded),
+            }))
+        } else {
+            None
+        };
+
+        let mappings = current
+            .database
+            .query_by_name(
+                &view.name,
+                key,
+                sort(descending),
+                limit,
+                AccessPolicy::UpdateBefore,
+            )
+            .await?;
+        for mapping in mappings {
+            let mapping = view.decode_mapping(&mapping)?;
+            write_json(
+                output,
+                &json!({
+                    "key": to_json(&mapping.key)?,
+                    "value": to_json(&mapping.value)?,
+                    "source": to_json(&collection.decode_id(&mapping.source.id)?)?,
+                }),
+            )?;
+        }
+        Ok(())
+    }
+
+    async fn key_value<W: Write>(
+        &self,
+        command: KeyValueCommand,
+        output: &mut W,
+    ) -> anyhow::Result<()> {
+        let database = &self.current()?.database;
+        let (namespace, key, command) = match command {
+            KeyValueCommand::Get { key, namespace } => {
+                (namespace, key, keyvalue::Command::Get { delete: false })
+            }
+            KeyValueCommand::Set {
+                key,
+                value,
+                namespace,
+            } => (
+                namespace,
+                key,
+                keyvalue::Command::Set(SetCommand {
+                    value: keyvalue::Value::Bytes(Bytes::from(pot::to_vec(&parse_json(&value))?)),
+                    expiration: None,
+                    keep_existing_expiration: false,
+                    check: None,
+                    return_previous_value: false,
+                }),
+            ),
+            KeyValueCommand::Delete { key, namespace } => {
+                (namespace, key, keyvalue::Command::Delete)
+            }
+        };
+
+        match database
+            .execute_key_operation(KeyOperation {
+                namespace,
+                key,
+                command,
+            })
+            .await?
+        {
+            Output::Status(status) => writeln!(output, "{status:?}")?,
+            Output::Value(Some(keyvalue::Value::Bytes(bytes))) => {
+                // Values set using `set_key` are serialized using Pot, but
+                // values set using `set_binary_key` can contain anything.
+                match pot::from_slice::<pot::Value<'_>>(&bytes) {
+                    Ok(value) => write_json(output, &to_json(&value)?)?,
+                    Err(_) => write_json(output, &json!({ "bytes": bytes.to_vec() }))?,
+                }
+            }
+            Output::Value(Some(keyvalue::Value::Numeric(numeric))) => match numeric {
+                Numeric::Integer(value) => write_json(output, &json!(value))?,
+                Numeric::UnsignedInteger(value) => write_json(output, &json!(value))?,
+                Numeric::Float(value) => write_json(output, &json!(value))?,
+            },
+            Output::Value(None) => writeln!(output, "key not found")?,
+        }
+        Ok(())
+    }
+}
+
+impl<B: Backend> CurrentDatabase<B> {
+    fn collection(&self, name: &CollectionName) -> anyhow::Result<&CollectionSummary> {
+        self.schema
+            .collection(name)
+            .ok_or_else(|| anyhow::anyhow!("collection not found: {name}"))
+    }
+}
+
+fn sort(descending: bool) -> Sort {
+    if descending {
+        Sort::Descending
+    } else {
+        Sort::Ascending
+    }
+}
+
+fn write_document<W: Write>(
+    output: &mut W,
+    collection: &CollectionSummary,
+    document: &OwnedDocument,
+) -> anyhow::Result<()> {
+    let document = collection.decode_document(document)?;
+    write_json(
+        output,
+        &json!({
+            "id": to_json(&document.id)?,
+            "revision": document.header.revision.id,
+            "contents": to_json(&document.contents)?,
+        }),
+    )
+}
+
+fn write_json<W: Write>(output: &mut W, value: &serde_json::Value) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut *output, value)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+/// Converts `value` to JSON. Fails if `value` contains a mapping whose keys
+/// can't be represented as JSON object keys.
+fn to_json(value: &pot::Value<'_>) -> anyhow::Result<serde_json::Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Parses `argument` as JSON, treating it as a string if it isn't valid JSON.
+fn parse_json(argument: &str) -> serde_json::Value {
+    serde_json::from_str(argument).unwrap_or_else(|_| serde_json::Value::from(argument))
+}
+
+/// Parses `argument` into a value that can be encoded using a
+/// [`KeyDescription`](bonsaidb_core::key::KeyDescription).
+fn parse_value(argument: &str) -> anyhow::Result<pot::Value<'static>> {
+    let serialized = pot::to_vec(&parse_json(argument))?;
+    Ok(pot::from_slice::<pot::Value<'_>>(&serialized)?.into_static())
+}
+
+/// Splits `line` into arguments separated by whitespace. Single or double
+/// quotes group text containing whitespace into a single argument, and a
+/// backslash escapes the next character.
+fn split_arguments(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut arguments = Vec::new();
+    let mut current = None::<String>;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, quote) {
+            ('\\', _) => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("trailing backslash"))?;
+                current.get_or_insert_with(String::new).push(escaped);
+            }
+            (ch, Some(open)) if ch == open => quote = None,
+            (_, Some(_)) => current.get_or_insert_with(String::new).push(ch),
+            ('"' | '\'', None) => {
+                quote = Some(ch);
+                current.get_or_insert_with(String::new);
+            }
+            (ch, None) if ch.is_whitespace() => arguments.extend(current.take()),
+            (ch, None) => current.get_or_insert_with(String::new).push(ch),
+        }
+    }
+
+    if quote.is_some() {
+        anyhow::bail!("unterminated quote");
+    }
+    arguments.extend(current);
+    Ok(arguments)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    use bonsaidb_core::test_util::{BasicSchema, TestDirectory};
+    use bonsaidb_local::config::Builder;
+    use bonsaidb_server::{DefaultPermissions, Server, ServerConfiguration};
+    use clap::Parser;
+
+    use super::{split_arguments, KeyValueCommand, ShellCommand, ShellLine, ShellState};
+    use crate::AnyServerConnection;
+
+    fn parse(line: &str) -> anyhow::Result<ShellCommand> {
+        Ok(ShellLine::try_parse_from(split_arguments(line)?)?.command)
+    }
+
+    #[test]
+    fn split_arguments_quoting() {
+        assert_eq!(
+            split_arguments("get  basic\t1").unwrap(),
+            ["get", "basic", "1"]
+        );
+        assert!(split_arguments("   ").unwrap().is_empty());
+        assert_eq!(
+            split_arguments(r#"kv set "a key" 'a value'"#).unwrap(),
+            ["kv", "set", "a key", "a value"]
+        );
+        // Quotes can begin or end within an argument, and empty quotes produce
+        // an empty argument.
+        assert_eq!(
+            split_arguments(r#"publish top"ic"s """#).unwrap(),
+            ["publish", "topics", ""]
+        );
+        // The other quote character has no special meaning within quotes.
+        assert_eq!(
+            split_arguments(r#"'say "hi"' "it's""#).unwrap(),
+            [r#"say "hi""#, "it's"]
+        );
+    }
+
+    #[test]
+    fn split_arguments_escapes() {
+        assert_eq!(split_arguments(r"a\ b c").unwrap(), ["a b", "c"]);
+        assert_eq!(
+            split_arguments(r#""a \"quoted\" value""#).unwrap(),
+            [r#"a "quoted" value"#]
+        );
+        assert_eq!(split_arguments(r"\\ \'").unwrap(), [r"\", "'"]);
+        assert!(split_arguments(r"trailing \").is_err());
+    }
+
+    #[test]
+    fn split_arguments_unterminated_quotes() {
+        assert!(split_arguments(r#"get "unterminated"#).is_err());
+        assert!(split_arguments("get 'unterminated").is_err());
+        assert!(split_arguments(r#"get 'mismatched""#).is_err());
+        assert!(split_arguments(r#"get "escaped\""#).is_err());
+    }
+
+    #[test]
+    fn command_parsing() {
+        assert!(matches!(parse("databases"), Ok(ShellCommand::Databases)));
+        assert!(matches!(parse("quit"), Ok(ShellCommand::Exit)));
+        assert!(matches!(
+            parse("use 'my database'"),
+            Ok(ShellCommand::Use { database }) if database == "my database"
+        ));
+        assert!(matches!(
+            parse("list myapp.things"),
+            Ok(ShellCommand::List {
+                limit: 25,
+                descending: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("query myapp.things.by-name --start a --limit 5 --descending"),
+            Ok(ShellCommand::Query {
+                key: None,
+                start: Some(start),
+                end: None,
+                limit: Some(5),
+                descending: true,
+                ..
+            }) if start == "a"
+        ));
+        assert!(matches!(
+            parse(r#"kv set key "{\"a\": 1}" --namespace ns"#),
+            Ok(ShellCommand::Kv(KeyValueCommand::Set {
+                key,
+                value,
+                namespace: Some(namespace),
+            })) if key == "key" && value == r#"{"a": 1}"# && namespace == "ns"
+        ));
+
+        // Unknown commands, missing arguments, invalid names, and conflicting
+        // options are rejected.
+        assert!(parse("unknown").is_err());
+        assert!(parse("use").is_err());
+        assert!(parse("list unqualified").is_err());
+        assert!(parse("list myapp.things --limit many").is_err());
+        assert!(parse("query myapp.things.by-name --key a --start b").is_err());
+        assert!(parse("kv get").is_err());
+    }
+
+    #[tokio::test]
+    async fn dispatch_errors() -> anyhow::Result<()> {
+        let test_dir = TestDirectory::new("shell-dispatch");
+        let server = Server::open(
+            ServerConfiguration::new(&test_dir)
+                .default_permissions(DefaultPermissions::AllowAll)
+                .with_schema::<BasicSchema>()?,
+        )
+        .await?;
+        server
+            .create_database::<BasicSchema>("tests", false)
+            .await?;
+        let mut state = ShellState::new(AnyServerConnection::Local(server));
+        let mut output = Vec::new();
+
+        // Commands that operate on a database fail until one is selected.
+        for line in ["collections", "kv get key", "publish topic payload"] {
+            let err = state.execute(parse(line)?, &mut output).await.unwrap_err();
+            assert!(err.to_string().contains("no database selected"), "{err}");
+        }
+        let err = state
+            .execute(parse("use missing")?, &mut output)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("database not found"), "{err}");
+        assert_eq!(state.prompt(), "bonsaidb");
+        assert!(output.is_empty());
+
+        state.execute(parse("use tests")?, &mut output).await?;
+        assert_eq!(state.prompt(), "bonsaidb:tests");
+        let err = state
+            .execute(parse("views myapp.missing")?, &mut output)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("collection not found"), "{err}");
+        let err = state
+            .execute(parse("query myapp.missing.view")?, &mut output)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("collection not found"), "{err}");
+
+        state.execute(parse("databases")?, &mut output).await?;
+        let listed = String::from_utf8(std::mem::take(&mut output))?;
+        assert!(
+            listed.lines().any(|line| line.starts_with("tests (")),
+            "{listed}"
+        );
+
+        assert!(matches!(
+            state.execute(parse("exit")?, &mut output).await?,
+            ControlFlow::Break(())
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+//! Tests authenticating clients using TLS client certificates.
+
+use std::time::Duration;
+
+use bonsaidb::client::url::Url;
+use bonsaidb::client::{AsyncClient, ReconnectPolicy};
+use bonsaidb::core::test_util::{Basic, TestDirectory};
+use bonsaidb::local::config::Builder;
+use bonsaidb::server::fabruic::KeyPair;
+use bonsaidb::server::{
+    BonsaiListenConfig, ClientCertificates, DefaultPermissions, Server, ServerConfiguration,
+};
+use bonsaidb_core::admin::{CertificateMatcher, ClientCertificate};
+use bonsaidb_core::connection::{AsyncStorageConnection, Identity, IdentityId};
+use bonsaidb_core::schema::SerializedCollection;
+use futures::Future;
+
+#[tokio::test]
+async fn quic() -> anyhow::Result<()> {
+    test_client_certificates(
+        "client-certificates-quic.bonsaidb",
+        "bonsaidb://localhost:12348",
+        |server| async move {
+            server
+                .listen_on(BonsaiListenConfig::from(12348).reuse_address(true))
+                .await
+                .unwrap();
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+#[cfg(feature = "websockets")]
+async fn websockets() -> anyhow::Result<()> {
+    test_client_certificates(
+        "client-certificates-wss.bonsaidb",
+        "wss://localhost:12349",
+        |server| async move {
+            server
+                .listen_for_websockets_on("localhost:12349", true)
+                .await
+                .unwrap();
+        },
+    )
+    .await
+}
+
+async fn test_client_certificates<F, Fut>(
+    dir_name: &str,
+    connect_addr: &str,
+    listen: F,
+) -> anyhow::Result<()>
+where
+    F: FnOnce(Server) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    drop(env_logger::try_init());
+    let dir = TestDirectory::new(dir_name);
+    let server = Server::open(
+        ServerConfiguration::new(&dir)
+            .server_name("localhost")
+            .default_permissions(DefaultPermissions::AllowAll)
+            .client_certificates(ClientCertificates::Required)
+            .with_schema::<Basic>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    let user_id = server.create_user("certified").await?;
+    let key_pair = KeyPair::new_self_signed("certified");
+    ClientCertificate::new(
+        CertificateMatcher::fingerprint_of(key_pair.end_entity_certificate().as_ref()),
+        IdentityId::User(user_id),
+    )
+    .push_into_async(&server.admin().await)
+    .await?;
+
+    tokio::spawn(listen(server.clone()));
+    // Give the listener a moment to become established.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // A client presenting a mapped certificate is assigned its identity.
+    let client = AsyncClient::build(Url::parse(connect_addr)?)
+        .with_certificate(certificate.clone())
+        .with_client_certificate(key_pair)
+        .build()?;
+    client.list_databases().await?;
+    let connected = server.connected_clients();
+    assert_eq!(connected.len(), 1);
+    let session = connected[0].session(None).expect("no session for client");
+    assert!(matches!(
+        session.identity(),
+        Some(Identity::User { id, .. }) if *id == user_id
+    ));
+
+    // Certificates are required, so a client without one is rejected.
+    let anonymous = AsyncClient::build(Url::parse(connect_addr)?)
+        .with_certificate(certificate)
+        .with_reconnect_policy(ReconnectPolicy::disabled())
+        .with_request_timeout(Duration::from_secs(5))
+        .build()?;
+    assert!(anonymous.list_databases().await.is_err());
+
+    Ok(())
+}
@@ -334,6 +334,7 @@ async fn assume_permissions(
             let administrator_group_id = match (PermissionGroup {
                 name: String::from(label),
                 statements,
+                conditional_statements: Vec::new(),
             }
             .push_into_async(&admin)
             .await)
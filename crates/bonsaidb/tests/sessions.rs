@@ -1,14 +1,16 @@
-//! Tests breaking a connection and detecting it on the Client.
+//! Tests breaking a connection and detecting it on the Client, and
+//! administering the sessions of connected clients.
 
 use std::sync::Arc;
 use std::time::Duration;
 
 use bonsaidb::client::url::Url;
-use bonsaidb::client::AsyncClient;
+use bonsaidb::client::{AsyncClient, ConnectionState, ReconnectPolicy};
 use bonsaidb::core::test_util::{Basic, TestDirectory};
 use bonsaidb::local::config::Builder;
 use bonsaidb::server::{DefaultPermissions, Server, ServerConfiguration};
-use bonsaidb_core::connection::{AsyncStorageConnection, HasSession, SensitiveString};
+use bonsaidb_core::connection::{AsyncStorageConnection, HasSession, Identity, SensitiveString};
+use bonsaidb_core::permissions::ClientTransport;
 use bonsaidb_core::schema::SerializedCollection;
 use bonsaidb_server::BonsaiListenConfig;
 use futures::Future;
@@ -112,18 +114,34 @@ where
         .with_certificate(certificate.clone())
         .build()?;
 
+    // A client that doesn't reconnect in the background.
+    let disabled_client = AsyncClient::build(Url::parse(connect_addr)?)
+        .with_certificate(certificate.clone())
+        .with_reconnect_policy(ReconnectPolicy::disabled())
+        .build()?;
+
     println!("Authenticating.");
     let authenticated = client
         .authenticate_with_password("ecton", SensitiveString::from("hunter2"))
         .await?;
+    let disabled_authenticated = disabled_client
+        .authenticate_with_password("ecton", SensitiveString::from("hunter2"))
+        .await?;
     println!("Creating db");
     let db = client.create_database::<Basic>("basic", true).await?;
+    let disabled_db = disabled_client.database::<Basic>("basic").await?;
     // Verify we have a session at this point.
     authenticated
         .session()
         .unwrap()
         .id
         .expect("session should be present");
+    disabled_authenticated
+        .session()
+        .unwrap()
+        .id
+        .expect("session should be present");
+    assert_eq!(client.connection_state(), ConnectionState::Connected);
 
     reboot.notify_one();
     rebooted.notified().await;
@@ -131,15 +149,117 @@ where
     tokio::time::sleep(Duration::from_millis(500)).await;
     println!("Continuing client.");
 
-    // Get a disconnection error
-    assert!(Basic::get_async(&0, &db).await.is_err());
+    // The client reconnects automatically, and requests made while
+    // reconnecting are sent once the connection is restored.
+    assert!(Basic::get_async(&0, &db).await.unwrap().is_none());
+    assert_eq!(client.connection_state(), ConnectionState::Connected);
+    println!("Checking restored session");
+    // The authenticated handle's session was restored.
+    authenticated
+        .session()
+        .expect("session should have been restored")
+        .id
+        .expect("session should be present");
+    assert!(authenticated.list_databases().await.is_ok());
+
+    // Without reconnecting, the first request gets a disconnection error.
+    assert!(Basic::get_async(&0, &disabled_db).await.is_err());
     println!("Reconnecting");
     // Reconnect
-    assert!(Basic::get_async(&0, &db).await.unwrap().is_none());
+    assert!(Basic::get_async(&0, &disabled_db).await.unwrap().is_none());
     println!("Checking session");
     // Verify the client recognizes it was de-authenticated
-    assert!(client.session().unwrap().id.is_none());
+    assert!(disabled_authenticated.session().is_none());
 
     println!("Done");
     Ok(())
 }
+
+#[tokio::test]
+async fn administering_sessions() -> anyhow::Result<()> {
+    drop(env_logger::try_init());
+    let dir = TestDirectory::new("sessions-admin.bonsaidb");
+    let server = Server::open(
+        ServerConfiguration::new(&dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .with_schema::<Basic>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+
+    let user_id = server.create_user("ecton").await?;
+    server
+        .set_user_password(user_id, SensitiveString::from("hunter2"))
+        .await?;
+
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server
+                .listen_on(BonsaiListenConfig::from(12347).reuse_address(true))
+                .await
+                .unwrap();
+        }
+    });
+    // Give the listener a moment to become established.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let admin = AsyncClient::build(Url::parse("bonsaidb://localhost:12347")?)
+        .with_certificate(certificate.clone())
+        .build()?;
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:12347")?)
+        .with_certificate(certificate)
+        .with_reconnect_policy(ReconnectPolicy::disabled())
+        .build()?;
+    let authenticated = client
+        .authenticate_with_password("ecton", SensitiveString::from("hunter2"))
+        .await?;
+    let session_id = authenticated.session().unwrap().id.unwrap();
+
+    let sessions = admin.list_sessions().await?;
+    let active = sessions
+        .iter()
+        .find(|active| active.session_id == Some(session_id))
+        .expect("session not listed");
+    assert!(matches!(
+        &active.identity,
+        Some(Identity::User { id, username }) if *id == user_id && username == "ecton"
+    ));
+    assert_eq!(active.transport, ClientTransport::Bonsai);
+    let client_id = active.client_id;
+    // The client's unauthenticated session is listed too.
+    assert!(sessions
+        .iter()
+        .any(|active| active.client_id == client_id && active.session_id.is_none()));
+
+    admin.terminate_session(session_id).await?;
+    assert!(!admin
+        .list_sessions()
+        .await?
+        .iter()
+        .any(|active| active.session_id == Some(session_id)));
+    assert!(matches!(
+        admin.terminate_session(session_id).await,
+        Err(bonsaidb_core::Error::SessionNotFound)
+    ));
+
+    admin.disconnect_client(client_id).await?;
+    // Give the server a moment to close the connection.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(!admin
+        .list_sessions()
+        .await?
+        .iter()
+        .any(|active| active.client_id == client_id));
+    assert!(client.list_databases().await.is_err());
+    assert!(matches!(
+        admin.disconnect_client(client_id).await,
+        Err(bonsaidb_core::Error::ClientNotFound)
+    ));
+
+    Ok(())
+}
@@ -73,6 +73,7 @@ async fn main() -> anyhow::Result<()> {
             .allowing(&BonsaiAction::Database(DatabaseAction::Document(
                 DocumentAction::Get,
             )))],
+        conditional_statements: Vec::new(),
     }
     .push_into_async(&admin)
     .await)
@@ -97,6 +98,7 @@ async fn main() -> anyhow::Result<()> {
     let superusers_group_id = match (PermissionGroup {
         name: String::from("superusers"),
         statements: vec![Statement::allow_all_for_any_resource()],
+        conditional_statements: Vec::new(),
     }
     .push_into_async(&admin)
     .await)
@@ -135,6 +137,7 @@ async fn main() -> anyhow::Result<()> {
         statements: vec![
             Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::AssumeIdentity))
         ],
+        conditional_statements: Vec::new(),
     }
     .push_into_async(&admin)
     .await)